@@ -4,10 +4,15 @@ use std::io::{self, Write};
 
 use crate::interfaces;
 use crate::interfaces::user::User;
+use crate::authentication::permissions::{self, Permission};
+use crate::authentication::session;
 use crate::db::dbqueries;
+use crate::db::two_factor;
+use crate::db::user_admin::{self, AccountStatus};
+use crate::logger::event::LogEvent;
 use crate::logger::logger;
 
-use crate::interfaces::menus::menu_generator;
+use crate::interfaces::menus::{menu_generator, session_menu};
 
 use clearscreen;
 
@@ -40,32 +45,19 @@ pub fn login(conn: &Connection) -> rusqlite::Result<()> {
 
         // if registration or sign in was successful and returned the User struct
         if let Some(user) = user {
-            // Get user role with proper error handling
-            match user.get_role(&conn) {
-                Ok(role) => {
-                    match role.as_str() {
-                        "user" => {
-                            logger::info(&format!("User ID: {} logged in as regular user", user.id));
-                            interfaces::user::user_menu(conn, &user)?;
-                        },
-                        "technician" => {
-                            logger::info(&format!("User ID: {} logged in as technician", user.id));
-                            interfaces::technician::technician_menu(conn, &user)?;
-                        },
-                        "commissioner" => {
-                            logger::info(&format!("User ID: {} logged in as commissioner", user.id));
-                            interfaces::commisioner::commissioner_menu(conn, &user)?;
-                        }, 
-                        _ => {
-                            logger::warning(&format!("User ID: {} has unknown role: {}", user.id, role));
-                            println!("{}", "Error: Unknown user role. Please contact administrator.".red().bold());
-                        }
-                    }
-                }
-                Err(e) => {
-                    logger::error(&format!("Failed to retrieve role for User ID: {}. Error: {}", user.id, e));
-                    println!("{}", "Error: Could not verify user role. Please try again.".red().bold());
-                }
+            if let Err(e) = session::start(conn, &user) {
+                logger::error(&format!("Failed to start session for User ID: {}. Error: {}", user.id, e));
+                println!("{}", "❌ System error occurred".red().bold());
+                continue;
+            }
+            // The menus a user gets depend on the permissions of their role
+            if let Err(e) = open_menus(conn, &user) {
+                logger::error(&format!("Failed to check permissions for User ID: {}. Error: {}", user.id, e));
+                println!("{}", "Error: Could not verify user role. Please try again.".red().bold());
+            }
+            // Menus also close when the session expires, which has ended it already
+            if let Err(e) = session::end(conn, &user, "logout") {
+                logger::error(&format!("Failed to end session for User ID: {}. Error: {}", user.id, e));
             }
         }
     }
@@ -73,6 +65,60 @@ pub fn login(conn: &Connection) -> rusqlite::Result<()> {
     Ok(())
 }
 
+// Open the menus the user's role permits. Players go to the games, staff to the technician menu,
+// the commissioner control panel, or a choice of both when their role reaches into each.
+fn open_menus(conn: &Connection, user: &User) -> rusqlite::Result<()> {
+    use crate::interfaces::technician::TECHNICIAN_OPTIONS;
+    use crate::interfaces::commisioner::COMMISSIONER_OPTIONS;
+
+    if permissions::user_has_permission(conn, user.id, Permission::GamesPlay)? {
+        logger::info(&format!("User ID: {} logged in as regular user", user.id));
+        return interfaces::user::user_menu(conn, user);
+    }
+
+    // Options both menus offer, such as round replay, do not decide which menu to open
+    let opens = |options: &[(&str, Permission)], other: &[(&str, Permission)]| -> rusqlite::Result<bool> {
+        for (_, permission) in options {
+            let shared = other.iter().any(|(_, other_permission)| other_permission == permission);
+            if !shared && permissions::user_has_permission(conn, user.id, *permission)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    };
+    let commissioner = opens(&COMMISSIONER_OPTIONS, &TECHNICIAN_OPTIONS)?;
+    let technician = opens(&TECHNICIAN_OPTIONS, &COMMISSIONER_OPTIONS)?
+        || (!commissioner && !permissions::permitted_options(conn, user, &TECHNICIAN_OPTIONS).is_empty());
+
+    match (technician, commissioner) {
+        (true, false) => {
+            logger::info(&format!("User ID: {} logged in as technician", user.id));
+            interfaces::technician::technician_menu(conn, user)
+        }
+        (false, true) => {
+            logger::info(&format!("User ID: {} logged in as commissioner", user.id));
+            interfaces::commisioner::commissioner_menu(conn, user)
+        }
+        (true, true) => {
+            logger::info(&format!("User ID: {} logged in with technician and commissioner access", user.id));
+            loop {
+                let menu_options = vec!["Tech Menu", "Commissioner Control Panel", "Logout"];
+                match session_menu(conn, user, "═══ 🎰 Staff Menus 🎰 ═══", &menu_options) {
+                    Some("Tech Menu") => interfaces::technician::technician_menu(conn, user)?,
+                    Some("Commissioner Control Panel") => interfaces::commisioner::commissioner_menu(conn, user)?,
+                    _ => return Ok(()),
+                }
+            }
+        }
+        (false, false) => {
+            let role = user.get_role(conn)?;
+            logger::warning(&format!("User ID: {} has role {} without any permissions", user.id, role));
+            println!("{}", "Error: Your role has no permissions. Please contact administrator.".red().bold());
+            Ok(())
+        }
+    }
+}
+
 // Function to handle a new user trying to register their account, returns a user struct
 pub fn register(conn: &Connection) -> Result<Option<User>> {
     use crate::db::validator::{validate_username, validate_password, display_validation_error};
@@ -226,17 +272,17 @@ pub fn sign_in(conn: &Connection) -> Result<Option<User>> {
     }
     
     // BRUTE FORCE PROTECTION: Check if account is locked
-    if is_account_locked(username) {
-        if let Some(remaining) = get_lockout_remaining(username) {
+    if is_account_locked(conn, username) {
+        if let Some(remaining) = get_lockout_remaining(conn, username) {
             println!("\n{}", "╔═══════════════════════════════════════════╗".red());
             println!("{}", "║        🔒 ACCOUNT LOCKED 🔒               ║".red().bold());
             println!("{}", "╠═══════════════════════════════════════════╣".red());
-            println!("{}", format!("║  Too many failed login attempts!          ║").red());
+            println!("{}", "║  Too many failed login attempts!          ║".to_string().red());
             println!("{}", format!("║  Account locked for {} seconds.           ║", remaining).red());
-            println!("{}", format!("║  Try again later.                         ║").red());
+            println!("{}", "║  Try again later.                         ║".to_string().red());
             println!("{}", "╚═══════════════════════════════════════════╝".red());
             println!();
-            logger::security(&format!("Blocked login attempt for locked account: {}", username));
+            logger::event(LogEvent::LoginFailed { username, reason: "Account is locked" });
             return Ok(None);
         }
     }
@@ -255,7 +301,7 @@ pub fn sign_in(conn: &Connection) -> Result<Option<User>> {
     // Validate password
     if let Err(error) = validate_password(&password) {
         display_validation_error(&error);
-        logger::warning(&format!("Failed login - invalid password format for username: {}", username));
+        logger::event(LogEvent::LoginFailed { username, reason: "Invalid password format" });
         return Ok(None);
     }
     
@@ -267,21 +313,74 @@ pub fn sign_in(conn: &Connection) -> Result<Option<User>> {
 
     match result {
         Ok(id) => {
+            let account = match user_admin::get_account(conn, id) {
+                Ok(account) => account,
+                Err(e) => {
+                    logger::error(&format!("Failed to load account status for User ID: {}: {}", id, e));
+                    println!("{}", "❌ System error occurred".red().bold());
+                    return Ok(None);
+                }
+            };
+
+            // Suspended accounts cannot sign in, even with the right password
+            if account.status == AccountStatus::Suspended {
+                println!("\n{}", "╔═══════════════════════════════════════════╗".red());
+                println!("{}", "║        🚫 ACCOUNT SUSPENDED 🚫            ║".red().bold());
+                println!("{}", "╠═══════════════════════════════════════════╣".red());
+                println!("{}", "║  This account has been suspended.         ║".red());
+                println!("{}", "║  Please contact casino staff.             ║".red());
+                println!("{}", "╚═══════════════════════════════════════════╝".red());
+                println!();
+                logger::event(LogEvent::LoginFailed { username, reason: "Account is suspended" });
+                return Ok(None);
+            }
+
+            // SECURITY: Second factor for enrolled accounts, enrollment for staff that policy requires it of
+            let (two_factor_enabled, two_factor_required) = match (two_factor::is_enabled(conn, id), two_factor::is_required(conn, id)) {
+                (Ok(enabled), Ok(required)) => (enabled, required),
+                (Err(e), _) | (_, Err(e)) => {
+                    logger::error(&format!("Failed to load two-factor settings for User ID: {}: {}", id, e));
+                    println!("{}", "❌ System error occurred".red().bold());
+                    return Ok(None);
+                }
+            };
+            if two_factor_enabled {
+                if !interfaces::two_factor::challenge(conn, id) {
+                    // BRUTE FORCE PROTECTION: A wrong code counts like a wrong password
+                    record_failed_attempt(conn, username);
+                    logger::event(LogEvent::LoginFailed { username, reason: "Two-factor authentication failed" });
+                    println!("{}", "❌ Invalid authentication code".red().bold());
+                    return Ok(None);
+                }
+            } else if two_factor_required {
+                println!("{}", "Two-factor authentication is required for your role.".yellow().bold());
+                if !interfaces::two_factor::enroll(conn, id, username) {
+                    logger::security(&format!("Login for username: {} abandoned at two-factor enrollment", username));
+                    return Ok(None);
+                }
+            }
+
             // BRUTE FORCE PROTECTION: Clear failed attempts on successful login
-            record_successful_login(username);
-            
-            logger::security(&format!("Successful login for username: {} (User ID: {})", username, id));
+            record_successful_login(conn, username);
+
+            // A password reset by staff must be replaced before the account can be used
+            if account.must_reset_password && !forced_password_change(conn, username) {
+                logger::security(&format!("Login for username: {} abandoned at forced password change", username));
+                return Ok(None);
+            }
+
+            logger::event(LogEvent::LoginSucceeded { username, user_id: id });
             println!("{}", "✓ Login successful!".green().bold());
             clearscreen::clear().expect("Failed clearscreen");
-            return Ok(Some(User { id: id}))
+            Ok(Some(User { id}))
         }
         Err(e) => {
             // BRUTE FORCE PROTECTION: Record failed attempt
-            record_failed_attempt(username);
-            let failed_count = get_failed_attempts(username);
+            record_failed_attempt(conn, username);
+            let failed_count = get_failed_attempts(conn, username);
             
-            logger::security(&format!("Failed login for username: {}. Error: {}. Failed attempts: {}", 
-                                    username, e, failed_count));
+            let reason = format!("Error: {}. Failed attempts: {}", e, failed_count);
+            logger::event(LogEvent::LoginFailed { username, reason: &reason });
             
             println!("\n{}", "╔═══════════════════════════════════════════╗".red());
             println!("{}", "║        ❌ Invalid Credentials!            ║".red().bold());
@@ -299,7 +398,52 @@ pub fn sign_in(conn: &Connection) -> Result<Option<User>> {
             println!("{}", "╚═══════════════════════════════════════════╝".red());
             println!();
             
-            return Ok(None)
+            Ok(None)
         },
     }
-}
\ No newline at end of file
+}
+
+// Make the user replace a temporary password issued by staff. Returns whether it was changed.
+fn forced_password_change(conn: &Connection, username: &str) -> bool {
+    use crate::db::validator::{validate_password, display_validation_error};
+    use dialoguer::Password;
+
+    println!("\n{}", "═══ 🔑 Password Reset Required 🔑 ═══".bright_yellow().bold());
+    println!("Your password was reset by casino staff. Choose a new password to continue.");
+
+    let password = match Password::new()
+        .with_prompt("New Password (min 12 chars)")
+        .with_confirmation("Confirm New Password", "Passwords don't match")
+        .interact() {
+            Ok(pwd) => pwd,
+            Err(_) => {
+                println!("{}", "❌ Password input cancelled".red().bold());
+                return false;
+            }
+        };
+
+    if let Err(error) = validate_password(&password) {
+        display_validation_error(&error);
+        logger::warning(&format!("Forced password change failed - invalid password for username: {}", username));
+        return false;
+    }
+
+    // The temporary password has been seen by staff, so it cannot be kept
+    if dbqueries::check_users(conn, username, &password).is_ok() {
+        println!("{}", "❌ Choose a password different from the temporary one".red().bold());
+        return false;
+    }
+
+    match dbqueries::update_user_password(conn, username, &password) {
+        Ok(_) => {
+            logger::security(&format!("Forced password change completed for username: {}", username));
+            println!("{}", "✓ Password changed".green().bold());
+            true
+        }
+        Err(e) => {
+            logger::error(&format!("Forced password change failed for username: {}: {}", username, e));
+            println!("{}", "❌ Password change failed".red().bold());
+            false
+        }
+    }
+}
@@ -1,11 +1,9 @@
 use rusqlite::Connection;
+use crate::authentication::permissions::{self, Permission};
 use crate::interfaces::user::User;
 use crate::logger::logger;
 use colored::*;
-use std::collections::HashMap;
-use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
-use lazy_static::lazy_static;
 
 // Authorization result type for cleaner error handling
 pub type AuthResult<T> = Result<T, AuthError>;
@@ -28,80 +26,51 @@ impl std::fmt::Display for AuthError {
     }
 }
 
-// Check if user has a specific role
-pub fn has_role(conn: &Connection, user: &User, required_role: &str) -> AuthResult<bool> {
-    match user.get_role(conn) {
-        Ok(role) => {
-            let has_permission = role == required_role;
-            if !has_permission {
+// Check if user's role grants a permission
+pub fn has_permission(conn: &Connection, user: &User, permission: Permission) -> AuthResult<bool> {
+    match permissions::user_has_permission(conn, user.id, permission) {
+        Ok(granted) => {
+            if !granted {
                 logger::security(&format!(
-                    "Authorization denied: User ID: {} (role: {}) attempted to access {} functionality",
-                    user.id, role, required_role
+                    "Authorization denied: User ID: {} lacks permission {}",
+                    user.id, permission.as_str()
                 ));
             }
-            Ok(has_permission)
+            Ok(granted)
         }
         Err(e) => {
             logger::error(&format!(
-                "Failed to retrieve role for User ID: {}: {}",
-                user.id, e
+                "Failed to check permission {} for User ID: {}: {}",
+                permission.as_str(), user.id, e
             ));
-            Err(AuthError::DatabaseError(format!("Could not verify user role: {}", e)))
+            Err(AuthError::DatabaseError(format!("Could not verify user permissions: {}", e)))
         }
     }
 }
 
-// Verify user is a commissioner, log and return error if not
-pub fn require_commissioner(conn: &Connection, user: &User) -> AuthResult<()> {
-    match has_role(conn, user, "commissioner")? {
+// Verify user's role grants a permission, log and return error if not
+pub fn require_permission(conn: &Connection, user: &User, permission: Permission) -> AuthResult<()> {
+    match has_permission(conn, user, permission)? {
         true => {
-            logger::security(&format!("Commissioner access granted for User ID: {}", user.id));
+            logger::security(&format!("Permission {} granted for User ID: {}", permission.as_str(), user.id));
             Ok(())
         }
         false => {
             let role = user.get_role(conn).unwrap_or_else(|_| "unknown".to_string());
             logger::security(&format!(
-                "SECURITY ALERT: User ID: {} (role: {}) attempted unauthorized commissioner access",
-                user.id, role
+                "SECURITY ALERT: User ID: {} (role: {}) attempted unauthorized access requiring {}",
+                user.id, role, permission.as_str()
             ));
             println!("\n{}", "╔═══════════════════════════════════════════╗".red());
             println!("{}", "║        🚫 ACCESS DENIED 🚫                ║".red().bold());
             println!("{}", "╠═══════════════════════════════════════════╣".red());
             println!("{}", "║  You do not have permission to access    ║".red());
             println!("{}", "║  this functionality.                     ║".red());
-            println!("{}", "║  Commissioner privileges required.        ║".red());
+            println!("{}", format!("║  Permission required: {:<20}║", permission.as_str()).red());
             println!("{}", "╚═══════════════════════════════════════════╝".red());
             println!();
             Err(AuthError::InsufficientPrivileges(
-                "Commissioner role required".to_string()
-            ))
-        }
-    }
-}
-
-// Verify user is a technician, log and return error if not
-pub fn require_technician(conn: &Connection, user: &User) -> AuthResult<()> {
-    match has_role(conn, user, "technician")? {
-        true => {
-            logger::security(&format!("Technician access granted for User ID: {}", user.id));
-            Ok(())
-        }
-        false => {
-            let role = user.get_role(conn).unwrap_or_else(|_| "unknown".to_string());
-            logger::security(&format!(
-                "SECURITY ALERT: User ID: {} (role: {}) attempted unauthorized technician access",
-                user.id, role
-            ));
-            println!("\n{}", "╔═══════════════════════════════════════════╗".red());
-            println!("{}", "║        🚫 ACCESS DENIED 🚫                ║".red().bold());
-            println!("{}", "╠═══════════════════════════════════════════╣".red());
-            println!("{}", "║  You do not have permission to access    ║".red());
-            println!("{}", "║  this functionality.                     ║".red());
-            println!("{}", "║  Technician privileges required.          ║".red());
-            println!("{}", "╚═══════════════════════════════════════════╝".red());
-            println!();
-            Err(AuthError::InsufficientPrivileges(
-                "Technician role required".to_string()
+                format!("Permission {} required", permission.as_str())
             ))
         }
     }
@@ -114,17 +83,14 @@ const MAX_FAILED_ATTEMPTS: u32 = 5;
 const LOCKOUT_DURATION_SECONDS: u64 = 900;  // 15 minutes lockout
 const ATTEMPT_WINDOW_SECONDS: u64 = 300;  // 5 minutes window for attempts
 
-// Structure to track login attempts for a user
-#[derive(Debug, Clone)]
-struct LoginAttempts {
-    failed_count: u32,
-    last_attempt_time: u64,
-    lockout_until: Option<u64>,
-}
-
-// Track login attempts per username
-lazy_static! {
-    static ref LOGIN_ATTEMPTS: Mutex<HashMap<String, LoginAttempts>> = Mutex::new(HashMap::new());
+// Failed login attempts and lockout of one username, as stored in the login_attempts table.
+// Kept in the database so lockouts survive restarts and apply to every process sharing casino.db.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoginAttempts {
+    pub username: String,
+    pub failed_count: u32,
+    pub last_attempt_time: u64,
+    pub lockout_until: Option<u64>,
 }
 
 // Get current Unix timestamp in seconds
@@ -135,101 +101,193 @@ fn current_timestamp() -> u64 {
         .as_secs()
 }
 
+fn get_login_attempts(conn: &Connection, username: &str) -> rusqlite::Result<Option<LoginAttempts>> {
+    use rusqlite::OptionalExtension;
+
+    conn.query_row(
+        "Select username, failed_count, last_attempt_time, lockout_until From login_attempts Where username = ?1",
+        [username],
+        |row| Ok(LoginAttempts {
+            username: row.get(0)?,
+            failed_count: row.get(1)?,
+            last_attempt_time: row.get(2)?,
+            lockout_until: row.get(3)?,
+        })
+    ).optional()
+}
+
 // Check if an account is currently locked out due to failed login attempts
-pub fn is_account_locked(username: &str) -> bool {
-    let mut attempts = LOGIN_ATTEMPTS.lock().unwrap();
+pub fn is_account_locked(conn: &Connection, username: &str) -> bool {
     let current_time = current_timestamp();
     
-    if let Some(user_attempts) = attempts.get(username) {
-        if let Some(lockout_until) = user_attempts.lockout_until {
+    match get_login_attempts(conn, username) {
+        Ok(Some(LoginAttempts { lockout_until: Some(lockout_until), .. })) => {
             if current_time < lockout_until {
                 let remaining = lockout_until - current_time;
                 logger::security(&format!("Account {} is locked. {} seconds remaining", username, remaining));
-                return true;
+                true
             } else {
                 // Lockout expired, clear it
                 logger::security(&format!("Lockout expired for account {}", username));
-                attempts.remove(username);
-                return false;
+                clear_login_attempts(conn, username);
+                false
             }
         }
+        Ok(_) => false,
+        Err(e) => {
+            // Fail closed: a lockout that cannot be checked is treated as in force
+            logger::error(&format!("Failed to check lockout for account {}: {}", username, e));
+            true
+        }
     }
-    
-    false
 }
 
 // Get remaining lockout time in seconds
-pub fn get_lockout_remaining(username: &str) -> Option<u64> {
-    let attempts = LOGIN_ATTEMPTS.lock().unwrap();
+pub fn get_lockout_remaining(conn: &Connection, username: &str) -> Option<u64> {
     let current_time = current_timestamp();
     
-    if let Some(user_attempts) = attempts.get(username) {
-        if let Some(lockout_until) = user_attempts.lockout_until {
-            if current_time < lockout_until {
-                return Some(lockout_until - current_time);
-            }
+    match get_login_attempts(conn, username) {
+        Ok(Some(LoginAttempts { lockout_until: Some(lockout_until), .. })) if current_time < lockout_until => {
+            Some(lockout_until - current_time)
         }
+        _ => None,
     }
-    
-    None
 }
 
 // Record a failed login attempt
-pub fn record_failed_attempt(username: &str) {
-    let mut attempts = LOGIN_ATTEMPTS.lock().unwrap();
+pub fn record_failed_attempt(conn: &Connection, username: &str) {
     let current_time = current_timestamp();
     
-    let user_attempts = attempts.entry(username.to_string())
-        .or_insert(LoginAttempts {
-            failed_count: 0,
-            last_attempt_time: current_time,
-            lockout_until: None,
-        });
-    
-    // Check if this is within the tracking window
-    if current_time - user_attempts.last_attempt_time > ATTEMPT_WINDOW_SECONDS {
-        // Outside window, reset counter
-        user_attempts.failed_count = 1;
-        user_attempts.last_attempt_time = current_time;
-        logger::info(&format!("Failed login attempt for {}: 1/{}", username, MAX_FAILED_ATTEMPTS));
-    } else {
-        // Within window, increment counter
-        user_attempts.failed_count += 1;
-        user_attempts.last_attempt_time = current_time;
-        logger::warning(&format!("Failed login attempt for {}: {}/{}", 
-                                 username, user_attempts.failed_count, MAX_FAILED_ATTEMPTS));
+    // One statement so processes sharing the database cannot lose each other's attempts.
+    // Outside the tracking window the counter starts again at 1.
+    let result = conn.execute(
+        "Insert Into login_attempts (username, failed_count, last_attempt_time) Values (?1, 1, ?2)
+        On Conflict(username) Do Update Set
+            failed_count = Case When ?2 - last_attempt_time > ?3 Then 1 Else failed_count + 1 End,
+            last_attempt_time = ?2",
+        rusqlite::params![username, current_time, ATTEMPT_WINDOW_SECONDS],
+    ).and_then(|_| conn.execute(
+        // Check if threshold exceeded
+        "Update login_attempts Set lockout_until = ?2 Where username = ?1 And failed_count >= ?3",
+        rusqlite::params![username, current_time + LOCKOUT_DURATION_SECONDS, MAX_FAILED_ATTEMPTS],
+    ));
+    if let Err(e) = result {
+        logger::error(&format!("Failed to record failed login attempt for {}: {}", username, e));
+        return;
     }
-    
-    // Check if threshold exceeded
-    if user_attempts.failed_count >= MAX_FAILED_ATTEMPTS {
-        let lockout_until = current_time + LOCKOUT_DURATION_SECONDS;
-        user_attempts.lockout_until = Some(lockout_until);
-        
+
+    let failed_count = get_failed_attempts(conn, username);
+    if failed_count >= MAX_FAILED_ATTEMPTS {
         logger::security(&format!(
             "ACCOUNT LOCKED: {} due to {} failed attempts. Duration: {} seconds ({:.1} minutes)", 
-            username, user_attempts.failed_count,
+            username, failed_count,
             LOCKOUT_DURATION_SECONDS, LOCKOUT_DURATION_SECONDS as f64 / 60.0
         ));
+    } else if failed_count > 1 {
+        logger::warning(&format!("Failed login attempt for {}: {}/{}", username, failed_count, MAX_FAILED_ATTEMPTS));
+    } else {
+        logger::info(&format!("Failed login attempt for {}: 1/{}", username, MAX_FAILED_ATTEMPTS));
     }
 }
 
 // Record a successful login attempt
-pub fn record_successful_login(username: &str) {
-    let mut attempts = LOGIN_ATTEMPTS.lock().unwrap();
-    
+pub fn record_successful_login(conn: &Connection, username: &str) {
     // Clear failed attempts on successful login
-    if attempts.remove(username).is_some() {
+    if clear_login_attempts(conn, username) {
         logger::info(&format!("Cleared failed login attempts for {}", username));
     }
 }
 
 // Get current failed attempt count for a username
-pub fn get_failed_attempts(username: &str) -> u32 {
-    let attempts = LOGIN_ATTEMPTS.lock().unwrap();
-    
-    if let Some(user_attempts) = attempts.get(username) {
-        user_attempts.failed_count
-    } else {
-        0
+pub fn get_failed_attempts(conn: &Connection, username: &str) -> u32 {
+    match get_login_attempts(conn, username) {
+        Ok(Some(user_attempts)) => user_attempts.failed_count,
+        _ => 0,
+    }
+}
+
+// Forget a username's failed attempts and lockout. Returns whether there were any.
+fn clear_login_attempts(conn: &Connection, username: &str) -> bool {
+    match conn.execute("Delete From login_attempts Where username = ?1", [username]) {
+        Ok(removed) => removed > 0,
+        Err(e) => {
+            logger::error(&format!("Failed to clear login attempts for {}: {}", username, e));
+            false
+        }
+    }
+}
+
+// Every username with failed attempts on record, locked accounts first
+pub fn get_all_login_attempts(conn: &Connection) -> rusqlite::Result<Vec<LoginAttempts>> {
+    let mut stmt = conn.prepare(
+        "Select username, failed_count, last_attempt_time, lockout_until From login_attempts
+        Order By lockout_until Is Null, lockout_until Desc, username"
+    )?;
+    let attempts = stmt.query_map([], |row| Ok(LoginAttempts {
+        username: row.get(0)?,
+        failed_count: row.get(1)?,
+        last_attempt_time: row.get(2)?,
+        lockout_until: row.get(3)?,
+    }))?.collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(attempts)
+}
+
+// Lift a lockout by hand - requires limits.manage, checked by the caller
+pub fn unlock_account(conn: &Connection, staff: &User, username: &str) -> bool {
+    let unlocked = clear_login_attempts(conn, username);
+    if unlocked {
+        logger::security(&format!("User ID: {} unlocked account {}", staff.id, username));
+    }
+    unlocked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::setup_test_db;
+
+    #[test]
+    fn test_account_locks_after_max_failed_attempts() {
+        let conn = setup_test_db();
+        for attempt in 1..MAX_FAILED_ATTEMPTS {
+            record_failed_attempt(&conn, "guesser");
+            assert_eq!(get_failed_attempts(&conn, "guesser"), attempt);
+            assert!(!is_account_locked(&conn, "guesser"));
+        }
+        record_failed_attempt(&conn, "guesser");
+        assert!(is_account_locked(&conn, "guesser"));
+        assert!(get_lockout_remaining(&conn, "guesser").unwrap() > LOCKOUT_DURATION_SECONDS - 5);
+        // Other accounts are unaffected
+        assert!(!is_account_locked(&conn, "bystander"));
+    }
+
+    #[test]
+    fn test_successful_login_clears_attempts() {
+        let conn = setup_test_db();
+        record_failed_attempt(&conn, "forgetful");
+        record_failed_attempt(&conn, "forgetful");
+        record_successful_login(&conn, "forgetful");
+        assert_eq!(get_failed_attempts(&conn, "forgetful"), 0);
+    }
+
+    #[test]
+    fn test_expired_lockout_and_manual_unlock() {
+        let conn = setup_test_db();
+        let technician = User { id: 1 };
+
+        conn.execute(
+            "Insert Into login_attempts (username, failed_count, last_attempt_time, lockout_until) Values ('expired', 5, 0, 1)",
+            [],
+        ).unwrap();
+        assert!(!is_account_locked(&conn, "expired"));
+        assert_eq!(get_failed_attempts(&conn, "expired"), 0);
+
+        for _ in 0..MAX_FAILED_ATTEMPTS {
+            record_failed_attempt(&conn, "locked");
+        }
+        assert_eq!(get_all_login_attempts(&conn).unwrap()[0].username, "locked");
+        assert!(unlock_account(&conn, &technician, "locked"));
+        assert!(!is_account_locked(&conn, "locked"));
+        assert!(!unlock_account(&conn, &technician, "locked"));
     }
 }
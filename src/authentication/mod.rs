@@ -1,2 +1,4 @@
 pub mod auth;
-pub mod authorization;
\ No newline at end of file
+pub mod authorization;
+pub mod permissions;
+pub mod session;
@@ -0,0 +1,366 @@
+use rusqlite::Connection;
+
+use crate::db::user_admin;
+use crate::interfaces::user::User;
+use crate::logger::logger;
+
+// SECURITY: What an account may do is decided by the permissions of its role, never by the role's name.
+// Permissions are fixed by the code that checks them, roles are stored in the database so a
+// commissioner can add custom ones such as an auditor or a floor manager.
+
+// Something a role can be allowed to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    // Play the casino games
+    GamesPlay,
+    // Enable and disable games
+    GamesToggle,
+    // View game statistics
+    GamesStats,
+    // View and verify security logs
+    LogsView,
+    // Reconcile balances with the ledger
+    LedgerReconcile,
+    // Change transaction limits, lift lockouts and rate limits
+    LimitsManage,
+    // Replay recorded rounds
+    RoundsReplay,
+    // Search accounts, suspend them and reset passwords
+    UsersManage,
+    // Assign roles, administer staff accounts and edit role permissions
+    RolesManage,
+    // Change symbol weights, payouts, wheel segments and RTP targets
+    PaytableEdit,
+    // Run fairness simulations and the RNG test battery
+    RngTest,
+    // View RTP, probability and RNG test reports
+    ReportsView,
+    // Rotate the database encryption key
+    KeysRotate,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 13] = [
+        Permission::GamesPlay,
+        Permission::GamesToggle,
+        Permission::GamesStats,
+        Permission::LogsView,
+        Permission::LedgerReconcile,
+        Permission::LimitsManage,
+        Permission::RoundsReplay,
+        Permission::UsersManage,
+        Permission::RolesManage,
+        Permission::PaytableEdit,
+        Permission::RngTest,
+        Permission::ReportsView,
+        Permission::KeysRotate,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::GamesPlay => "games.play",
+            Permission::GamesToggle => "games.toggle",
+            Permission::GamesStats => "games.stats",
+            Permission::LogsView => "logs.view",
+            Permission::LedgerReconcile => "ledger.reconcile",
+            Permission::LimitsManage => "limits.manage",
+            Permission::RoundsReplay => "rounds.replay",
+            Permission::UsersManage => "users.manage",
+            Permission::RolesManage => "roles.manage",
+            Permission::PaytableEdit => "paytable.edit",
+            Permission::RngTest => "rng.test",
+            Permission::ReportsView => "reports.view",
+            Permission::KeysRotate => "keys.rotate",
+        }
+    }
+}
+
+// A role and what it allows
+#[derive(Debug, Clone, PartialEq)]
+pub struct Role {
+    pub name: String,
+    pub description: String,
+    // Built-in roles keep their permissions so the casino always has players and administrators
+    pub builtin: bool,
+    pub permissions: Vec<Permission>,
+}
+
+// Roles created at initialization: name, description, built-in, permissions
+const DEFAULT_ROLES: [(&str, &str, bool, &[Permission]); 5] = [
+    ("user", "Player", true, &[Permission::GamesPlay]),
+    ("technician", "Machine technician", true, &[
+        Permission::GamesToggle,
+        Permission::GamesStats,
+        Permission::LogsView,
+        Permission::LedgerReconcile,
+        Permission::LimitsManage,
+        Permission::RoundsReplay,
+        Permission::UsersManage,
+        Permission::KeysRotate,
+    ]),
+    ("commissioner", "Gaming commissioner", true, &[
+        Permission::PaytableEdit,
+        Permission::RngTest,
+        Permission::ReportsView,
+        Permission::RoundsReplay,
+        Permission::UsersManage,
+        Permission::RolesManage,
+        Permission::KeysRotate,
+    ]),
+    ("auditor", "Read-only access to logs and reports", false, &[
+        Permission::GamesStats,
+        Permission::LogsView,
+        Permission::ReportsView,
+        Permission::RoundsReplay,
+    ]),
+    ("floor_manager", "Runs the casino floor", false, &[
+        Permission::GamesToggle,
+        Permission::GamesStats,
+        Permission::LogsView,
+        Permission::LimitsManage,
+        Permission::UsersManage,
+    ]),
+];
+
+// Add the default roles that do not exist yet. Permissions are only given to custom roles created
+// here, so changes made to them are kept. Built-in roles cannot be edited and get permissions added
+// by later versions.
+pub fn add_default_roles(conn: &Connection) -> rusqlite::Result<()> {
+    for (name, description, builtin, permissions) in DEFAULT_ROLES {
+        let created = conn.execute(
+            "Insert Or Ignore Into roles (name, description, builtin) Values (?1, ?2, ?3)",
+            rusqlite::params![name, description, builtin],
+        )?;
+        if created == 0 && !builtin {
+            continue;
+        }
+        for permission in permissions {
+            conn.execute(
+                "Insert Or Ignore Into role_permissions (role, permission) Values (?1, ?2)",
+                [name, permission.as_str()],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// Whether a user's role grants a permission
+pub fn user_has_permission(conn: &Connection, user_id: i32, permission: Permission) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "Select Exists(
+            Select 1 From users u Join role_permissions p On p.role = u.role
+            Where u.id = ?1 And p.permission = ?2
+        )",
+        rusqlite::params![user_id, permission.as_str()],
+        |row| row.get(0)
+    )
+}
+
+// Staff roles grant anything beyond playing
+pub fn is_staff_role(conn: &Connection, role: &str) -> rusqlite::Result<bool> {
+    let role = get_role(conn, role)?;
+    Ok(role.permissions.iter().any(|permission| *permission != Permission::GamesPlay))
+}
+
+// The menu options a user's role allows. Options that cannot be checked are left out.
+pub fn permitted_options<'a>(conn: &Connection, user: &User, options: &[(&'a str, Permission)]) -> Vec<&'a str> {
+    options.iter()
+        .filter(|(_, permission)| user_has_permission(conn, user.id, *permission).unwrap_or(false))
+        .map(|(label, _)| *label)
+        .collect()
+}
+
+pub fn get_role(conn: &Connection, name: &str) -> rusqlite::Result<Role> {
+    let (description, builtin) = conn.query_row(
+        "Select description, builtin From roles Where name = ?1",
+        [name],
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?))
+    )?;
+    let mut stmt = conn.prepare("Select permission From role_permissions Where role = ?1")?;
+    let granted = stmt.query_map([name], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    // Listed in the order of Permission::ALL, permissions no longer known to the code are ignored
+    let permissions = Permission::ALL.into_iter()
+        .filter(|permission| granted.iter().any(|name| name == permission.as_str()))
+        .collect();
+    Ok(Role { name: name.to_string(), description, builtin, permissions })
+}
+
+// Every role, built-in roles first
+pub fn get_roles(conn: &Connection) -> rusqlite::Result<Vec<Role>> {
+    let mut stmt = conn.prepare("Select name From roles Order By builtin Desc, name")?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    names.iter().map(|name| get_role(conn, name)).collect()
+}
+
+// SECURITY: Role management needs roles.manage, and built-in roles cannot be changed
+fn authorize_role_change(conn: &Connection, admin: &User, role: Option<&Role>) -> rusqlite::Result<()> {
+    if crate::authentication::authorization::require_permission(conn, admin, Permission::RolesManage).is_err() {
+        return Err(rusqlite::Error::InvalidParameterName("Permission roles.manage required".to_string()));
+    }
+    if let Some(role) = role {
+        if role.builtin {
+            logger::security(&format!("SECURITY ALERT: User ID: {} attempted to change built-in role {}", admin.id, role.name));
+            return Err(rusqlite::Error::InvalidParameterName(format!("The built-in role {} cannot be changed", role.name)));
+        }
+    }
+    Ok(())
+}
+
+/// Create a custom role without permissions - REQUIRES roles.manage
+pub fn create_role(conn: &Connection, admin: &User, name: &str, description: &str) -> rusqlite::Result<()> {
+    authorize_role_change(conn, admin, None)?;
+    let valid_name = (3..=32).contains(&name.len())
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !valid_name {
+        return Err(rusqlite::Error::InvalidParameterName(
+            "Role names are 3 to 32 lowercase letters, digits or underscores".to_string()
+        ));
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "Insert Into roles (name, description, builtin) Values (?1, ?2, 0)",
+        [name, description],
+    )?;
+    user_admin::record_admin_action(&tx, admin, None, "create_role", name)?;
+    tx.commit()
+}
+
+/// Grant or revoke one permission of a custom role - REQUIRES roles.manage
+pub fn set_role_permission(conn: &Connection, admin: &User, role: &str, permission: Permission, granted: bool) -> rusqlite::Result<()> {
+    let role = get_role(conn, role)?;
+    authorize_role_change(conn, admin, Some(&role))?;
+
+    let tx = conn.unchecked_transaction()?;
+    if granted {
+        tx.execute(
+            "Insert Or Ignore Into role_permissions (role, permission) Values (?1, ?2)",
+            [role.name.as_str(), permission.as_str()],
+        )?;
+    } else {
+        tx.execute(
+            "Delete From role_permissions Where role = ?1 And permission = ?2",
+            [role.name.as_str(), permission.as_str()],
+        )?;
+    }
+    let action = if granted { "grant_permission" } else { "revoke_permission" };
+    user_admin::record_admin_action(&tx, admin, None, action, &format!("{} {}", role.name, permission.as_str()))?;
+    tx.commit()
+}
+
+/// Delete a custom role no account holds - REQUIRES roles.manage
+pub fn delete_role(conn: &Connection, admin: &User, role: &str) -> rusqlite::Result<()> {
+    let role = get_role(conn, role)?;
+    authorize_role_change(conn, admin, Some(&role))?;
+    let holders: i64 = conn.query_row("Select Count(*) From users Where role = ?1", [&role.name], |row| row.get(0))?;
+    if holders > 0 {
+        return Err(rusqlite::Error::InvalidParameterName(format!("{} account(s) still have the role {}", holders, role.name)));
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("Delete From role_permissions Where role = ?1", [&role.name])?;
+    tx.execute("Delete From roles Where name = ?1", [&role.name])?;
+    user_admin::record_admin_action(&tx, admin, None, "delete_role", &role.name)?;
+    tx.commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::dbqueries;
+    use crate::db::test_support::{create_account, setup_test_db};
+
+    #[test]
+    fn test_default_roles_grant_their_permissions() {
+        let conn = setup_test_db();
+        let auditor = create_account(&conn, "auditor_one", "auditor");
+        let player = create_account(&conn, "player_one", "user");
+
+        assert!(user_has_permission(&conn, auditor.id, Permission::LogsView).unwrap());
+        assert!(user_has_permission(&conn, auditor.id, Permission::ReportsView).unwrap());
+        // Auditors only read
+        assert!(!user_has_permission(&conn, auditor.id, Permission::GamesToggle).unwrap());
+        assert!(!user_has_permission(&conn, auditor.id, Permission::PaytableEdit).unwrap());
+        assert!(user_has_permission(&conn, player.id, Permission::GamesPlay).unwrap());
+        assert!(!user_has_permission(&conn, player.id, Permission::LogsView).unwrap());
+
+        // Reinitializing does not give back permissions taken from an editable role
+        let admin = User { id: dbqueries::get_user_id_by_username(&conn, "commissioner").unwrap() };
+        set_role_permission(&conn, &admin, "auditor", Permission::RoundsReplay, false).unwrap();
+        crate::db::dbinitialize::initialize_dbs(&conn).unwrap();
+        assert!(!user_has_permission(&conn, auditor.id, Permission::RoundsReplay).unwrap());
+    }
+
+    #[test]
+    fn test_custom_roles() {
+        let conn = setup_test_db();
+        let admin = User { id: dbqueries::get_user_id_by_username(&conn, "commissioner").unwrap() };
+
+        create_role(&conn, &admin, "cashier", "Handles payouts").unwrap();
+        assert!(create_role(&conn, &admin, "Bad Name", "").is_err());
+        set_role_permission(&conn, &admin, "cashier", Permission::LedgerReconcile, true).unwrap();
+        assert_eq!(get_role(&conn, "cashier").unwrap().permissions, vec![Permission::LedgerReconcile]);
+
+        let cashier = create_account(&conn, "cashier_one", "cashier");
+        assert!(user_has_permission(&conn, cashier.id, Permission::LedgerReconcile).unwrap());
+        // Roles in use cannot be deleted, and accounts cannot be given roles that do not exist
+        assert!(delete_role(&conn, &admin, "cashier").is_err());
+        assert!(conn.execute("Update users Set role = 'ghost' Where id = ?1", [cashier.id]).is_err());
+
+        conn.execute("Update users Set role = 'user' Where id = ?1", [cashier.id]).unwrap();
+        delete_role(&conn, &admin, "cashier").unwrap();
+        assert!(get_role(&conn, "cashier").is_err());
+    }
+
+    #[test]
+    fn test_only_role_managers_change_custom_roles() {
+        let conn = setup_test_db();
+        let admin = User { id: dbqueries::get_user_id_by_username(&conn, "commissioner").unwrap() };
+        let technician = create_account(&conn, "tech_one", "technician");
+
+        assert!(set_role_permission(&conn, &technician, "auditor", Permission::PaytableEdit, true).is_err());
+        assert!(create_role(&conn, &technician, "helpers", "").is_err());
+        // Built-in roles are fixed, even for commissioners
+        assert!(set_role_permission(&conn, &admin, "technician", Permission::PaytableEdit, true).is_err());
+        assert!(delete_role(&conn, &admin, "user").is_err());
+        assert!(!user_has_permission(&conn, technician.id, Permission::PaytableEdit).unwrap());
+    }
+
+    #[test]
+    fn test_users_table_with_fixed_roles_migrates() {
+        use crate::cryptography::crypto::encrypt_secret;
+
+        crate::cryptography::crypto::initialize_encryption_key();
+        let conn = Connection::open_in_memory().unwrap();
+        // The users table of earlier versions, and a table referencing it
+        conn.execute_batch(
+            "Create Table users (
+                id Integer Primary Key, username Text Unique Not Null, password Text Not Null,
+                balance Text Not Null Default '0.0',
+                role Text Default 'user' Check(role In ('user', 'technician', 'commissioner'))
+            );
+            Create Table transaction_log (
+                id Integer Primary Key, user_id Integer Not Null, category Text Not Null,
+                amount_cents Integer Not Null, created_at Integer Not Null,
+                Foreign Key (user_id) References users(id)
+            );"
+        ).unwrap();
+        conn.execute(
+            "Insert Into users (username, password, balance, role) Values ('veteran', 'hash', ?1, 'technician')",
+            [encrypt_secret("0").unwrap()]
+        ).unwrap();
+        conn.execute("Insert Into transaction_log (user_id, category, amount_cents, created_at) Values (1, 'cash', 100, 0)", []).unwrap();
+
+        crate::db::dbinitialize::initialize_dbs(&conn).unwrap();
+
+        let veteran = User { id: dbqueries::get_user_id_by_username(&conn, "veteran").unwrap() };
+        assert!(user_has_permission(&conn, veteran.id, Permission::GamesToggle).unwrap());
+        conn.execute("Update users Set role = 'auditor' Where id = ?1", [veteran.id]).unwrap();
+        // References to users survive the copy and are still enforced
+        let sql: String = conn.query_row("Select sql From sqlite_master Where name = 'transaction_log'", [], |row| row.get(0)).unwrap();
+        assert!(sql.contains("References users(id)"));
+        assert!(conn.execute("Insert Into transaction_log (user_id, category, amount_cents, created_at) Values (999, 'cash', 1, 0)", []).is_err());
+    }
+}
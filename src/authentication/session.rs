@@ -0,0 +1,357 @@
+use rusqlite::{Connection, OptionalExtension};
+use colored::*;
+
+use crate::authentication::authorization;
+use crate::authentication::permissions::{self, Permission};
+use crate::db::dbqueries;
+use crate::db::money::Money;
+use crate::db::user_admin;
+use crate::interfaces::user::User;
+use crate::logger::logger;
+
+// SECURITY: Sessions of signed in users.
+// A session starts when sign in or registration succeeds and ends at logout, or once the user has
+// been idle for longer than their role's timeout so a terminal left alone does not stay signed in.
+// Sessions are kept in the sessions table, so every process sharing casino.db sees the same state.
+// Sensitive actions additionally ask for the password again (step-up re-authentication).
+
+const STAFF_IDLE_TIMEOUT_POLICY: &str = "session_idle_timeout_staff";
+const PLAYER_IDLE_TIMEOUT_POLICY: &str = "session_idle_timeout_player";
+const STEP_UP_WITHDRAWAL_POLICY: &str = "step_up_withdrawal_cents";
+
+// Shortest idle timeout that can be configured, anything less logs users out mid-action
+pub const MIN_IDLE_TIMEOUT_SECONDS: u32 = 60;
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+// Idle timeouts and step-up threshold as configured by the commissioner
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SessionPolicy {
+    // Seconds a session of a role granting more than play may be idle
+    pub staff_idle_timeout: u32,
+    // Seconds a player session may be idle
+    pub player_idle_timeout: u32,
+    // Withdrawals above this amount need the password again. Zero asks for every withdrawal.
+    pub step_up_withdrawal: Money,
+}
+
+impl Default for SessionPolicy {
+    fn default() -> Self {
+        SessionPolicy {
+            staff_idle_timeout: 300,
+            player_idle_timeout: 900,
+            step_up_withdrawal: Money::from_dollars(500),
+        }
+    }
+}
+
+// A signed in user's session
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub id: i64,
+    pub user_id: i32,
+    // Unix times in seconds
+    pub created_at: i64,
+    pub last_activity: i64,
+    pub idle_timeout: u32,
+}
+
+impl Session {
+    pub fn is_expired(&self, now: i64) -> bool {
+        now - self.last_activity > self.idle_timeout as i64
+    }
+}
+
+fn get_policy_value(conn: &Connection, name: &str) -> rusqlite::Result<i64> {
+    conn.query_row("Select value From security_policies Where name = ?1", [name], |row| row.get(0))
+}
+
+pub fn get_policy(conn: &Connection) -> rusqlite::Result<SessionPolicy> {
+    Ok(SessionPolicy {
+        staff_idle_timeout: get_policy_value(conn, STAFF_IDLE_TIMEOUT_POLICY)? as u32,
+        player_idle_timeout: get_policy_value(conn, PLAYER_IDLE_TIMEOUT_POLICY)? as u32,
+        step_up_withdrawal: Money::from_cents(get_policy_value(conn, STEP_UP_WITHDRAWAL_POLICY)?),
+    })
+}
+
+/// Change the idle timeouts and step-up threshold - REQUIRES roles.manage
+pub fn set_policy(conn: &Connection, admin: &User, policy: &SessionPolicy) -> rusqlite::Result<()> {
+    if authorization::require_permission(conn, admin, Permission::RolesManage).is_err() {
+        return Err(rusqlite::Error::InvalidParameterName("Permission roles.manage required".to_string()));
+    }
+    if policy.staff_idle_timeout < MIN_IDLE_TIMEOUT_SECONDS || policy.player_idle_timeout < MIN_IDLE_TIMEOUT_SECONDS {
+        return Err(rusqlite::Error::InvalidParameterName(format!("Idle timeouts must be at least {} seconds", MIN_IDLE_TIMEOUT_SECONDS)));
+    }
+    if policy.step_up_withdrawal.is_negative() {
+        return Err(rusqlite::Error::InvalidParameterName("The step-up threshold cannot be negative".to_string()));
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    for (name, value) in [
+        (STAFF_IDLE_TIMEOUT_POLICY, policy.staff_idle_timeout as i64),
+        (PLAYER_IDLE_TIMEOUT_POLICY, policy.player_idle_timeout as i64),
+        (STEP_UP_WITHDRAWAL_POLICY, policy.step_up_withdrawal.cents()),
+    ] {
+        tx.execute("Update security_policies Set value = ?2 Where name = ?1", rusqlite::params![name, value])?;
+    }
+    let details = format!(
+        "staff idle {}s, player idle {}s, step-up above ${}",
+        policy.staff_idle_timeout, policy.player_idle_timeout, policy.step_up_withdrawal
+    );
+    user_admin::record_admin_action(&tx, admin, None, "session_policy", &details)?;
+    tx.commit()?;
+    logger::security(&format!("Session policy set to {} by User ID: {}", details, admin.id));
+    Ok(())
+}
+
+// Add the default session policies that are missing
+pub fn add_default_policies(conn: &Connection) -> rusqlite::Result<()> {
+    let defaults = SessionPolicy::default();
+    for (name, value) in [
+        (STAFF_IDLE_TIMEOUT_POLICY, defaults.staff_idle_timeout as i64),
+        (PLAYER_IDLE_TIMEOUT_POLICY, defaults.player_idle_timeout as i64),
+        (STEP_UP_WITHDRAWAL_POLICY, defaults.step_up_withdrawal.cents()),
+    ] {
+        conn.execute(
+            "Insert Or Ignore Into security_policies (name, value) Values (?1, ?2)",
+            rusqlite::params![name, value],
+        )?;
+    }
+    Ok(())
+}
+
+// Idle timeout of a user's sessions, staff get the shorter one
+pub fn idle_timeout(conn: &Connection, user_id: i32) -> rusqlite::Result<u32> {
+    let policy = get_policy(conn)?;
+    let role: String = conn.query_row("Select role From users Where id = ?1", [user_id], |row| row.get(0))?;
+    if permissions::is_staff_role(conn, &role)? {
+        Ok(policy.staff_idle_timeout)
+    } else {
+        Ok(policy.player_idle_timeout)
+    }
+}
+
+/// Start a session for a user who just signed in. Ends any session they still had open.
+pub fn start(conn: &Connection, user: &User) -> rusqlite::Result<Session> {
+    let idle_timeout = idle_timeout(conn, user.id)?;
+    let created_at = now();
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "Update sessions Set ended_at = ?2, end_reason = 'replaced' Where user_id = ?1 And ended_at Is Null",
+        rusqlite::params![user.id, created_at],
+    )?;
+    tx.execute(
+        "Insert Into sessions (user_id, created_at, last_activity) Values (?1, ?2, ?2)",
+        rusqlite::params![user.id, created_at],
+    )?;
+    let id = tx.last_insert_rowid();
+    tx.commit()?;
+
+    logger::security(&format!("Session {} started for User ID: {} (idle timeout {}s)", id, user.id, idle_timeout));
+    Ok(Session { id, user_id: user.id, created_at, last_activity: created_at, idle_timeout })
+}
+
+/// The session a user has open, if any, expired or not
+pub fn current(conn: &Connection, user_id: i32) -> rusqlite::Result<Option<Session>> {
+    let session = conn.query_row(
+        "Select id, created_at, last_activity From sessions Where user_id = ?1 And ended_at Is Null
+        Order By id Desc Limit 1",
+        [user_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    ).optional()?;
+    let Some((id, created_at, last_activity)) = session else {
+        return Ok(None);
+    };
+    // The timeout follows the current policy and role, so changes apply to open sessions
+    let idle_timeout = idle_timeout(conn, user_id)?;
+    Ok(Some(Session { id, user_id, created_at, last_activity, idle_timeout }))
+}
+
+/// End a user's open session, for example at logout
+pub fn end(conn: &Connection, user: &User, reason: &str) -> rusqlite::Result<()> {
+    let ended = conn.execute(
+        "Update sessions Set ended_at = ?2, end_reason = ?3 Where user_id = ?1 And ended_at Is Null",
+        rusqlite::params![user.id, now(), reason],
+    )?;
+    if ended > 0 {
+        logger::security(&format!("Session ended for User ID: {} ({})", user.id, reason));
+    }
+    Ok(())
+}
+
+// Check the user's session at a Unix time and, if `refresh`, record activity.
+// Expired sessions are ended here. Fails closed: no session or a database error is not active.
+fn check_at(conn: &Connection, user: &User, at: i64, refresh: bool) -> bool {
+    let session = match current(conn, user.id) {
+        Ok(Some(session)) => session,
+        Ok(None) => return false,
+        Err(e) => {
+            logger::error(&format!("Failed to check session of User ID: {}: {}", user.id, e));
+            return false;
+        }
+    };
+
+    if session.is_expired(at) {
+        let idle = at - session.last_activity;
+        if let Err(e) = end(conn, user, "idle_timeout") {
+            logger::error(&format!("Failed to end expired session of User ID: {}: {}", user.id, e));
+        }
+        logger::security(&format!(
+            "Automatic logout of User ID: {} after {}s idle (timeout {}s)",
+            user.id, idle, session.idle_timeout
+        ));
+        println!("\n{}", "╔═══════════════════════════════════════════╗".yellow());
+        println!("{}", "║        ⏰ SESSION EXPIRED ⏰              ║".yellow().bold());
+        println!("{}", "╠═══════════════════════════════════════════╣".yellow());
+        println!("{}", "║  You were logged out after being idle.    ║".yellow());
+        println!("{}", "║  Please sign in again.                    ║".yellow());
+        println!("{}", "╚═══════════════════════════════════════════╝".yellow());
+        println!();
+        return false;
+    }
+
+    if refresh {
+        if let Err(e) = conn.execute("Update sessions Set last_activity = ?2 Where id = ?1", rusqlite::params![session.id, at]) {
+            logger::error(&format!("Failed to record activity of User ID: {}: {}", user.id, e));
+            return false;
+        }
+    }
+    true
+}
+
+/// Whether the user's session is still open and not idle for too long
+pub fn is_active(conn: &Connection, user: &User) -> bool {
+    check_at(conn, user, now(), false)
+}
+
+/// Record activity on the user's session. Returns false, after logging the user out, when the
+/// session had already been idle for too long or was ended.
+pub fn touch(conn: &Connection, user: &User) -> bool {
+    check_at(conn, user, now(), true)
+}
+
+// Whether a withdrawal is large enough to need the password again
+pub fn withdrawal_needs_step_up(conn: &Connection, amount: Money) -> bool {
+    match get_policy(conn) {
+        Ok(policy) => amount > policy.step_up_withdrawal,
+        Err(e) => {
+            // Fail closed
+            logger::error(&format!("Failed to load step-up threshold: {}", e));
+            true
+        }
+    }
+}
+
+/// Ask for the password again before a sensitive action. Failures count towards the login lockout,
+/// and a lockout ends the session.
+pub fn reauthenticate(conn: &Connection, user: &User, action: &str) -> bool {
+    use dialoguer::Password;
+
+    let username = match user.get_username(conn) {
+        Ok(username) => username,
+        Err(e) => {
+            logger::error(&format!("Failed to load username of User ID: {} for re-authentication: {}", user.id, e));
+            return false;
+        }
+    };
+
+    println!("\n{}", format!("🔐 Confirm your password to {}", action).bright_yellow().bold());
+    let password = match Password::new().with_prompt("Password").interact() {
+        Ok(pwd) => pwd,
+        Err(_) => {
+            println!("{}", "❌ Password input cancelled".red().bold());
+            return false;
+        }
+    };
+
+    match dbqueries::check_users(conn, &username, &password) {
+        Ok(id) if id == user.id => {
+            logger::security(&format!("User ID: {} re-authenticated to {}", user.id, action));
+            touch(conn, user)
+        }
+        _ => {
+            authorization::record_failed_attempt(conn, &username);
+            logger::security(&format!("SECURITY ALERT: Failed re-authentication by User ID: {} to {}", user.id, action));
+            println!("{}", "❌ Incorrect password".red().bold());
+            if authorization::is_account_locked(conn, &username) {
+                if let Err(e) = end(conn, user, "reauthentication_failed") {
+                    logger::error(&format!("Failed to end session of User ID: {}: {}", user.id, e));
+                }
+                println!("{}", "🔒 Too many failed attempts, you have been logged out".red().bold());
+            }
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{create_account, setup_test_db};
+
+    #[test]
+    fn test_idle_session_expires() {
+        let conn = setup_test_db();
+        let player = create_account(&conn, "idle_player", "user");
+        let session = start(&conn, &player).unwrap();
+        assert_eq!(session.idle_timeout, 900);
+        assert!(touch(&conn, &player));
+
+        // Still within the timeout
+        assert!(check_at(&conn, &player, session.last_activity + 900, true));
+        assert!(is_active(&conn, &player));
+
+        // Idle for longer than the timeout
+        let last_activity = current(&conn, player.id).unwrap().unwrap().last_activity;
+        assert!(!check_at(&conn, &player, last_activity + 901, true));
+        assert!(current(&conn, player.id).unwrap().is_none());
+        assert!(!touch(&conn, &player));
+
+        let reason: String = conn.query_row("Select end_reason From sessions Where id = ?1", [session.id], |row| row.get(0)).unwrap();
+        assert_eq!(reason, "idle_timeout");
+    }
+
+    #[test]
+    fn test_staff_get_the_shorter_timeout() {
+        let conn = setup_test_db();
+        let technician = create_account(&conn, "idle_tech", "technician");
+        let session = start(&conn, &technician).unwrap();
+        assert_eq!(session.idle_timeout, 300);
+        assert!(!check_at(&conn, &technician, session.last_activity + 301, false));
+    }
+
+    #[test]
+    fn test_new_session_replaces_old_and_logout_ends_it() {
+        let conn = setup_test_db();
+        let player = create_account(&conn, "two_terminals", "user");
+        let first = start(&conn, &player).unwrap();
+        let second = start(&conn, &player).unwrap();
+        assert_eq!(current(&conn, player.id).unwrap().unwrap().id, second.id);
+        let reason: String = conn.query_row("Select end_reason From sessions Where id = ?1", [first.id], |row| row.get(0)).unwrap();
+        assert_eq!(reason, "replaced");
+
+        end(&conn, &player, "logout").unwrap();
+        assert!(!is_active(&conn, &player));
+    }
+
+    #[test]
+    fn test_session_policy() {
+        let conn = setup_test_db();
+        let admin = User { id: dbqueries::get_user_id_by_username(&conn, "commissioner").unwrap() };
+        let technician = create_account(&conn, "policy_tech", "technician");
+        assert_eq!(get_policy(&conn).unwrap(), SessionPolicy::default());
+        assert!(!withdrawal_needs_step_up(&conn, Money::from_dollars(500)));
+        assert!(withdrawal_needs_step_up(&conn, Money::from_cents(50_001)));
+
+        let policy = SessionPolicy { staff_idle_timeout: 120, player_idle_timeout: 600, step_up_withdrawal: Money::from_dollars(100) };
+        assert!(set_policy(&conn, &technician, &policy).is_err());
+        assert!(set_policy(&conn, &admin, &SessionPolicy { staff_idle_timeout: 10, ..policy }).is_err());
+        set_policy(&conn, &admin, &policy).unwrap();
+        assert_eq!(get_policy(&conn).unwrap(), policy);
+        assert_eq!(idle_timeout(&conn, technician.id).unwrap(), 120);
+        assert!(withdrawal_needs_step_up(&conn, Money::from_dollars(101)));
+    }
+}
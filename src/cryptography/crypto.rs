@@ -6,12 +6,17 @@ use argon2::{
     Argon2, Algorithm, Version, Params
 };
 use aes_gcm::{
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
     Aes256Gcm
 };
 use base64::{Engine as _, engine::general_purpose};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use crate::cryptography::keyfile::{self, StoredKeys};
+use crate::db::money::Money;
 use crate::logger::logger;
-use std::sync::OnceLock;
+use std::collections::BTreeMap;
+use std::sync::{OnceLock, RwLock};
 use std::fs;
 use std::path::Path;
 use std::env;
@@ -108,9 +113,10 @@ pub fn generate_encryption_key() -> Vec<u8> {
     key.to_vec()
 }
 
-/// Encrypt sensitive data using AES-256-GCM authenticated encryption
+/// Encrypt sensitive data using AES-256-GCM authenticated encryption, bound to associated data:
+/// decryption fails unless the same associated data is given
 /// Returns generic error messages to prevent information disclosure
-pub fn encrypt_data(data: &str, key: &[u8]) -> Result<String, String> {
+pub fn encrypt_data_with_aad(data: &str, key: &[u8], aad: &[u8]) -> Result<String, String> {
     // Generate unique nonce
     let mut nonce_bytes = [0u8; NONCE_SIZE];
     OsRng.fill_bytes(&mut nonce_bytes);
@@ -125,7 +131,7 @@ pub fn encrypt_data(data: &str, key: &[u8]) -> Result<String, String> {
     };
     
     // Encrypt (nonce_bytes.as_ref() gives us &[u8] which coerces to Nonce)
-    let ciphertext = match cipher.encrypt(nonce_bytes.as_ref().into(), data.as_bytes().as_ref()) {
+    let ciphertext = match cipher.encrypt(nonce_bytes.as_ref().into(), Payload { msg: data.as_bytes(), aad }) {
         Ok(c) => c,
         Err(_) => {
             logger::error("Encryption operation failed");
@@ -141,9 +147,9 @@ pub fn encrypt_data(data: &str, key: &[u8]) -> Result<String, String> {
     Ok(general_purpose::STANDARD.encode(result))
 }
 
-/// Decrypt data that was encrypted with `encrypt_data()`
+/// Decrypt data that was encrypted with `encrypt_data_with_aad()` and the same associated data
 /// Returns generic error messages to prevent information disclosure
-pub fn decrypt_data(encrypted_data: &str, key: &[u8]) -> Result<String, String> {
+pub fn decrypt_data_with_aad(encrypted_data: &str, key: &[u8], aad: &[u8]) -> Result<String, String> {
     // Decode from base64
     let decoded = match general_purpose::STANDARD.decode(encrypted_data) {
         Ok(d) => d,
@@ -172,7 +178,7 @@ pub fn decrypt_data(encrypted_data: &str, key: &[u8]) -> Result<String, String>
     };
     
     // Decrypt and verify (slice automatically coerces to Nonce)
-    let plaintext = match cipher.decrypt((&decoded[..NONCE_SIZE]).into(), ciphertext.as_ref()) {
+    let plaintext = match cipher.decrypt((&decoded[..NONCE_SIZE]).into(), Payload { msg: ciphertext, aad }) {
         Ok(p) => p,
         Err(_) => {
             logger::error("Decryption or authentication failed");
@@ -192,24 +198,72 @@ pub fn decrypt_data(encrypted_data: &str, key: &[u8]) -> Result<String, String>
 
 // ==================== Encryption Key Management ====================
 
-// Global encryption key (initialized once at startup)
-static ENCRYPTION_KEY: OnceLock<Vec<u8>> = OnceLock::new();
+// SECURITY: Keys are versioned so they can be rotated. Every ciphertext starts with the id of the key
+// it was made with ("k2:..."), new data is encrypted with the active key and older keys are kept to
+// decrypt what was encrypted before a rotation. Ciphertexts without an id predate versioning and
+// were made with key 1, the original CASINO_ENCRYPTION_KEY.
+struct Keyring {
+    active: u32,
+    keys: BTreeMap<u32, Vec<u8>>,
+    // Hash chains the log. Kept apart from the data keys, so any of those can be retired.
+    chain_key: Vec<u8>,
+    storage: KeyStorage,
+}
+
+// Where the keys are kept between runs
+enum KeyStorage {
+    // Hex keys in .env, the fallback when there is no key file
+    Env,
+    // Passphrase protected key file
+    KeyFile(keyfile::Unlocked),
+}
+
+impl Keyring {
+    fn stored_keys(&self) -> StoredKeys {
+        StoredKeys { active: self.active, keys: self.keys.clone(), chain_key: Some(self.chain_key.clone()) }
+    }
+}
+
+// Global keyring (initialized once at startup, extended by rotation)
+static KEYRING: OnceLock<RwLock<Keyring>> = OnceLock::new();
+
+#[cfg(test)]
+thread_local! {
+    // Keyring of the current test, set by isolate_keyring, used instead of the global one
+    static TEST_KEYRING: std::cell::Cell<Option<&'static RwLock<Keyring>>> = const { std::cell::Cell::new(None) };
+}
 
 // Environment configuration
-const ENV_FILE_PATH: &str = ".env";
+pub const ENV_FILE_PATH: &str = ".env";
+// Key 1, later keys are CASINO_ENCRYPTION_KEY_2, CASINO_ENCRYPTION_KEY_3, ...
 const ENV_KEY_NAME: &str = "CASINO_ENCRYPTION_KEY";
+// Id of the key new data is encrypted with
+const ENV_ACTIVE_KEY_NAME: &str = "CASINO_ENCRYPTION_KEY_ID";
+const FIRST_KEY_ID: u32 = 1;
+// Key the log is hash chained with
+const ENV_CHAIN_KEY_NAME: &str = "CASINO_LOG_CHAIN_KEY";
+// Passphrase protected key file, used instead of .env when it exists
+pub const KEY_FILE_PATH: &str = "casino.keys";
+
+fn env_key_name(id: u32) -> String {
+    if id == FIRST_KEY_ID {
+        ENV_KEY_NAME.to_string()
+    } else {
+        format!("{}_{}", ENV_KEY_NAME, id)
+    }
+}
 
-/// Load encryption key from environment variable
-fn load_key_from_env() -> Option<Vec<u8>> {
-    match env::var(ENV_KEY_NAME) {
+/// Load an encryption key from an environment variable
+fn load_key_from_env(name: &str) -> Option<Vec<u8>> {
+    match env::var(name) {
         Ok(key_hex) => {
             match hex::decode(&key_hex) {
                 Ok(key) => {
                     if key.len() == 32 {
-                        logger::security(&format!("Encryption key loaded from environment variable: {}", ENV_KEY_NAME));
+                        logger::security(&format!("Encryption key loaded from environment variable: {}", name));
                         Some(key)
                     } else {
-                        logger::error(&format!("Environment variable {} contains invalid key length (expected 32 bytes, got {})", ENV_KEY_NAME, key.len()));
+                        logger::error(&format!("Environment variable {} contains invalid key length (expected 32 bytes, got {})", name, key.len()));
                         None
                     }
                 }
@@ -220,16 +274,72 @@ fn load_key_from_env() -> Option<Vec<u8>> {
             }
         }
         Err(_) => {
-            logger::info(&format!("Environment variable {} not found", ENV_KEY_NAME));
+            logger::info(&format!("Environment variable {} not found", name));
             None
         }
     }
 }
 
-/// Save encryption key to .env file
-fn save_key_to_env(key: &[u8]) -> Result<(), String> {
+/// Load key 1 and every rotated key from the environment
+fn load_keyring_from_env() -> Option<Keyring> {
+    let mut keys = BTreeMap::new();
+    keys.insert(FIRST_KEY_ID, load_key_from_env(ENV_KEY_NAME)?);
+
+    let prefix = format!("{}_", ENV_KEY_NAME);
+    for (name, _) in env::vars() {
+        let Some(id) = name.strip_prefix(&prefix).and_then(|id| id.parse::<u32>().ok()) else {
+            continue;
+        };
+        if let Some(key) = load_key_from_env(&name) {
+            keys.insert(id, key);
+        }
+    }
+
+    let newest = *keys.keys().next_back().unwrap_or(&FIRST_KEY_ID);
+    let active = match env::var(ENV_ACTIVE_KEY_NAME).ok().and_then(|id| id.parse::<u32>().ok()) {
+        Some(id) if keys.contains_key(&id) => id,
+        Some(id) => {
+            logger::critical(&format!("Active encryption key {} is missing, encrypting with key {}", id, newest));
+            newest
+        }
+        None => FIRST_KEY_ID,
+    };
+
+    let chain_key = match load_key_from_env(ENV_CHAIN_KEY_NAME) {
+        Some(key) => key,
+        None => {
+            let key = legacy_chain_key(&keys[&FIRST_KEY_ID]);
+            if let Err(e) = save_key_to_env(ENV_CHAIN_KEY_NAME, &key) {
+                logger::error(&format!("Failed to save the log chain key: {}", e));
+            }
+            key
+        }
+    };
+    Some(Keyring { active, keys, chain_key, storage: KeyStorage::Env })
+}
+
+// Restrict .env, which holds keys and passwords, to its owner
+fn secure_env_file() {
+    // On Unix systems, set file permissions to read/write for owner only (600)
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = fs::metadata(ENV_FILE_PATH) {
+            let mut perms = metadata.permissions();
+            perms.set_mode(0o600);
+            if let Err(e) = fs::set_permissions(ENV_FILE_PATH, perms) {
+                logger::warning(&format!("Failed to set secure file permissions: {}", e));
+            } else {
+                logger::security("Secure file permissions set (read/write owner only)");
+            }
+        }
+    }
+}
+
+/// Save an encryption key to .env file. A key already saved under the name is never overwritten.
+fn save_key_to_env(name: &str, key: &[u8]) -> Result<(), String> {
     let key_hex = hex::encode(key);
-    let entry = format!("{}={}\n", ENV_KEY_NAME, key_hex);
+    let entry = format!("{}={}\n", name, key_hex);
     
     // Read existing content if file exists
     let existing_content = if Path::new(ENV_FILE_PATH).exists() {
@@ -239,8 +349,8 @@ fn save_key_to_env(key: &[u8]) -> Result<(), String> {
     };
     
     // Check if key already exists
-    if existing_content.contains(&format!("{}=", ENV_KEY_NAME)) {
-        logger::info(&format!("Encryption key already exists in {}", ENV_FILE_PATH));
+    if existing_content.lines().any(|line| line.starts_with(&format!("{}=", name))) {
+        logger::info(&format!("Encryption key {} already exists in {}", name, ENV_FILE_PATH));
         return Ok(());
     }
     
@@ -254,23 +364,8 @@ fn save_key_to_env(key: &[u8]) -> Result<(), String> {
             use std::io::Write;
             match file.write_all(entry.as_bytes()) {
                 Ok(_) => {
-                    logger::security(&format!("Encryption key saved to {}", ENV_FILE_PATH));
-                    
-                    // On Unix systems, set file permissions to read/write for owner only (600)
-                    #[cfg(unix)]
-                    {
-                        use std::os::unix::fs::PermissionsExt;
-                        if let Ok(metadata) = fs::metadata(ENV_FILE_PATH) {
-                            let mut perms = metadata.permissions();
-                            perms.set_mode(0o600);
-                            if let Err(e) = fs::set_permissions(ENV_FILE_PATH, perms) {
-                                logger::warning(&format!("Failed to set secure file permissions: {}", e));
-                            } else {
-                                logger::security("Secure file permissions set (read/write owner only)");
-                            }
-                        }
-                    }
-                    
+                    logger::security(&format!("Encryption key {} saved to {}", name, ENV_FILE_PATH));
+                    secure_env_file();
                     Ok(())
                 }
                 Err(e) => {
@@ -288,25 +383,58 @@ fn save_key_to_env(key: &[u8]) -> Result<(), String> {
     }
 }
 
-/// Initialize the encryption key for database operations.
+/// Set a value in .env file, replacing the line that holds it if there is one
+fn set_env_value(name: &str, value: &str) -> Result<(), String> {
+    let mut lines = env_lines_without(|line_name| line_name == name);
+    lines.push(format!("{}={}", name, value));
+    write_env_lines(&lines)
+}
+
+/// Remove the values whose names match from .env file
+fn remove_env_values(matches: impl Fn(&str) -> bool) -> Result<(), String> {
+    write_env_lines(&env_lines_without(matches))
+}
+
+fn env_lines_without(matches: impl Fn(&str) -> bool) -> Vec<String> {
+    fs::read_to_string(ENV_FILE_PATH).unwrap_or_default()
+        .lines()
+        .filter(|line| !line.split_once('=').is_some_and(|(name, _)| matches(name)))
+        .map(|line| line.to_string())
+        .collect()
+}
+
+fn write_env_lines(lines: &[String]) -> Result<(), String> {
+    fs::write(ENV_FILE_PATH, lines.join("\n") + "\n").map_err(|e| {
+        let error_msg = format!("Failed to write {}: {}", ENV_FILE_PATH, e);
+        logger::error(&error_msg);
+        error_msg
+    })?;
+    secure_env_file();
+    Ok(())
+}
+
+/// Initialize the encryption keys for database operations.
 /// Priority order:
-/// 1. Load from environment variable (CASINO_ENCRYPTION_KEY in .env)
+/// 1. Load from environment variables (CASINO_ENCRYPTION_KEY and rotated keys in .env)
 /// 2. Generate new key and save to .env file
 pub fn initialize_encryption_key() {
-    ENCRYPTION_KEY.get_or_init(|| {
-        logger::info("Initializing encryption key for database operations");
-        
-        // Try to load from environment variable
-        if let Some(key) = load_key_from_env() {
-            logger::security("Using encryption key from .env file");
-            return key;
+    KEYRING.get_or_init(|| {
+        // Try to load from environment variables
+        if let Some(keyring) = load_keyring_from_env() {
+            logger::start_chain(keyring.chain_key.clone());
+            logger::info("Initializing encryption key for database operations");
+            logger::security(&format!("Using encryption keys from .env file, active key {}", keyring.active));
+            return RwLock::new(keyring);
         }
         
         // Generate new key and save to .env file
-        logger::warning("No existing encryption key found - generating new key");
         let key = generate_encryption_key();
+        let keys = BTreeMap::from([(FIRST_KEY_ID, key.clone())]);
+        let chain_key = generate_encryption_key();
+        logger::start_chain(chain_key.clone());
+        logger::warning("No existing encryption key found - generated new key");
         
-        match save_key_to_env(&key) {
+        match save_key_to_env(ENV_KEY_NAME, &key).and_then(|_| save_key_to_env(ENV_CHAIN_KEY_NAME, &chain_key)) {
             Ok(_) => {
                 logger::security("New encryption key generated and saved to .env file");
                 logger::info("Key will persist across application restarts via .env file");
@@ -317,24 +445,224 @@ pub fn initialize_encryption_key() {
             }
         }
         
-        key
+        RwLock::new(Keyring { active: FIRST_KEY_ID, keys, chain_key, storage: KeyStorage::Env })
     });
 }
 
-/// Get the encryption key for database operations.
-fn get_encryption_key() -> &'static [u8] {
-    ENCRYPTION_KEY.get()
+// Logs written before the chain key was kept on its own were chained with a key derived from key 1.
+// Keys without a chain key take that one over and keep it, so their log still verifies after key 1
+// is retired.
+fn legacy_chain_key(first_key: &[u8]) -> Vec<u8> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(first_key).expect("HMAC accepts keys of any length");
+    mac.update(b"casino log hash chain");
+    logger::security("Log chain key derived from key 1, it is saved on its own from now on");
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn keyring() -> &'static RwLock<Keyring> {
+    #[cfg(test)]
+    if let Some(keyring) = TEST_KEYRING.get() {
+        return keyring;
+    }
+    KEYRING.get()
         .expect("Encryption key not initialized! Call initialize_encryption_key() first")
 }
 
+/// Give the current test a keyring of its own, starting as a copy of the global one, so the keys it
+/// adds or activates never reach other tests running in the same process
+#[cfg(test)]
+pub fn isolate_keyring() {
+    initialize_encryption_key();
+    let global = KEYRING.get().unwrap().read().unwrap();
+    let keyring = Keyring {
+        active: global.active,
+        keys: global.keys.clone(),
+        chain_key: global.chain_key.clone(),
+        storage: KeyStorage::Env,
+    };
+    TEST_KEYRING.set(Some(Box::leak(Box::new(RwLock::new(keyring)))));
+}
+
+/// Get an encryption key by id
+fn get_encryption_key(id: u32) -> Result<Vec<u8>, String> {
+    let keyring = keyring().read().map_err(|_| "Encryption keys unavailable".to_string())?;
+    keyring.keys.get(&id).cloned().ok_or_else(|| {
+        logger::error(&format!("Encryption key {} is not loaded", id));
+        "Cryptographic operation failed".to_string()
+    })
+}
+
+/// Id of the key new data is encrypted with
+pub fn active_key_id() -> u32 {
+    keyring().read().map(|keyring| keyring.active).unwrap_or(FIRST_KEY_ID)
+}
+
+/// Id of the key a ciphertext was made with
+pub fn key_id_of(encrypted: &str) -> u32 {
+    split_key_id(encrypted).0
+}
+
+fn split_key_id(encrypted: &str) -> (u32, &str) {
+    encrypted.split_once(':')
+        .and_then(|(prefix, ciphertext)| Some((prefix.strip_prefix('k')?.parse().ok()?, ciphertext)))
+        .unwrap_or((FIRST_KEY_ID, encrypted))
+}
+
+/// Add a key to the keyring under the next id without activating it. Returns the id.
+pub fn add_key(key: Vec<u8>) -> Result<u32, String> {
+    let mut keyring = keyring().write().map_err(|_| "Encryption keys unavailable".to_string())?;
+    let id = keyring.keys.keys().next_back().map_or(FIRST_KEY_ID, |id| id + 1);
+    keyring.keys.insert(id, key);
+    Ok(id)
+}
+
+/// Generate a new key and save it where the keys are kept, keeping every older key. It is not used
+/// to encrypt until `activate_key` is called. Returns its id.
+pub fn generate_next_key() -> Result<u32, String> {
+    let key = generate_encryption_key();
+    let id = add_key(key.clone())?;
+
+    let mut keyring = keyring().write().map_err(|_| "Encryption keys unavailable".to_string())?;
+    let saved = match &keyring.storage {
+        KeyStorage::Env => save_key_to_env(&env_key_name(id), &key),
+        KeyStorage::KeyFile(unlocked) => keyfile::save(Path::new(KEY_FILE_PATH), unlocked, &keyring.stored_keys()),
+    };
+    if let Err(e) = saved {
+        // A key that would be lost at the next restart must never encrypt anything
+        keyring.keys.remove(&id);
+        return Err(e);
+    }
+    logger::security(&format!("Encryption key {} generated", id));
+    Ok(id)
+}
+
+/// Encrypt new data with a key from now on, here and after restarts
+pub fn activate_key(id: u32) -> Result<(), String> {
+    get_encryption_key(id)?;
+    let mut keyring = keyring().write().map_err(|_| "Encryption keys unavailable".to_string())?;
+    match &keyring.storage {
+        KeyStorage::Env => set_env_value(ENV_ACTIVE_KEY_NAME, &id.to_string())?,
+        KeyStorage::KeyFile(unlocked) => {
+            let stored = StoredKeys { active: id, ..keyring.stored_keys() };
+            keyfile::save(Path::new(KEY_FILE_PATH), unlocked, &stored)?;
+        }
+    }
+    keyring.active = id;
+    logger::security(&format!("Encryption key {} activated", id));
+    Ok(())
+}
+
+/// Whether an encryption key was saved by an earlier run, in .env or in the key file.
+/// Call after .env is loaded and before the keys are initialized.
+pub fn stored_key_exists() -> bool {
+    env::var(ENV_KEY_NAME).is_ok() || Path::new(KEY_FILE_PATH).exists()
+}
+
+/// Whether the keys are kept in the passphrase protected key file rather than .env
+pub fn uses_key_file() -> bool {
+    keyring().read().map(|keyring| matches!(keyring.storage, KeyStorage::KeyFile(_))).unwrap_or(false)
+}
+
+/// Load the encryption keys at startup: from the key file when there is one, asking for its
+/// passphrase, otherwise from .env as initialize_encryption_key does
+pub fn initialize_key_storage() -> Result<(), String> {
+    let path = Path::new(KEY_FILE_PATH);
+    if !path.exists() {
+        initialize_encryption_key();
+        return Ok(());
+    }
+
+    // Never fall back to .env or a new key here, data encrypted with the file's keys would be lost
+    let passphrase = keyfile::read_passphrase("Key file passphrase")?;
+    let (unlocked, stored) = keyfile::open(path, &passphrase).inspect_err(|e| {
+        logger::security(&format!("SECURITY ALERT: Key file {} could not be opened: {}", KEY_FILE_PATH, e));
+    })?;
+
+    let chain_key = stored_chain_key(&stored)?;
+    if stored.chain_key.is_none() {
+        keyfile::save(path, &unlocked, &StoredKeys { chain_key: Some(chain_key.clone()), ..stored.clone() })?;
+    }
+    let keyring = Keyring { active: stored.active, keys: stored.keys, chain_key, storage: KeyStorage::KeyFile(unlocked) };
+    logger::start_chain(keyring.chain_key.clone());
+    if env::var(ENV_KEY_NAME).is_ok() {
+        logger::warning(&format!("{} is still in .env although the keys are in {}, remove it", ENV_KEY_NAME, KEY_FILE_PATH));
+    }
+    logger::security(&format!("Using encryption keys from {}, active key {}", KEY_FILE_PATH, keyring.active));
+    KEYRING.set(RwLock::new(keyring)).map_err(|_| "Encryption keys were already initialized".to_string())
+}
+
+// The chain key of a key file, taken over from key 1 for files written before it was kept on its own.
+// Never runs with an unchained log, which could not be verified afterwards.
+fn stored_chain_key(stored: &StoredKeys) -> Result<Vec<u8>, String> {
+    if let Some(key) = &stored.chain_key {
+        return Ok(key.clone());
+    }
+    let first_key = stored.keys.get(&FIRST_KEY_ID).ok_or_else(|| {
+        logger::critical(&format!("SECURITY ALERT: The log chain key is missing from {} and key 1 is not there to derive it", KEY_FILE_PATH));
+        format!("The log chain key is missing from {}", KEY_FILE_PATH)
+    })?;
+    Ok(legacy_chain_key(first_key))
+}
+
+/// Move the keys from .env into a new key file protected by `passphrase`, then remove them from .env
+pub fn move_keys_to_key_file(passphrase: &str) -> Result<(), String> {
+    let path = Path::new(KEY_FILE_PATH);
+    let mut keyring = keyring().write().map_err(|_| "Encryption keys unavailable".to_string())?;
+    if matches!(keyring.storage, KeyStorage::KeyFile(_)) || path.exists() {
+        return Err(format!("The keys are already in {}", KEY_FILE_PATH));
+    }
+
+    let stored = keyring.stored_keys();
+    let unlocked = keyfile::create(path, passphrase, &stored)?;
+    // The keys only leave .env once the file is known to give them back
+    match keyfile::open(path, passphrase) {
+        Ok((_, reopened)) if reopened == stored => {}
+        _ => {
+            let _ = fs::remove_file(path);
+            return Err("The key file could not be verified, the keys stay in .env".to_string());
+        }
+    }
+    keyring.storage = KeyStorage::KeyFile(unlocked);
+
+    let key_names: Vec<String> = stored.keys.keys().map(|id| env_key_name(*id)).collect();
+    remove_env_values(|name| {
+        name == ENV_ACTIVE_KEY_NAME || name == ENV_CHAIN_KEY_NAME || key_names.iter().any(|key_name| key_name == name)
+    })?;
+    logger::security(&format!("Encryption keys moved from {} to {}", ENV_FILE_PATH, KEY_FILE_PATH));
+    Ok(())
+}
+
+// Encrypt with a key of the keyring, prefixing its id
+fn encrypt_with_key(data: &str, aad: &[u8], key_id: u32) -> Result<String, String> {
+    let encrypted = encrypt_data_with_aad(data, &get_encryption_key(key_id)?, aad)?;
+    Ok(format!("k{}:{}", key_id, encrypted))
+}
+
+// Decrypt with whichever key of the keyring the ciphertext was made with
+fn decrypt_with_keyring(encrypted: &str, aad: &[u8]) -> Result<String, String> {
+    let (key_id, ciphertext) = split_key_id(encrypted);
+    decrypt_data_with_aad(ciphertext, &get_encryption_key(key_id)?, aad)
+}
+
 // ==================== Balance Encryption ====================
 
-/// Encrypt a balance value for storage in the database
-pub fn encrypt_balance(balance: f64) -> Result<String, String> {
-    let balance_str = balance.to_string();
-    let key = get_encryption_key();
+// Balances are encrypted as "cents:<integer>". Anything else is a dollar float from before
+// amounts were kept in cents, which migrate_balance converts.
+const BALANCE_PREFIX: &str = "cents:";
+
+// SECURITY: A balance ciphertext is authenticated together with the id of the user it belongs to
+// and the version of the balance, the number of entries in the player's ledger account, which goes
+// up with every change. A ciphertext copied into another user's row, or written back over a newer
+// balance, fails authentication instead of decrypting.
+fn balance_aad(user_id: i32, version: i64) -> Vec<u8> {
+    format!("users.balance:{}:{}", user_id, version).into_bytes()
+}
+
+/// Encrypt version `version` of a user's balance for storage in the database
+pub fn encrypt_balance(user_id: i32, version: i64, balance: Money) -> Result<String, String> {
+    let balance_str = format!("{}{}", BALANCE_PREFIX, balance.cents());
     
-    match encrypt_data(&balance_str, key) {
+    match encrypt_with_key(&balance_str, &balance_aad(user_id, version), active_key_id()) {
         Ok(encrypted) => {
             logger::info("Balance encrypted successfully");
             Ok(encrypted)
@@ -346,26 +674,104 @@ pub fn encrypt_balance(balance: f64) -> Result<String, String> {
     }
 }
 
-/// Decrypt a balance value from the database
-pub fn decrypt_balance(encrypted_balance: &str) -> Result<f64, String> {
-    let key = get_encryption_key();
-    
-    match decrypt_data(encrypted_balance, key) {
-        Ok(decrypted_str) => {
-            match decrypted_str.parse::<f64>() {
-                Ok(balance) => {
-                    logger::info("Balance decrypted successfully");
-                    Ok(balance)
-                }
-                Err(e) => {
-                    logger::error(&format!("Failed to parse decrypted balance: {}", e));
-                    Err(format!("Invalid balance format: {}", e))
-                }
+/// Decrypt a user's balance from the database, stored as version `version`.
+/// A ciphertext that belongs to another user or version is reported as tampering.
+pub fn decrypt_balance(user_id: i32, version: i64, encrypted_balance: &str) -> Result<Money, String> {
+    let text = decrypt_with_keyring(encrypted_balance, &balance_aad(user_id, version)).map_err(|_| {
+        logger::critical(&format!(
+            "SECURITY ALERT: Balance of User ID: {} failed authentication at version {}. It was tampered with, copied from another account or rolled back",
+            user_id, version
+        ));
+        "Balance failed authentication".to_string()
+    })?;
+    parse_balance(&text)
+}
+
+fn parse_balance(text: &str) -> Result<Money, String> {
+    match text.strip_prefix(BALANCE_PREFIX) {
+        Some(cents) => match cents.parse::<i64>() {
+            Ok(cents) => {
+                logger::info("Balance decrypted successfully");
+                Ok(Money::from_cents(cents))
+            }
+            Err(e) => {
+                logger::error(&format!("Failed to parse decrypted balance: {}", e));
+                Err(format!("Invalid balance format: {}", e))
             }
+        },
+        None => {
+            logger::error("Decrypted balance is in the legacy dollar format");
+            Err("Balance has not been migrated to cents".to_string())
         }
-        Err(e) => {
-            logger::error(&format!("Failed to decrypt balance: {}", e));
-            Err(e)
+    }
+}
+
+/// Re-encrypt a balance from before balances were bound to their user, as version `version` of
+/// `user_id`'s balance. Balances in the legacy dollar format are converted to cents, rounding to the
+/// nearest cent.
+pub fn migrate_balance(user_id: i32, version: i64, encrypted_balance: &str) -> Result<String, String> {
+    let text = decrypt_with_keyring(encrypted_balance, &[]).map_err(|e| {
+        logger::error(&format!("Failed to decrypt balance: {}", e));
+        e
+    })?;
+    let balance = match text.strip_prefix(BALANCE_PREFIX) {
+        Some(_) => parse_balance(&text)?,
+        None => {
+            let dollars = text.parse::<f64>().map_err(|e| format!("Invalid balance format: {}", e))?;
+            Money::from_f64(dollars).ok_or_else(|| format!("Invalid legacy balance: {}", text))?
         }
+    };
+    encrypt_balance(user_id, version, balance)
+}
+
+// ==================== Secret Encryption ====================
+
+/// Encrypt a secret (such as an unrevealed server seed) for storage in the database
+pub fn encrypt_secret(secret: &str) -> Result<String, String> {
+    encrypt_with_key(secret, &[], active_key_id()).inspect_err(|e| {
+        logger::error(&format!("Failed to encrypt secret: {}", e));
+    })
+}
+
+/// Decrypt a secret stored with encrypt_secret
+pub fn decrypt_secret(encrypted_secret: &str) -> Result<String, String> {
+    decrypt_with_keyring(encrypted_secret, &[]).inspect_err(|e| {
+        logger::error(&format!("Failed to decrypt secret: {}", e));
+    })
+}
+
+// ==================== Key Rotation ====================
+
+/// Re-encrypt a balance stored with encrypt_balance under another key of the keyring
+pub fn reencrypt_balance(user_id: i32, version: i64, encrypted_balance: &str, key_id: u32) -> Result<String, String> {
+    let aad = balance_aad(user_id, version);
+    let text = decrypt_with_keyring(encrypted_balance, &aad)?;
+    encrypt_with_key(&text, &aad, key_id)
+}
+
+/// Re-encrypt a secret stored with encrypt_secret under another key of the keyring
+pub fn reencrypt_secret(encrypted_secret: &str, key_id: u32) -> Result<String, String> {
+    let secret = decrypt_with_keyring(encrypted_secret, &[])?;
+    encrypt_with_key(&secret, &[], key_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_key_outlives_key_1() {
+        let keys = BTreeMap::from([(1, vec![1u8; 32]), (2, vec![2u8; 32])]);
+        let stored = StoredKeys { active: 2, keys: keys.clone(), chain_key: Some(vec![9u8; 32]) };
+        assert_eq!(stored_chain_key(&stored).unwrap(), vec![9u8; 32]);
+
+        // Key files from before the chain key was kept on its own take over the one derived from key 1
+        let legacy = StoredKeys { chain_key: None, ..stored.clone() };
+        assert_eq!(stored_chain_key(&legacy).unwrap(), legacy_chain_key(&[1u8; 32]));
+
+        // Once saved, key 1 can be retired
+        let retired = StoredKeys { keys: BTreeMap::from([(2, vec![2u8; 32])]), ..stored };
+        assert_eq!(stored_chain_key(&retired).unwrap(), vec![9u8; 32]);
+        assert!(stored_chain_key(&StoredKeys { chain_key: None, ..retired }).is_err());
     }
-}
\ No newline at end of file
+}
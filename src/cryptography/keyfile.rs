@@ -0,0 +1,230 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::cryptography::crypto::{decrypt_data_with_aad, encrypt_data_with_aad};
+use crate::logger::logger;
+
+// SECURITY: Passphrase protected storage for the database encryption keys.
+// The keys are encrypted with AES-256-GCM under a key derived from the operator's passphrase with
+// Argon2id, so the key file is useless without the passphrase, unlike a hex key in .env.
+// The file is text:
+//   CASINO-KEYFILE-1
+//   argon2id m=<KiB> t=<iterations> p=<lanes> salt=<hex>
+//   <base64 nonce and ciphertext of "active=<id>", "chain=<hex key>" and "<id>=<hex key>" lines>
+// The first two lines are the associated data of the ciphertext, so the parameters cannot be changed.
+
+const MAGIC: &str = "CASINO-KEYFILE-1";
+const SALT_SIZE: usize = 16;
+// Key derivation is done once per start, so it can be much more expensive than password hashing
+const KDF_MEM_COST: u32 = 65536; // 64 MiB
+const KDF_TIME_COST: u32 = 3;
+const KDF_PARALLELISM: u32 = 1;
+// Shortest passphrase accepted for a new key file
+pub const MIN_PASSPHRASE_LENGTH: usize = 12;
+// Environment variable naming a file descriptor to read the passphrase from instead of prompting
+const PASSPHRASE_FD_VAR: &str = "CASINO_KEY_PASSPHRASE_FD";
+
+// Encryption keys by id, the id of the one new data is encrypted with and the key the log is
+// hash chained with. Key files written before the chain key was kept on its own have none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredKeys {
+    pub active: u32,
+    pub keys: BTreeMap<u32, Vec<u8>>,
+    pub chain_key: Option<Vec<u8>>,
+}
+
+// Key derived from the passphrase, kept in memory so the file can be rewritten after a rotation
+// without asking for the passphrase again
+pub struct Unlocked {
+    header: String,
+    wrapping_key: Vec<u8>,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], mem_cost: u32, time_cost: u32, parallelism: u32) -> Result<Vec<u8>, String> {
+    let params = Params::new(mem_cost, time_cost, parallelism, Some(32))
+        .map_err(|e| format!("Invalid key file parameters: {}", e))?;
+    let mut key = vec![0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+fn encode_keys(keys: &StoredKeys) -> String {
+    let mut text = format!("active={}\n", keys.active);
+    if let Some(chain_key) = &keys.chain_key {
+        text.push_str(&format!("chain={}\n", hex::encode(chain_key)));
+    }
+    for (id, key) in &keys.keys {
+        text.push_str(&format!("{}={}\n", id, hex::encode(key)));
+    }
+    text
+}
+
+fn decode_keys(text: &str) -> Result<StoredKeys, String> {
+    let mut active = None;
+    let mut chain_key = None;
+    let mut keys = BTreeMap::new();
+    for line in text.lines() {
+        let (name, value) = line.split_once('=').ok_or("Malformed key file")?;
+        if name == "active" {
+            active = Some(value.parse().map_err(|_| "Malformed key file")?);
+        } else if name == "chain" {
+            let key = hex::decode(value).map_err(|_| "Malformed key file")?;
+            if key.len() != 32 {
+                return Err("The log chain key in the key file has an invalid length".to_string());
+            }
+            chain_key = Some(key);
+        } else {
+            let id: u32 = name.parse().map_err(|_| "Malformed key file")?;
+            let key = hex::decode(value).map_err(|_| "Malformed key file")?;
+            if key.len() != 32 {
+                return Err(format!("Key {} in the key file has an invalid length", id));
+            }
+            keys.insert(id, key);
+        }
+    }
+    let active = active.ok_or("Key file has no active key")?;
+    if !keys.contains_key(&active) {
+        return Err(format!("Active key {} is missing from the key file", active));
+    }
+    Ok(StoredKeys { active, keys, chain_key })
+}
+
+/// Write the keys to a new key file protected by `passphrase`
+pub fn create(path: &Path, passphrase: &str, keys: &StoredKeys) -> Result<Unlocked, String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LENGTH {
+        return Err(format!("The passphrase must be at least {} characters", MIN_PASSPHRASE_LENGTH));
+    }
+    let mut salt = [0u8; SALT_SIZE];
+    OsRng.fill_bytes(&mut salt);
+    let unlocked = Unlocked {
+        header: format!(
+            "{}\nargon2id m={} t={} p={} salt={}",
+            MAGIC, KDF_MEM_COST, KDF_TIME_COST, KDF_PARALLELISM, hex::encode(salt)
+        ),
+        wrapping_key: derive_key(passphrase, &salt, KDF_MEM_COST, KDF_TIME_COST, KDF_PARALLELISM)?,
+    };
+    save(path, &unlocked, keys)?;
+    Ok(unlocked)
+}
+
+/// Replace the keys in a key file, keeping its passphrase
+pub fn save(path: &Path, unlocked: &Unlocked, keys: &StoredKeys) -> Result<(), String> {
+    let ciphertext = encrypt_data_with_aad(&encode_keys(keys), &unlocked.wrapping_key, unlocked.header.as_bytes())?;
+
+    // Written next to the file and renamed over it, so a crash never leaves a half written key file
+    let temporary = path.with_extension("tmp");
+    fs::write(&temporary, format!("{}\n{}\n", unlocked.header, ciphertext))
+        .map_err(|e| format!("Failed to write {}: {}", temporary.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&temporary, fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to secure {}: {}", temporary.display(), e))?;
+    }
+    fs::rename(&temporary, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))?;
+    logger::security(&format!("Encryption keys saved to key file {}", path.display()));
+    Ok(())
+}
+
+/// Decrypt a key file with its passphrase
+pub fn open(path: &Path, passphrase: &str) -> Result<(Unlocked, StoredKeys), String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut lines = contents.lines();
+    let (Some(MAGIC), Some(params), Some(ciphertext)) = (lines.next(), lines.next(), lines.next()) else {
+        return Err(format!("{} is not a key file", path.display()));
+    };
+
+    let mut fields = BTreeMap::new();
+    for field in params.strip_prefix("argon2id ").ok_or("Unsupported key file")?.split(' ') {
+        let (name, value) = field.split_once('=').ok_or("Malformed key file")?;
+        fields.insert(name, value);
+    }
+    let number = |name: &str| -> Result<u32, String> {
+        fields.get(name).and_then(|value| value.parse().ok()).ok_or_else(|| "Malformed key file".to_string())
+    };
+    let salt = hex::decode(fields.get("salt").ok_or("Malformed key file")?).map_err(|_| "Malformed key file")?;
+
+    let unlocked = Unlocked {
+        header: format!("{}\n{}", MAGIC, params),
+        wrapping_key: derive_key(passphrase, &salt, number("m")?, number("t")?, number("p")?)?,
+    };
+    // A wrong passphrase and a tampered file look the same
+    let text = decrypt_data_with_aad(ciphertext, &unlocked.wrapping_key, unlocked.header.as_bytes())
+        .map_err(|_| "Wrong passphrase or damaged key file".to_string())?;
+    let keys = decode_keys(&text)?;
+    Ok((unlocked, keys))
+}
+
+/// Get the key file passphrase from the file descriptor in CASINO_KEY_PASSPHRASE_FD, so it can be
+/// passed in by a service manager, or else ask the operator for it
+pub fn read_passphrase(prompt: &str) -> Result<String, String> {
+    if let Ok(fd) = std::env::var(PASSPHRASE_FD_VAR) {
+        let fd: u32 = fd.parse().map_err(|_| format!("{} must be a file descriptor number", PASSPHRASE_FD_VAR))?;
+        let passphrase = fs::read_to_string(format!("/dev/fd/{}", fd))
+            .map_err(|e| format!("Failed to read the passphrase from file descriptor {}: {}", fd, e))?;
+        return Ok(passphrase.trim_end_matches(['\r', '\n']).to_string());
+    }
+    dialoguer::Password::new()
+        .with_prompt(prompt)
+        .interact()
+        .map_err(|_| "Passphrase input cancelled".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("casino-{}-{}.keys", name, std::process::id()))
+    }
+
+    fn test_keys() -> StoredKeys {
+        StoredKeys { active: 2, keys: BTreeMap::from([(1, vec![1u8; 32]), (2, vec![2u8; 32])]), chain_key: Some(vec![9u8; 32]) }
+    }
+
+    #[test]
+    fn test_key_file_round_trip() {
+        let path = test_path("round-trip");
+        let unlocked = create(&path, "correct horse battery", &test_keys()).unwrap();
+        let (_, keys) = open(&path, "correct horse battery").unwrap();
+        assert_eq!(keys, test_keys());
+        assert!(!fs::read_to_string(&path).unwrap().contains(&hex::encode([2u8; 32])));
+        assert!(!fs::read_to_string(&path).unwrap().contains(&hex::encode([9u8; 32])));
+
+        // Rewriting after a rotation keeps the passphrase
+        let mut rotated = test_keys();
+        rotated.keys.insert(3, vec![3u8; 32]);
+        rotated.active = 3;
+        save(&path, &unlocked, &rotated).unwrap();
+        assert_eq!(open(&path, "correct horse battery").unwrap().1, rotated);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_key_file_without_chain_key_opens() {
+        let path = test_path("no-chain");
+        let keys = StoredKeys { chain_key: None, ..test_keys() };
+        create(&path, "correct horse battery", &keys).unwrap();
+        assert_eq!(open(&path, "correct horse battery").unwrap().1.chain_key, None);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_wrong_passphrase_or_tampering_is_refused() {
+        let path = test_path("refused");
+        assert!(create(&path, "too short", &test_keys()).is_err());
+        create(&path, "correct horse battery", &test_keys()).unwrap();
+        assert!(open(&path, "wrong horse battery").is_err());
+
+        // Weakening the key derivation parameters breaks authentication
+        let contents = fs::read_to_string(&path).unwrap().replace("t=3", "t=1");
+        fs::write(&path, contents).unwrap();
+        assert!(open(&path, "correct horse battery").is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,2 +1,5 @@
 pub mod crypto;
-pub mod rng;
\ No newline at end of file
+pub mod rng;
+pub mod provably_fair;
+pub mod totp;
+pub mod keyfile;
@@ -0,0 +1,296 @@
+// Provably fair play.
+// Before any round the casino commits to SHA-256(server seed). The player chooses the client seed and
+// every round uses the next nonce, so outcomes come from HMAC-SHA256(server seed, "client_seed:nonce:block").
+// Rotating the seed pair reveals the old server seed, after which every round played under it can be
+// regenerated and checked by the player.
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+use crate::cryptography::rng::CasinoRng;
+use crate::db::money::Money;
+use crate::play::game::{Game, RoundOutcome};
+
+// A server/client seed pair owned by one user
+#[derive(Debug, Clone, PartialEq)]
+pub struct FairSeed {
+    pub id: i64,
+    pub user_id: i32,
+    // Secret until revealed, never show it for an active seed
+    pub server_seed: String,
+    // SHA-256 of the server seed, shown to the player before play
+    pub commitment: String,
+    pub client_seed: String,
+    // Nonce the next round will use
+    pub next_nonce: u64,
+    pub revealed: bool,
+    pub created_at: String,
+}
+
+// A round played under a seed pair, as recorded when it was played
+#[derive(Debug, Clone, PartialEq)]
+pub struct FairRound {
+    pub id: i64,
+    pub seed_id: i64,
+    pub nonce: u64,
+    pub game_name: String,
+    pub bet: Money,
+    pub holds: Vec<usize>,
+    pub result: String,
+    pub payout: Money,
+    // Paytable the round was played with, empty for rounds recorded before snapshots were kept
+    pub paytable: Vec<(String, usize, f64)>,
+    // Fingerprint of that paytable
+    pub paytable_hash: String,
+}
+
+// Fresh 256-bit server seed in hex
+pub fn generate_server_seed() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+// Default client seed until the player picks their own
+pub fn generate_client_seed() -> String {
+    let mut bytes = [0u8; 8];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+// SHA-256 of the server seed text in hex, what `echo -n <seed> | sha256sum` prints
+pub fn commitment(server_seed: &str) -> String {
+    hex::encode(Sha256::digest(server_seed.as_bytes()))
+}
+
+// Fingerprint of a paytable, to tell whether a round can be replayed with today's configuration
+pub fn paytable_hash(paytable: &[(String, usize, f64)]) -> String {
+    let mut hasher = Sha256::new();
+    for (symbol, weight, payout) in paytable {
+        hasher.update(format!("{}:{}:{};", symbol, weight, payout).as_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+// Paytable snapshot for storage, one "symbol<TAB>weight<TAB>multiplier" line per entry
+pub fn encode_paytable(paytable: &[(String, usize, f64)]) -> String {
+    paytable.iter()
+        .map(|(symbol, weight, payout)| format!("{}\t{}\t{}", symbol, weight, payout))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Read a snapshot written by encode_paytable, None if it is malformed
+pub fn decode_paytable(snapshot: &str) -> Option<Vec<(String, usize, f64)>> {
+    if snapshot.is_empty() {
+        return Some(Vec::new());
+    }
+    snapshot.lines()
+        .map(|line| {
+            let mut fields = line.split('\t');
+            let symbol = fields.next()?.to_string();
+            let weight = fields.next()?.parse().ok()?;
+            let payout = fields.next()?.parse().ok()?;
+            Some((symbol, weight, payout))
+        })
+        .collect()
+}
+
+// One line summary of a round's grid, as stored with the round
+pub fn result_text(outcome: &RoundOutcome) -> String {
+    outcome.grid.iter().map(|row| row.join(" | ")).collect::<Vec<_>>().join(" / ")
+}
+
+// Regenerate a round from its seeds and nonce. `game` must be configured with the paytable the
+// round was played with.
+pub fn replay(game: &dyn Game, server_seed: &str, client_seed: &str, round: &FairRound) -> RoundOutcome {
+    let mut rng = CasinoRng::provably_fair(server_seed, client_seed, round.nonce);
+    game.replay_round(&mut rng, round.bet, &round.holds)
+}
+
+// Outcome of checking one recorded round against a revealed seed
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verification {
+    Verified,
+    // The regenerated result differs from the recorded one
+    Mismatch(String),
+    // A round without a paytable snapshot whose paytable has changed since, so it cannot be regenerated
+    PaytableChanged,
+    // The recorded paytable snapshot does not match the fingerprint taken when the round was played
+    SnapshotTampered,
+}
+
+// Configure `game` with the paytable the round was played with and regenerate it. Rounds recorded
+// before snapshots were kept can only be checked while the game's loaded paytable is unchanged.
+pub fn verify_round(game: &mut dyn Game, seed: &FairSeed, round: &FairRound) -> Verification {
+    if !round.paytable.is_empty() {
+        if paytable_hash(&round.paytable) != round.paytable_hash {
+            return Verification::SnapshotTampered;
+        }
+        game.set_paytable(round.paytable.clone());
+    } else if paytable_hash(game.paytable()) != round.paytable_hash {
+        return Verification::PaytableChanged;
+    }
+    let outcome = replay(game, &seed.server_seed, &seed.client_seed, round);
+    let result = result_text(&outcome);
+    if result == round.result && outcome.payout == round.payout {
+        Verification::Verified
+    } else {
+        Verification::Mismatch(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{self, setup_test_db};
+
+    fn configured_game(name: &str) -> Box<dyn Game> {
+        test_support::configured_game(&setup_test_db(), name)
+    }
+
+    fn record(game: &dyn Game, seed: &FairSeed, nonce: u64, holds: Vec<usize>) -> FairRound {
+        let mut rng = CasinoRng::provably_fair(&seed.server_seed, &seed.client_seed, nonce);
+        let outcome = game.replay_round(&mut rng, Money::from_dollars(1), &holds);
+        FairRound {
+            id: 0,
+            seed_id: seed.id,
+            nonce,
+            game_name: game.name().to_string(),
+            bet: Money::from_dollars(1),
+            holds: outcome.holds.clone(),
+            result: result_text(&outcome),
+            payout: outcome.payout,
+            paytable: game.paytable().to_vec(),
+            paytable_hash: paytable_hash(game.paytable()),
+        }
+    }
+
+    fn seed() -> FairSeed {
+        FairSeed {
+            id: 1,
+            user_id: 1,
+            server_seed: "a".repeat(64),
+            commitment: commitment(&"a".repeat(64)),
+            client_seed: "player".to_string(),
+            next_nonce: 0,
+            revealed: true,
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_seed_rotation_and_nonces() {
+        use crate::db::dbqueries;
+
+        let conn = setup_test_db();
+        let user = test_support::create_user(&conn, "fairplayer");
+
+        let active = dbqueries::get_active_fair_seed(&conn, &user).unwrap();
+        assert_eq!(dbqueries::get_active_fair_seed(&conn, &user).unwrap(), active);
+        assert_eq!(commitment(&active.server_seed), active.commitment);
+        assert_eq!(dbqueries::take_fair_nonce(&conn, active.id).unwrap(), 0);
+        assert_eq!(dbqueries::take_fair_nonce(&conn, active.id).unwrap(), 1);
+
+        let mut game = configured_game("normal");
+        let round = FairRound { id: 1, ..record(game.as_ref(), &active, 1, Vec::new()) };
+        dbqueries::insert_fair_round(&conn, &round).unwrap();
+
+        let revealed = dbqueries::rotate_fair_seed(&conn, &user, Some("my lucky seed")).unwrap();
+        assert!(revealed.revealed);
+        assert_eq!(revealed.server_seed, active.server_seed);
+        assert!(dbqueries::take_fair_nonce(&conn, active.id).is_err());
+
+        let next = dbqueries::get_active_fair_seed(&conn, &user).unwrap();
+        assert_ne!(next.commitment, active.commitment);
+        assert_eq!(next.client_seed, "my lucky seed");
+        assert_eq!(next.next_nonce, 0);
+
+        let stored = dbqueries::get_fair_rounds(&conn, active.id).unwrap();
+        assert_eq!(stored, vec![round.clone()]);
+        let revealed_seeds = dbqueries::get_revealed_fair_seeds(&conn, &user).unwrap();
+        assert_eq!(revealed_seeds.len(), 1);
+        assert_eq!(verify_round(game.as_mut(), &revealed_seeds[0], &stored[0]), Verification::Verified);
+        assert!(dbqueries::rotate_fair_seed(&conn, &user, Some("")).is_err());
+
+        // Staff can replay rounds whether or not the seed has been revealed
+        let round_id: i64 = conn.query_row("Select id From fair_rounds", [], |row| row.get(0)).unwrap();
+        let (staff_round, staff_seed) = dbqueries::get_fair_round(&conn, round_id).unwrap();
+        assert_eq!(staff_round.paytable, round.paytable);
+        assert_eq!(staff_seed.server_seed, active.server_seed);
+        let recent = dbqueries::get_recent_fair_rounds(&conn, 5).unwrap();
+        assert_eq!(recent, vec![("fairplayer".to_string(), staff_round)]);
+    }
+
+    #[test]
+    fn test_rounds_replay_with_their_own_paytable() {
+        let seed = seed();
+        let mut game = configured_game("normal");
+        let round = record(game.as_ref(), &seed, 5, Vec::new());
+
+        // The commissioner has since made every symbol pay 100x
+        let changed: Vec<_> = game.paytable().iter().map(|(s, w, _)| (s.clone(), *w, 100.0)).collect();
+        game.set_paytable(changed);
+        assert_eq!(verify_round(game.as_mut(), &seed, &round), Verification::Verified);
+        assert_eq!(game.paytable(), round.paytable.as_slice());
+    }
+
+    #[test]
+    fn test_paytable_snapshot_round_trip() {
+        let paytable = vec![("🍒".to_string(), 30, 2.0), ("7️⃣".to_string(), 1, 77.5)];
+        assert_eq!(decode_paytable(&encode_paytable(&paytable)), Some(paytable));
+        assert_eq!(decode_paytable(""), Some(Vec::new()));
+        assert_eq!(decode_paytable("🍒\tmany\t2.0"), None);
+    }
+
+    #[test]
+    fn test_commitment_is_sha256_hex() {
+        assert_eq!(commitment("abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert_eq!(generate_server_seed().len(), 64);
+        assert_ne!(generate_server_seed(), generate_server_seed());
+    }
+
+    #[test]
+    fn test_stream_depends_on_every_input() {
+        let draw = |server: &str, client: &str, nonce: u64| CasinoRng::provably_fair(server, client, nonce).next_u64();
+        assert_eq!(draw("s", "c", 1), draw("s", "c", 1));
+        assert_ne!(draw("s", "c", 1), draw("s", "c", 2));
+        assert_ne!(draw("s", "c", 1), draw("s", "d", 1));
+        assert_ne!(draw("s", "c", 1), draw("t", "c", 1));
+    }
+
+    #[test]
+    fn test_rounds_verify_from_revealed_seed() {
+        let seed = seed();
+        let conn = setup_test_db();
+        for mut game in crate::play::game::registered_games() {
+            game.load_config(&conn).unwrap();
+            for nonce in 0..20 {
+                let round = record(game.as_ref(), &seed, nonce, vec![0, 1]);
+                assert_eq!(verify_round(game.as_mut(), &seed, &round), Verification::Verified, "{} nonce {}", game.name(), nonce);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tampered_rounds_fail_verification() {
+        let seed = seed();
+        let mut game = configured_game("normal");
+        let round = record(game.as_ref(), &seed, 3, Vec::new());
+
+        let mut wrong_seed = seed.clone();
+        wrong_seed.server_seed = "b".repeat(64);
+        let mut paid_less = round.clone();
+        paid_less.payout += Money::from_dollars(1);
+        let mut tampered = round.clone();
+        tampered.paytable[0].2 += 100.0;
+        let mut legacy = round.clone();
+        legacy.paytable = Vec::new();
+        legacy.paytable_hash = "0".repeat(64);
+
+        assert!(matches!(verify_round(game.as_mut(), &wrong_seed, &round), Verification::Mismatch(_)));
+        assert!(matches!(verify_round(game.as_mut(), &seed, &paid_less), Verification::Mismatch(_)));
+        assert_eq!(verify_round(game.as_mut(), &seed, &tampered), Verification::SnapshotTampered);
+        assert_eq!(verify_round(game.as_mut(), &seed, &legacy), Verification::PaytableChanged);
+    }
+}
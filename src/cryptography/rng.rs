@@ -1,11 +1,43 @@
 use argon2::password_hash::rand_core::{OsRng, RngCore as ArgonRngCore};
 use rand::{RngCore as RandRngCore, SeedableRng};
 use rand_chacha::ChaCha20Rng;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
 use crate::logger::logger;
 
 enum RngType {
     Secure(OsRng),
-    Seeded(ChaCha20Rng),
+    Seeded(Box<ChaCha20Rng>),
+    ProvablyFair(Box<FairStream>),
+}
+
+// Byte stream of a provably fair round: the concatenation of
+// HMAC-SHA256(key = server seed, message = "client_seed:nonce:block") for block = 0, 1, 2, ...
+// Anyone holding the revealed server seed can regenerate it.
+struct FairStream {
+    server_seed: String,
+    client_seed: String,
+    nonce: u64,
+    block: u64,
+    buffer: [u8; 32],
+    used: usize,
+}
+
+impl FairStream {
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest.iter_mut() {
+            if self.used == self.buffer.len() {
+                let mut mac = Hmac::<Sha256>::new_from_slice(self.server_seed.as_bytes())
+                    .expect("HMAC accepts keys of any length");
+                mac.update(format!("{}:{}:{}", self.client_seed, self.nonce, self.block).as_bytes());
+                self.buffer.copy_from_slice(&mac.finalize().into_bytes());
+                self.block += 1;
+                self.used = 0;
+            }
+            *byte = self.buffer[self.used];
+            self.used += 1;
+        }
+    }
 }
 
 pub struct CasinoRng {
@@ -23,7 +55,32 @@ impl CasinoRng {
     // Create a seeded RNG for reproducible testing (commissioner use only)
     pub fn seeded(seed: u64) -> Self {
         logger::info(&format!("Seeded RNG initialized with seed: {}", seed));
-        CasinoRng { rng: RngType::Seeded(ChaCha20Rng::seed_from_u64(seed)) }
+        CasinoRng { rng: RngType::Seeded(Box::new(ChaCha20Rng::seed_from_u64(seed))) }
+    }
+
+    // Create the provably fair RNG for one round. Results are fully determined by the seeds and
+    // nonce, so the round can be verified once the server seed is revealed.
+    pub fn provably_fair(server_seed: &str, client_seed: &str, nonce: u64) -> Self {
+        let stream = FairStream {
+            server_seed: server_seed.to_string(),
+            client_seed: client_seed.to_string(),
+            nonce,
+            block: 0,
+            buffer: [0u8; 32],
+            used: 32,
+        };
+        CasinoRng { rng: RngType::ProvablyFair(Box::new(stream)) }
+    }
+
+    // Raw 64 bits of generator output
+    pub fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        match &mut self.rng {
+            RngType::Secure(rng) => ArgonRngCore::fill_bytes(rng, &mut bytes),
+            RngType::Seeded(rng) => RandRngCore::fill_bytes(rng.as_mut(), &mut bytes),
+            RngType::ProvablyFair(stream) => stream.fill_bytes(&mut bytes),
+        }
+        u64::from_le_bytes(bytes)
     }
 
     // Generate a random number in the range [min, max).
@@ -35,12 +92,7 @@ impl CasinoRng {
         // Use rejection sampling for uniform distribution
         // This ensures no bias in the random numbers
         loop {
-            let mut bytes = [0u8; 8];
-            match &mut self.rng {
-                RngType::Secure(rng) => ArgonRngCore::fill_bytes(rng, &mut bytes),
-                RngType::Seeded(rng) => RandRngCore::fill_bytes(rng, &mut bytes),
-            }
-            let random_value = u64::from_le_bytes(bytes);
+            let random_value = self.next_u64();
             
             // Use the random value if it's within our usable range
             // This prevents modulo bias
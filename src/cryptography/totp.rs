@@ -0,0 +1,121 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+
+// Time-based one-time passwords (RFC 6238) as used by authenticator apps:
+// HMAC-SHA1 over the number of 30 second steps since the Unix epoch, truncated to 6 digits.
+
+const STEP_SECONDS: u64 = 30;
+const DIGITS: u32 = 6;
+// Codes from one step either side are accepted to allow for clock drift
+const ALLOWED_DRIFT_STEPS: u64 = 1;
+// 160 bit secrets, as recommended by RFC 4226
+const SECRET_BYTES: usize = 20;
+
+/// Generate a new random shared secret
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; SECRET_BYTES];
+    OsRng.fill_bytes(&mut secret);
+    secret
+}
+
+// The time step a Unix time falls in
+pub fn time_step(unix_time: u64) -> u64 {
+    unix_time / STEP_SECONDS
+}
+
+// HOTP (RFC 4226) value of a counter
+fn hotp(secret: &[u8], counter: u64, digits: u32) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    // Dynamic truncation
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([hash[offset] & 0x7f, hash[offset + 1], hash[offset + 2], hash[offset + 3]]);
+    binary % 10u32.pow(digits)
+}
+
+/// The code an authenticator shows at a Unix time
+pub fn code_at(secret: &[u8], unix_time: u64) -> String {
+    format!("{:0width$}", hotp(secret, time_step(unix_time), DIGITS), width = DIGITS as usize)
+}
+
+/// Check a code against the steps around a Unix time. Returns the step it matched so the caller
+/// can refuse to accept a step a second time.
+pub fn verify(secret: &[u8], code: &str, unix_time: u64) -> Option<u64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let current = time_step(unix_time);
+    (current.saturating_sub(ALLOWED_DRIFT_STEPS)..=current + ALLOWED_DRIFT_STEPS)
+        .find(|step| constant_time_eq(code_at(secret, step * STEP_SECONDS).as_bytes(), code.as_bytes()))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Base32 (RFC 4648, no padding), the form authenticator apps take secrets in
+pub fn base32_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+    let mut encoded = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for byte in data {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    encoded
+}
+
+/// otpauth:// URI for authenticator apps, usually shown as a QR code
+pub fn provisioning_uri(secret: &[u8], username: &str) -> String {
+    format!(
+        "otpauth://totp/Casino:{}?secret={}&issuer=Casino&algorithm=SHA1&digits={}&period={}",
+        username, base32_encode(secret), DIGITS, STEP_SECONDS
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test secret of RFC 6238 Appendix B for HMAC-SHA1
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn test_rfc_6238_vectors() {
+        for (time, expected) in [(59, 94287082), (1111111109, 7081804), (1111111111, 14050471), (1234567890, 89005924), (2000000000, 69279037)] {
+            assert_eq!(hotp(RFC_SECRET, time_step(time), 8), expected);
+        }
+        assert_eq!(code_at(RFC_SECRET, 1111111109), "081804");
+    }
+
+    #[test]
+    fn test_verify_allows_one_step_of_drift() {
+        let now = 1_700_000_000;
+        let code = code_at(RFC_SECRET, now);
+        assert_eq!(verify(RFC_SECRET, &code, now), Some(time_step(now)));
+        assert_eq!(verify(RFC_SECRET, &code, now + STEP_SECONDS), Some(time_step(now)));
+        assert_eq!(verify(RFC_SECRET, &code, now + 3 * STEP_SECONDS), None);
+        assert_eq!(verify(RFC_SECRET, "12345", now), None);
+        assert_eq!(verify(b"another secret", &code, now), None);
+    }
+
+    #[test]
+    fn test_base32_encode() {
+        assert_eq!(base32_encode(b""), "");
+        assert_eq!(base32_encode(b"f"), "MY");
+        assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(base32_encode(RFC_SECRET), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+    }
+}
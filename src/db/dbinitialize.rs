@@ -1,19 +1,70 @@
 use rusqlite::{Connection, Result};
 
+use crate::authentication::permissions;
+use crate::authentication::session;
+use crate::db::ledger;
+use crate::db::transaction_limits;
+use crate::db::two_factor;
+
+// Users table, shared with the migration to roles kept in the roles table.
+// balance_bound is set once the encrypted balance is bound to the user id and the version kept by the ledger.
+const CREATE_USERS: &str = "Create Table If Not Exists users (
+    id Integer Primary Key,
+    username Text Unique Not Null,
+    password Text Not Null,
+    balance Text Not Null Default '0.0',
+    role Text Not Null Default 'user' References roles(name),
+    status Text Not Null Default 'active' Check(status In ('active', 'suspended')),
+    must_reset_password Integer Not Null Default 0,
+    balance_bound Bool Not Null Default 0
+)";
+
+// Ledger entries table, shared with the migration from dollar amounts
+const CREATE_LEDGER_ENTRIES: &str = "Create Table If Not Exists ledger_entries (
+    id Integer Primary Key,
+    transaction_id Integer Not Null,
+    user_id Integer Not Null,
+    account Text Not Null Check (account In ('player', 'house', 'cashier', 'adjustment')),
+    entry_type Text Not Null Check (entry_type In ('deposit', 'withdrawal', 'bet', 'hold_charge', 'win', 'refund', 'adjustment')),
+    amount_cents Integer Not Null,
+    round_id Integer,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    Foreign Key (user_id) References users(id),
+    Foreign Key (round_id) References rounds(id)
+)";
+
 // Initialize all database tables for the casino application
 pub fn initialize_dbs(conn: &Connection) -> Result<(), rusqlite::Error> {
-    // Create Users table with security constraints
-    // Note: balance is stored as TEXT to hold encrypted data
+    // Create Roles table and the permissions each role grants
     conn.execute(
-        "Create Table If Not Exists users (
-            id Integer Primary Key,
-            username Text Unique Not Null,
-            password Text Not Null,
-            balance Text Not Null Default '0.0',
-            role Text Default 'user' Check(role In ('user', 'technician', 'commissioner'))
+        "Create Table If Not Exists roles (
+            name Text Primary Key,
+            description Text Not Null Default '',
+            builtin Bool Not Null Default 0
         )",
         [],
     )?;
+    conn.execute(
+        "Create Table If Not Exists role_permissions (
+            role Text Not Null,
+            permission Text Not Null,
+            Primary Key (role, permission),
+            Foreign Key (role) References roles(name)
+        )",
+        [],
+    )?;
+    permissions::add_default_roles(conn)?;
+
+    // Create Users table with security constraints
+    // Note: balance is stored as TEXT to hold encrypted data
+    conn.execute(CREATE_USERS, [])?;
+    // Accounts created before staff could suspend them or force a password reset
+    add_column_if_missing(conn, "users", "status", "Text Not Null Default 'active' Check(status In ('active', 'suspended'))")?;
+    add_column_if_missing(conn, "users", "must_reset_password", "Integer Not Null Default 0")?;
+    // Balances encrypted before they were bound to their user are not bound
+    add_column_if_missing(conn, "users", "balance_bound", "Bool Not Null Default 0")?;
+    // Users tables from before custom roles only allowed the three built-in ones
+    migrate_users_to_role_table(conn)?;
 
     // Create Games table
     conn.execute(
@@ -36,13 +87,14 @@ pub fn initialize_dbs(conn: &Connection) -> Result<(), rusqlite::Error> {
             game_id Integer Not Null,
             win Integer,
             loss Integer,
-            highest_payout Real,
+            highest_payout_cents Integer Not Null Default 0,
             last_played Text,
             Foreign Key (user_id) References users(id),
             Foreign Key (game_id) References games(id)
         )",
         [],
     )?;
+    migrate_column_to_cents(conn, "user_statistics", "highest_payout")?;
 
     // Create Symbol Probabilities table for commissioner control
     conn.execute(
@@ -69,22 +121,500 @@ pub fn initialize_dbs(conn: &Connection) -> Result<(), rusqlite::Error> {
             partials INTEGER,
             losses INTEGER,
             rtp REAL,
-            timestamp DATETIME DEFAULT CURRENT_TIMESTAMP
+            timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+            theoretical_rtp REAL,
+            rtp_ci_low REAL,
+            rtp_ci_high REAL,
+            hit_rate REAL,
+            hit_ci_low REAL,
+            hit_ci_high REAL,
+            chi_square REAL,
+            chi_square_df INTEGER,
+            chi_square_p REAL,
+            target_rtp_min REAL,
+            target_rtp_max REAL,
+            verdict TEXT
+        )",
+        [],
+    )?;
+
+    // Logs created before fairness verdicts existed only have the raw counts
+    for (column, definition) in [
+        ("theoretical_rtp", "REAL"),
+        ("rtp_ci_low", "REAL"),
+        ("rtp_ci_high", "REAL"),
+        ("hit_rate", "REAL"),
+        ("hit_ci_low", "REAL"),
+        ("hit_ci_high", "REAL"),
+        ("chi_square", "REAL"),
+        ("chi_square_df", "INTEGER"),
+        ("chi_square_p", "REAL"),
+        ("target_rtp_min", "REAL"),
+        ("target_rtp_max", "REAL"),
+        ("verdict", "TEXT"),
+    ] {
+        add_column_if_missing(conn, "commissioner_log", column, definition)?;
+    }
+
+    // Create Wheel Segments table for commissioner control of the wheel of fortune
+    conn.execute(
+        "Create Table If Not Exists wheel_segments (
+            id Integer Primary Key,
+            game_id Integer Not Null,
+            position Integer Not Null,
+            label Text Not Null,
+            weight Integer Not Null Default 1,
+            multiplier Real Not Null Default 0.0,
+            Foreign Key (game_id) References games(id),
+            Unique(game_id, position)
+        )",
+        [],
+    )?;
+
+    // Create RTP Targets table, the band a fairness test must land in to pass
+    conn.execute(
+        "Create Table If Not Exists rtp_targets (
+            id Integer Primary Key,
+            game_id Integer Not Null Unique,
+            min_rtp Real Not Null,
+            max_rtp Real Not Null,
+            Foreign Key (game_id) References games(id)
+        )",
+        [],
+    )?;
+
+    // Create RNG Reports tables, one report per battery run with one row per test
+    conn.execute(
+        "Create Table If Not Exists rng_reports (
+            id Integer Primary Key,
+            source Text Not Null,
+            samples Integer Not Null,
+            passed Integer Not Null,
+            timestamp Text Not Null
+        )",
+        [],
+    )?;
+    conn.execute(
+        "Create Table If Not Exists rng_test_results (
+            id Integer Primary Key,
+            report_id Integer Not Null,
+            test_name Text Not Null,
+            statistic Real Not Null,
+            p_value Real Not Null,
+            passed Integer Not Null,
+            Foreign Key (report_id) References rng_reports(id)
+        )",
+        [],
+    )?;
+
+    // Create Fair Seeds table, the provably fair seed pairs of each user.
+    // The server seed is encrypted until the pair is rotated and the seed revealed.
+    conn.execute(
+        "Create Table If Not Exists fair_seeds (
+            id Integer Primary Key,
+            user_id Integer Not Null,
+            server_seed Text Not Null,
+            commitment Text Not Null,
+            client_seed Text Not Null,
+            next_nonce Integer Not Null Default 0,
+            revealed Integer Not Null Default 0,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            revealed_at DATETIME,
+            Foreign Key (user_id) References users(id)
+        )",
+        [],
+    )?;
+
+    // Create Fair Rounds table, every round played under a seed pair
+    conn.execute(
+        "Create Table If Not Exists fair_rounds (
+            id Integer Primary Key,
+            seed_id Integer Not Null,
+            nonce Integer Not Null,
+            game_name Text Not Null,
+            bet_cents Integer Not Null,
+            holds Text Not Null Default '',
+            result Text Not Null,
+            payout_cents Integer Not Null,
+            paytable Text Not Null Default '',
+            paytable_hash Text Not Null,
+            timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+            Foreign Key (seed_id) References fair_seeds(id),
+            Unique(seed_id, nonce)
+        )",
+        [],
+    )?;
+    migrate_column_to_cents(conn, "fair_rounds", "bet")?;
+    migrate_column_to_cents(conn, "fair_rounds", "payout")?;
+
+    // Create Rounds table, the history of every settled round
+    conn.execute(
+        "Create Table If Not Exists rounds (
+            id Integer Primary Key,
+            user_id Integer Not Null,
+            game_name Text Not Null,
+            timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+            bet_cents Integer Not Null,
+            hold_charges_cents Integer Not Null Default 0,
+            grid Text Not Null,
+            lines Text Not Null Default '',
+            payout_cents Integer Not Null,
+            balance_cents Integer Not Null,
+            fair_round_id Integer,
+            status Text Not Null Default 'settled',
+            Foreign Key (user_id) References users(id),
+            Foreign Key (fair_round_id) References fair_rounds(id)
+        )",
+        [],
+    )?;
+    conn.execute("Create Index If Not Exists idx_rounds_user On rounds(user_id, id)", [])?;
+    // Rounds recorded before bets and settlements were committed separately were all settled
+    add_column_if_missing(conn, "rounds", "status", "Text Not Null Default 'settled'")?;
+    conn.execute("Create Index If Not Exists idx_rounds_status On rounds(status)", [])?;
+    for column in ["bet", "hold_charges", "payout", "balance"] {
+        migrate_column_to_cents(conn, "rounds", column)?;
+    }
+
+    // Ledgers from before amounts were kept in cents, or before refunds had their own entry type
+    migrate_ledger_to_cents(conn)?;
+    migrate_ledger_refunds(conn)?;
+
+    // Create Ledger Entries table. Every balance movement is a ledger transaction of two entries,
+    // one to the player's account and one to the house, cashier or adjustment account, summing to zero.
+    conn.execute(CREATE_LEDGER_ENTRIES, [])?;
+    conn.execute("Create Index If Not Exists idx_ledger_user On ledger_entries(user_id, account)", [])?;
+    conn.execute("Create Index If Not Exists idx_ledger_transaction On ledger_entries(transaction_id)", [])?;
+    // The ledger is append-only, corrections are posted as adjustments
+    conn.execute(
+        "Create Trigger If Not Exists ledger_entries_no_update Before Update On ledger_entries
+        Begin Select Raise(Abort, 'ledger entries are append-only'); End",
+        [],
+    )?;
+    conn.execute(
+        "Create Trigger If Not Exists ledger_entries_no_delete Before Delete On ledger_entries
+        Begin Select Raise(Abort, 'ledger entries are append-only'); End",
+        [],
+    )?;
+
+    // Create Transaction Policies table, the anti-fraud limits of each category of transaction
+    conn.execute(
+        "Create Table If Not Exists transaction_policies (
+            id Integer Primary Key,
+            category Text Unique Not Null Check (category In ('cash', 'wager')),
+            max_per_minute Integer Not Null,
+            identical_per_minute Integer Not Null
+        )",
+        [],
+    )?;
+
+    // Create Transaction Log table, recent balance movements counted against the transaction policies
+    conn.execute(
+        "Create Table If Not Exists transaction_log (
+            id Integer Primary Key,
+            user_id Integer Not Null,
+            category Text Not Null Check (category In ('cash', 'wager')),
+            amount_cents Integer Not Null,
+            created_at Integer Not Null,
+            Foreign Key (user_id) References users(id)
+        )",
+        [],
+    )?;
+    conn.execute("Create Index If Not Exists idx_transaction_log_user On transaction_log(user_id, category, created_at)", [])?;
+
+    // Create Login Attempts table, failed logins and lockouts per username (Unix seconds)
+    conn.execute(
+        "Create Table If Not Exists login_attempts (
+            username Text Primary Key,
+            failed_count Integer Not Null,
+            last_attempt_time Integer Not Null,
+            lockout_until Integer
+        )",
+        [],
+    )?;
+
+    // Create Admin Audit table, every action taken in the user administration console
+    conn.execute(
+        "Create Table If Not Exists admin_audit (
+            id Integer Primary Key,
+            admin_id Integer Not Null,
+            target_user_id Integer,
+            action Text Not Null,
+            details Text Not Null,
+            timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+            Foreign Key (admin_id) References users(id),
+            Foreign Key (target_user_id) References users(id)
+        )",
+        [],
+    )?;
+    // The audit trail is append-only
+    conn.execute(
+        "Create Trigger If Not Exists admin_audit_no_update Before Update On admin_audit
+        Begin Select Raise(Abort, 'admin audit entries are append-only'); End",
+        [],
+    )?;
+    conn.execute(
+        "Create Trigger If Not Exists admin_audit_no_delete Before Delete On admin_audit
+        Begin Select Raise(Abort, 'admin audit entries are append-only'); End",
+        [],
+    )?;
+
+    // Create Two Factor table, the encrypted TOTP secret of each enrolled account and the last
+    // time step accepted so codes cannot be replayed
+    conn.execute(
+        "Create Table If Not Exists two_factor (
+            user_id Integer Primary Key,
+            secret Text Not Null,
+            last_used_step Integer,
+            enabled_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            Foreign Key (user_id) References users(id)
         )",
         [],
     )?;
 
-    add_technician_commissioner(&conn)?;
-    add_games(&conn)?;
-    add_default_symbols(&conn)?;
+    // Create Recovery Codes table, single use hashed codes for accounts that lost their authenticator
+    conn.execute(
+        "Create Table If Not Exists recovery_codes (
+            id Integer Primary Key,
+            user_id Integer Not Null,
+            code_hash Text Not Null,
+            used_at DATETIME,
+            Foreign Key (user_id) References users(id)
+        )",
+        [],
+    )?;
+    conn.execute("Create Index If Not Exists idx_recovery_codes_user On recovery_codes(user_id, code_hash)", [])?;
+
+    // Create Security Policies table, switches such as mandatory two-factor authentication for staff
+    conn.execute(
+        "Create Table If Not Exists security_policies (
+            name Text Primary Key,
+            value Integer Not Null
+        )",
+        [],
+    )?;
+
+    // Create Sessions table, one row per sign in with Unix times so idle sessions can be expired
+    conn.execute(
+        "Create Table If Not Exists sessions (
+            id Integer Primary Key,
+            user_id Integer Not Null,
+            created_at Integer Not Null,
+            last_activity Integer Not Null,
+            ended_at Integer,
+            end_reason Text,
+            Foreign Key (user_id) References users(id)
+        )",
+        [],
+    )?;
+    conn.execute("Create Index If Not Exists idx_sessions_user On sessions(user_id, ended_at)", [])?;
+
+    // Rounds recorded before paytable snapshots were kept
+    add_column_if_missing(conn, "fair_rounds", "paytable", "Text Not Null Default ''")?;
+
+    add_technician_commissioner(conn)?;
+    add_games(conn)?;
+    add_default_symbols(conn)?;
+    add_default_wheel_segments(conn)?;
+    add_default_rtp_targets(conn)?;
+    transaction_limits::add_default_policies(conn)?;
+    two_factor::add_default_policies(conn)?;
+    session::add_default_policies(conn)?;
+    anchor_balance_versions_to_ledger(conn)?;
+    bind_balances_to_users(conn)?;
+    // Balances from before the ledger existed
+    ledger::add_opening_balances(conn)?;
+
+    Ok(())
+}
+
+fn column_exists(conn: &Connection, table: &str, column: &str) -> Result<bool,rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt.query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>,_>>()?
+        .iter()
+        .any(|name| name == column);
+    Ok(exists)
+}
+
+// Add a column to an existing table unless an earlier run already did
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<(),rusqlite::Error> {
+    if !column_exists(conn, table, column)? {
+        conn.execute(&format!("Alter Table {} Add Column {} {}", table, column, definition), [])?;
+    }
+    Ok(())
+}
+
+// Amounts used to be stored as dollar floats. A dollar `column` is replaced by an integer
+// `<column>_cents` holding each row's amount rounded to the nearest cent.
+fn migrate_column_to_cents(conn: &Connection, table: &str, column: &str) -> Result<(),rusqlite::Error> {
+    if !column_exists(conn, table, column)? {
+        return Ok(());
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(&format!("Alter Table {} Add Column {}_cents Integer Not Null Default 0", table, column), [])?;
+    tx.execute(&format!("Update {} Set {}_cents = Cast(Round(Coalesce({}, 0) * 100) As Integer)", table, column, column), [])?;
+    tx.execute(&format!("Alter Table {} Drop Column {}", table, column), [])?;
+    tx.commit()
+}
+
+// Balances used to be bound to a balance_version counter kept in their own row, so writing back an
+// older row restored both. They are re-encrypted as the version their ledger gives, and the column is
+// dropped so a row written back later can never be trusted again.
+fn anchor_balance_versions_to_ledger(conn: &Connection) -> Result<(),rusqlite::Error> {
+    use crate::cryptography::crypto::{decrypt_balance, encrypt_balance};
+    use crate::logger::logger;
+
+    if !column_exists(conn, "users", "balance_version")? {
+        return Ok(());
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    let users = tx.prepare("Select id, balance, balance_version From users Where balance_version Is Not Null")?
+        .query_map([], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?)))?
+        .collect::<Result<Vec<_>,_>>()?;
+
+    for (user_id, encrypted, version) in users {
+        let ledger_version = ledger::balance_version(&tx, user_id)?;
+        match decrypt_balance(user_id, version, &encrypted).and_then(|balance| encrypt_balance(user_id, ledger_version, balance)) {
+            Ok(anchored) => {
+                tx.execute(
+                    "Update users Set balance = ?1, balance_bound = 1 Where id = ?2",
+                    rusqlite::params![anchored, user_id]
+                )?;
+            }
+            // Left unbound for reconciliation to report rather than blocking startup
+            Err(e) => logger::error(&format!("Cannot bind balance of User ID: {} to its ledger: {}", user_id, e)),
+        }
+    }
+    tx.execute("Alter Table users Drop Column balance_version", [])?;
+    tx.commit()
+}
+
+// Re-encrypt balances from before they were bound to their user as the version their ledger gives,
+// converting balances stored as dollar floats to integer cents on the way
+fn bind_balances_to_users(conn: &Connection) -> Result<(),rusqlite::Error> {
+    use crate::cryptography::crypto::migrate_balance;
+    use crate::logger::logger;
 
+    let mut stmt = conn.prepare("Select id, balance From users Where Not balance_bound")?;
+    let users = stmt.query_map([], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<Result<Vec<_>,_>>()?;
+
+    for (user_id, encrypted) in users {
+        match migrate_balance(user_id, ledger::balance_version(conn, user_id)?, &encrypted) {
+            Ok(migrated) => {
+                conn.execute(
+                    "Update users Set balance = ?1, balance_bound = 1 Where id = ?2",
+                    rusqlite::params![migrated, user_id]
+                )?;
+                logger::info(&format!("Balance of User ID: {} bound to the account", user_id));
+            }
+            // Left for reconciliation to report rather than blocking startup
+            Err(e) => logger::error(&format!("Cannot bind balance of User ID: {} to the account: {}", user_id, e)),
+        }
+    }
     Ok(())
 }
 
+// The users table used to check roles against a fixed list, which custom roles would fail.
+// SQLite cannot drop a check, so the table is copied into one whose role references the roles table.
+fn migrate_users_to_role_table(conn: &Connection) -> Result<(),rusqlite::Error> {
+    let sql: String = conn.query_row("Select sql From sqlite_master Where type = 'table' And name = 'users'", [], |row| row.get(0))?;
+    if !sql.contains("Check(role In") {
+        return Ok(());
+    }
+
+    // Other tables reference users, so the old table is dropped without checking them and the
+    // rename keeps their references pointing at users
+    let foreign_keys: bool = conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0))?;
+    conn.execute_batch("PRAGMA foreign_keys = OFF; PRAGMA legacy_alter_table = ON;")?;
+    let result = (|| {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("Alter Table users Rename To users_fixed_roles", [])?;
+        tx.execute(CREATE_USERS, [])?;
+        tx.execute(
+            "Insert Into users (id, username, password, balance, role, status, must_reset_password, balance_bound)
+            Select id, username, password, balance, Coalesce(role, 'user'), status, must_reset_password, balance_bound
+            From users_fixed_roles",
+            [],
+        )?;
+        tx.execute("Drop Table users_fixed_roles", [])?;
+        tx.commit()
+    })();
+    conn.execute_batch("PRAGMA legacy_alter_table = OFF;")?;
+    if foreign_keys {
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+    }
+    result
+}
+
+// Ledger entries used to hold dollar floats in an `amount` column. The ledger is append-only, so
+// the old table is copied into one with integer cents rather than updated in place.
+fn migrate_ledger_to_cents(conn: &Connection) -> Result<(),rusqlite::Error> {
+    let mut stmt = conn.prepare("PRAGMA table_info(ledger_entries)")?;
+    let legacy = stmt.query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>,_>>()?
+        .iter()
+        .any(|name| name == "amount");
+    if !legacy {
+        return Ok(());
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("Alter Table ledger_entries Rename To ledger_entries_dollars", [])?;
+    tx.execute("Drop Index If Exists idx_ledger_user", [])?;
+    tx.execute("Drop Index If Exists idx_ledger_transaction", [])?;
+    tx.execute(CREATE_LEDGER_ENTRIES, [])?;
+    tx.execute(
+        "Insert Into ledger_entries (id, transaction_id, user_id, account, entry_type, amount_cents, round_id, created_at)
+        Select id, transaction_id, user_id, account, entry_type, Cast(Round(amount * 100) As Integer), round_id, created_at
+        From ledger_entries_dollars",
+        [],
+    )?;
+    // Dropping a table does not fire its delete trigger, the triggers go with it
+    tx.execute("Drop Table ledger_entries_dollars", [])?;
+    tx.commit()
+}
+
+// Refunds used to be posted as bet entries paying the player. The entry type check is part of the
+// table, so the ledger is copied into one that allows refunds, with those entries reclassified.
+fn migrate_ledger_refunds(conn: &Connection) -> Result<(),rusqlite::Error> {
+    let legacy: bool = conn.query_row(
+        "Select Count(*) > 0 From sqlite_master
+        Where type = 'table' And name = 'ledger_entries' And sql Not Like '%''refund''%'",
+        [],
+        |row| row.get(0)
+    )?;
+    if !legacy {
+        return Ok(());
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("Alter Table ledger_entries Rename To ledger_entries_no_refunds", [])?;
+    tx.execute("Drop Index If Exists idx_ledger_user", [])?;
+    tx.execute("Drop Index If Exists idx_ledger_transaction", [])?;
+    tx.execute(CREATE_LEDGER_ENTRIES, [])?;
+    tx.execute(
+        "Insert Into ledger_entries (id, transaction_id, user_id, account, entry_type, amount_cents, round_id, created_at)
+        Select id, transaction_id, user_id, account,
+            Case When entry_type = 'bet' And transaction_id In (
+                Select transaction_id From ledger_entries_no_refunds
+                Where account = 'player' And entry_type = 'bet' And amount_cents > 0
+            ) Then 'refund' Else entry_type End,
+            amount_cents, round_id, created_at
+        From ledger_entries_no_refunds",
+        [],
+    )?;
+    tx.execute("Drop Table ledger_entries_no_refunds", [])?;
+    tx.commit()
+}
+
 /// Create default administrator accounts with secure password setup
 /// SECURITY: Credentials loaded from environment or generated and saved to .env
 fn add_technician_commissioner(conn: &Connection) -> Result<(),rusqlite::Error> {
-    use crate::cryptography::crypto::{hash_password, encrypt_balance};
+    use crate::cryptography::crypto::hash_password;
+    use crate::db::dbqueries::set_opening_balance;
     use std::env;
     
     const ENV_FILE: &str = ".env";
@@ -119,31 +649,27 @@ fn add_technician_commissioner(conn: &Connection) -> Result<(),rusqlite::Error>
     
     // Hash passwords securely with Argon2id
     let hashed_tech_password = hash_password(&tech_password)
-        .map_err(|e| rusqlite::Error::InvalidParameterName(e))?;
+        .map_err(rusqlite::Error::InvalidParameterName)?;
     
     let hashed_comm_password = hash_password(&comm_password)
-        .map_err(|e| rusqlite::Error::InvalidParameterName(e))?;
+        .map_err(rusqlite::Error::InvalidParameterName)?;
     
-    // Encrypt initial balances
-    let encrypted_tech_balance = encrypt_balance(0.0)
-        .map_err(|e| rusqlite::Error::InvalidParameterName(e))?;
-    
-    let encrypted_comm_balance = encrypt_balance(0.0)
-        .map_err(|e| rusqlite::Error::InvalidParameterName(e))?;
-    
-    // Create technician account
-    conn.execute(
-        "Insert Or Ignore Into users (username, password, role, balance) 
-        Values (?1, ?2, 'technician', ?3)",
-        [&tech_username, &hashed_tech_password, &encrypted_tech_balance]
-    )?;
-
-    // Create commissioner account
-    conn.execute(
-        "Insert Or Ignore Into users (username, password, role, balance) 
-        Values (?1, ?2, 'commissioner', ?3)",
-        [&comm_username, &hashed_comm_password, &encrypted_comm_balance]
-    )?;
+    // Create the accounts that do not exist yet, with an encrypted balance of 0 bound to each
+    for (username, hashed_password, role) in [
+        (&tech_username, &hashed_tech_password, "technician"),
+        (&comm_username, &hashed_comm_password, "commissioner"),
+    ] {
+        let tx = conn.unchecked_transaction()?;
+        let created = tx.execute(
+            "Insert Or Ignore Into users (username, password, role, balance, balance_bound)
+            Values (?1, ?2, ?3, '', 1)",
+            [username, hashed_password, role]
+        )?;
+        if created == 1 {
+            set_opening_balance(&tx, tx.last_insert_rowid() as i32)?;
+        }
+        tx.commit()?;
+    }
 
     Ok(())
 }
@@ -199,7 +725,7 @@ fn save_to_env_file(file_path: &str, key: &str, value: &str) {
                 _ => "",
             };
             
-            if let Err(e) = write!(file, "{}{}\n", comment, format!("{}={}", key, value)) {
+            if let Err(e) = writeln!(file, "{}{}={}", comment, key, value) {
                 eprintln!("Failed to write to {}: {}", file_path, e);
             }
         }
@@ -209,8 +735,8 @@ fn save_to_env_file(file_path: &str, key: &str, value: &str) {
     }
 }
 
-// MANDATORY: Generate a secure random password for default accounts
-fn generate_secure_password() -> String {
+// MANDATORY: Generate a secure random password for default accounts and forced resets
+pub fn generate_secure_password() -> String {
     use rand::Rng;
     
     // Character set for password generation
@@ -291,6 +817,8 @@ fn add_default_symbols(conn: &Connection) -> Result<(),rusqlite::Error> {
                     ("7️⃣", 3, 5.0),   // Seven: 3% - Rare
                 ]
             },
+            // The wheel has segments instead of symbols, see add_default_wheel_segments
+            "wheel of fortune" => continue,
             _ => {
                 // Fallback for any other games - use balanced settings
                 vec![
@@ -316,3 +844,35 @@ fn add_default_symbols(conn: &Connection) -> Result<(),rusqlite::Error> {
 
     Ok(())
 }
+
+// Populate the default wheel of fortune layout, one row per segment in wheel order
+fn add_default_wheel_segments(conn: &Connection) -> Result<(),rusqlite::Error> {
+    use crate::play::wheel_of_fortune::WHEEL;
+
+    let game_id: i32 = conn.query_row(
+        "Select id From games Where name = 'wheel of fortune'",
+        [],
+        |row| row.get(0)
+    )?;
+
+    for (position, segment) in WHEEL.iter().enumerate() {
+        conn.execute(
+            "Insert Or Ignore Into wheel_segments (game_id, position, label, weight, multiplier)
+            Values (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![game_id, position as i32 + 1, segment.display, segment.weight as i32, segment.multiplier as f64]
+        )?;
+    }
+
+    Ok(())
+}
+
+// Give every game the default target RTP band, leaving bands the commissioner changed alone
+fn add_default_rtp_targets(conn: &Connection) -> Result<(),rusqlite::Error> {
+    conn.execute(
+        "Insert Or Ignore Into rtp_targets (game_id, min_rtp, max_rtp)
+        Select id, 85.0, 98.0 From games",
+        []
+    )?;
+
+    Ok(())
+}
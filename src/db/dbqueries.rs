@@ -1,31 +1,23 @@
 use rusqlite::Connection;
 use chrono;
-use std::collections::HashMap;
-use std::sync::Mutex;
-use std::io;
 
 use crate::interfaces::user::User;
+use crate::logger::event::{LogEvent, TransactionKind};
 use crate::logger::logger;
-
-// SECURITY: Transaction rate limiting and fraud detection
-lazy_static::lazy_static! {
-    // Track transaction timestamps per user for rate limiting
-    static ref TRANSACTION_TRACKER: Mutex<HashMap<i32, Vec<chrono::DateTime<chrono::Local>>>> = Mutex::new(HashMap::new());
-    
-    // Track daily transaction totals per user
-    static ref DAILY_TOTALS: Mutex<HashMap<i32, (chrono::NaiveDate, f64, f64)>> = Mutex::new(HashMap::new());
-}
-
-// Transaction security limits
-const MAX_TRANSACTIONS_PER_MINUTE: usize = 5; // DONT FORGET TO SET THIS BACK TO 5
-const SUSPICIOUS_PATTERN_THRESHOLD: usize = 3; // Rapid identical transactions DONT FORGET TO SET THIS BACK TO 3
+use crate::play::simulator::{FairnessReport, SimulationStats};
+use crate::statistics::rng_battery::{RngReport, RngTestResult};
+use crate::cryptography::provably_fair::{FairRound, FairSeed};
+use crate::play::engine::{RoundRecord, RoundStatus};
+use crate::db::ledger::{self, EntryType};
+use crate::db::money::Money;
+use crate::db::transaction_limits::{self, TransactionCategory};
 
 /*  ---------------------------------------------------------------------------------------------------------------------------------- */
 // db queries for registering and signing in users
 
 // Inserts a new user into the database with a securely hashed password.
 pub fn insert_users(conn: &Connection, username: &str, password: &str) -> rusqlite::Result<usize> {
-    use crate::cryptography::crypto::{hash_password, encrypt_balance};
+    use crate::cryptography::crypto::hash_password;
     
     logger::info(&format!("Attempting to insert new user: {}", username));
     
@@ -39,20 +31,31 @@ pub fn insert_users(conn: &Connection, username: &str, password: &str) -> rusqli
         }
     };
     
-    // Encrypt the initial balance (0) for security
-    let encrypted_balance = match encrypt_balance(0.0) {
-        Ok(enc) => enc,
-        Err(e) => {
-            logger::error(&format!("Failed to encrypt initial balance for user {}: {}", username, e));
-            return Err(rusqlite::Error::InvalidParameterName(e));
-        }
-    };
-    
-    // Store username with hashed password and encrypted balance
+    // Store username with hashed password, then the encrypted balance which is bound to the new id
+    let tx = conn.unchecked_transaction()?;
+    let inserted = tx.execute(
+        "Insert Into users (username, password, balance, balance_bound) Values (?1, ?2, '', 1)",
+        rusqlite::params![username, hashed_password],
+    )?;
+    set_opening_balance(&tx, tx.last_insert_rowid() as i32).inspect_err(|e| {
+        logger::error(&format!("Failed to encrypt initial balance for user {}: {}", username, e));
+    })?;
+    tx.commit()?;
+    Ok(inserted)
+}
+
+/// Store the encrypted initial balance (0) of a newly inserted account, inside the caller's
+/// database transaction
+pub fn set_opening_balance(conn: &Connection, user_id: i32) -> rusqlite::Result<()> {
+    use crate::cryptography::crypto::encrypt_balance;
+
+    let encrypted_balance = encrypt_balance(user_id, ledger::balance_version(conn, user_id)?, Money::ZERO)
+        .map_err(rusqlite::Error::InvalidParameterName)?;
     conn.execute(
-        "Insert Into users (username, password, balance) Values (?1, ?2, ?3)",
-        rusqlite::params![username, hashed_password, encrypted_balance],
-    )
+        "Update users Set balance = ?1, balance_bound = 1 Where id = ?2",
+        rusqlite::params![encrypted_balance, user_id],
+    )?;
+    Ok(())
 }
 
 pub fn update_user_password(conn: &Connection, username: &str, password: &str) -> rusqlite::Result<usize> {
@@ -70,9 +73,9 @@ pub fn update_user_password(conn: &Connection, username: &str, password: &str) -
         }
     };
         
-    // Store username with hashed password and encrypted balance
+    // Store the new hash, which also completes any forced password reset
     conn.execute(
-        "Update users set password = ?1 where username = ?2",
+        "Update users set password = ?1, must_reset_password = 0 where username = ?2",
         rusqlite::params![hashed_password, username],
     )
 }
@@ -128,18 +131,21 @@ pub fn user_get_username(conn: &Connection, id: i32) -> rusqlite::Result<String>
 }
 
 // Retrieve and decrypt a user's balance from the database.
-pub fn user_get_balance(conn: &Connection, id: i32) -> rusqlite::Result<f64> {
+pub fn user_get_balance(conn: &Connection, id: i32) -> rusqlite::Result<Money> {
     use crate::cryptography::crypto::decrypt_balance;
     
     // Retrieve encrypted balance from database
-    let encrypted_balance: String = conn.query_row(
-        "Select balance From users Where id = ?1",
+    let (encrypted_balance, bound): (String, bool) = conn.query_row(
+        "Select balance, balance_bound From users Where id = ?1",
         [id],
-        |row| row.get(0)
+        |row| Ok((row.get(0)?, row.get(1)?))
     )?;
-    // Decrypt the balance
-    decrypt_balance(&encrypted_balance)
-        .map_err(|e| rusqlite::Error::InvalidParameterName(e))
+    if !bound {
+        return Err(rusqlite::Error::InvalidParameterName("Balance is not bound to the account".to_string()));
+    }
+    // Decrypt the balance, which only authenticates for this user and their ledger's version
+    decrypt_balance(id, ledger::balance_version(conn, id)?, &encrypted_balance)
+        .map_err(rusqlite::Error::InvalidParameterName)
 }
 
 pub fn user_get_role(conn: &Connection, id: i32) -> rusqlite::Result<String> {
@@ -150,115 +156,50 @@ pub fn user_get_role(conn: &Connection, id: i32) -> rusqlite::Result<String> {
     )
 }
 
-/// Execute a financial transaction with comprehensive security checks
-pub fn transaction(conn: &Connection, user: &User, amount: f64) -> f64 {
+/// Move money in or out of a player's balance inside the caller's database transaction.
+/// The encrypted balance and its ledger entries are written together, returning the new balance.
+fn apply_balance_change(conn: &Connection, user_id: i32, amount: Money, entry_type: EntryType, round_id: Option<i64>) -> rusqlite::Result<Money> {
     use crate::cryptography::crypto::{encrypt_balance, decrypt_balance};
-    
-    logger::transaction(&format!("User ID: {} transaction attempt for amount: {:.2}", user.id, amount));
-    
-    // SECURITY TIER 1: Rate limiting check
-    if let Err(e) = check_rate_limit(user.id) {
-        logger::security(&format!("SECURITY ALERT: Rate limit exceeded for User ID: {}: {}", user.id, e));
-        eprintln!("❌ Transaction blocked: Too many transactions. Please wait.");
-        return user.get_balance(conn).unwrap_or(0.0);
-    }
-    
-    // SECURITY TIER 2: Fraud pattern detection
-    if detect_fraud_patterns(user.id, amount) {
-        logger::security(&format!("SECURITY ALERT: Suspicious pattern detected for User ID: {}. Amount: {:.2}", user.id, amount));
-        eprintln!("❌ Transaction blocked: Suspicious activity detected. Contact support.");
-        return user.get_balance(conn).unwrap_or(0.0);
+
+    let (encrypted, bound): (String, bool) = conn.query_row(
+        "Select balance, balance_bound From users Where id = ?1",
+        [user_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).inspect_err(|e| logger::error(&format!("Failed to lock user row for User ID: {}: {}", user_id, e)))?;
+    if !bound {
+        logger::error(&format!("Balance of User ID: {} is not bound to the account", user_id));
+        return Err(rusqlite::Error::InvalidParameterName("Balance is not bound to the account".to_string()));
     }
-    
-    // Start database transaction with proper locking
-    let tx = match conn.unchecked_transaction() {
-        Ok(t) => t,
-        Err(e) => {
-            logger::error(&format!("Failed to start transaction for User ID: {}: {}", user.id, e));
-            eprintln!("Transaction Failed - System error");
-            return user.get_balance(conn).unwrap_or(0.0);
-        }
-    };
-    
-    // Lock the user row for update (prevents race conditions)
-    let current_balance = match tx.query_row(
-        "Select balance From users Where id = ?1",
-        [user.id],
-        |row| row.get::<_, String>(0)
-    ) {
-        Ok(encrypted) => match decrypt_balance(&encrypted) {
-            Ok(bal) => bal,
-            Err(e) => {
-                logger::error(&format!("Decryption failed for User ID: {}: {}", user.id, e));
-                let _ = tx.rollback();
-                eprintln!("Transaction Failed - Cannot retrieve balance");
-                return 0.0;
-            }
-        },
-        Err(e) => {
-            logger::error(&format!("Failed to lock user row for User ID: {}: {}", user.id, e));
-            let _ = tx.rollback();
-            eprintln!("Transaction Failed - Cannot retrieve balance");
-            return 0.0;
-        }
-    };
-    
-    // Calculate new balance
-    let new_balance = current_balance + amount;
-    
+    let version = ledger::balance_version(conn, user_id)?;
+    let current_balance = decrypt_balance(user_id, version, &encrypted).map_err(|e| {
+        logger::error(&format!("Decryption failed for User ID: {}: {}", user_id, e));
+        rusqlite::Error::InvalidParameterName(e)
+    })?;
+
     // Validate new balance is non-negative
-    if new_balance < 0.0 {
-        logger::warning(&format!("Transaction would result in negative balance for User ID: {}. Current: {:.2}, Amount: {:.2}", user.id, current_balance, amount));
-        let _ = tx.rollback();
-        eprintln!("Transaction Failed - Insufficient funds");
-        return current_balance;
+    let new_balance = current_balance + amount;
+    if new_balance.is_negative() {
+        logger::warning(&format!("Transaction would result in negative balance for User ID: {}. Current: {}, Amount: {}", user_id, current_balance, amount));
+        return Err(rusqlite::Error::InvalidParameterName("Insufficient funds".to_string()));
     }
-    
-    // Encrypt the new balance
-    let encrypted_balance = match encrypt_balance(new_balance) {
-        Ok(enc) => enc,
-        Err(e) => {
-            logger::error(&format!("Encryption failed for User ID: {}: {}", user.id, e));
-            let _ = tx.rollback();
-            eprintln!("Transaction Failed - Encryption error");
-            return current_balance;
-        }
-    };
-    
-    // Update database with encrypted balance
-    match tx.execute(
+
+    // Every change posts a ledger entry and so is a new version, an older ciphertext written back
+    // no longer decrypts
+    let encrypted_balance = encrypt_balance(user_id, version + 1, new_balance).map_err(|e| {
+        logger::error(&format!("Encryption failed for User ID: {}: {}", user_id, e));
+        rusqlite::Error::InvalidParameterName(e)
+    })?;
+    conn.execute(
         "Update users Set balance = ?1 Where id = ?2",
-        rusqlite::params![encrypted_balance, user.id]
-    ) {
-        Ok(_) => {
-            // Commit the transaction
-            match tx.commit() {
-                Ok(_) => {
-                    // Update tracking after successful commit
-                    record_transaction(user.id, amount);
-                    
-                    logger::transaction(&format!("User ID: {} transaction completed: {:.2}. New balance: {:.2}", user.id, amount, new_balance));
-                    new_balance
-                }
-                Err(e) => {
-                    logger::error(&format!("Failed to commit transaction for User ID: {}: {}", user.id, e));
-                    eprintln!("Transaction Failed - Commit error");
-                    current_balance
-                }
-            }
-        }
-        Err(e) => {
-            logger::error(&format!("Failed to update balance for User ID: {}: {}", user.id, e));
-            let _ = tx.rollback();
-            eprintln!("Transaction Failed");
-            current_balance
-        }
-    }
+        rusqlite::params![encrypted_balance, user_id]
+    )?;
+    ledger::post(conn, user_id, entry_type, amount, round_id)?;
+    Ok(new_balance)
 }
 
 // Check if a user has sufficient funds for a transaction.
-pub fn check_funds(conn: &Connection, user: &User, limit: f64) -> bool {
-    logger::info(&format!("Checking funds for User ID: {} against limit: {:.2}", user.id, limit));
+pub fn check_funds(conn: &Connection, user: &User, limit: Money) -> bool {
+    logger::info(&format!("Checking funds for User ID: {} against limit: {}", user.id, limit));
     
     // Query the user's current balance
     match user.get_balance(conn) {
@@ -266,87 +207,34 @@ pub fn check_funds(conn: &Connection, user: &User, limit: f64) -> bool {
             let has_funds = balance >= limit;
             if !has_funds {
                 // SECURITY: Log insufficient funds attempts to detect potential fraud
-                logger::warning(&format!("Insufficient funds for User ID: {}. Balance: {:.2}, Required: {:.2}", 
+                logger::warning(&format!("Insufficient funds for User ID: {}. Balance: {}, Required: {}", 
                     user.id, balance, limit));
             }
-            return has_funds;
+            has_funds
         }
         Err(e) => {
             // Fail-safe: if we can't check funds, don't allow the transaction
             logger::error(&format!("Failed to check funds for User ID: {}. Error: {}", user.id, e));
             println!("Unable to check funds");
-            return false;
+            false
         }
     }
 }
 
-/// Rate limiting check - prevents transaction spam
-fn check_rate_limit(user_id: i32) -> Result<(), String> {
-    let mut tracker = TRANSACTION_TRACKER.lock().unwrap();
-    let now = chrono::Local::now();
-    
-    // Get or create user's transaction history
-    let transactions = tracker.entry(user_id).or_insert_with(|| Vec::new());
-    
-    // Remove transactions older than 1 hour
-    transactions.retain(|ts| now.signed_duration_since(*ts).num_seconds() < 60);
-    
-    // Check per-minute limit
-    let last_minute = transactions.iter()
-        .filter(|ts| now.signed_duration_since(**ts).num_seconds() < 60)
-        .count();
-    
-    if last_minute >= MAX_TRANSACTIONS_PER_MINUTE {
-        return Err(format!("Rate limit: {} transactions per minute", MAX_TRANSACTIONS_PER_MINUTE));
-    }
-    Ok(())
-}
-
-/// Record transaction timestamp for rate limiting
-fn record_transaction(user_id: i32, _amount: f64) {
-    let mut tracker = TRANSACTION_TRACKER.lock().unwrap();
-    let transactions = tracker.entry(user_id).or_insert_with(|| Vec::new());
-    transactions.push(chrono::Local::now());
-}
-
-/// Basic fraud detection - detects suspicious patterns
-fn detect_fraud_patterns(user_id: i32, _amount: f64) -> bool {
-    let tracker = TRANSACTION_TRACKER.lock().unwrap();
-    
-    if let Some(transactions) = tracker.get(&user_id) {
-        let now = chrono::Local::now();
-        
-        // Check for rapid identical transactions (common fraud pattern)
-        let recent_similar = transactions.iter()
-            .filter(|ts| now.signed_duration_since(**ts).num_seconds() < 60)
-            .count();
-        
-        if recent_similar >= SUSPICIOUS_PATTERN_THRESHOLD {
-            return true;
-        }
-    }
-    
-    false
-}
-
-/// Change user balance with encrypted storage
-pub fn change_balance(conn: &Connection, user: &User, deposit: f64) -> rusqlite::Result<bool> {
-    use crate::cryptography::crypto::{encrypt_balance, decrypt_balance};
-    
-    logger::transaction(&format!("Balance change for User ID: {} amount: {:.2}", user.id, deposit));
-    
-    // SECURITY: Apply multi-tier validation (same as transaction())
-    // TIER 1: Rate limiting
-    if let Err(e) = check_rate_limit(user.id) {
-        logger::security(&format!("SECURITY ALERT: Rate limit exceeded for User ID: {}: {}", user.id, e));
-        return Err(rusqlite::Error::InvalidParameterName(format!("Rate limit exceeded: {}", e)));
-    }
+/// Change user balance with encrypted storage, a deposit or a withdrawal when `deposit` is negative.
+/// Returns the new balance.
+pub fn change_balance(conn: &Connection, user: &User, deposit: Money) -> rusqlite::Result<Money> {
+    let (requested, committed) = if !deposit.is_negative() {
+        (TransactionKind::DepositRequested, TransactionKind::Deposit)
+    } else {
+        (TransactionKind::WithdrawalRequested, TransactionKind::Withdrawal)
+    };
+    let amount = if deposit.is_negative() { -deposit } else { deposit };
+    logger::event(LogEvent::requested(requested, user.id, Some(amount)));
     
-    // TIER 2: Fraud pattern detection
-    if detect_fraud_patterns(user.id, deposit) {
-        logger::security(&format!("SECURITY ALERT: Suspicious pattern detected for User ID: {}. Amount: {:.2}", user.id, deposit));
-        return Err(rusqlite::Error::InvalidParameterName("Suspicious activity detected".to_string()));
-    }
+    // SECURITY: Rate limiting and fraud pattern detection for cash-in and cash-out
+    transaction_limits::check(conn, user.id, TransactionCategory::Cash, deposit)
+        .map_err(rusqlite::Error::InvalidParameterName)?;
     
     // SECURITY: Use database transaction with proper locking
     let tx = conn.unchecked_transaction()
@@ -354,72 +242,18 @@ pub fn change_balance(conn: &Connection, user: &User, deposit: f64) -> rusqlite:
             logger::error(&format!("Failed to start transaction for User ID: {}: {}", user.id, e));
             e
         })?;
-    
-    // Lock the user row for update (prevents race conditions)
-    let current_balance = match tx.query_row(
-        "Select balance From users Where id = ?1",
-        [user.id],
-        |row| row.get::<_, String>(0)
-    ) {
-        Ok(encrypted) => match decrypt_balance(&encrypted) {
-            Ok(bal) => bal,
-            Err(e) => {
-                logger::error(&format!("Decryption failed for User ID: {}: {}", user.id, e));
-                let _ = tx.rollback();
-                return Err(rusqlite::Error::InvalidParameterName(e));
-            }
-        },
-        Err(e) => {
-            logger::error(&format!("Failed to lock user row for User ID: {}: {}", user.id, e));
-            let _ = tx.rollback();
-            return Err(e);
-        }
-    };
-    
-    // Calculate new balance
-    let new_balance = current_balance + deposit;
-    
-    // Validate non-negative balance
-    if new_balance < 0.0 {
-        logger::warning(&format!("Balance change would result in negative balance for User ID: {}. Current: {:.2}, Deposit: {:.2}", user.id, current_balance, deposit));
-        let _ = tx.rollback();
-        return Err(rusqlite::Error::InvalidParameterName("Insufficient funds".to_string()));
-    }
-    
-    // Encrypt the new balance
-    let encrypted_balance = match encrypt_balance(new_balance) {
-        Ok(enc) => enc,
-        Err(e) => {
-            logger::error(&format!("Encryption failed for User ID: {}: {}", user.id, e));
-            let _ = tx.rollback();
-            return Err(rusqlite::Error::InvalidParameterName(e));
-        }
-    };
-    
-    // Update database
-    match tx.execute(
-        "Update users Set balance = ?1 where id = ?2",
-        rusqlite::params![encrypted_balance, user.id]
-    ) {
-        Ok(_) => {
-            // Commit the transaction
-            tx.commit().map_err(|e| {
-                logger::error(&format!("Failed to commit transaction for User ID: {}: {}", user.id, e));
-                e
-            })?;
-            
-            // Update tracking after successful commit
-            record_transaction(user.id, deposit);
-            
-            logger::transaction(&format!("User ID: {} balance updated. New balance: {:.2}", user.id, new_balance));
-            Ok(true)
-        }
-        Err(e) => {
-            logger::error(&format!("Failed to update balance for User ID: {}. Error: {}", user.id, e));
-            let _ = tx.rollback();
-            Err(e)
-        }
-    }
+
+    // Update the balance and post the matching ledger entries
+    let entry_type = if !deposit.is_negative() { EntryType::Deposit } else { EntryType::Withdrawal };
+    let new_balance = apply_balance_change(&tx, user.id, deposit, entry_type, None)?;
+    transaction_limits::record(&tx, user.id, TransactionCategory::Cash, deposit)?;
+    tx.commit().map_err(|e| {
+        logger::error(&format!("Failed to commit transaction for User ID: {}: {}", user.id, e));
+        e
+    })?;
+
+    logger::event(LogEvent::balance_changed(committed, user.id, amount, new_balance));
+    Ok(new_balance)
 }
 
 /// Record that a game was played
@@ -460,9 +294,7 @@ pub fn add_loss(conn: &Connection, game: &str) -> rusqlite::Result<()> {
 }
 
 /// Record a user's win in a specific game
-pub fn add_user_win(conn: &Connection, user: &User, game: &str, winnings: f64) -> rusqlite::Result<()> {
-    logger::transaction(&format!("User ID: {} won {:.2} in game: {}", user.id, winnings, game));
-    
+pub fn add_user_win(conn: &Connection, user: &User, game: &str, winnings: Money) -> rusqlite::Result<()> {
     // Query to get the game_id from the game name
     let game_id: i32 = conn.query_row(
         "Select id From games Where name = ?1",
@@ -470,16 +302,16 @@ pub fn add_user_win(conn: &Connection, user: &User, game: &str, winnings: f64) -
         |row| row.get(0),
     )?;
 
-    // Get the current highest_payout
-    let current_payout: f64 = conn.query_row(
-        "Select highest_payout From user_statistics Where user_id = ?1 And game_id = ?2",
+    // Get the current highest payout
+    let current_payout: Money = conn.query_row(
+        "Select highest_payout_cents From user_statistics Where user_id = ?1 And game_id = ?2",
         rusqlite::params![user.id, game_id],
         |row| row.get(0),
     )?;
 
     // Update highest_payout if winnings is greater
     let new_payout = if winnings > current_payout {
-        logger::info(&format!("New highest payout for User ID: {} in game {}: {:.2}", user.id, game, winnings));
+        logger::info(&format!("New highest payout for User ID: {} in game {}: {}", user.id, game, winnings));
         winnings
     } else {
         current_payout
@@ -487,7 +319,7 @@ pub fn add_user_win(conn: &Connection, user: &User, game: &str, winnings: f64) -
 
     // Update the user_statistics table
     conn.execute(
-        "Update user_statistics SET win = win + 1, highest_payout = ?1, last_played = ?2 where user_id = ?3 And game_id = ?4",
+        "Update user_statistics SET win = win + 1, highest_payout_cents = ?1, last_played = ?2 where user_id = ?3 And game_id = ?4",
         rusqlite::params![new_payout, chrono::Local::now().to_rfc3339(), user.id, game_id],
     )?;
 
@@ -569,10 +401,9 @@ pub fn get_game_statistics(conn: &Connection) -> rusqlite::Result<()>{
 pub fn query_user_statistics(conn: &Connection, user: &User) -> rusqlite::Result<()> {
     
     logger::info(&format!("Retrieving statistics for User ID: {}", user.id));
-    let mut stmt = conn.prepare("Select * From user_statistics Where user_id = ?1")?;
-    // FIX HERE
+    let mut stmt = conn.prepare("Select game_id, win, loss, highest_payout_cents From user_statistics Where user_id = ?1")?;
     let stats = stmt.query_map([user.id], |row| {
-        Ok((row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?))
+        Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
     })?;
 
     println!("\n{}", "=".repeat(80));
@@ -582,7 +413,7 @@ pub fn query_user_statistics(conn: &Connection, user: &User) -> rusqlite::Result
     println!("{}", "-".repeat(80));
 
     for stat in stats {
-        let (game_id, win, loss, high): (i32, i32, i32, f64) = stat?;
+        let (game_id, win, loss, high): (i32, i32, i32, Money) = stat?;
         let mut stmt = conn.prepare("Select name From games Where id = ?1")?;
 
         let game_name = stmt.query_row([game_id], |row| row.get::<_, String>(0))?;
@@ -595,7 +426,7 @@ pub fn query_user_statistics(conn: &Connection, user: &User) -> rusqlite::Result
         };
         // Fix Print Over there
         
-        println!("{:<20} {:>10} {:>10} {:>10} {:>9.1}% {:>15.2}", 
+        println!("{:<20} {:>10} {:>10} {:>10} {:>9.1}% {:>15}", 
             game_name, 
             total_played, 
             win, 
@@ -625,9 +456,9 @@ pub fn initialize_user_statistics(conn: &Connection, username: &str, _password:
     // Insert a new statistics entry for each game
     for game_id in game_ids {
         conn.execute(
-            "Insert Into user_statistics (user_id, game_id, win, loss, highest_payout, last_played)
+            "Insert Into user_statistics (user_id, game_id, win, loss, highest_payout_cents, last_played)
                     Values (?1, ?2, ?3, ?4, ?5, ?6)",
-            rusqlite::params![user_id, game_id, 0, 0, 0.0, "yesterday"],
+            rusqlite::params![user_id, game_id, 0, 0, Money::ZERO, "yesterday"],
         )?;
     }
     
@@ -708,27 +539,510 @@ pub fn update_symbol_payout(conn: &Connection, game_name: &str, symbol: &str, ne
     Ok(())
 }
 
-/// Insert a commissioner test log entry
+/// Get the wheel segments for a game in wheel order as (label, weight, multiplier)
+pub fn get_wheel_segments(conn: &Connection, game_name: &str) -> rusqlite::Result<Vec<(String, usize, f64)>> {
+    logger::info(&format!("Retrieving wheel segments for game: {}", game_name));
+
+    // Get game ID
+    let game_id: i32 = conn.query_row(
+        "Select id From games Where name = ?1",
+        [game_name],
+        |row| row.get(0)
+    )?;
+
+    let mut stmt = conn.prepare(
+        "Select label, weight, multiplier From wheel_segments Where game_id = ?1 Order By position"
+    )?;
+
+    let segments = stmt.query_map([game_id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, i32>(1)? as usize,
+            row.get::<_, f64>(2)?
+        ))
+    })?.collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(segments)
+}
+
+/// Update the weight and multiplier of the wheel segment at a position (1-based, wheel order)
+pub fn update_wheel_segment(conn: &Connection, game_name: &str, position: usize, new_weight: usize, new_multiplier: f64) -> rusqlite::Result<()> {
+    logger::security(&format!("Updating wheel segment for game: {}, position: {}, new weight: {}, new multiplier: {}", game_name, position, new_weight, new_multiplier));
+
+    // Get game ID
+    let game_id: i32 = conn.query_row(
+        "Select id From games Where name = ?1",
+        [game_name],
+        |row| row.get(0)
+    )?;
+
+    let updated = conn.execute(
+        "Update wheel_segments Set weight = ?1, multiplier = ?2 Where game_id = ?3 And position = ?4",
+        rusqlite::params![new_weight as i32, new_multiplier, game_id, position as i32]
+    )?;
+    if updated == 0 {
+        return Err(rusqlite::Error::InvalidParameterName(format!("No wheel segment at position {}", position)));
+    }
+
+    logger::security(&format!("Wheel segment {} updated successfully in {}", position, game_name));
+    Ok(())
+}
+
+/// Get the target RTP band (min, max in percent) a game's fairness test must land in
+pub fn get_rtp_target(conn: &Connection, game_name: &str) -> rusqlite::Result<(f64, f64)> {
+    logger::info(&format!("Retrieving target RTP band for game: {}", game_name));
+
+    conn.query_row(
+        "Select t.min_rtp, t.max_rtp From rtp_targets t Join games g On t.game_id = g.id Where g.name = ?1",
+        [game_name],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    )
+}
+
+/// Set the target RTP band (min, max in percent) for a game
+pub fn set_rtp_target(conn: &Connection, game_name: &str, min_rtp: f64, max_rtp: f64) -> rusqlite::Result<()> {
+    logger::security(&format!("Updating target RTP band for game: {} to {:.2}% - {:.2}%", game_name, min_rtp, max_rtp));
+
+    if !(0.0..=max_rtp).contains(&min_rtp) {
+        return Err(rusqlite::Error::InvalidParameterName("Target RTP band must satisfy 0 <= min <= max".to_string()));
+    }
+
+    // Get game ID
+    let game_id: i32 = conn.query_row(
+        "Select id From games Where name = ?1",
+        [game_name],
+        |row| row.get(0)
+    )?;
+
+    conn.execute(
+        "Insert Into rtp_targets (game_id, min_rtp, max_rtp) Values (?1, ?2, ?3)
+        On Conflict(game_id) Do Update Set min_rtp = excluded.min_rtp, max_rtp = excluded.max_rtp",
+        rusqlite::params![game_id, min_rtp, max_rtp]
+    )?;
+
+    logger::security(&format!("Target RTP band updated successfully for {}", game_name));
+    Ok(())
+}
+
+/// Insert a commissioner test log entry together with its statistical verdict
 pub fn insert_commissioner_log(
     conn: &Connection,
     game_name: &str,
     seed: &str,
-    rounds: u32,
-    wins: u32,
-    partials: u32,
-    losses: u32,
-    rtp: f64
+    stats: &SimulationStats,
+    report: &FairnessReport,
+    theoretical_rtp: f64
 ) -> rusqlite::Result<()> {
     logger::info(&format!(
-        "Storing commissioner test: game={}, seed={}, rounds={}, rtp={:.2}%",
-        game_name, seed, rounds, rtp
+        "Storing commissioner test: game={}, seed={}, rounds={}, rtp={:.2}%, verdict={}",
+        game_name, seed, stats.rounds, stats.rtp(), report.verdict.as_str()
     ));
     
     conn.execute(
-        "INSERT INTO commissioner_log (game_name, seed, rounds, wins, partials, losses, rtp)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-        rusqlite::params![game_name, seed, rounds as i32, wins as i32, partials as i32, losses as i32, rtp],
+        "INSERT INTO commissioner_log (game_name, seed, rounds, wins, partials, losses, rtp,
+            theoretical_rtp, rtp_ci_low, rtp_ci_high, hit_rate, hit_ci_low, hit_ci_high,
+            chi_square, chi_square_df, chi_square_p, target_rtp_min, target_rtp_max, verdict)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
+        rusqlite::params![
+            game_name, seed, stats.rounds as i32, stats.wins as i32, stats.partials as i32, stats.losses as i32, stats.rtp(),
+            theoretical_rtp, report.rtp_interval.0, report.rtp_interval.1,
+            report.hit_rate, report.hit_interval.0, report.hit_interval.1,
+            report.chi_square.statistic, report.chi_square.df as i32, report.chi_square.p_value,
+            report.target.0, report.target.1, report.verdict.as_str()
+        ],
     )?;
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Store an RNG test battery report with all of its test results, returning the report id
+pub fn insert_rng_report(conn: &Connection, report: &RngReport) -> rusqlite::Result<i64> {
+    logger::info(&format!(
+        "Storing RNG test report: source={}, samples={}, passed={}",
+        report.source, report.samples, report.passed()
+    ));
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "Insert Into rng_reports (source, samples, passed, timestamp) Values (?1, ?2, ?3, ?4)",
+        rusqlite::params![report.source, report.samples as i64, report.passed(), report.timestamp],
+    )?;
+    let report_id = tx.last_insert_rowid();
+
+    for result in &report.results {
+        tx.execute(
+            "Insert Into rng_test_results (report_id, test_name, statistic, p_value, passed) Values (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![report_id, result.name, result.statistic, result.p_value, result.passed],
+        )?;
+    }
+    tx.commit()?;
+
+    Ok(report_id)
+}
+
+/// Get the most recent RNG test battery reports, newest first
+pub fn get_rng_reports(conn: &Connection, limit: usize) -> rusqlite::Result<Vec<RngReport>> {
+    logger::info(&format!("Retrieving last {} RNG test reports", limit));
+
+    let mut stmt = conn.prepare(
+        "Select id, source, samples, timestamp From rng_reports Order By id Desc Limit ?1"
+    )?;
+    let headers = stmt.query_map([limit as i64], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, i64>(2)? as usize,
+            row.get::<_, String>(3)?
+        ))
+    })?.collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut results_stmt = conn.prepare(
+        "Select test_name, statistic, p_value, passed From rng_test_results Where report_id = ?1 Order By id"
+    )?;
+    let mut reports = Vec::new();
+    for (id, source, samples, timestamp) in headers {
+        let results = results_stmt.query_map([id], |row| {
+            Ok(RngTestResult {
+                name: row.get(0)?,
+                statistic: row.get(1)?,
+                p_value: row.get(2)?,
+                passed: row.get(3)?,
+            })
+        })?.collect::<rusqlite::Result<Vec<_>>>()?;
+        reports.push(RngReport { source, samples, timestamp, results });
+    }
+
+    Ok(reports)
+}
+
+// Columns of fair_seeds in the order fair_seed_from_row reads them
+const FAIR_SEED_COLUMNS: &str = "id, user_id, server_seed, commitment, client_seed, next_nonce, revealed, created_at";
+
+// Build a FairSeed from a fair_seeds row, decrypting the server seed
+fn fair_seed_from_row(row: &rusqlite::Row) -> rusqlite::Result<FairSeed> {
+    use crate::cryptography::crypto::decrypt_secret;
+
+    let encrypted_seed: String = row.get(2)?;
+    Ok(FairSeed {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        server_seed: decrypt_secret(&encrypted_seed).map_err(rusqlite::Error::InvalidParameterName)?,
+        commitment: row.get(3)?,
+        client_seed: row.get(4)?,
+        next_nonce: row.get::<_, i64>(5)? as u64,
+        revealed: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+// Create a new active seed pair for a user, committing to a fresh server seed
+fn create_fair_seed(conn: &Connection, user: &User, client_seed: &str) -> rusqlite::Result<FairSeed> {
+    use crate::cryptography::crypto::encrypt_secret;
+    use crate::cryptography::provably_fair::{commitment, generate_server_seed};
+
+    let server_seed = generate_server_seed();
+    let encrypted_seed = encrypt_secret(&server_seed).map_err(rusqlite::Error::InvalidParameterName)?;
+    conn.execute(
+        "Insert Into fair_seeds (user_id, server_seed, commitment, client_seed) Values (?1, ?2, ?3, ?4)",
+        rusqlite::params![user.id, encrypted_seed, commitment(&server_seed), client_seed],
+    )?;
+    logger::security(&format!("New provably fair seed pair committed for User ID: {}", user.id));
+
+    conn.query_row(
+        &format!("Select {} From fair_seeds Where id = ?1", FAIR_SEED_COLUMNS),
+        [conn.last_insert_rowid()],
+        fair_seed_from_row
+    )
+}
+
+/// Get the user's active (unrevealed) provably fair seed pair, committing to a new one if there is none
+pub fn get_active_fair_seed(conn: &Connection, user: &User) -> rusqlite::Result<FairSeed> {
+    use crate::cryptography::provably_fair::generate_client_seed;
+
+    let active = conn.query_row(
+        &format!("Select {} From fair_seeds Where user_id = ?1 And revealed = 0 Order By id Desc Limit 1", FAIR_SEED_COLUMNS),
+        [user.id],
+        fair_seed_from_row
+    );
+    match active {
+        Ok(seed) => Ok(seed),
+        Err(rusqlite::Error::QueryReturnedNoRows) => create_fair_seed(conn, user, &generate_client_seed()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Reveal the user's active server seed and commit to a new one with the given client seed
+/// (or keep the current client seed). Returns the revealed seed pair.
+pub fn rotate_fair_seed(conn: &Connection, user: &User, client_seed: Option<&str>) -> rusqlite::Result<FairSeed> {
+    if let Some(client_seed) = client_seed {
+        if client_seed.is_empty() || client_seed.len() > 64 {
+            return Err(rusqlite::Error::InvalidParameterName("Client seed must be 1-64 characters".to_string()));
+        }
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    let mut current = get_active_fair_seed(&tx, user)?;
+    tx.execute(
+        "Update fair_seeds Set revealed = 1, revealed_at = CURRENT_TIMESTAMP Where id = ?1",
+        [current.id],
+    )?;
+    create_fair_seed(&tx, user, client_seed.unwrap_or(&current.client_seed))?;
+    tx.commit()?;
+
+    logger::security(&format!("Provably fair seed pair {} revealed for User ID: {}", current.id, user.id));
+    current.revealed = true;
+    Ok(current)
+}
+
+/// Reserve the next nonce of a seed pair for a round
+pub fn take_fair_nonce(conn: &Connection, seed_id: i64) -> rusqlite::Result<u64> {
+    let nonce: i64 = conn.query_row(
+        "Update fair_seeds Set next_nonce = next_nonce + 1 Where id = ?1 And revealed = 0 Returning next_nonce - 1",
+        [seed_id],
+        |row| row.get(0)
+    )?;
+    Ok(nonce as u64)
+}
+
+/// Record a round played under a seed pair so it can be verified once the seed is revealed
+/// and replayed by staff at any time
+pub fn insert_fair_round(conn: &Connection, round: &FairRound) -> rusqlite::Result<i64> {
+    use crate::cryptography::provably_fair::encode_paytable;
+
+    let holds: Vec<String> = round.holds.iter().map(|hold| hold.to_string()).collect();
+    conn.execute(
+        "Insert Into fair_rounds (seed_id, nonce, game_name, bet_cents, holds, result, payout_cents, paytable, paytable_hash)
+        Values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        rusqlite::params![
+            round.seed_id, round.nonce as i64, round.game_name, round.bet, holds.join(","),
+            round.result, round.payout, encode_paytable(&round.paytable), round.paytable_hash
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Get the user's revealed seed pairs, newest first
+pub fn get_revealed_fair_seeds(conn: &Connection, user: &User) -> rusqlite::Result<Vec<FairSeed>> {
+    let mut stmt = conn.prepare(
+        &format!("Select {} From fair_seeds Where user_id = ?1 And revealed = 1 Order By id Desc", FAIR_SEED_COLUMNS)
+    )?;
+    let seeds = stmt.query_map([user.id], fair_seed_from_row)?.collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(seeds)
+}
+
+// Columns of fair_rounds in the order fair_round_from_row reads them
+const FAIR_ROUND_COLUMNS: &str = "id, seed_id, nonce, game_name, bet_cents, holds, result, payout_cents, paytable, paytable_hash";
+
+// Build a FairRound from a fair_rounds row
+fn fair_round_from_row(row: &rusqlite::Row) -> rusqlite::Result<FairRound> {
+    use crate::cryptography::provably_fair::decode_paytable;
+
+    let holds: String = row.get(5)?;
+    let snapshot: String = row.get(8)?;
+    Ok(FairRound {
+        id: row.get(0)?,
+        seed_id: row.get(1)?,
+        nonce: row.get::<_, i64>(2)? as u64,
+        game_name: row.get(3)?,
+        bet: row.get(4)?,
+        holds: holds.split(',').filter_map(|hold| hold.parse().ok()).collect(),
+        result: row.get(6)?,
+        payout: row.get(7)?,
+        paytable: decode_paytable(&snapshot)
+            .ok_or_else(|| rusqlite::Error::InvalidParameterName("Malformed paytable snapshot".to_string()))?,
+        paytable_hash: row.get(9)?,
+    })
+}
+
+/// Get every round played under a seed pair in nonce order
+pub fn get_fair_rounds(conn: &Connection, seed_id: i64) -> rusqlite::Result<Vec<FairRound>> {
+    let mut stmt = conn.prepare(
+        &format!("Select {} From fair_rounds Where seed_id = ?1 Order By nonce", FAIR_ROUND_COLUMNS)
+    )?;
+    let rounds = stmt.query_map([seed_id], fair_round_from_row)?.collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rounds)
+}
+
+/// Get one recorded round with the seed pair it was played under, revealed or not (staff replay)
+pub fn get_fair_round(conn: &Connection, round_id: i64) -> rusqlite::Result<(FairRound, FairSeed)> {
+    let round = conn.query_row(
+        &format!("Select {} From fair_rounds Where id = ?1", FAIR_ROUND_COLUMNS),
+        [round_id],
+        fair_round_from_row
+    )?;
+    let seed = conn.query_row(
+        &format!("Select {} From fair_seeds Where id = ?1", FAIR_SEED_COLUMNS),
+        [round.seed_id],
+        fair_seed_from_row
+    )?;
+    Ok((round, seed))
+}
+
+/// Get the most recent rounds of all players with the username that played them, newest first
+pub fn get_recent_fair_rounds(conn: &Connection, limit: usize) -> rusqlite::Result<Vec<(String, FairRound)>> {
+    let mut stmt = conn.prepare(&format!(
+        "Select {}, (Select u.username From fair_seeds s Join users u On s.user_id = u.id Where s.id = fair_rounds.seed_id)
+        From fair_rounds Order By id Desc Limit ?1",
+        FAIR_ROUND_COLUMNS
+    ))?;
+    let rounds = stmt.query_map([limit as i64], |row| {
+        Ok((row.get::<_, Option<String>>(10)?.unwrap_or_default(), fair_round_from_row(row)?))
+    })?.collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rounds)
+}
+
+/// Take a bet and open its round in one database transaction. The round stays pending until
+/// settle_round, so a round interrupted by a crash is found and refunded on the next start.
+/// Returns the round id and the balance left after the bet.
+pub fn place_bet(conn: &Connection, user: &User, game_name: &str, bet: Money) -> rusqlite::Result<(i64, Money)> {
+    logger::event(LogEvent::bet_requested(user.id, game_name, bet));
+
+    // SECURITY: Wagers have their own limits so normal play is not mistaken for cash fraud.
+    // Only the bet is checked, the settlement that follows is never blocked.
+    transaction_limits::check(conn, user.id, TransactionCategory::Wager, bet)
+        .map_err(rusqlite::Error::InvalidParameterName)?;
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "Insert Into rounds (user_id, game_name, bet_cents, grid, payout_cents, balance_cents, status) Values (?1, ?2, ?3, '', 0, 0, ?4)",
+        rusqlite::params![user.id, game_name, bet, RoundStatus::Pending.as_str()],
+    )?;
+    let round_id = tx.last_insert_rowid();
+    let balance = apply_balance_change(&tx, user.id, -bet, EntryType::Bet, Some(round_id))?;
+    transaction_limits::record(&tx, user.id, TransactionCategory::Wager, bet)?;
+    tx.commit()?;
+
+    logger::event(LogEvent::round(TransactionKind::BetPlaced, user.id, game_name, round_id, bet, balance));
+    Ok((round_id, balance))
+}
+
+/// Settle a round opened by place_bet in one database transaction: charge any extras bought during
+/// the round, credit the payout, update the statistics and store the provably fair record and the
+/// round history. Either all of it is committed or none of it, leaving the round pending.
+/// Fills in the record's balance and fair round id.
+pub fn settle_round(conn: &Connection, user: &User, fair_round: &FairRound, round: &mut RoundRecord) -> rusqlite::Result<()> {
+    let tx = conn.unchecked_transaction()?;
+
+    let status: String = tx.query_row("Select status From rounds Where id = ?1 And user_id = ?2", rusqlite::params![round.id, user.id], |row| row.get(0))?;
+    if status != RoundStatus::Pending.as_str() {
+        return Err(rusqlite::Error::InvalidParameterName(format!("Round {} is already {}", round.id, status)));
+    }
+
+    let mut balance = user_get_balance(&tx, user.id)?;
+    if round.hold_charges.is_positive() {
+        balance = apply_balance_change(&tx, user.id, -round.hold_charges, EntryType::HoldCharge, Some(round.id))?;
+    }
+    // Accounts created without statistics rows get one rather than failing the settlement
+    tx.execute(
+        "Insert Into user_statistics (user_id, game_id, win, loss, highest_payout_cents, last_played)
+        Select ?1, id, 0, 0, 0, 'yesterday' From games Where name = ?2
+        And Not Exists (Select 1 From user_statistics Where user_id = ?1 And game_id = games.id)",
+        rusqlite::params![user.id, round.game_name],
+    )?;
+    if round.payout.is_positive() {
+        balance = apply_balance_change(&tx, user.id, round.payout, EntryType::Win, Some(round.id))?;
+        add_win(&tx, &round.game_name)?;
+        add_user_win(&tx, user, &round.game_name, round.payout)?;
+    } else {
+        add_loss(&tx, &round.game_name)?;
+        add_user_loss(&tx, user, &round.game_name)?;
+    }
+    let fair_round_id = insert_fair_round(&tx, fair_round)?;
+
+    tx.execute(
+        "Update rounds Set hold_charges_cents = ?1, grid = ?2, lines = ?3, payout_cents = ?4, balance_cents = ?5, fair_round_id = ?6,
+        status = ?7 Where id = ?8",
+        rusqlite::params![
+            round.hold_charges, round.grid, round.lines.join("\n"), round.payout, balance,
+            fair_round_id, RoundStatus::Settled.as_str(), round.id
+        ],
+    )?;
+    tx.commit()?;
+
+    round.balance = balance;
+    round.fair_round_id = Some(fair_round_id);
+    round.status = RoundStatus::Settled;
+    Ok(())
+}
+
+/// Void a pending round and give the player back its bet, returning the new balance.
+/// The bet is returned with a refund entry against the house account so it balances too.
+pub fn refund_round(conn: &Connection, round_id: i64) -> rusqlite::Result<Money> {
+    let tx = conn.unchecked_transaction()?;
+    let (user_id, game_name, status): (i32, String, String) = tx.query_row(
+        "Select user_id, game_name, status From rounds Where id = ?1",
+        [round_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+    )?;
+    if status != RoundStatus::Pending.as_str() {
+        return Err(rusqlite::Error::InvalidParameterName(format!("Round {} is already {}", round_id, status)));
+    }
+    // A round whose stake cannot be found stays pending rather than being refunded a guess
+    let bet = ledger::round_stake(&tx, user_id, round_id)?;
+
+    let balance = apply_balance_change(&tx, user_id, bet, EntryType::Refund, Some(round_id))?;
+    tx.execute(
+        "Update rounds Set balance_cents = ?1, status = ?2 Where id = ?3",
+        rusqlite::params![balance, RoundStatus::Refunded.as_str(), round_id],
+    )?;
+    tx.commit()?;
+
+    logger::event(LogEvent::round(TransactionKind::Refund, user_id, &game_name, round_id, bet, balance));
+    Ok(balance)
+}
+
+/// Refund every round left pending by an interrupted session. Run at startup.
+pub fn recover_pending_rounds(conn: &Connection) -> rusqlite::Result<usize> {
+    let mut stmt = conn.prepare("Select id From rounds Where status = ?1 Order By id")?;
+    let pending = stmt.query_map([RoundStatus::Pending.as_str()], |row| row.get::<_, i64>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut recovered = 0;
+    for round_id in pending {
+        logger::warning(&format!("Round {} was interrupted before it was settled, refunding its bet", round_id));
+        match refund_round(conn, round_id) {
+            Ok(_) => recovered += 1,
+            // Left pending for the next start rather than blocking this one
+            Err(e) => logger::critical(&format!("Failed to refund interrupted round {}: {}", round_id, e)),
+        }
+    }
+    Ok(recovered)
+}
+
+/// Number of rounds in a user's history
+pub fn count_user_rounds(conn: &Connection, user: &User) -> rusqlite::Result<usize> {
+    let count: i64 = conn.query_row(
+        "Select Count(*) From rounds Where user_id = ?1",
+        [user.id],
+        |row| row.get(0)
+    )?;
+    Ok(count as usize)
+}
+
+/// One page of a user's round history, newest first. Pages start at 0.
+pub fn get_user_rounds(conn: &Connection, user: &User, page: usize, page_size: usize) -> rusqlite::Result<Vec<RoundRecord>> {
+    logger::info(&format!("Retrieving round history page {} for User ID: {}", page, user.id));
+
+    let mut stmt = conn.prepare(
+        "Select id, user_id, game_name, timestamp, bet_cents, hold_charges_cents, grid, lines, payout_cents, balance_cents, fair_round_id, status
+        From rounds Where user_id = ?1 Order By id Desc Limit ?2 Offset ?3"
+    )?;
+    let rounds = stmt.query_map(rusqlite::params![user.id, page_size as i64, (page * page_size) as i64], |row| {
+        let lines: String = row.get(7)?;
+        Ok(RoundRecord {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            game_name: row.get(2)?,
+            timestamp: row.get(3)?,
+            bet: row.get(4)?,
+            hold_charges: row.get(5)?,
+            grid: row.get(6)?,
+            lines: lines.lines().map(String::from).collect(),
+            payout: row.get(8)?,
+            balance: row.get(9)?,
+            fair_round_id: row.get(10)?,
+            status: RoundStatus::parse(&row.get::<_, String>(11)?),
+        })
+    })?.collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rounds)
+}
@@ -0,0 +1,183 @@
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+
+use crate::authentication::authorization;
+use crate::authentication::permissions::Permission;
+use crate::cryptography::crypto;
+use crate::db::ledger;
+use crate::db::user_admin;
+use crate::interfaces::user::User;
+use crate::logger::logger;
+
+// SECURITY: Rotation of the database encryption key.
+// A new key is generated and saved next to the old ones, every encrypted column is re-encrypted with
+// it in one database transaction, and only then does it become the key new data is encrypted with.
+// Old keys are kept with the new one, so anything still encrypted with them keeps decrypting.
+
+// Columns holding values encrypted with encrypt_secret: table, primary key, encrypted column
+const SECRET_COLUMNS: [(&str, &str, &str); 2] = [
+    ("two_factor", "user_id", "secret"),
+    ("fair_seeds", "id", "server_seed"),
+];
+
+// What a rotation re-encrypted
+#[derive(Debug, Clone, PartialEq)]
+pub struct RotationReport {
+    pub key_id: u32,
+    pub balances: usize,
+    pub secrets: usize,
+}
+
+fn encrypted_values(conn: &Connection) -> rusqlite::Result<Vec<String>> {
+    let mut values: Vec<String> = conn.prepare("Select balance From users")?
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    for (table, _, column) in SECRET_COLUMNS {
+        let mut stmt = conn.prepare(&format!("Select {} From {}", column, table))?;
+        values.extend(stmt.query_map([], |row| row.get::<_, String>(0))?.collect::<rusqlite::Result<Vec<_>>>()?);
+    }
+    Ok(values)
+}
+
+/// Number of stored ciphertexts made with each key, to see whether an old key is still in use
+pub fn key_usage(conn: &Connection) -> rusqlite::Result<BTreeMap<u32, usize>> {
+    let mut usage = BTreeMap::new();
+    for value in encrypted_values(conn)? {
+        *usage.entry(crypto::key_id_of(&value)).or_insert(0) += 1;
+    }
+    Ok(usage)
+}
+
+/// Re-encrypt every encrypted column with key `key_id`, inside the caller's database transaction.
+/// Fails without changing anything usable when a value cannot be decrypted, such as a tampered balance.
+pub fn reencrypt_all(conn: &Connection, key_id: u32) -> rusqlite::Result<RotationReport> {
+    let mut report = RotationReport { key_id, balances: 0, secrets: 0 };
+
+    // Balances that could not be bound to their user are left to reconciliation to report
+    let users: Vec<(i32, String)> = conn.prepare("Select id, balance From users Where balance_bound")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    for (user_id, encrypted) in users {
+        let version = ledger::balance_version(conn, user_id)?;
+        let reencrypted = crypto::reencrypt_balance(user_id, version, &encrypted, key_id).map_err(|e| {
+            rusqlite::Error::InvalidParameterName(format!("Balance of User ID: {} cannot be re-encrypted: {}", user_id, e))
+        })?;
+        conn.execute("Update users Set balance = ?1 Where id = ?2", rusqlite::params![reencrypted, user_id])?;
+        report.balances += 1;
+    }
+
+    for (table, key_column, column) in SECRET_COLUMNS {
+        let rows: Vec<(i64, String)> = conn.prepare(&format!("Select {}, {} From {}", key_column, column, table))?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        for (id, encrypted) in rows {
+            let reencrypted = crypto::reencrypt_secret(&encrypted, key_id).map_err(|e| {
+                rusqlite::Error::InvalidParameterName(format!("{}.{} of row {} cannot be re-encrypted: {}", table, column, id, e))
+            })?;
+            conn.execute(
+                &format!("Update {} Set {} = ?1 Where {} = ?2", table, column, key_column),
+                rusqlite::params![reencrypted, id],
+            )?;
+            report.secrets += 1;
+        }
+    }
+    Ok(report)
+}
+
+/// Generate a new encryption key, re-encrypt everything with it and make it the active key - REQUIRES keys.rotate
+pub fn rotate(conn: &Connection, admin: &User) -> rusqlite::Result<RotationReport> {
+    if authorization::require_permission(conn, admin, Permission::KeysRotate).is_err() {
+        return Err(rusqlite::Error::InvalidParameterName("Permission keys.rotate required".to_string()));
+    }
+
+    // The key is saved before any data depends on it
+    let key_id = crypto::generate_next_key().map_err(rusqlite::Error::InvalidParameterName)?;
+
+    let tx = conn.unchecked_transaction()?;
+    let report = reencrypt_all(&tx, key_id).inspect_err(|e| {
+        logger::critical(&format!("Encryption key rotation by User ID: {} failed, nothing was re-encrypted: {}", admin.id, e));
+    })?;
+    let details = format!("key {}: {} balances, {} secrets re-encrypted", key_id, report.balances, report.secrets);
+    user_admin::record_admin_action(&tx, admin, None, "rotate_encryption_key", &details)?;
+    tx.commit()?;
+
+    // Older keys still decrypt, so data written before activation stays readable
+    crypto::activate_key(key_id).map_err(|e| {
+        logger::critical(&format!("Encryption key {} could not be activated after re-encryption: {}", key_id, e));
+        rusqlite::Error::InvalidParameterName(e)
+    })?;
+    logger::security(&format!("Encryption key rotated by User ID: {}: {}", admin.id, details));
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::dbqueries;
+    use crate::db::money::Money;
+    use crate::db::test_support::{self, create_user};
+    use crate::db::two_factor;
+
+    fn setup_test_db() -> Connection {
+        crypto::isolate_keyring();
+        test_support::setup_test_db()
+    }
+
+    #[test]
+    fn test_reencrypt_all_moves_every_column_to_the_new_key() {
+        let conn = setup_test_db();
+        let user = create_user(&conn, "rotated");
+        dbqueries::change_balance(&conn, &user, Money::from_dollars(75)).unwrap();
+        let seed = dbqueries::get_active_fair_seed(&conn, &user).unwrap();
+        two_factor::enable(&conn, user.id, &crate::cryptography::totp::generate_secret(), 0).unwrap();
+
+        // Added to this test's own keyring, other tests never see it
+        let key_id = crypto::add_key(crypto::generate_encryption_key()).unwrap();
+        assert!(!key_usage(&conn).unwrap().contains_key(&key_id));
+
+        let tx = conn.unchecked_transaction().unwrap();
+        let report = reencrypt_all(&tx, key_id).unwrap();
+        tx.commit().unwrap();
+        assert_eq!(report.secrets, 2);
+        assert_eq!(key_usage(&conn).unwrap().keys().collect::<Vec<_>>(), vec![&key_id]);
+
+        assert_eq!(user.get_balance(&conn).unwrap(), Money::from_dollars(75));
+        assert_eq!(dbqueries::get_active_fair_seed(&conn, &user).unwrap().server_seed, seed.server_seed);
+        assert!(two_factor::is_enabled(&conn, user.id).unwrap());
+        dbqueries::change_balance(&conn, &user, -Money::from_dollars(25)).unwrap();
+        assert_eq!(user.get_balance(&conn).unwrap(), Money::from_dollars(50));
+    }
+
+    #[test]
+    fn test_tampered_balance_stops_reencryption() {
+        let conn = setup_test_db();
+        let technician = User { id: dbqueries::get_user_id_by_username(&conn, "technician").unwrap() };
+        let commissioner = User { id: dbqueries::get_user_id_by_username(&conn, "commissioner").unwrap() };
+        conn.execute(
+            "Update users Set balance = (Select balance From users Where id = ?1) Where id = ?2",
+            [technician.id, commissioner.id]
+        ).unwrap();
+
+        let key_id = crypto::add_key(crypto::generate_encryption_key()).unwrap();
+        assert!(reencrypt_all(&conn, key_id).is_err());
+    }
+
+    #[test]
+    fn test_keys_added_by_a_test_stay_in_its_keyring() {
+        crypto::isolate_keyring();
+        let key_id = crypto::add_key(crypto::generate_encryption_key()).unwrap();
+        let secret = crypto::encrypt_secret("secret").unwrap();
+        assert!(crypto::reencrypt_secret(&secret, key_id).is_ok());
+
+        // Another test's thread uses the global keyring, which never got the key
+        let other = std::thread::spawn(move || crypto::reencrypt_secret(&secret, key_id).is_err());
+        assert!(other.join().unwrap());
+    }
+
+    #[test]
+    fn test_rotation_requires_permission() {
+        let conn = setup_test_db();
+        let player = create_user(&conn, "not_staff");
+        assert!(rotate(&conn, &player).is_err());
+    }
+}
@@ -0,0 +1,451 @@
+use rusqlite::Connection;
+
+use crate::db::money::Money;
+use crate::logger::event::{LogEvent, TransactionKind};
+use crate::logger::logger;
+
+// Kind of balance movement recorded in the ledger
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryType {
+    Deposit,
+    Withdrawal,
+    Bet,
+    HoldCharge,
+    Win,
+    Refund,
+    Adjustment,
+}
+
+impl EntryType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryType::Deposit => "deposit",
+            EntryType::Withdrawal => "withdrawal",
+            EntryType::Bet => "bet",
+            EntryType::HoldCharge => "hold_charge",
+            EntryType::Win => "win",
+            EntryType::Refund => "refund",
+            EntryType::Adjustment => "adjustment",
+        }
+    }
+
+    // The account on the other side of the player's account
+    pub fn counter_account(&self) -> &'static str {
+        match self {
+            EntryType::Deposit | EntryType::Withdrawal => "cashier",
+            EntryType::Bet | EntryType::HoldCharge | EntryType::Win | EntryType::Refund => "house",
+            EntryType::Adjustment => "adjustment",
+        }
+    }
+}
+
+// Post one balance movement as a pair of entries: `amount` to the player's account and `-amount`
+// to the counter account, so every ledger transaction sums to zero. Call it inside the same
+// database transaction that updates users.balance.
+pub fn post(conn: &Connection, user_id: i32, entry_type: EntryType, amount: Money, round_id: Option<i64>) -> rusqlite::Result<i64> {
+    let transaction_id: i64 = conn.query_row(
+        "Select Coalesce(Max(transaction_id), 0) + 1 From ledger_entries",
+        [],
+        |row| row.get(0)
+    )?;
+
+    for (account, leg) in [("player", amount), (entry_type.counter_account(), -amount)] {
+        conn.execute(
+            "Insert Into ledger_entries (transaction_id, user_id, account, entry_type, amount_cents, round_id)
+            Values (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![transaction_id, user_id, account, entry_type.as_str(), leg, round_id],
+        )?;
+    }
+
+    Ok(transaction_id)
+}
+
+// Balance of a player recomputed from the ledger
+pub fn ledger_balance(conn: &Connection, user_id: i32) -> rusqlite::Result<Money> {
+    conn.query_row(
+        "Select Coalesce(Sum(amount_cents), 0) From ledger_entries Where user_id = ?1 And account = 'player'",
+        [user_id],
+        |row| row.get(0)
+    )
+}
+
+// SECURITY: The version a player's encrypted balance is bound to is the number of entries in their
+// ledger account. Each balance change posts one, and the ledger is append-only, so the version lives
+// outside the users row and writing an older row back, version and all, cannot bring it back in step.
+pub fn balance_version(conn: &Connection, user_id: i32) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "Select Count(*) From ledger_entries Where user_id = ?1 And account = 'player'",
+        [user_id],
+        |row| row.get(0)
+    )
+}
+
+// The stake the player paid for a round, read from the round's bet entries so a refund returns
+// exactly what was taken
+pub fn round_stake(conn: &Connection, user_id: i32, round_id: i64) -> rusqlite::Result<Money> {
+    let paid: Money = conn.query_row(
+        "Select Coalesce(Sum(amount_cents), 0) From ledger_entries
+        Where user_id = ?1 And round_id = ?2 And account = 'player' And entry_type = ?3",
+        rusqlite::params![user_id, round_id, EntryType::Bet.as_str()],
+        |row| row.get(0)
+    )?;
+    if !paid.is_negative() {
+        return Err(rusqlite::Error::InvalidParameterName(format!("No stake recorded for round {}", round_id)));
+    }
+    Ok(-paid)
+}
+
+// Result of comparing one player's stored balance with the ledger. A stored balance that cannot
+// be decrypted holds the reason instead, and never matches.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reconciliation {
+    pub user_id: i32,
+    pub stored_balance: Result<Money, String>,
+    pub ledger_balance: Money,
+}
+
+impl Reconciliation {
+    pub fn matches(&self) -> bool {
+        self.stored_balance == Ok(self.ledger_balance)
+    }
+}
+
+// Compare every user's stored encrypted balance with the ledger sum, and check that every ledger
+// transaction balances. Raises a CRITICAL alert for each discrepancy and returns the comparisons.
+pub fn reconcile(conn: &Connection) -> rusqlite::Result<Vec<Reconciliation>> {
+    logger::info("Reconciling stored balances with the ledger");
+
+    let mut stmt = conn.prepare("Select id, balance, balance_bound From users Order By id")?;
+    let users = stmt.query_map([], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?, row.get::<_, bool>(2)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut results = Vec::new();
+    for (user_id, encrypted, bound) in users {
+        let stored_balance = decrypt_stored_balance(user_id, bound, balance_version(conn, user_id)?, &encrypted);
+        let result = Reconciliation { user_id, stored_balance, ledger_balance: ledger_balance(conn, user_id)? };
+        match &result.stored_balance {
+            Err(e) => logger::critical(&format!(
+                "Stored balance of User ID: {} cannot be decrypted for reconciliation: {}", user_id, e
+            )),
+            Ok(stored) if !result.matches() => logger::critical(&format!(
+                "Balance mismatch for User ID: {}: stored {}, ledger {}", user_id, stored, result.ledger_balance
+            )),
+            Ok(_) => {}
+        }
+        results.push(result);
+    }
+
+    let mut stmt = conn.prepare(
+        "Select transaction_id, Sum(amount_cents) From ledger_entries Group By transaction_id Having Sum(amount_cents) != 0"
+    )?;
+    let unbalanced = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Money>(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (transaction_id, sum) in unbalanced {
+        logger::critical(&format!("Ledger transaction {} does not balance: entries sum to {}", transaction_id, sum));
+    }
+
+    Ok(results)
+}
+
+// Balances that are not bound could not be bound to their user when the database was upgraded
+fn decrypt_stored_balance(user_id: i32, bound: bool, version: i64, encrypted: &str) -> Result<Money, String> {
+    if !bound {
+        return Err("Balance is not bound to the account".to_string());
+    }
+    crate::cryptography::crypto::decrypt_balance(user_id, version, encrypted)
+}
+
+// Give balances that predate the ledger an opening adjustment so they reconcile. The adjustment
+// is a new ledger entry, so the balance is encrypted again as the next version.
+pub fn add_opening_balances(conn: &Connection) -> rusqlite::Result<()> {
+    use crate::cryptography::crypto::encrypt_balance;
+
+    let mut stmt = conn.prepare(
+        "Select id, balance, balance_bound From users Where id Not In (Select Distinct user_id From ledger_entries)"
+    )?;
+    let users = stmt.query_map([], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?, row.get::<_, bool>(2)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    for (user_id, encrypted, bound) in users {
+        // Left for reconciliation to report rather than blocking startup
+        let balance = match decrypt_stored_balance(user_id, bound, 0, &encrypted) {
+            Ok(balance) => balance,
+            Err(e) => {
+                logger::error(&format!("Cannot post opening ledger balance for User ID: {}: {}", user_id, e));
+                continue;
+            }
+        };
+        if balance != Money::ZERO {
+            let tx = conn.unchecked_transaction()?;
+            post(&tx, user_id, EntryType::Adjustment, balance, None)?;
+            let encrypted = encrypt_balance(user_id, 1, balance).map_err(rusqlite::Error::InvalidParameterName)?;
+            tx.execute("Update users Set balance = ?1 Where id = ?2", rusqlite::params![encrypted, user_id])?;
+            tx.commit()?;
+            logger::event(LogEvent::balance_changed(TransactionKind::OpeningBalance, user_id, balance, balance));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::dbqueries;
+    use crate::db::test_support::{create_user, setup_test_db};
+
+    #[test]
+    fn test_entries_balance_and_recompute() {
+        let conn = setup_test_db();
+        let user = create_user(&conn, "ledgeruser");
+
+        conn.execute(
+            "Insert Into rounds (user_id, game_name, bet_cents, grid, payout_cents, balance_cents) Values (?1, 'normal', 500, '', 0, 0)",
+            [user.id]
+        ).unwrap();
+        let round_id = conn.last_insert_rowid();
+        post(&conn, user.id, EntryType::Deposit, Money::from_dollars(100), None).unwrap();
+        post(&conn, user.id, EntryType::Bet, -Money::from_dollars(5), Some(round_id)).unwrap();
+        post(&conn, user.id, EntryType::Win, Money::from_cents(1250), Some(round_id)).unwrap();
+        assert_eq!(ledger_balance(&conn, user.id).unwrap(), Money::from_cents(10750));
+
+        let house: Money = conn.query_row(
+            "Select Sum(amount_cents) From ledger_entries Where account = 'house'", [], |row| row.get(0)
+        ).unwrap();
+        assert_eq!(house, Money::from_cents(-750));
+    }
+
+    #[test]
+    fn test_ledger_is_append_only() {
+        let conn = setup_test_db();
+        let user = create_user(&conn, "appendonly");
+        post(&conn, user.id, EntryType::Deposit, Money::from_dollars(10), None).unwrap();
+
+        assert!(conn.execute("Update ledger_entries Set amount_cents = 100000", []).is_err());
+        assert!(conn.execute("Delete From ledger_entries", []).is_err());
+    }
+
+    #[test]
+    fn test_reconcile_detects_tampered_balance() {
+        use crate::cryptography::crypto::encrypt_balance;
+
+        let conn = setup_test_db();
+        let user = create_user(&conn, "reconciled");
+        post(&conn, user.id, EntryType::Deposit, Money::from_dollars(40), None).unwrap();
+        conn.execute(
+            "Update users Set balance = ?1 Where id = ?2",
+            rusqlite::params![encrypt_balance(user.id, 1, Money::from_dollars(40)).unwrap(), user.id]
+        ).unwrap();
+        let find = |results: Vec<Reconciliation>| results.into_iter().find(|r| r.user_id == user.id).unwrap();
+        assert!(find(reconcile(&conn).unwrap()).matches());
+
+        conn.execute(
+            "Update users Set balance = ?1 Where id = ?2",
+            rusqlite::params![encrypt_balance(user.id, 1, Money::from_dollars(1_000_000)).unwrap(), user.id]
+        ).unwrap();
+        let result = find(reconcile(&conn).unwrap());
+        assert!(!result.matches());
+        assert_eq!(result.ledger_balance, Money::from_dollars(40));
+    }
+
+    #[test]
+    fn test_copied_or_rolled_back_balance_fails_authentication() {
+        let conn = setup_test_db();
+        let rich = create_user(&conn, "rich");
+        let poor = create_user(&conn, "poor");
+        dbqueries::change_balance(&conn, &rich, Money::from_dollars(1000)).unwrap();
+        let rich_balance: String = conn.query_row("Select balance From users Where id = ?1", [rich.id], |row| row.get(0)).unwrap();
+
+        // Copying another user's ciphertext does not decrypt
+        conn.execute("Update users Set balance = ?1 Where id = ?2", rusqlite::params![rich_balance, poor.id]).unwrap();
+        assert!(poor.get_balance(&conn).is_err());
+        assert!(dbqueries::change_balance(&conn, &poor, -Money::from_dollars(1000)).is_err());
+        // and reconciliation counts it as a failure instead of skipping it
+        let result = reconcile(&conn).unwrap().into_iter().find(|result| result.user_id == poor.id).unwrap();
+        assert!(result.stored_balance.is_err());
+        assert!(!result.matches());
+
+        // Writing an older ciphertext back over a newer balance does not decrypt either
+        dbqueries::change_balance(&conn, &rich, -Money::from_dollars(900)).unwrap();
+        conn.execute("Update users Set balance = ?1 Where id = ?2", rusqlite::params![rich_balance, rich.id]).unwrap();
+        assert!(rich.get_balance(&conn).is_err());
+    }
+
+    #[test]
+    fn test_whole_row_rolled_back_fails_authentication() {
+        let conn = setup_test_db();
+        let user = create_user(&conn, "rollback");
+        dbqueries::change_balance(&conn, &user, Money::from_dollars(1000)).unwrap();
+        conn.execute("Create Temp Table snapshot As Select * From users Where id = ?1", [user.id]).unwrap();
+        dbqueries::change_balance(&conn, &user, -Money::from_dollars(900)).unwrap();
+
+        // Every column of the row written back together, as from a backup
+        conn.execute("Insert Or Replace Into users Select * From snapshot", []).unwrap();
+        assert!(user.get_balance(&conn).is_err());
+        assert!(dbqueries::change_balance(&conn, &user, -Money::from_dollars(1000)).is_err());
+        let result = reconcile(&conn).unwrap().into_iter().find(|result| result.user_id == user.id).unwrap();
+        assert!(!result.matches());
+        assert_eq!(result.ledger_balance, Money::from_dollars(100));
+    }
+
+    #[test]
+    fn test_opening_balances_for_pre_ledger_users() {
+        use crate::cryptography::crypto::encrypt_balance;
+
+        let conn = setup_test_db();
+        let user = create_user(&conn, "preledger");
+        conn.execute(
+            "Update users Set balance = ?1 Where id = ?2",
+            rusqlite::params![encrypt_balance(user.id, 0, Money::from_dollars(25)).unwrap(), user.id]
+        ).unwrap();
+
+        add_opening_balances(&conn).unwrap();
+        add_opening_balances(&conn).unwrap();
+        assert_eq!(ledger_balance(&conn, user.id).unwrap(), Money::from_dollars(25));
+        // The opening adjustment is a new version of the balance
+        assert_eq!(user.get_balance(&conn).unwrap(), Money::from_dollars(25));
+    }
+
+    #[test]
+    fn test_balance_versions_kept_in_the_row_move_to_the_ledger() {
+        use crate::cryptography::crypto::encrypt_balance;
+
+        let conn = setup_test_db();
+        let user = create_user(&conn, "rowversion");
+        dbqueries::change_balance(&conn, &user, Money::from_dollars(30)).unwrap();
+
+        // Recreate a balance bound to a counter in its own row, which had gone up three times
+        conn.execute_batch("Alter Table users Add Column balance_version Integer").unwrap();
+        conn.execute(
+            "Update users Set balance = ?1, balance_version = 3 Where id = ?2",
+            rusqlite::params![encrypt_balance(user.id, 3, Money::from_dollars(30)).unwrap(), user.id]
+        ).unwrap();
+
+        crate::db::dbinitialize::initialize_dbs(&conn).unwrap();
+        crate::db::dbinitialize::initialize_dbs(&conn).unwrap();
+
+        assert_eq!(user.get_balance(&conn).unwrap(), Money::from_dollars(30));
+        assert!(reconcile(&conn).unwrap().iter().all(|result| result.matches()));
+        assert!(conn.prepare("Select balance_version From users").is_err());
+    }
+
+    #[test]
+    fn test_dollar_balances_and_ledger_migrate_to_cents() {
+        use crate::cryptography::crypto::{decrypt_balance, encrypt_secret};
+
+        let conn = setup_test_db();
+        let user = create_user(&conn, "legacy");
+
+        // Recreate the state left by earlier versions: a dollar float balance and ledger
+        conn.execute_batch(
+            "Drop Table ledger_entries;
+            Create Table ledger_entries (
+                id Integer Primary Key, transaction_id Integer Not Null, user_id Integer Not Null,
+                account Text Not Null, entry_type Text Not Null, amount Real Not Null,
+                round_id Integer, created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            );"
+        ).unwrap();
+        conn.execute(
+            "Insert Into ledger_entries (transaction_id, user_id, account, entry_type, amount)
+            Values (1, ?1, 'player', 'deposit', 12.1), (1, ?1, 'cashier', 'deposit', -12.1)",
+            [user.id]
+        ).unwrap();
+        conn.execute(
+            "Update users Set balance = ?1, balance_bound = 0 Where id = ?2",
+            rusqlite::params![encrypt_secret("12.1").unwrap(), user.id]
+        ).unwrap();
+        assert!(decrypt_balance(user.id, 0, &encrypt_secret("cents:1210").unwrap()).is_err());
+
+        crate::db::dbinitialize::initialize_dbs(&conn).unwrap();
+
+        assert_eq!(ledger_balance(&conn, user.id).unwrap(), Money::from_cents(1210));
+        let stored: String = conn.query_row("Select balance From users Where id = ?1", [user.id], |row| row.get(0)).unwrap();
+        assert_eq!(decrypt_balance(user.id, balance_version(&conn, user.id).unwrap(), &stored).unwrap(), Money::from_cents(1210));
+        assert!(reconcile(&conn).unwrap().iter().all(|result| result.matches()));
+        // The migrated table is append-only again
+        assert!(conn.execute("Delete From ledger_entries", []).is_err());
+    }
+
+    #[test]
+    fn test_refunds_posted_as_bets_migrate_to_refunds() {
+        let conn = setup_test_db();
+        let user = create_user(&conn, "legacyrefund");
+        conn.execute(
+            "Insert Into rounds (user_id, game_name, bet_cents, grid, payout_cents, balance_cents) Values (?1, 'normal', 500, '', 0, 0)",
+            [user.id]
+        ).unwrap();
+        let round_id = conn.last_insert_rowid();
+
+        // Recreate the ledger of earlier versions, which had no refund entry type
+        conn.execute_batch(
+            "Drop Table ledger_entries;
+            Create Table ledger_entries (
+                id Integer Primary Key, transaction_id Integer Not Null, user_id Integer Not Null,
+                account Text Not Null Check (account In ('player', 'house', 'cashier', 'adjustment')),
+                entry_type Text Not Null Check (entry_type In ('deposit', 'withdrawal', 'bet', 'hold_charge', 'win', 'adjustment')),
+                amount_cents Integer Not Null, round_id Integer, created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            );"
+        ).unwrap();
+        conn.execute(
+            "Insert Into ledger_entries (transaction_id, user_id, account, entry_type, amount_cents, round_id)
+            Values (1, ?1, 'player', 'bet', -500, ?2), (1, ?1, 'house', 'bet', 500, ?2),
+                (2, ?1, 'player', 'bet', 500, ?2), (2, ?1, 'house', 'bet', -500, ?2)",
+            [user.id, round_id as i32]
+        ).unwrap();
+
+        crate::db::dbinitialize::initialize_dbs(&conn).unwrap();
+
+        let types: Vec<String> = conn.prepare("Select entry_type From ledger_entries Order By id").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .collect::<rusqlite::Result<_>>().unwrap();
+        assert_eq!(types, vec!["bet", "bet", "refund", "refund"]);
+        assert_eq!(round_stake(&conn, user.id, round_id).unwrap(), Money::from_dollars(5));
+        post(&conn, user.id, EntryType::Refund, Money::from_dollars(1), Some(round_id)).unwrap();
+        assert!(conn.execute("Delete From ledger_entries", []).is_err());
+    }
+
+    #[test]
+    fn test_dollar_round_amounts_migrate_to_cents() {
+        let conn = setup_test_db();
+        let user = create_user(&conn, "legacyrounds");
+
+        // Recreate the round history and statistics tables as earlier versions kept them, in dollars
+        conn.execute_batch(
+            "Drop Table rounds;
+            Create Table rounds (
+                id Integer Primary Key, user_id Integer Not Null, game_name Text Not Null,
+                timestamp DATETIME DEFAULT CURRENT_TIMESTAMP, bet Real Not Null, hold_charges Real Not Null Default 0.0,
+                grid Text Not Null, lines Text Not Null Default '', payout Real Not Null, balance Real Not Null,
+                fair_round_id Integer, status Text Not Null Default 'settled'
+            );
+            Drop Table user_statistics;
+            Create Table user_statistics (
+                id Integer Primary Key, user_id Integer Not Null, game_id Integer Not Null,
+                win Integer, loss Integer, highest_payout Real, last_played Text
+            );"
+        ).unwrap();
+        conn.execute(
+            "Insert Into rounds (user_id, game_name, bet, hold_charges, grid, payout, balance)
+            Values (?1, 'holding', 0.3, 0.1, '', 12.1, 99.99)",
+            [user.id]
+        ).unwrap();
+        conn.execute(
+            "Insert Into user_statistics (user_id, game_id, win, loss, highest_payout, last_played)
+            Values (?1, 1, 1, 0, 12.1, 'yesterday')",
+            [user.id]
+        ).unwrap();
+
+        crate::db::dbinitialize::initialize_dbs(&conn).unwrap();
+        crate::db::dbinitialize::initialize_dbs(&conn).unwrap();
+
+        let rounds = dbqueries::get_user_rounds(&conn, &user, 0, 10).unwrap();
+        assert_eq!(rounds.len(), 1);
+        assert_eq!(rounds[0].bet, Money::from_cents(30));
+        assert_eq!(rounds[0].hold_charges, Money::from_cents(10));
+        assert_eq!(rounds[0].payout, Money::from_cents(1210));
+        assert_eq!(rounds[0].balance, Money::from_cents(9999));
+        let highest: Money = conn.query_row(
+            "Select highest_payout_cents From user_statistics Where user_id = ?1", [user.id], |row| row.get(0)
+        ).unwrap();
+        assert_eq!(highest, Money::from_cents(1210));
+    }
+}
@@ -1,3 +1,12 @@
 pub mod dbqueries;
 pub mod dbinitialize;
-pub mod validator;
\ No newline at end of file
+pub mod validator;
+pub mod ledger;
+pub mod money;
+pub mod transaction_limits;
+pub mod user_admin;
+pub mod two_factor;
+pub mod key_rotation;
+pub mod startup_check;
+#[cfg(test)]
+pub mod test_support;
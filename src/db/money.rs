@@ -0,0 +1,222 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+
+pub const CENTS_PER_DOLLAR: i64 = 100;
+
+// An amount of money in whole cents. Every balance, bet, charge and payout is a Money, so sums
+// are exact; f64 is only used for ratios such as multipliers and for statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub const fn from_cents(cents: i64) -> Self {
+        Money(cents)
+    }
+
+    pub const fn from_dollars(dollars: i64) -> Self {
+        Money(dollars * CENTS_PER_DOLLAR)
+    }
+
+    pub fn cents(self) -> i64 {
+        self.0
+    }
+
+    // Nearest cent to a dollar amount, for values stored before amounts were kept in cents.
+    // None for NaN, infinity or amounts too large to hold.
+    pub fn from_f64(dollars: f64) -> Option<Self> {
+        let cents = (dollars * CENTS_PER_DOLLAR as f64).round();
+        if cents.is_finite() && cents.abs() < i64::MAX as f64 {
+            Some(Money(cents as i64))
+        } else {
+            None
+        }
+    }
+
+    // Dollar value as a float, for statistics and reports only
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / CENTS_PER_DOLLAR as f64
+    }
+
+    // Parse a dollar amount typed by a person, e.g. "12", "12.5" or "12.50". Exact: anything finer
+    // than a cent is rejected rather than rounded.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        let (negative, digits) = match input.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, input),
+        };
+        let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+
+        let all_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+        if (whole.is_empty() && fraction.is_empty()) || !all_digits(whole) || !all_digits(fraction) {
+            return Err(format!("'{}' is not an amount of money", input));
+        }
+        if fraction.len() > 2 {
+            return Err("Amounts can have at most 2 decimal places".to_string());
+        }
+
+        let whole: i64 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| "Amount is too large".to_string())? };
+        let fraction: i64 = format!("{:0<2}", fraction).parse().unwrap_or(0);
+        let cents = whole.checked_mul(CENTS_PER_DOLLAR)
+            .and_then(|cents| cents.checked_add(fraction))
+            .ok_or_else(|| "Amount is too large".to_string())?;
+        Ok(Money(if negative { -cents } else { cents }))
+    }
+
+    // Scale by a multiplier, e.g. a bet times a paytable multiplier. Fractions of a cent round to
+    // the nearest cent and exact halves round to the even cent, so neither the player nor the
+    // house gains from rounding on average.
+    pub fn times(self, factor: f64) -> Self {
+        let exact = self.0 as f64 * factor;
+        // Strip float noise so amounts like 12.5 cents stay exact halves
+        let exact = (exact * 1e6).round() / 1e6;
+        let floor = exact.floor();
+        let cents = match exact - floor {
+            diff if diff > 0.5 => floor + 1.0,
+            diff if diff < 0.5 => floor,
+            _ if floor % 2.0 == 0.0 => floor,
+            _ => floor + 1.0,
+        };
+        Money(cents as i64)
+    }
+
+    pub fn is_positive(self) -> bool {
+        self.0 > 0
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+}
+
+impl fmt::Display for Money {
+    // Plain dollars with two decimals. Width and alignment are honoured like a number's,
+    // a precision is ignored since there are always exactly two decimals.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let cents = self.0.unsigned_abs();
+        let text = format!("{}{}.{:02}", sign, cents / CENTS_PER_DOLLAR as u64, cents % CENTS_PER_DOLLAR as u64);
+        let width = f.width().unwrap_or(0);
+        match f.align() {
+            Some(fmt::Alignment::Left) => write!(f, "{:<width$}", text),
+            Some(fmt::Alignment::Center) => write!(f, "{:^width$}", text),
+            _ => write!(f, "{:>width$}", text),
+        }
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+    fn sub(self, other: Money) -> Money {
+        Money(self.0 - other.0)
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        self.0 += other.0;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        self.0 -= other.0;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+// Stored as integer cents
+impl ToSql for Money {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.0))
+    }
+}
+
+impl FromSql for Money {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Integer(cents) => Ok(Money(cents)),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_exact_amounts() {
+        assert_eq!(Money::parse("12").unwrap(), Money::from_cents(1200));
+        assert_eq!(Money::parse("12.5").unwrap(), Money::from_cents(1250));
+        assert_eq!(Money::parse(" 0.07 ").unwrap(), Money::from_cents(7));
+        assert_eq!(Money::parse(".5").unwrap(), Money::from_cents(50));
+        assert_eq!(Money::parse("-3.10").unwrap(), Money::from_cents(-310));
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_input() {
+        for input in ["", ".", "abc", "1.2.3", "12.345", "1e3", "NaN", "inf", "--1", "99999999999999999999"] {
+            assert!(Money::parse(input).is_err(), "{} should be rejected", input);
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Money::from_cents(1205).to_string(), "12.05");
+        assert_eq!(Money::from_cents(-5).to_string(), "-0.05");
+        assert_eq!(Money::ZERO.to_string(), "0.00");
+        assert_eq!(format!("{:<7}|", Money::from_cents(100)), "1.00   |");
+        assert_eq!(format!("{:>6.2}|", Money::from_cents(100)), "  1.00|");
+    }
+
+    #[test]
+    fn test_times_rounds_half_to_even() {
+        assert_eq!(Money::from_dollars(10).times(2.5), Money::from_cents(2500));
+        // 0.25 * 0.5 = 12.5 cents and 0.35 * 0.5 = 17.5 cents
+        assert_eq!(Money::from_cents(25).times(0.5), Money::from_cents(12));
+        assert_eq!(Money::from_cents(35).times(0.5), Money::from_cents(18));
+        assert_eq!(Money::from_cents(100).times(1.0 / 3.0), Money::from_cents(33));
+        assert_eq!(Money::from_cents(100).times(2.0 / 3.0), Money::from_cents(67));
+        assert_eq!(Money::from_cents(3).times(1.1), Money::from_cents(3));
+    }
+
+    #[test]
+    fn test_from_f64_rounds_to_nearest_cent() {
+        assert_eq!(Money::from_f64(0.1 + 0.2), Some(Money::from_cents(30)));
+        assert_eq!(Money::from_f64(99.999), Some(Money::from_cents(10000)));
+        assert_eq!(Money::from_f64(f64::NAN), None);
+        assert_eq!(Money::from_f64(f64::INFINITY), None);
+    }
+
+    #[test]
+    fn test_arithmetic_is_exact() {
+        let total: Money = (0..10).map(|_| Money::from_cents(10)).sum();
+        assert_eq!(total, Money::from_dollars(1));
+        assert_eq!(Money::from_dollars(1) - Money::from_cents(30), Money::from_cents(70));
+    }
+}
@@ -0,0 +1,152 @@
+use std::fs;
+use std::path::Path;
+
+use crate::cryptography::crypto;
+use crate::logger::logger;
+
+// SAFETY: Startup integrity check.
+// Decides from the files on disk whether this is a fresh install, a normal start, or a damaged
+// install that must not start. Only the log is recreated when missing; a database without its key,
+// or a key without its database, stops the casino with an explanation instead of starting over.
+// Nothing is ever deleted here: starting from scratch takes the explicit `--reset` command, and even
+// that moves the old files into a backup directory.
+
+pub const DATABASE_PATH: &str = "casino.db";
+// Command line argument asking to move the current install aside and start from scratch
+pub const RESET_ARGUMENT: &str = "--reset";
+
+// Which of the files a running casino needs are present
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StartupFiles {
+    pub database: bool,
+    pub key: bool,
+    pub log: bool,
+}
+
+// What startup does with the files it found
+#[derive(Debug, Clone, PartialEq)]
+pub enum StartupPlan {
+    // No database and no key: create both
+    FreshInstall,
+    // Database and key present, a missing log is recreated
+    Continue { recreate_log: bool },
+    // A component that cannot be recreated safely is missing
+    Refuse(String),
+}
+
+/// Look for the database, the encryption key (.env or key file) and the log.
+/// Call after .env is loaded.
+pub fn find_files() -> StartupFiles {
+    StartupFiles {
+        database: Path::new(DATABASE_PATH).exists(),
+        key: crypto::stored_key_exists(),
+        log: Path::new(logger::LOG_FILE_PATH).exists(),
+    }
+}
+
+/// Decide how to start from the files present
+pub fn diagnose(files: StartupFiles) -> StartupPlan {
+    match (files.database, files.key) {
+        (false, false) => StartupPlan::FreshInstall,
+        (true, true) => StartupPlan::Continue { recreate_log: !files.log },
+        // A new key could never decrypt the balances already in the database
+        (true, false) => StartupPlan::Refuse(format!(
+            "{} exists but its encryption key is missing: there is no CASINO_ENCRYPTION_KEY in {} and no {}.\n\
+             Every balance in the database is encrypted with that key, so a new one cannot be generated.\n\
+             Restore {} or {} from a backup and start again.",
+            DATABASE_PATH, crypto::ENV_FILE_PATH, crypto::KEY_FILE_PATH, crypto::ENV_FILE_PATH, crypto::KEY_FILE_PATH
+        )),
+        // An empty database in its place would hide that every account was lost
+        (false, true) => StartupPlan::Refuse(format!(
+            "The encryption key exists but {} is missing.\n\
+             Restore {} from a backup and start again. To deliberately start over with a new casino,\n\
+             run with {}: the current files are moved into a backup directory, not deleted.",
+            DATABASE_PATH, DATABASE_PATH, RESET_ARGUMENT
+        )),
+    }
+}
+
+/// Move the database, keys, .env and log into a new `casino-backup-<time>` directory so the next
+/// start is a fresh install. Returns the backup directory.
+pub fn move_to_backup() -> Result<String, String> {
+    let directory = format!("casino-backup-{}", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    fs::create_dir(&directory).map_err(|e| format!("Failed to create {}: {}", directory, e))?;
+    for file in [DATABASE_PATH, crypto::ENV_FILE_PATH, crypto::KEY_FILE_PATH, logger::LOG_FILE_PATH] {
+        if Path::new(file).exists() {
+            fs::rename(file, Path::new(&directory).join(file))
+                .map_err(|e| format!("Failed to move {} into {}: {}", file, directory, e))?;
+        }
+    }
+    Ok(directory)
+}
+
+/// On a fresh install, rename a log left by an earlier install to `<name>-<time>.log`. The new
+/// install chains its log with a new key, so entries appended to the old log would not verify.
+/// Returns the new name of the old log, None when there was no log.
+pub fn set_aside_old_log(log_path: &str) -> Result<Option<String>, String> {
+    let path = Path::new(log_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let aside = path.with_file_name(format!("{}-{}.log", stem, chrono::Local::now().format("%Y%m%d-%H%M%S")));
+    fs::rename(path, &aside).map_err(|e| format!("Failed to move {} to {}: {}", log_path, aside.display(), e))?;
+    Ok(Some(aside.display().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logger::event::LogEvent;
+    use crate::logger::logger::{LogLevel, Logger};
+
+    #[test]
+    fn test_only_the_log_is_recreated() {
+        assert_eq!(diagnose(StartupFiles { database: false, key: false, log: false }), StartupPlan::FreshInstall);
+        // An old log is set aside on a fresh install, see set_aside_old_log
+        assert_eq!(diagnose(StartupFiles { database: false, key: false, log: true }), StartupPlan::FreshInstall);
+        assert_eq!(
+            diagnose(StartupFiles { database: true, key: true, log: false }),
+            StartupPlan::Continue { recreate_log: true }
+        );
+        assert_eq!(
+            diagnose(StartupFiles { database: true, key: true, log: true }),
+            StartupPlan::Continue { recreate_log: false }
+        );
+    }
+
+    #[test]
+    fn test_missing_key_or_database_refuses_to_start() {
+        for log in [false, true] {
+            assert!(matches!(diagnose(StartupFiles { database: true, key: false, log }), StartupPlan::Refuse(_)));
+            assert!(matches!(diagnose(StartupFiles { database: false, key: true, log }), StartupPlan::Refuse(_)));
+        }
+    }
+
+    #[test]
+    fn test_log_chain_verifies_after_a_fresh_install() {
+        crate::cryptography::crypto::initialize_encryption_key();
+        let directory = std::env::temp_dir().join(format!("casino-fresh-install-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let log_path = directory.join("casino_logs.log").display().to_string();
+        let log_entry = |text: &str| Logger::new(&log_path).unwrap().log(&LogEvent::Message { level: LogLevel::Info, text }).unwrap();
+
+        // A log chained by an earlier install with a key that is gone
+        let old_log = format!("{{\"level\":\"INFO\",\"message\":\"Earlier install\",\"chain\":\"1:{}\"}}\n", "0".repeat(64));
+        fs::write(&log_path, &old_log).unwrap();
+        log_entry("Appended to the old log");
+        assert!(logger::verify_chain_file(&log_path).unwrap().first_break.is_some());
+
+        fs::write(&log_path, &old_log).unwrap();
+        let aside = set_aside_old_log(&log_path).unwrap().unwrap();
+        assert_eq!(fs::read_to_string(&aside).unwrap(), old_log);
+        log_entry("Application is starting");
+        log_entry("Database encryption initialized");
+        let report = logger::verify_chain_file(&log_path).unwrap();
+        assert_eq!(report.chained, 2);
+        assert_eq!(report.first_break, None);
+
+        assert_eq!(set_aside_old_log(&directory.join("missing.log").display().to_string()), Ok(None));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
\ No newline at end of file
@@ -0,0 +1,42 @@
+// Fixtures shared by the test modules
+use rusqlite::Connection;
+
+use crate::db::dbinitialize;
+use crate::db::dbqueries;
+use crate::db::money::Money;
+use crate::interfaces::user::User;
+
+pub const TEST_PASSWORD: &str = "Password123!@#";
+
+// An in-memory database set up the way main sets up the real one
+pub fn setup_test_db() -> Connection {
+    crate::cryptography::crypto::initialize_encryption_key();
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute("PRAGMA foreign_keys = ON", []).unwrap();
+    dbinitialize::initialize_dbs(&conn).unwrap();
+    conn
+}
+
+pub fn create_user(conn: &Connection, username: &str) -> User {
+    dbqueries::insert_users(conn, username, TEST_PASSWORD).unwrap();
+    User { id: dbqueries::get_user_id_by_username(conn, username).unwrap() }
+}
+
+pub fn create_account(conn: &Connection, username: &str, role: &str) -> User {
+    let user = create_user(conn, username);
+    conn.execute("Update users Set role = ?1 Where id = ?2", rusqlite::params![role, user.id]).unwrap();
+    user
+}
+
+pub fn funded_user(conn: &Connection, username: &str, deposit: Money) -> User {
+    let user = create_user(conn, username);
+    dbqueries::change_balance(conn, &user, deposit).unwrap();
+    user
+}
+
+// A registered game as configured by a fresh database
+pub fn configured_game(conn: &Connection, name: &str) -> Box<dyn crate::play::game::Game> {
+    let mut game = crate::play::game::find_game(name).unwrap();
+    game.load_config(conn).unwrap();
+    game
+}
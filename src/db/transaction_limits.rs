@@ -0,0 +1,273 @@
+use rusqlite::Connection;
+
+use crate::db::money::Money;
+use crate::logger::logger;
+
+// SECURITY: Anti-fraud limits on balance movements.
+// Cash-in and cash-out are where fraud happens, wagers are what a player does every few seconds,
+// so each category has its own policy. Win credits and other settlement movements are never limited:
+// once a bet has been taken its round always settles.
+// Recent transactions are kept in the transaction_log table, so limits survive restarts and apply to
+// every process sharing casino.db.
+
+// Window the limits are counted over
+const WINDOW_MILLIS: i64 = 60_000;
+// How long transactions are kept for technicians to look at
+const RETENTION_MILLIS: i64 = 3_600_000;
+
+// Kind of balance movement a policy applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransactionCategory {
+    // Deposits and withdrawals
+    Cash,
+    // Bets placed on a game
+    Wager,
+}
+
+impl TransactionCategory {
+    pub const ALL: [TransactionCategory; 2] = [TransactionCategory::Cash, TransactionCategory::Wager];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionCategory::Cash => "cash",
+            TransactionCategory::Wager => "wager",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TransactionCategory::Cash => "Deposits and withdrawals",
+            TransactionCategory::Wager => "Bets",
+        }
+    }
+
+    // Limits used until a technician changes them
+    pub fn default_policy(&self) -> TransactionPolicy {
+        match self {
+            TransactionCategory::Cash => TransactionPolicy { category: *self, max_per_minute: 5, identical_per_minute: 3 },
+            // Repeating the same bet is how slots are played, so identical wagers are not suspicious
+            TransactionCategory::Wager => TransactionPolicy { category: *self, max_per_minute: 60, identical_per_minute: 0 },
+        }
+    }
+}
+
+// Limits for one category of transaction. A limit of 0 is switched off.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransactionPolicy {
+    pub category: TransactionCategory,
+    // Transactions allowed per user per minute
+    pub max_per_minute: u32,
+    // Transactions of the same amount per user per minute before they are treated as suspicious
+    pub identical_per_minute: u32,
+}
+
+// Policy of a category as configured by the technician
+pub fn get_policy(conn: &Connection, category: TransactionCategory) -> rusqlite::Result<TransactionPolicy> {
+    conn.query_row(
+        "Select max_per_minute, identical_per_minute From transaction_policies Where category = ?1",
+        [category.as_str()],
+        |row| Ok(TransactionPolicy { category, max_per_minute: row.get(0)?, identical_per_minute: row.get(1)? })
+    )
+}
+
+pub fn get_policies(conn: &Connection) -> rusqlite::Result<Vec<TransactionPolicy>> {
+    TransactionCategory::ALL.iter().map(|category| get_policy(conn, *category)).collect()
+}
+
+pub fn set_policy(conn: &Connection, policy: &TransactionPolicy) -> rusqlite::Result<()> {
+    conn.execute(
+        "Insert Into transaction_policies (category, max_per_minute, identical_per_minute) Values (?1, ?2, ?3)
+        On Conflict(category) Do Update Set max_per_minute = ?2, identical_per_minute = ?3",
+        rusqlite::params![policy.category.as_str(), policy.max_per_minute, policy.identical_per_minute],
+    )?;
+    logger::security(&format!(
+        "Transaction policy for {} set to {} per minute, {} identical per minute",
+        policy.category.as_str(), policy.max_per_minute, policy.identical_per_minute
+    ));
+    Ok(())
+}
+
+// Add the default policies for categories that have none
+pub fn add_default_policies(conn: &Connection) -> rusqlite::Result<()> {
+    for category in TransactionCategory::ALL {
+        let policy = category.default_policy();
+        conn.execute(
+            "Insert Or Ignore Into transaction_policies (category, max_per_minute, identical_per_minute) Values (?1, ?2, ?3)",
+            rusqlite::params![category.as_str(), policy.max_per_minute, policy.identical_per_minute],
+        )?;
+    }
+    Ok(())
+}
+
+// Check a transaction against its category's policy before it is made.
+// Returns the reason it is blocked, already logged as a security alert.
+pub fn check(conn: &Connection, user_id: i32, category: TransactionCategory, amount: Money) -> Result<(), String> {
+    let policy = get_policy(conn, category).unwrap_or_else(|e| {
+        logger::error(&format!("Failed to load {} transaction policy, using the default: {}", category.as_str(), e));
+        category.default_policy()
+    });
+
+    // Only the last minute matters
+    let (recent, identical): (u32, u32) = conn.query_row(
+        "Select Count(*), Coalesce(Sum(amount_cents = ?3), 0) From transaction_log
+        Where user_id = ?1 And category = ?2 And created_at > ?4",
+        rusqlite::params![user_id, category.as_str(), amount, now_millis() - WINDOW_MILLIS],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).map_err(|e| {
+        // Fail closed: a transaction that cannot be checked is not allowed
+        logger::error(&format!("Failed to check transaction limits for User ID: {}: {}", user_id, e));
+        "Transaction limits could not be checked".to_string()
+    })?;
+
+    // SECURITY TIER 1: Rate limiting
+    if policy.max_per_minute > 0 && recent >= policy.max_per_minute {
+        let reason = format!("Rate limit: {} {} transactions per minute", policy.max_per_minute, category.as_str());
+        logger::security(&format!("SECURITY ALERT: Rate limit exceeded for User ID: {}: {}", user_id, reason));
+        return Err(reason);
+    }
+
+    // SECURITY TIER 2: Fraud pattern detection, rapid identical transactions
+    if policy.identical_per_minute > 0 && identical >= policy.identical_per_minute {
+        logger::security(&format!("SECURITY ALERT: Suspicious pattern detected for User ID: {}. Amount: {}", user_id, amount));
+        return Err("Suspicious activity detected".to_string());
+    }
+
+    Ok(())
+}
+
+// Count a transaction towards its category's limits. Call it inside the database transaction
+// that moves the money, so only committed transactions count.
+pub fn record(conn: &Connection, user_id: i32, category: TransactionCategory, amount: Money) -> rusqlite::Result<()> {
+    let now = now_millis();
+    conn.execute(
+        "Delete From transaction_log Where created_at <= ?1",
+        [now - RETENTION_MILLIS],
+    )?;
+    conn.execute(
+        "Insert Into transaction_log (user_id, category, amount_cents, created_at) Values (?1, ?2, ?3, ?4)",
+        rusqlite::params![user_id, category.as_str(), amount, now],
+    )?;
+    Ok(())
+}
+
+// Transactions a user made in the last minute in one category
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecentActivity {
+    pub user_id: i32,
+    pub username: String,
+    pub category: TransactionCategory,
+    pub transactions: u32,
+}
+
+// Everyone who has made transactions in the last minute, busiest first
+pub fn get_recent_activity(conn: &Connection) -> rusqlite::Result<Vec<RecentActivity>> {
+    let mut stmt = conn.prepare(
+        "Select t.user_id, u.username, t.category, Count(*) From transaction_log t
+        Join users u On u.id = t.user_id
+        Where t.created_at > ?1
+        Group By t.user_id, t.category
+        Order By Count(*) Desc, t.user_id"
+    )?;
+    let activity = stmt.query_map([now_millis() - WINDOW_MILLIS], |row| {
+        let category: String = row.get(2)?;
+        Ok(RecentActivity {
+            user_id: row.get(0)?,
+            username: row.get(1)?,
+            category: if category == TransactionCategory::Cash.as_str() { TransactionCategory::Cash } else { TransactionCategory::Wager },
+            transactions: row.get(3)?,
+        })
+    })?.collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(activity)
+}
+
+// Forget a user's recent transactions, lifting any rate limit they have hit.
+// Returns the number of transactions forgotten.
+pub fn clear_user(conn: &Connection, user_id: i32) -> rusqlite::Result<usize> {
+    conn.execute("Delete From transaction_log Where user_id = ?1", [user_id])
+}
+
+fn now_millis() -> i64 {
+    chrono::Local::now().timestamp_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support::{create_user, setup_test_db};
+
+    #[test]
+    fn test_default_policies_are_stored() {
+        let conn = setup_test_db();
+        for category in TransactionCategory::ALL {
+            assert_eq!(get_policy(&conn, category).unwrap(), category.default_policy());
+        }
+    }
+
+    #[test]
+    fn test_wagers_do_not_use_up_cash_limits() {
+        let conn = setup_test_db();
+        let user_id = create_user(&conn, "spinner").id;
+
+        for _ in 0..10 {
+            assert!(check(&conn, user_id, TransactionCategory::Wager, Money::from_dollars(1)).is_ok());
+            record(&conn, user_id, TransactionCategory::Wager, Money::from_dollars(1)).unwrap();
+        }
+        assert!(check(&conn, user_id, TransactionCategory::Cash, Money::from_dollars(1)).is_ok());
+
+        let activity = get_recent_activity(&conn).unwrap();
+        assert_eq!(activity.len(), 1);
+        assert_eq!(activity[0].username, "spinner");
+        assert_eq!(activity[0].category, TransactionCategory::Wager);
+        assert_eq!(activity[0].transactions, 10);
+    }
+
+    #[test]
+    fn test_rate_limit_and_identical_amounts() {
+        let conn = setup_test_db();
+        let user_id = create_user(&conn, "cashier").id;
+        set_policy(&conn, &TransactionPolicy { category: TransactionCategory::Cash, max_per_minute: 4, identical_per_minute: 2 }).unwrap();
+
+        for cents in [100, 100] {
+            assert!(check(&conn, user_id, TransactionCategory::Cash, Money::from_cents(cents)).is_ok());
+            record(&conn, user_id, TransactionCategory::Cash, Money::from_cents(cents)).unwrap();
+        }
+        // A third identical amount is suspicious, a different one is not
+        assert!(check(&conn, user_id, TransactionCategory::Cash, Money::from_cents(100)).is_err());
+        for cents in [200, 300] {
+            assert!(check(&conn, user_id, TransactionCategory::Cash, Money::from_cents(cents)).is_ok());
+            record(&conn, user_id, TransactionCategory::Cash, Money::from_cents(cents)).unwrap();
+        }
+        assert!(check(&conn, user_id, TransactionCategory::Cash, Money::from_cents(400)).is_err());
+
+        // A technician can lift the limit for one user
+        assert_eq!(clear_user(&conn, user_id).unwrap(), 4);
+        assert!(check(&conn, user_id, TransactionCategory::Cash, Money::from_cents(100)).is_ok());
+
+        // A limit of 0 is switched off
+        for _ in 0..5 {
+            record(&conn, user_id, TransactionCategory::Cash, Money::from_cents(100)).unwrap();
+        }
+        set_policy(&conn, &TransactionPolicy { category: TransactionCategory::Cash, max_per_minute: 0, identical_per_minute: 0 }).unwrap();
+        assert!(check(&conn, user_id, TransactionCategory::Cash, Money::from_cents(100)).is_ok());
+    }
+
+    #[test]
+    fn test_limits_survive_reconnecting() {
+        let path = std::env::temp_dir().join(format!("casino_limits_test_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        crate::cryptography::crypto::initialize_encryption_key();
+
+        let conn = Connection::open(&path).unwrap();
+        crate::db::dbinitialize::initialize_dbs(&conn).unwrap();
+        let user_id = create_user(&conn, "restarted").id;
+        for _ in 0..3 {
+            record(&conn, user_id, TransactionCategory::Cash, Money::from_dollars(20)).unwrap();
+        }
+        drop(conn);
+
+        let conn = Connection::open(&path).unwrap();
+        assert!(check(&conn, user_id, TransactionCategory::Cash, Money::from_dollars(20)).is_err());
+        drop(conn);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
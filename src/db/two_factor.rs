@@ -0,0 +1,253 @@
+use rusqlite::{Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+
+use crate::authentication::authorization;
+use crate::authentication::permissions::{self, Permission};
+use crate::cryptography::crypto::{decrypt_secret, encrypt_secret};
+use crate::cryptography::totp;
+use crate::db::user_admin;
+use crate::interfaces::user::User;
+use crate::logger::logger;
+
+// SECURITY: Two-factor authentication with TOTP authenticator apps.
+// Secrets are kept encrypted with the database encryption key, each time step is accepted once,
+// and recovery codes are single use and stored only as hashes.
+
+const RECOVERY_CODE_COUNT: usize = 10;
+// Policy making two-factor authentication mandatory for every role granting more than play
+const STAFF_POLICY: &str = "two_factor_required_for_staff";
+
+fn now() -> u64 {
+    chrono::Utc::now().timestamp() as u64
+}
+
+pub fn is_enabled(conn: &Connection, user_id: i32) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "Select Exists(Select 1 From two_factor Where user_id = ?1)",
+        [user_id],
+        |row| row.get(0)
+    )
+}
+
+// Whether policy makes two-factor authentication mandatory for staff
+pub fn is_required_for_staff(conn: &Connection) -> rusqlite::Result<bool> {
+    conn.query_row("Select value From security_policies Where name = ?1", [STAFF_POLICY], |row| row.get(0))
+}
+
+// Whether an account has to use two-factor authentication
+pub fn is_required(conn: &Connection, user_id: i32) -> rusqlite::Result<bool> {
+    if !is_required_for_staff(conn)? {
+        return Ok(false);
+    }
+    let role: String = conn.query_row("Select role From users Where id = ?1", [user_id], |row| row.get(0))?;
+    permissions::is_staff_role(conn, &role)
+}
+
+/// Make two-factor authentication mandatory for staff or optional - REQUIRES roles.manage
+pub fn set_required_for_staff(conn: &Connection, admin: &User, required: bool) -> rusqlite::Result<()> {
+    if authorization::require_permission(conn, admin, Permission::RolesManage).is_err() {
+        return Err(rusqlite::Error::InvalidParameterName("Permission roles.manage required".to_string()));
+    }
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("Update security_policies Set value = ?2 Where name = ?1", rusqlite::params![STAFF_POLICY, required])?;
+    let details = if required { "mandatory for staff" } else { "optional" };
+    user_admin::record_admin_action(&tx, admin, None, "two_factor_policy", details)?;
+    tx.commit()
+}
+
+// Add the default security policies that are missing: two-factor authentication is mandatory for staff
+pub fn add_default_policies(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "Insert Or Ignore Into security_policies (name, value) Values (?1, 1)",
+        [STAFF_POLICY],
+    )?;
+    Ok(())
+}
+
+/// Turn on two-factor authentication with a secret the user has proven they hold by entering the
+/// code of `confirmed_step`. Replaces any earlier secret. Returns new recovery codes to show once.
+pub fn enable(conn: &Connection, user_id: i32, secret: &[u8], confirmed_step: u64) -> rusqlite::Result<Vec<String>> {
+    let encrypted = encrypt_secret(&hex::encode(secret)).map_err(rusqlite::Error::InvalidParameterName)?;
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "Insert Into two_factor (user_id, secret, last_used_step) Values (?1, ?2, ?3)
+        On Conflict(user_id) Do Update Set secret = ?2, last_used_step = ?3, enabled_at = CURRENT_TIMESTAMP",
+        rusqlite::params![user_id, encrypted, confirmed_step as i64],
+    )?;
+    let codes = replace_recovery_codes(&tx, user_id)?;
+    tx.commit()?;
+
+    logger::security(&format!("Two-factor authentication enabled for User ID: {}", user_id));
+    Ok(codes)
+}
+
+/// Turn off two-factor authentication and forget the recovery codes
+pub fn disable(conn: &Connection, user_id: i32) -> rusqlite::Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    remove(&tx, user_id)?;
+    tx.commit()
+}
+
+// Delete a user's secret and recovery codes, inside the caller's database transaction
+pub fn remove(conn: &Connection, user_id: i32) -> rusqlite::Result<()> {
+    conn.execute("Delete From recovery_codes Where user_id = ?1", [user_id])?;
+    conn.execute("Delete From two_factor Where user_id = ?1", [user_id])?;
+    logger::security(&format!("Two-factor authentication disabled for User ID: {}", user_id));
+    Ok(())
+}
+
+/// Check a code from the authenticator app, or failing that a recovery code
+pub fn verify(conn: &Connection, user_id: i32, code: &str) -> rusqlite::Result<bool> {
+    if verify_totp(conn, user_id, code)? {
+        return Ok(true);
+    }
+    if use_recovery_code(conn, user_id, code)? {
+        let remaining = remaining_recovery_codes(conn, user_id)?;
+        logger::security(&format!("Recovery code used by User ID: {}. {} remaining", user_id, remaining));
+        return Ok(true);
+    }
+    logger::security(&format!("SECURITY ALERT: Invalid two-factor code for User ID: {}", user_id));
+    Ok(false)
+}
+
+fn verify_totp(conn: &Connection, user_id: i32, code: &str) -> rusqlite::Result<bool> {
+    let encrypted: Option<String> = conn.query_row(
+        "Select secret From two_factor Where user_id = ?1",
+        [user_id],
+        |row| row.get(0)
+    ).optional()?;
+    let Some(encrypted) = encrypted else {
+        return Ok(false);
+    };
+    let secret = decrypt_secret(&encrypted)
+        .and_then(|secret| hex::decode(secret).map_err(|e| e.to_string()))
+        .map_err(rusqlite::Error::InvalidParameterName)?;
+
+    let Some(step) = totp::verify(&secret, code, now()) else {
+        return Ok(false);
+    };
+    // A code is only good once, even while it is still shown
+    let accepted = conn.execute(
+        "Update two_factor Set last_used_step = ?2 Where user_id = ?1 And (last_used_step Is Null Or last_used_step < ?2)",
+        rusqlite::params![user_id, step as i64],
+    )?;
+    if accepted == 0 {
+        logger::security(&format!("SECURITY ALERT: Reused two-factor code for User ID: {}", user_id));
+    }
+    Ok(accepted == 1)
+}
+
+// Recovery codes are random, so a fast hash is enough. Dashes and case are ignored.
+fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    hex::encode(Sha256::digest(normalized.as_bytes()))
+}
+
+fn use_recovery_code(conn: &Connection, user_id: i32, code: &str) -> rusqlite::Result<bool> {
+    let used = conn.execute(
+        "Update recovery_codes Set used_at = CURRENT_TIMESTAMP Where user_id = ?1 And code_hash = ?2 And used_at Is Null",
+        rusqlite::params![user_id, hash_recovery_code(code)],
+    )?;
+    Ok(used == 1)
+}
+
+pub fn remaining_recovery_codes(conn: &Connection, user_id: i32) -> rusqlite::Result<u32> {
+    conn.query_row(
+        "Select Count(*) From recovery_codes Where user_id = ?1 And used_at Is Null",
+        [user_id],
+        |row| row.get(0)
+    )
+}
+
+/// Replace every recovery code of a user. Returns the new codes to show once.
+pub fn regenerate_recovery_codes(conn: &Connection, user_id: i32) -> rusqlite::Result<Vec<String>> {
+    let tx = conn.unchecked_transaction()?;
+    let codes = replace_recovery_codes(&tx, user_id)?;
+    tx.commit()?;
+    logger::security(&format!("Recovery codes regenerated for User ID: {}", user_id));
+    Ok(codes)
+}
+
+fn replace_recovery_codes(conn: &Connection, user_id: i32) -> rusqlite::Result<Vec<String>> {
+    conn.execute("Delete From recovery_codes Where user_id = ?1", [user_id])?;
+    let mut codes = Vec::with_capacity(RECOVERY_CODE_COUNT);
+    for _ in 0..RECOVERY_CODE_COUNT {
+        // 50 random bits, shown as XXXXX-XXXXX
+        let random = totp::base32_encode(&totp::generate_secret()[..7]);
+        let code = format!("{}-{}", &random[..5], &random[5..10]);
+        conn.execute(
+            "Insert Into recovery_codes (user_id, code_hash) Values (?1, ?2)",
+            rusqlite::params![user_id, hash_recovery_code(&code)],
+        )?;
+        codes.push(code);
+    }
+    Ok(codes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::dbqueries;
+    use crate::db::test_support::{create_account, setup_test_db};
+
+    #[test]
+    fn test_codes_are_accepted_once() {
+        let conn = setup_test_db();
+        let user_id = create_account(&conn, "tech_2fa", "technician").id;
+        let secret = totp::generate_secret();
+        // Enrolled with the code of a minute ago, so the current code has not been used
+        enable(&conn, user_id, &secret, totp::time_step(now()) - 2).unwrap();
+        assert!(is_enabled(&conn, user_id).unwrap());
+
+        let code = totp::code_at(&secret, now());
+        assert!(verify(&conn, user_id, &code).unwrap());
+        assert!(!verify(&conn, user_id, &code).unwrap());
+        assert!(!verify(&conn, user_id, "not a code").unwrap());
+
+        // The secret is not stored in the clear
+        let stored: String = conn.query_row("Select secret From two_factor Where user_id = ?1", [user_id], |row| row.get(0)).unwrap();
+        assert!(!stored.contains(&hex::encode(&secret)));
+    }
+
+    #[test]
+    fn test_recovery_codes_are_single_use() {
+        let conn = setup_test_db();
+        let user_id = create_account(&conn, "lost_phone", "commissioner").id;
+        let codes = enable(&conn, user_id, &totp::generate_secret(), 0).unwrap();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert_eq!(remaining_recovery_codes(&conn, user_id).unwrap(), 10);
+
+        assert!(verify(&conn, user_id, &codes[0].to_lowercase()).unwrap());
+        assert!(!verify(&conn, user_id, &codes[0]).unwrap());
+        assert_eq!(remaining_recovery_codes(&conn, user_id).unwrap(), 9);
+
+        let new_codes = regenerate_recovery_codes(&conn, user_id).unwrap();
+        assert!(!verify(&conn, user_id, &codes[1]).unwrap());
+        assert!(verify(&conn, user_id, &new_codes[1]).unwrap());
+
+        disable(&conn, user_id).unwrap();
+        assert!(!is_enabled(&conn, user_id).unwrap());
+        assert!(!verify(&conn, user_id, &new_codes[2]).unwrap());
+    }
+
+    #[test]
+    fn test_policy_requires_two_factor_for_staff_only() {
+        let conn = setup_test_db();
+        let admin = User { id: dbqueries::get_user_id_by_username(&conn, "commissioner").unwrap() };
+        let technician = create_account(&conn, "policy_tech", "technician").id;
+        let auditor = create_account(&conn, "policy_auditor", "auditor").id;
+        let player = create_account(&conn, "policy_player", "user").id;
+
+        assert!(is_required(&conn, technician).unwrap());
+        assert!(is_required(&conn, auditor).unwrap());
+        assert!(!is_required(&conn, player).unwrap());
+
+        assert!(set_required_for_staff(&conn, &User { id: technician }, false).is_err());
+        set_required_for_staff(&conn, &admin, false).unwrap();
+        assert!(!is_required(&conn, technician).unwrap());
+    }
+}
@@ -0,0 +1,340 @@
+use rusqlite::Connection;
+
+use crate::authentication::authorization;
+use crate::authentication::permissions::{self, Permission};
+use crate::interfaces::user::User;
+use crate::logger::logger;
+
+// Whether an account may sign in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccountStatus {
+    Active,
+    Suspended,
+}
+
+impl AccountStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountStatus::Active => "active",
+            AccountStatus::Suspended => "suspended",
+        }
+    }
+
+    fn parse(status: &str) -> Self {
+        match status {
+            "suspended" => AccountStatus::Suspended,
+            _ => AccountStatus::Active,
+        }
+    }
+}
+
+// What staff see of an account in the administration console
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountSummary {
+    pub id: i32,
+    pub username: String,
+    pub role: String,
+    pub status: AccountStatus,
+    // Set by a forced password reset until the owner picks a new password
+    pub must_reset_password: bool,
+}
+
+// One entry of the administration audit trail
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdminAction {
+    pub id: i64,
+    pub admin_id: i32,
+    pub admin_username: String,
+    pub target_user_id: Option<i32>,
+    pub target_username: Option<String>,
+    pub action: String,
+    pub details: String,
+    pub timestamp: String,
+}
+
+fn account_from_row(row: &rusqlite::Row) -> rusqlite::Result<AccountSummary> {
+    Ok(AccountSummary {
+        id: row.get(0)?,
+        username: row.get(1)?,
+        role: row.get(2)?,
+        status: AccountStatus::parse(&row.get::<_, String>(3)?),
+        must_reset_password: row.get(4)?,
+    })
+}
+
+/// Accounts whose username contains `query`, an empty query lists every account
+pub fn search_accounts(conn: &Connection, query: &str) -> rusqlite::Result<Vec<AccountSummary>> {
+    let mut stmt = conn.prepare(
+        "Select id, username, role, status, must_reset_password From users
+        Where username Like '%' || ?1 || '%' Escape '\\' Order By username Limit 50"
+    )?;
+    // Wildcards typed by staff are matched literally
+    let pattern = query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    let accounts = stmt.query_map([pattern], account_from_row)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(accounts)
+}
+
+pub fn get_account(conn: &Connection, user_id: i32) -> rusqlite::Result<AccountSummary> {
+    conn.query_row(
+        "Select id, username, role, status, must_reset_password From users Where id = ?1",
+        [user_id],
+        account_from_row
+    )
+}
+
+/// Record an administration action in the audit trail. Mutations call it inside their own
+/// database transaction so an action and its audit entry are committed together.
+pub fn record_admin_action(conn: &Connection, admin: &User, target_user_id: Option<i32>, action: &str, details: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "Insert Into admin_audit (admin_id, target_user_id, action, details) Values (?1, ?2, ?3, ?4)",
+        rusqlite::params![admin.id, target_user_id, action, details],
+    )?;
+    logger::security(&format!(
+        "ADMIN ACTION by User ID: {}: {} (target User ID: {}) {}",
+        admin.id, action, target_user_id.map(|id| id.to_string()).unwrap_or_else(|| "-".to_string()), details
+    ));
+    Ok(())
+}
+
+/// Most recent administration actions, newest first
+pub fn get_audit_trail(conn: &Connection, limit: usize) -> rusqlite::Result<Vec<AdminAction>> {
+    let mut stmt = conn.prepare(
+        "Select a.id, a.admin_id, admin.username, a.target_user_id, target.username, a.action, a.details, a.timestamp
+        From admin_audit a
+        Join users admin On admin.id = a.admin_id
+        Left Join users target On target.id = a.target_user_id
+        Order By a.id Desc Limit ?1"
+    )?;
+    let actions = stmt.query_map([limit as i64], |row| Ok(AdminAction {
+        id: row.get(0)?,
+        admin_id: row.get(1)?,
+        admin_username: row.get(2)?,
+        target_user_id: row.get(3)?,
+        target_username: row.get(4)?,
+        action: row.get(5)?,
+        details: row.get(6)?,
+        timestamp: row.get(7)?,
+    }))?.collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(actions)
+}
+
+// SECURITY: Staff may not act on their own account, administering staff accounts needs
+// roles.manage as well as users.manage, and the last active account able to manage roles
+// cannot be demoted or suspended.
+fn authorize_account_change(conn: &Connection, admin: &User, target: &AccountSummary) -> rusqlite::Result<()> {
+    let deny = |reason: &str| {
+        logger::security(&format!(
+            "SECURITY ALERT: User ID: {} denied administration of User ID: {}: {}", admin.id, target.id, reason
+        ));
+        Err(rusqlite::Error::InvalidParameterName(reason.to_string()))
+    };
+
+    if admin.id == target.id {
+        return deny("Staff cannot administer their own account");
+    }
+    if authorization::require_permission(conn, admin, Permission::UsersManage).is_err() {
+        return deny("Permission users.manage required");
+    }
+    if permissions::is_staff_role(conn, &target.role)? && !permissions::user_has_permission(conn, admin.id, Permission::RolesManage)? {
+        return deny("Permission roles.manage required to administer staff accounts");
+    }
+    Ok(())
+}
+
+// Whether changing the target would leave no active account able to manage roles
+fn is_last_role_manager(conn: &Connection, target: &AccountSummary) -> rusqlite::Result<bool> {
+    if target.status != AccountStatus::Active || !permissions::user_has_permission(conn, target.id, Permission::RolesManage)? {
+        return Ok(false);
+    }
+    let active: i64 = conn.query_row(
+        "Select Count(*) From users u Join role_permissions p On p.role = u.role
+        Where u.status = 'active' And p.permission = ?1",
+        [Permission::RolesManage.as_str()],
+        |row| row.get(0)
+    )?;
+    Ok(active <= 1)
+}
+
+/// Give an account a new role - REQUIRES roles.manage
+pub fn set_role(conn: &Connection, admin: &User, target_user_id: i32, role: &str) -> rusqlite::Result<()> {
+    if authorization::require_permission(conn, admin, Permission::RolesManage).is_err() {
+        return Err(rusqlite::Error::InvalidParameterName("Permission roles.manage required".to_string()));
+    }
+    let new_role = permissions::get_role(conn, role)
+        .map_err(|_| rusqlite::Error::InvalidParameterName(format!("Unknown role: {}", role)))?;
+    let target = get_account(conn, target_user_id)?;
+    authorize_account_change(conn, admin, &target)?;
+    if !new_role.permissions.contains(&Permission::RolesManage) && is_last_role_manager(conn, &target)? {
+        return Err(rusqlite::Error::InvalidParameterName("The last account able to manage roles cannot be demoted".to_string()));
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("Update users Set role = ?1 Where id = ?2", rusqlite::params![new_role.name, target.id])?;
+    record_admin_action(&tx, admin, Some(target.id), "set_role", &format!("{} -> {}", target.role, new_role.name))?;
+    tx.commit()
+}
+
+/// Suspend or reactivate an account. Suspended accounts cannot sign in.
+pub fn set_status(conn: &Connection, admin: &User, target_user_id: i32, status: AccountStatus) -> rusqlite::Result<()> {
+    let target = get_account(conn, target_user_id)?;
+    authorize_account_change(conn, admin, &target)?;
+    if status == AccountStatus::Suspended && is_last_role_manager(conn, &target)? {
+        return Err(rusqlite::Error::InvalidParameterName("The last account able to manage roles cannot be suspended".to_string()));
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("Update users Set status = ?1 Where id = ?2", rusqlite::params![status.as_str(), target.id])?;
+    let action = match status {
+        AccountStatus::Active => "reactivate",
+        AccountStatus::Suspended => "suspend",
+    };
+    record_admin_action(&tx, admin, Some(target.id), action, &format!("{} -> {}", target.status.as_str(), status.as_str()))?;
+    tx.commit()
+}
+
+/// Replace an account's password with a random temporary one that must be changed at the next
+/// sign in. Returns the temporary password for staff to hand over.
+pub fn force_password_reset(conn: &Connection, admin: &User, target_user_id: i32) -> rusqlite::Result<String> {
+    use crate::cryptography::crypto::hash_password;
+    use crate::db::dbinitialize::generate_secure_password;
+
+    let target = get_account(conn, target_user_id)?;
+    authorize_account_change(conn, admin, &target)?;
+
+    let temporary_password = generate_secure_password();
+    let hashed_password = hash_password(&temporary_password).map_err(rusqlite::Error::InvalidParameterName)?;
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "Update users Set password = ?1, must_reset_password = 1 Where id = ?2",
+        rusqlite::params![hashed_password, target.id],
+    )?;
+    record_admin_action(&tx, admin, Some(target.id), "force_password_reset", "temporary password issued")?;
+    tx.commit()?;
+    Ok(temporary_password)
+}
+
+/// Turn off two-factor authentication for an account that lost its authenticator and its
+/// recovery codes. Where policy requires it, the owner enrolls again at the next sign in.
+pub fn reset_two_factor(conn: &Connection, admin: &User, target_user_id: i32) -> rusqlite::Result<()> {
+    let target = get_account(conn, target_user_id)?;
+    authorize_account_change(conn, admin, &target)?;
+
+    let tx = conn.unchecked_transaction()?;
+    crate::db::two_factor::remove(&tx, target.id)?;
+    record_admin_action(&tx, admin, Some(target.id), "reset_two_factor", "")?;
+    tx.commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::dbqueries;
+    use crate::db::test_support::{create_account, setup_test_db, TEST_PASSWORD};
+
+    fn actions(conn: &Connection) -> Vec<String> {
+        get_audit_trail(conn, 10).unwrap().into_iter().map(|action| action.action).collect()
+    }
+
+    #[test]
+    fn test_search_matches_wildcards_literally() {
+        let conn = setup_test_db();
+        create_account(&conn, "alice_smith", "user");
+        create_account(&conn, "alicexsmith", "user");
+
+        let found = search_accounts(&conn, "alice_").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].username, "alice_smith");
+        assert_eq!(found[0].status, AccountStatus::Active);
+        assert!(search_accounts(&conn, "").unwrap().len() >= 2);
+    }
+
+    #[test]
+    fn test_technician_manages_players_but_not_staff() {
+        let conn = setup_test_db();
+        let technician = create_account(&conn, "tech_admin", "technician");
+        let player = create_account(&conn, "player_one", "user");
+        let commissioner = create_account(&conn, "comm_admin", "commissioner");
+
+        set_status(&conn, &technician, player.id, AccountStatus::Suspended).unwrap();
+        assert_eq!(get_account(&conn, player.id).unwrap().status, AccountStatus::Suspended);
+        assert!(set_status(&conn, &technician, commissioner.id, AccountStatus::Suspended).is_err());
+        assert!(set_status(&conn, &technician, technician.id, AccountStatus::Suspended).is_err());
+        assert!(set_role(&conn, &technician, player.id, "technician").is_err());
+        assert_eq!(get_account(&conn, player.id).unwrap().role, "user");
+
+        // A player has no access at all
+        assert!(set_status(&conn, &player, technician.id, AccountStatus::Suspended).is_err());
+        assert_eq!(actions(&conn), vec!["suspend"]);
+    }
+
+    #[test]
+    fn test_commissioner_assigns_roles_but_keeps_a_commissioner() {
+        let conn = setup_test_db();
+        // The commissioner created at initialization
+        let admin = User { id: dbqueries::get_user_id_by_username(&conn, "commissioner").unwrap() };
+        let player = create_account(&conn, "promoted", "user");
+
+        set_role(&conn, &admin, player.id, "technician").unwrap();
+        assert_eq!(get_account(&conn, player.id).unwrap().role, "technician");
+        assert!(set_role(&conn, &admin, player.id, "owner").is_err());
+        assert!(set_role(&conn, &admin, admin.id, "user").is_err());
+
+        let second = create_account(&conn, "second_comm", "commissioner");
+        set_status(&conn, &admin, second.id, AccountStatus::Suspended).unwrap();
+        // The only active commissioner left cannot be taken away, even by another commissioner
+        assert!(set_role(&conn, &second, admin.id, "user").is_err());
+
+        let trail = get_audit_trail(&conn, 10).unwrap();
+        assert_eq!(trail[0].action, "suspend");
+        assert_eq!(trail[1].action, "set_role");
+        assert_eq!(trail[1].details, "user -> technician");
+        assert_eq!(trail[1].target_username.as_deref(), Some("promoted"));
+    }
+
+    #[test]
+    fn test_forced_password_reset() {
+        let conn = setup_test_db();
+        let technician = create_account(&conn, "reset_tech", "technician");
+        let player = create_account(&conn, "forgot", "user");
+
+        let temporary = force_password_reset(&conn, &technician, player.id).unwrap();
+        assert!(get_account(&conn, player.id).unwrap().must_reset_password);
+        assert!(dbqueries::check_users(&conn, "forgot", TEST_PASSWORD).is_err());
+        assert_eq!(dbqueries::check_users(&conn, "forgot", &temporary).unwrap(), player.id);
+
+        // Choosing a new password completes the reset
+        dbqueries::update_user_password(&conn, "forgot", "NewPassword456!@#").unwrap();
+        assert!(!get_account(&conn, player.id).unwrap().must_reset_password);
+    }
+
+    #[test]
+    fn test_two_factor_reset() {
+        use crate::db::two_factor;
+
+        let conn = setup_test_db();
+        let technician = create_account(&conn, "tfa_tech", "technician");
+        let player = create_account(&conn, "tfa_player", "user");
+        two_factor::enable(&conn, player.id, b"12345678901234567890", 0).unwrap();
+        two_factor::enable(&conn, technician.id, b"12345678901234567890", 0).unwrap();
+
+        reset_two_factor(&conn, &technician, player.id).unwrap();
+        assert!(!two_factor::is_enabled(&conn, player.id).unwrap());
+        assert_eq!(two_factor::remaining_recovery_codes(&conn, player.id).unwrap(), 0);
+        // Staff cannot switch off their own second factor this way
+        assert!(reset_two_factor(&conn, &technician, technician.id).is_err());
+        assert!(two_factor::is_enabled(&conn, technician.id).unwrap());
+        assert_eq!(actions(&conn), vec!["reset_two_factor"]);
+    }
+
+    #[test]
+    fn test_audit_trail_is_append_only() {
+        let conn = setup_test_db();
+        let technician = create_account(&conn, "audited", "technician");
+        record_admin_action(&conn, &technician, None, "search", "query ''").unwrap();
+
+        assert!(conn.execute("Update admin_audit Set action = 'nothing'", []).is_err());
+        assert!(conn.execute("Delete From admin_audit", []).is_err());
+    }
+}
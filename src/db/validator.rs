@@ -1,5 +1,7 @@
 use colored::*;
 
+use crate::db::money::Money;
+
 // Validation result with error message
 pub type ValidationResult = Result<(), String>;
 
@@ -10,10 +12,10 @@ const MIN_PASSWORD_LENGTH: usize = 12;
 const MAX_PASSWORD_LENGTH: usize = 128;
 
 // Constants for validation limits - Financial
-const MIN_DEPOSIT: f64 = 0.01;
-const MAX_DEPOSIT: f64 = 1_000_000.0;
-const MIN_WITHDRAWAL: f64 = 0.01;
-const MAX_WITHDRAWAL: f64 = 100_000.0;
+const MIN_DEPOSIT: Money = Money::from_cents(1);
+const MAX_DEPOSIT: Money = Money::from_dollars(1_000_000);
+const MIN_WITHDRAWAL: Money = Money::from_cents(1);
+const MAX_WITHDRAWAL: Money = Money::from_dollars(100_000);
 
 // ==================== Authentication Validation ====================
 
@@ -87,71 +89,49 @@ pub fn validate_password(password: &str) -> ValidationResult {
 
 // ==================== Financial Validation ====================
 
-// Validate deposit amount.
-pub fn validate_deposit(amount: f64) -> ValidationResult {
-    // Check if valid number
-    if amount.is_nan() || amount.is_infinite() {
-        return Err("❌ Invalid deposit amount!".to_string());
-    }
-    
+// Validate deposit amount. Money::parse has already rejected anything finer than a cent.
+pub fn validate_deposit(amount: Money) -> ValidationResult {
     // Check if positive
-    if amount <= 0.0 {
+    if !amount.is_positive() {
         return Err("❌ Deposit amount must be greater than zero!".to_string());
     }
     
     // Check minimum
     if amount < MIN_DEPOSIT {
-        return Err(format!("❌ Minimum deposit is ${:.2}!", MIN_DEPOSIT));
+        return Err(format!("❌ Minimum deposit is ${}!", MIN_DEPOSIT));
     }
     
     // Check maximum (prevent overflow and unrealistic values)
     if amount > MAX_DEPOSIT {
-        return Err(format!("❌ Maximum deposit is ${:.2} per transaction!", MAX_DEPOSIT));
-    }
-    
-    // Check for reasonable precision (max 2 decimal places)
-    let rounded = (amount * 100.0).round() / 100.0;
-    if (amount - rounded).abs() > 0.001 {
-        return Err("❌ Deposit amount can have at most 2 decimal places!".to_string());
+        return Err(format!("❌ Maximum deposit is ${} per transaction!", MAX_DEPOSIT));
     }
     
     Ok(())
 }
 
 // Validate withdrawal amount.
-pub fn validate_withdrawal(amount: f64, current_balance: f64) -> ValidationResult {
-    // Check if valid number
-    if amount.is_nan() || amount.is_infinite() {
-        return Err("❌ Invalid withdrawal amount!".to_string());
-    }
-    
+pub fn validate_withdrawal(amount: Money, current_balance: Money) -> ValidationResult {
     // Check if positive
-    if amount <= 0.0 {
+    if !amount.is_positive() {
         return Err("❌ Withdrawal amount must be greater than zero!".to_string());
     }
     
     // Check minimum
     if amount < MIN_WITHDRAWAL {
-        return Err(format!("❌ Minimum withdrawal is ${:.2}!", MIN_WITHDRAWAL));
+        return Err(format!("❌ Minimum withdrawal is ${}!", MIN_WITHDRAWAL));
     }
     
     // Check maximum
     if amount > MAX_WITHDRAWAL {
-        return Err(format!("❌ Maximum withdrawal is ${:.2} per transaction!", MAX_WITHDRAWAL));
+        return Err(format!("❌ Maximum withdrawal is ${} per transaction!", MAX_WITHDRAWAL));
     }
     
     // Check if user has sufficient funds
     if amount > current_balance {
-        return Err(format!("❌ Insufficient funds! You have ${:.2}, trying to withdraw ${:.2}", 
+        return Err(format!("❌ Insufficient funds! You have ${}, trying to withdraw ${}", 
                           current_balance, amount));
     }
     
-    // Check for reasonable precision (max 2 decimal places)
-    let rounded = (amount * 100.0).round() / 100.0;
-    if (amount - rounded).abs() > 0.001 {
-        return Err("❌ Withdrawal amount can have at most 2 decimal places!".to_string());
-    }
-    
     Ok(())
 }
 
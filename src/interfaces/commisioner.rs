@@ -4,27 +4,53 @@ use std::io::Write;
 use rusqlite::Connection;
 use crate::interfaces::user::User;
 use crate::authentication::authorization;
+use crate::authentication::permissions::{self, Permission};
+use crate::authentication::session;
+use crate::db::money::Money;
 use crate::logger::logger;
-use crate::interfaces::menus::menu_generator;
+use crate::interfaces::menus::{menu_generator, session_menu};
+
+/// Commissioner Control Panel - options depend on the permissions of the user's role
+// Options of the commissioner menu and the permission each one needs
+pub const COMMISSIONER_OPTIONS: [(&str, Permission); 13] = [
+    ("Run fairness test", Permission::RngTest),
+    ("View theoretical RTP", Permission::ReportsView),
+    ("View game probabilities", Permission::ReportsView),
+    ("Adjust symbol weights", Permission::PaytableEdit),
+    ("Adjust symbol payouts", Permission::PaytableEdit),
+    ("Adjust wheel segments", Permission::PaytableEdit),
+    ("Set target RTP band", Permission::PaytableEdit),
+    ("Run RNG test battery", Permission::RngTest),
+    ("View RNG test reports", Permission::ReportsView),
+    ("Replay round", Permission::RoundsReplay),
+    ("User administration", Permission::UsersManage),
+    ("Roles and permissions", Permission::RolesManage),
+    ("Encryption keys", Permission::KeysRotate),
+];
 
-/// Commissioner Control Panel - Requires commissioner role
 pub fn commissioner_menu(conn: &Connection, user: &User) -> rusqlite::Result<()> {
-    // SECURITY: Verify user has commissioner role
-    if let Err(e) = authorization::require_commissioner(conn, user) {
-        logger::security(&format!("Blocked unauthorized access to commissioner menu by User ID: {}: {}", user.id, e));
+    // SECURITY: Only the options the user's role permits are offered
+    let mut menu_options = permissions::permitted_options(conn, user, &COMMISSIONER_OPTIONS);
+    if menu_options.is_empty() {
+        println!("{}", "Access denied: Insufficient privileges".red());
+        logger::security(&format!("Blocked unauthorized access to commissioner menu by User ID: {}", user.id));
         return Ok(());
     }
+    menu_options.push("Two-factor authentication");
+    menu_options.push("Logout");
     
     logger::security(&format!("Commissioner (User ID: {}) accessed commissioner menu", user.id));
-    loop {
-        let menu_options = vec!["Run fairness test", "View game probabilities", "Adjust symbol weights", "Adjust symbol payouts", "Logout"];
-        let user_input = menu_generator("═══ 🧮 Commissioner Control Panel 🧮 ═══", &menu_options);
+    while let Some(user_input) = session_menu(conn, user, "═══ 🧮 Commissioner Control Panel 🧮 ═══", &menu_options) {
 
         match user_input.trim() {
             "Run fairness test" => {
                 logger::info(&format!("Commissioner (User ID: {}) running fairness test", user.id));
                 run_commissioner_test(conn, user)
             },
+            "View theoretical RTP" => {
+                logger::info(&format!("Commissioner (User ID: {}) viewing theoretical RTP", user.id));
+                view_theoretical_rtp(conn, user)
+            },
             "View game probabilities" => {
                 logger::info(&format!("Commissioner (User ID: {}) viewing game probabilities", user.id));
                 view_game_probabilities(conn, user)
@@ -37,6 +63,41 @@ pub fn commissioner_menu(conn: &Connection, user: &User) -> rusqlite::Result<()>
                 logger::security(&format!("Commissioner (User ID: {}) adjusting symbol payouts", user.id));
                 adjust_symbol_payouts(conn, user)
             },
+            "Adjust wheel segments" => {
+                logger::security(&format!("Commissioner (User ID: {}) adjusting wheel segments", user.id));
+                adjust_wheel_segments(conn, user)
+            },
+            "Set target RTP band" => {
+                logger::security(&format!("Commissioner (User ID: {}) setting target RTP band", user.id));
+                set_target_rtp_band(conn, user)
+            },
+            "Run RNG test battery" => {
+                logger::info(&format!("Commissioner (User ID: {}) running RNG test battery", user.id));
+                run_rng_battery(conn, user)
+            },
+            "View RNG test reports" => {
+                logger::info(&format!("Commissioner (User ID: {}) viewing RNG test reports", user.id));
+                view_rng_reports(conn, user)
+            },
+            "Replay round" => {
+                logger::security(&format!("Commissioner (User ID: {}) accessed round replay", user.id));
+                crate::interfaces::replay::replay_round_menu(conn, user)?;
+            },
+            "User administration" => {
+                logger::security(&format!("Commissioner (User ID: {}) accessed user administration", user.id));
+                crate::interfaces::user_admin::user_admin_menu(conn, user)
+            },
+            "Roles and permissions" => {
+                logger::security(&format!("Commissioner (User ID: {}) accessed roles and permissions", user.id));
+                crate::interfaces::user_admin::roles_menu(conn, user)
+            },
+            "Encryption keys" => {
+                logger::security(&format!("Commissioner (User ID: {}) accessed encryption keys", user.id));
+                crate::interfaces::key_rotation::key_rotation_menu(conn, user)
+            },
+            "Two-factor authentication" => {
+                crate::interfaces::two_factor::two_factor_menu(conn, user)
+            },
             "Logout" => {
                 logger::info(&format!("Commissioner (User ID: {}) exited commissioner menu", user.id));
                 break;
@@ -50,38 +111,44 @@ pub fn commissioner_menu(conn: &Connection, user: &User) -> rusqlite::Result<()>
     Ok(())
 }
 
-/// Automated fairness test for X rounds - REQUIRES COMMISSIONER ROLE
+/// Automated fairness test for X rounds - REQUIRES rng.test
 fn run_commissioner_test(conn: &Connection, user: &User) {
     use crate::db::dbqueries;
     use crate::cryptography::rng::CasinoRng;
+    use crate::play::game::{find_game, registered_games};
+    use crate::play::simulator::{self, FairnessReport, Verdict};
+    use crate::play::theory::{TheoreticalReport, DEVIATION_Z};
     
     // SECURITY: Double-check authorization
-    if authorization::require_commissioner(conn, user).is_err() {
+    if authorization::require_permission(conn, user, Permission::RngTest).is_err() {
         return;
     }
     
     logger::security(&format!("Commissioner (User ID: {}) initiated fairness test", user.id));
     
     // Select game to test
-    let game_options = vec!["normal", "multi", "holding", "Cancel"];
-    let game_choice = menu_generator("Select Game to Test", &game_options);
+    let mut game_options: Vec<&str> = registered_games().iter().map(|game| game.name()).collect();
+    game_options.push("Cancel");
+    let game_name = menu_generator("Select Game to Test", &game_options);
     
-    if game_choice == "Cancel" {
+    if game_name == "Cancel" {
         return;
     }
-    
-    let game_name = game_choice;
-    
-    // Load symbol probabilities from database
-    let symbol_probs = match dbqueries::get_symbol_probabilities(conn, game_name) {
-        Ok(probs) => probs,
-        Err(e) => {
-            logger::error(&format!("Failed to load symbol probabilities: {}", e));
-            println!("{}", "Error loading game configuration".red());
+
+    // Drive the same game code players get, with the same commissioner-configured paytable
+    let mut game = match find_game(game_name) {
+        Some(game) => game,
+        None => {
+            println!("{}", "Unknown game type!".red());
             return;
         }
     };
-    
+    if let Err(e) = game.load_config(conn) {
+        logger::error(&format!("Failed to load configuration for {}: {}", game_name, e));
+        println!("{}", "Error loading game configuration".red());
+        return;
+    }
+
     println!("\n{}", format!("═══ Testing {} Game ═══", game_name.to_uppercase()).bright_cyan().bold());
     
     // Ask for seed (for reproducible testing)
@@ -105,11 +172,6 @@ fn run_commissioner_test(conn: &Connection, user: &User) {
 
     println!("\nRunning {} rounds for {} game...", rounds, game_name);
 
-    // Convert to weighted format for RNG
-    let weighted_symbols: Vec<(&str, usize)> = symbol_probs.iter()
-        .map(|(sym, weight, _)| (sym.as_str(), *weight))
-        .collect();
-
     // Initialize RNG (seeded if seed provided, otherwise random)
     let mut rng = if seed_str.is_empty() {
         CasinoRng::new()
@@ -126,196 +188,9 @@ fn run_commissioner_test(conn: &Connection, user: &User) {
         CasinoRng::seeded(seed)
     };
 
-    let mut wins = 0;
-    let mut partials = 0;
-    let mut losses = 0;
-    let mut total_bet = 0.0;
-    let mut total_payout = 0.0;
-
-    // Run game-specific simulation
-    match game_name {
-        "normal" => {
-            // Normal slots: 3 symbols, match 3 or 2
-            for _ in 0..rounds {
-                let slot1 = rng.weighted_choice(&weighted_symbols).unwrap();
-                let slot2 = rng.weighted_choice(&weighted_symbols).unwrap();
-                let slot3 = rng.weighted_choice(&weighted_symbols).unwrap();
-
-                let bet = 1.0;
-                total_bet += bet;
-
-                if slot1 == slot2 && slot2 == slot3 {
-                    let payout_multiplier = symbol_probs.iter()
-                        .find(|(sym, _, _)| sym.as_str() == *slot1)
-                        .map(|(_, _, mult)| mult)
-                        .unwrap_or(&3.0);
-                    wins += 1;
-                    total_payout += payout_multiplier * bet;
-                } else if slot1 == slot2 || slot2 == slot3 || slot1 == slot3 {
-                    let matching_symbol = if slot1 == slot2 { slot1 } else if slot2 == slot3 { slot2 } else { slot1 };
-                    let base_multiplier = symbol_probs.iter()
-                        .find(|(sym, _, _)| sym.as_str() == *matching_symbol)
-                        .map(|(_, _, mult)| mult)
-                        .unwrap_or(&3.0);
-                    let payout_multiplier = base_multiplier * 0.5;
-                    partials += 1;
-                    total_payout += payout_multiplier * bet;
-                } else {
-                    losses += 1;
-                }
-            }
-        },
-        "multi" => {
-            // Multi-win: 5x5 grid, match rows/columns/diagonals
-            let base_multiplier: f64 = symbol_probs.iter()
-                .map(|(_, _, mult)| mult)
-                .sum::<f64>() / symbol_probs.len() as f64;
-
-            for _ in 0..rounds {
-                let bet = 1.0;
-                total_bet += bet;
-
-                // Generate 5x5 grid using weighted symbols
-                let mut grid = [[' '; 5]; 5];
-                for i in 0..5 {
-                    for j in 0..5 {
-                        let symbol = rng.weighted_choice(&weighted_symbols).unwrap();
-                        grid[i][j] = symbol.chars().next().unwrap();
-                    }
-                }
-
-                // Check for wins - must match actual game logic
-                let mut has_horizontal_win = false;
-                let mut has_four_corner_win = false;
-                let mut any_win = false;
-
-                // Check rows (horizontal wins)
-                for row in grid.iter() {
-                    if row.iter().all(|&s| s == row[0]) {
-                        has_horizontal_win = true;
-                        any_win = true;
-                    }
-                }
-
-                // Check columns
-                for col in 0..5 {
-                    if (0..5).all(|row| grid[row][col] == grid[0][col]) {
-                        any_win = true;
-                    }
-                }
-
-                // Check diagonals
-                if (0..5).all(|i| grid[i][i] == grid[0][0]) {
-                    any_win = true;
-                }
-                if (0..5).all(|i| grid[i][4-i] == grid[0][4]) {
-                    any_win = true;
-                }
-
-                // Check four corners
-                if grid[0][0] == grid[0][4] && grid[0][0] == grid[4][0] && grid[0][0] == grid[4][4] {
-                    has_four_corner_win = true;
-                    any_win = true;
-                }
-
-                if any_win {
-                    wins += 1;
-                    if has_horizontal_win && has_four_corner_win {
-                        total_payout += base_multiplier * 2.0 * bet; // Double jackpot
-                    } else {
-                        total_payout += base_multiplier * bet;
-                    }
-                } else {
-                    losses += 1;
-                }
-            }
-        },
-        "holding" => {
-            // Holding: 5 symbols with hold feature (2 spins)
-            // This simulation models the actual game with hold mechanics
-            for _ in 0..rounds {
-                let bet = 1.0;
-                total_bet += bet;
-
-                // First spin: Generate 5 symbols
-                let mut reels: Vec<&str> = (0..5)
-                    .map(|_| *rng.weighted_choice(&weighted_symbols).unwrap())
-                    .collect();
-
-                // Count occurrences after first spin
-                let mut counts = std::collections::HashMap::new();
-                for &symbol in &reels {
-                    *counts.entry(symbol).or_insert(0) += 1;
-                }
-
-                // Simple hold strategy: hold reels with most common symbol (up to 2)
-                let most_common_symbol = counts.iter()
-                    .max_by_key(|(_, &count)| count)
-                    .map(|(sym, _)| *sym)
-                    .unwrap_or(&"");
-                
-                let mut held_indices = Vec::new();
-                for (i, &symbol) in reels.iter().enumerate() {
-                    if symbol == most_common_symbol && held_indices.len() < 2 {
-                        held_indices.push(i);
-                    }
-                }
-                
-                // Calculate hold charge (25% per held reel)
-                let held_count = held_indices.len();
-                let hold_charge = bet * 0.25 * held_count as f64;
-                total_bet += hold_charge;
-
-                // Second spin: Respin non-held reels
-                for i in 0..5 {
-                    if !held_indices.contains(&i) {
-                        reels[i] = *rng.weighted_choice(&weighted_symbols).unwrap();
-                    }
-                }
-
-                // Count occurrences after second spin
-                counts.clear();
-                for &symbol in &reels {
-                    *counts.entry(symbol).or_insert(0) += 1;
-                }
-
-                let max_count = counts.values().copied().max().unwrap_or(0);
-                
-                // Calculate final bet (base + hold charges)
-                let final_bet = bet * (1.0 + 0.25 * held_count as f64);
-                
-                if max_count >= 3 {
-                    let winning_symbol = counts.iter()
-                        .max_by_key(|(_, &count)| count)
-                        .map(|(sym, _)| *sym)
-                        .unwrap();
-
-                    let base_multiplier = symbol_probs.iter()
-                        .find(|(sym, _, _)| sym.as_str() == winning_symbol)
-                        .map(|(_, _, mult)| *mult)
-                        .unwrap_or(2.0);
-
-                    let payout = match max_count {
-                        5 => base_multiplier * 5.0 * final_bet,
-                        4 => base_multiplier * 2.5 * final_bet,
-                        3 => base_multiplier * final_bet,
-                        _ => 0.0,
-                    };
-
-                    wins += 1;
-                    total_payout += payout;
-                } else {
-                    losses += 1;
-                }
-            }
-        },
-        _ => {
-            println!("{}", "Unknown game type!".red());
-            return;
-        }
-    }
-
-    let rtp = (total_payout / total_bet) * 100.0;
+    // Run the real round resolution, with holds made by the game's automatic strategy
+    let stats = simulator::simulate(game.as_ref(), &mut rng, rounds, Money::from_dollars(1));
+    let rtp = stats.rtp();
 
     println!("\n{}", "🎰 Test Results 🎰".bright_yellow().bold());
     println!("Game: {}", game_name);
@@ -324,39 +199,78 @@ fn run_commissioner_test(conn: &Connection, user: &User) {
     }
     println!("Total rounds: {}", rounds);
     
-    match game_name {
-        "normal" => {
-            println!("Wins (3 match): {}", wins);
-            println!("Two-symbol matches: {}", partials);
-            println!("Losses: {}", losses);
-        },
-        "multi" => {
-            println!("Wins (any line match): {}", wins);
-            println!("Losses: {}", losses);
-        },
-        "holding" => {
-            println!("Wins (3+ of a kind): {}", wins);
-            println!("Losses: {}", losses);
-        },
-        _ => {}
+    let labels = game.outcome_labels();
+    println!("{}: {}", labels.win, stats.wins);
+    if let Some(partial) = labels.partial {
+        println!("{}: {}", partial, stats.partials);
     }
+    println!("{}: {}", labels.loss, stats.losses);
     
-    println!("Total Bet: ${:.2}", total_bet);
-    println!("Total Payout: ${:.2}", total_payout);
+    println!("Total Bet: ${:.2}", stats.total_bet);
+    println!("Total Payout: ${:.2}", stats.total_payout);
     println!("RTP (Return To Player): {:.2}%", rtp);
     
-    // Display symbol distribution
+    // Compare against the exact figures for the same paytable
+    let theory = TheoreticalReport::from_classes(game.exact_outcomes());
+    print_theoretical_report(&theory);
+
+    let tolerance = theory.rtp_tolerance(rounds, DEVIATION_Z);
+    println!("Simulated - theoretical RTP: {:+.2} points (99% bound ±{:.2})", rtp - theory.rtp, tolerance);
+    if theory.deviates(rtp, rounds) {
+        logger::warning(&format!(
+            "Fairness test for {} deviates from theory: simulated RTP {:.2}%, theoretical {:.2}% (±{:.2}) over {} rounds",
+            game_name, rtp, theory.rtp, tolerance, rounds
+        ));
+        println!("{}", "⚠️  Simulated RTP deviates from theory beyond the 99% confidence bound!".red().bold());
+    } else {
+        println!("{}", "✓ Simulated RTP is consistent with theory.".green());
+    }
+    
+    // Display symbol distribution, configured against drawn
     println!("\n{}", "Symbol Probabilities:".bright_cyan());
-    let total_weight: usize = symbol_probs.iter().map(|(_, w, _)| w).sum();
-    for (symbol, weight, payout) in &symbol_probs {
+    let total_weight: usize = game.paytable().iter().map(|(_, w, _)| w).sum();
+    let draws: u64 = stats.symbol_counts.iter().map(|(_, count)| count).sum();
+    for (symbol, weight, payout) in game.paytable() {
         let probability = (*weight as f64 / total_weight as f64) * 100.0;
-        println!("  {} - {:.1}% chance, {:.1}x payout", symbol, probability, payout);
+        let observed = if draws == 0 { 0.0 } else { stats.symbol_count(symbol) as f64 / draws as f64 * 100.0 };
+        println!("  {} - {:.1}% chance (drawn {:.1}%), {:.1}x payout", symbol, probability, observed, payout);
+    }
+
+    // Judge the run against the commissioner's target band
+    let target = match dbqueries::get_rtp_target(conn, game_name) {
+        Ok(target) => target,
+        Err(e) => {
+            logger::error(&format!("Failed to load target RTP band for {}: {}", game_name, e));
+            println!("{}", "Error loading target RTP band".red());
+            return;
+        }
+    };
+    let report = FairnessReport::new(&stats, game.paytable(), target);
+
+    println!("\n{}", "📐 Statistical Verdict 📐".bright_yellow().bold());
+    println!("RTP 95% confidence interval: {:.2}% - {:.2}%", report.rtp_interval.0, report.rtp_interval.1);
+    println!("Hit frequency: {:.2}% (95% confidence interval {:.2}% - {:.2}%)", report.hit_rate, report.hit_interval.0, report.hit_interval.1);
+    println!(
+        "Symbol chi-square: {:.2} with {} degrees of freedom (p = {:.4}, fails below {})",
+        report.chi_square.statistic, report.chi_square.df, report.chi_square.p_value, simulator::CHI_SQUARE_ALPHA
+    );
+    println!("Target RTP band: {:.2}% - {:.2}%", report.target.0, report.target.1);
+    match report.verdict {
+        Verdict::Pass => println!("{}", "✓ PASS: RTP interval lies inside the target band and symbols fit their weights.".green().bold()),
+        Verdict::Fail => {
+            logger::warning(&format!(
+                "Fairness test for {} failed: RTP interval {:.2}% - {:.2}%, target {:.2}% - {:.2}%, chi-square p = {:.4}",
+                game_name, report.rtp_interval.0, report.rtp_interval.1, report.target.0, report.target.1, report.chi_square.p_value
+            ));
+            println!("{}", "✗ FAIL: RTP interval is outside the target band or symbols do not fit their weights.".red().bold())
+        },
+        Verdict::Inconclusive => println!("{}", "? INCONCLUSIVE: RTP interval straddles the target band, run more rounds.".yellow().bold()),
     }
 
     // Store test summary in DB
     let seed_for_db = if seed_str.is_empty() { "random" } else { seed_str };
     
-    match dbqueries::insert_commissioner_log(conn, game_name, seed_for_db, rounds, wins, partials, losses, rtp) {
+    match dbqueries::insert_commissioner_log(conn, game_name, seed_for_db, &stats, &report, theory.rtp) {
         Ok(_) => println!("\n{}", "✓ Test results stored in commissioner_log table.".green().bold()),
         Err(e) => {
             logger::error(&format!("Failed to store test results: {}", e));
@@ -558,10 +472,49 @@ mod tests {
         );
     }
 }*/
-/// View probabilities for all games - REQUIRES COMMISSIONER ROLE
+// Print exact RTP, hit rate, volatility and the contribution of each outcome class
+fn print_theoretical_report(report: &crate::play::theory::TheoreticalReport) {
+    println!("\n{}", "Theoretical (exact):".bright_cyan());
+    println!("RTP: {:.4}%", report.rtp);
+    println!("Hit rate: {:.4}%", report.hit_rate);
+    println!("Standard deviation per round: {:.4} bets", report.std_dev);
+    println!("{:<32} {:>12} {:>10} {:>14}", "Outcome", "Probability", "Pays", "RTP share");
+    println!("{}", "-".repeat(71));
+    for class in report.classes.iter().filter(|c| c.probability > 0.0) {
+        println!("{:<32} {:>11.6}% {:>9.2}x {:>13.4}%",
+            class.label, class.probability * 100.0, class.payout, report.contribution(class));
+    }
+}
+
+/// View exact RTP figures for every game - REQUIRES reports.view
+fn view_theoretical_rtp(conn: &Connection, user: &User) {
+    use crate::play::game::registered_games;
+    use crate::play::theory::TheoreticalReport;
+
+    // SECURITY: Double-check authorization
+    if authorization::require_permission(conn, user, Permission::ReportsView).is_err() {
+        return;
+    }
+
+    for mut game in registered_games() {
+        println!("\n{}", format!("═══ {} ═══", game.name().to_uppercase()).bright_cyan());
+
+        if let Err(e) = game.load_config(conn) {
+            logger::error(&format!("Failed to load configuration for {}: {}", game.name(), e));
+            println!("Error loading game configuration: {}", e);
+            continue;
+        }
+        print_theoretical_report(&TheoreticalReport::from_classes(game.exact_outcomes()));
+    }
+
+    println!("\nPress Enter to continue...");
+    io::stdin().read_line(&mut String::new()).ok();
+}
+
+/// View probabilities for all games - REQUIRES reports.view
 fn view_game_probabilities(conn: &Connection, user: &User) {
     // SECURITY: Double-check authorization
-    if authorization::require_commissioner(conn, user).is_err() {
+    if authorization::require_permission(conn, user, Permission::ReportsView).is_err() {
         return;
     }
     
@@ -594,15 +547,32 @@ fn view_game_probabilities(conn: &Connection, user: &User) {
             Err(e) => println!("Error retrieving probabilities: {}", e),
         }
     }
+
+    println!("\n{}", "═══ WHEEL OF FORTUNE ═══".bright_cyan());
+    match dbqueries::get_wheel_segments(conn, "wheel of fortune") {
+        Ok(segments) => {
+            let total_weight: usize = segments.iter().map(|(_, w, _)| w).sum();
+
+            println!("{:<5} {:<20} {:<10} {:<15} {:<10}", "#", "Segment", "Weight", "Probability", "Payout");
+            println!("{}", "-".repeat(65));
+
+            for (position, (label, weight, multiplier)) in segments.iter().enumerate() {
+                let probability = (*weight as f64 / total_weight as f64) * 100.0;
+                println!("{:<5} {:<20} {:<10} {:<14.2}% {:<10.1}x",
+                    position + 1, label, weight, probability, multiplier);
+            }
+        }
+        Err(e) => println!("Error retrieving wheel segments: {}", e),
+    }
     
     println!("\nPress Enter to continue...");
     io::stdin().read_line(&mut String::new()).ok();
 }
 
-/// Adjust symbol weights (probabilities) for a game - REQUIRES COMMISSIONER ROLE
+/// Adjust symbol weights (probabilities) for a game - REQUIRES paytable.edit
 fn adjust_symbol_weights(conn: &Connection, user: &User) {
     // SECURITY: Double-check authorization before allowing game modifications
-    if authorization::require_commissioner(conn, user).is_err() {
+    if authorization::require_permission(conn, user, Permission::PaytableEdit).is_err() {
         return;
     }
     
@@ -663,6 +633,11 @@ fn adjust_symbol_weights(conn: &Connection, user: &User) {
                 }
             };
             
+            // SECURITY: Paytable changes need the password again
+            if !session::reauthenticate(conn, user, "adjust symbol weights") {
+                return;
+            }
+
             match dbqueries::update_symbol_weight(conn, game_name, symbol, new_weight) {
                 Ok(_) => println!("{}", format!("✓ Weight updated for {} to {}", symbol, new_weight).green()),
                 Err(e) => println!("{}", format!("Error updating weight: {}", e).red()),
@@ -675,10 +650,10 @@ fn adjust_symbol_weights(conn: &Connection, user: &User) {
     io::stdin().read_line(&mut String::new()).ok();
 }
 
-/// Adjust symbol payout multipliers for a game - REQUIRES COMMISSIONER ROLE
+/// Adjust symbol payout multipliers for a game - REQUIRES paytable.edit
 fn adjust_symbol_payouts(conn: &Connection, user: &User) {
     // SECURITY: Double-check authorization before allowing payout modifications
-    if authorization::require_commissioner(conn, user).is_err() {
+    if authorization::require_permission(conn, user, Permission::PaytableEdit).is_err() {
         return;
     }
     
@@ -732,13 +707,18 @@ fn adjust_symbol_payouts(conn: &Connection, user: &User) {
             io::stdin().read_line(&mut payout_input).ok();
             
             let new_payout: f64 = match payout_input.trim().parse() {
-                Ok(p) if p >= 0.5 && p <= 50.0 => p,
+                Ok(p) if (0.5..=50.0).contains(&p) => p,
                 _ => {
                     println!("{}", "Invalid payout! Must be 0.5-50.0".red());
                     return;
                 }
             };
             
+            // SECURITY: Paytable changes need the password again
+            if !session::reauthenticate(conn, user, "adjust symbol payouts") {
+                return;
+            }
+
             match dbqueries::update_symbol_payout(conn, game_name, symbol, new_payout) {
                 Ok(_) => println!("{}", format!("✓ Payout updated for {} to {}x", symbol, new_payout).green()),
                 Err(e) => println!("{}", format!("Error updating payout: {}", e).red()),
@@ -749,4 +729,245 @@ fn adjust_symbol_payouts(conn: &Connection, user: &User) {
     
     println!("\nPress Enter to continue...");
     io::stdin().read_line(&mut String::new()).ok();
-}
\ No newline at end of file
+}
+
+/// Adjust weight and payout of a wheel of fortune segment - REQUIRES paytable.edit
+fn adjust_wheel_segments(conn: &Connection, user: &User) {
+    // SECURITY: Double-check authorization before allowing wheel modifications
+    if authorization::require_permission(conn, user, Permission::PaytableEdit).is_err() {
+        return;
+    }
+
+    logger::security(&format!("Commissioner (User ID: {}) accessing wheel segment adjustment", user.id));
+    use crate::db::dbqueries;
+
+    let game_name = "wheel of fortune";
+
+    match dbqueries::get_wheel_segments(conn, game_name) {
+        Ok(segments) => {
+            // Create segment options for menu
+            let segment_options: Vec<String> = segments.iter()
+                .enumerate()
+                .map(|(i, (label, weight, multiplier))| format!("#{} {} (weight: {}, payout: {}x)", i + 1, label, weight, multiplier))
+                .collect();
+
+            let mut menu_opts: Vec<&str> = segment_options.iter()
+                .map(|s| s.as_str())
+                .collect();
+            menu_opts.push("Cancel");
+
+            let segment_choice = menu_generator("Current wheel segments", &menu_opts);
+
+            if segment_choice == "Cancel" {
+                return;
+            }
+
+            // Find the selected segment position
+            let seg_idx = segment_options.iter()
+                .position(|option| option == segment_choice)
+                .unwrap_or(0);
+
+            let (label, old_weight, old_multiplier) = &segments[seg_idx];
+
+            println!("\nCurrent weight for {}: {}", label, old_weight);
+            print!("Enter new weight (1-100): ");
+            io::stdout().flush().ok();
+            let mut weight_input = String::new();
+            io::stdin().read_line(&mut weight_input).ok();
+
+            let new_weight: usize = match weight_input.trim().parse() {
+                Ok(w) if w > 0 && w <= 100 => w,
+                _ => {
+                    println!("{}", "Invalid weight! Must be 1-100".red());
+                    return;
+                }
+            };
+
+            println!("\nCurrent payout for {}: {}x", label, old_multiplier);
+            print!("Enter new payout multiplier (0.0-50.0): ");
+            io::stdout().flush().ok();
+            let mut payout_input = String::new();
+            io::stdin().read_line(&mut payout_input).ok();
+
+            let new_multiplier: f64 = match payout_input.trim().parse() {
+                Ok(p) if (0.0..=50.0).contains(&p) => p,
+                _ => {
+                    println!("{}", "Invalid payout! Must be 0.0-50.0".red());
+                    return;
+                }
+            };
+
+            // SECURITY: Paytable changes need the password again
+            if !session::reauthenticate(conn, user, "adjust wheel segments") {
+                return;
+            }
+
+            match dbqueries::update_wheel_segment(conn, game_name, seg_idx + 1, new_weight, new_multiplier) {
+                Ok(_) => println!("{}", format!("✓ Segment {} updated to weight {} and {}x", label, new_weight, new_multiplier).green()),
+                Err(e) => println!("{}", format!("Error updating segment: {}", e).red()),
+            }
+        }
+        Err(e) => println!("{}", format!("Error loading wheel segments: {}", e).red()),
+    }
+
+    println!("\nPress Enter to continue...");
+    io::stdin().read_line(&mut String::new()).ok();
+}
+
+/// Set the RTP band fairness tests must land in for a game - REQUIRES paytable.edit
+fn set_target_rtp_band(conn: &Connection, user: &User) {
+    // SECURITY: Double-check authorization before allowing target modifications
+    if authorization::require_permission(conn, user, Permission::PaytableEdit).is_err() {
+        return;
+    }
+
+    use crate::db::dbqueries;
+    use crate::play::game::registered_games;
+
+    let mut game_options: Vec<&str> = registered_games().iter().map(|game| game.name()).collect();
+    game_options.push("Cancel");
+    let game_name = menu_generator("Select Game", &game_options);
+
+    if game_name == "Cancel" {
+        return;
+    }
+
+    match dbqueries::get_rtp_target(conn, game_name) {
+        Ok((min_rtp, max_rtp)) => println!("\nCurrent target RTP band for {}: {:.2}% - {:.2}%", game_name, min_rtp, max_rtp),
+        Err(e) => {
+            println!("{}", format!("Error loading target RTP band: {}", e).red());
+            return;
+        }
+    }
+
+    print!("Enter new minimum RTP % (0-200): ");
+    io::stdout().flush().ok();
+    let mut min_input = String::new();
+    io::stdin().read_line(&mut min_input).ok();
+
+    print!("Enter new maximum RTP % (0-200): ");
+    io::stdout().flush().ok();
+    let mut max_input = String::new();
+    io::stdin().read_line(&mut max_input).ok();
+
+    let (min_rtp, max_rtp): (f64, f64) = match (min_input.trim().parse(), max_input.trim().parse()) {
+        (Ok(min), Ok(max)) if (0.0..=200.0).contains(&min) && (min..=200.0).contains(&max) => (min, max),
+        _ => {
+            println!("{}", "Invalid band! Both values must be 0-200 with minimum <= maximum".red());
+            return;
+        }
+    };
+
+    // SECURITY: Paytable changes need the password again
+    if !session::reauthenticate(conn, user, "set the target RTP band") {
+        return;
+    }
+
+    match dbqueries::set_rtp_target(conn, game_name, min_rtp, max_rtp) {
+        Ok(_) => println!("{}", format!("✓ Target RTP band for {} set to {:.2}% - {:.2}%", game_name, min_rtp, max_rtp).green()),
+        Err(e) => println!("{}", format!("Error updating target RTP band: {}", e).red()),
+    }
+
+    println!("\nPress Enter to continue...");
+    io::stdin().read_line(&mut String::new()).ok();
+}
+
+// Print one RNG battery report
+fn print_rng_report(report: &crate::statistics::rng_battery::RngReport) {
+    println!("\n{}", format!("RNG report {} - {} ({} samples per test)", report.timestamp, report.source, report.samples).bright_cyan().bold());
+    for result in &report.results {
+        let status = if result.passed { "PASS".green() } else { "FAIL".red() };
+        println!("  {:<32} statistic {:>10.4}  p = {:.4}  {}", result.name, result.statistic, result.p_value, status);
+    }
+    if report.passed() {
+        println!("{}", "✓ All RNG tests passed.".green().bold());
+    } else {
+        println!("{}", "✗ At least one RNG test failed. A fair generator fails each test 1% of the time, rerun before concluding.".red().bold());
+    }
+}
+
+/// Run the statistical test battery against the casino RNG - REQUIRES rng.test
+fn run_rng_battery(conn: &Connection, user: &User) {
+    use crate::cryptography::rng::CasinoRng;
+    use crate::db::dbqueries;
+    use crate::statistics::rng_battery;
+
+    // SECURITY: Double-check authorization
+    if authorization::require_permission(conn, user, Permission::RngTest).is_err() {
+        return;
+    }
+
+    let rng_options = vec!["Secure RNG (used for play)", "Seeded RNG", "Cancel"];
+    let rng_choice = menu_generator("Select RNG to Test", &rng_options);
+
+    let (mut rng, source) = match rng_choice {
+        "Secure RNG (used for play)" => (CasinoRng::new(), "secure".to_string()),
+        "Seeded RNG" => {
+            print!("Enter seed: ");
+            io::stdout().flush().ok();
+            let mut seed_input = String::new();
+            io::stdin().read_line(&mut seed_input).ok();
+            match seed_input.trim().parse::<u64>() {
+                Ok(seed) => (CasinoRng::seeded(seed), format!("seeded:{}", seed)),
+                Err(_) => {
+                    println!("{}", "Invalid seed! Must be a whole number".red());
+                    return;
+                }
+            }
+        },
+        _ => return,
+    };
+
+    print!("Enter samples per test (1000-1000000, default 10000): ");
+    io::stdout().flush().ok();
+    let mut samples_input = String::new();
+    io::stdin().read_line(&mut samples_input).ok();
+    let samples: usize = match samples_input.trim() {
+        "" => 10_000,
+        input => match input.parse() {
+            Ok(n) if (1_000..=1_000_000).contains(&n) => n,
+            _ => {
+                println!("{}", "Invalid sample size! Must be 1000-1000000".red());
+                return;
+            }
+        },
+    };
+
+    println!("\nRunning RNG test battery...");
+    let report = rng_battery::run_battery(&mut rng, &source, samples);
+    print_rng_report(&report);
+
+    if !report.passed() {
+        logger::warning(&format!("RNG test battery failed for {} with {} samples", report.source, report.samples));
+    }
+
+    match dbqueries::insert_rng_report(conn, &report) {
+        Ok(_) => println!("\n{}", "✓ Report stored in rng_reports table.".green().bold()),
+        Err(e) => {
+            logger::error(&format!("Failed to store RNG test report: {}", e));
+            println!("{}", format!("Warning: Failed to store RNG test report: {}", e).yellow());
+        }
+    }
+
+    println!("\nPress Enter to continue...");
+    io::stdin().read_line(&mut String::new()).ok();
+}
+
+/// Show the most recent stored RNG test battery reports - REQUIRES reports.view
+fn view_rng_reports(conn: &Connection, user: &User) {
+    use crate::db::dbqueries;
+
+    // SECURITY: Double-check authorization
+    if authorization::require_permission(conn, user, Permission::ReportsView).is_err() {
+        return;
+    }
+
+    match dbqueries::get_rng_reports(conn, 10) {
+        Ok(reports) if reports.is_empty() => println!("\nNo RNG test reports stored yet."),
+        Ok(reports) => reports.iter().for_each(print_rng_report),
+        Err(e) => println!("{}", format!("Error loading RNG test reports: {}", e).red()),
+    }
+
+    println!("\nPress Enter to continue...");
+    io::stdin().read_line(&mut String::new()).ok();
+}
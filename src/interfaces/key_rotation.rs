@@ -0,0 +1,93 @@
+use rusqlite::Connection;
+use colored::*;
+
+use crate::authentication::authorization;
+use crate::authentication::permissions::Permission;
+use crate::authentication::session;
+use crate::cryptography::{crypto, keyfile};
+use crate::db::{key_rotation, user_admin};
+use crate::interfaces::menus::menu_generator;
+use crate::interfaces::user::User;
+use crate::logger::logger;
+
+/// Show which keys encrypt the stored data and rotate to a new key - REQUIRES keys.rotate
+pub fn key_rotation_menu(conn: &Connection, user: &User) {
+    if authorization::require_permission(conn, user, Permission::KeysRotate).is_err() {
+        logger::security(&format!("User ID: {} attempted to access key rotation without proper permissions", user.id));
+        return;
+    }
+
+    println!("\n{}", "═══ 🔐 Encryption Keys 🔐 ═══".cyan());
+    println!("Active key: {}", crypto::active_key_id());
+    let storage = if crypto::uses_key_file() { "passphrase protected key file" } else { "plaintext in .env" };
+    println!("Stored in: {}", storage);
+    match key_rotation::key_usage(conn) {
+        Ok(usage) => {
+            for (key_id, count) in usage {
+                println!("  Key {:<4} encrypts {} value(s)", key_id, count);
+            }
+        }
+        Err(e) => {
+            println!("{}", format!("Error loading key usage: {}", e).red());
+            return;
+        }
+    }
+    println!("Rotation generates a new key, re-encrypts every balance and secret with it and keeps");
+    println!("the old keys so values written meanwhile can still be decrypted.");
+
+    let mut menu_options = vec!["Rotate Key"];
+    if !crypto::uses_key_file() {
+        menu_options.push("Move Keys to Passphrase File");
+    }
+    menu_options.push("Cancel");
+    match menu_generator("What would you like to do?", &menu_options) {
+        "Rotate Key" => rotate_key(conn, user),
+        "Move Keys to Passphrase File" => move_keys_to_key_file(conn, user),
+        _ => {}
+    }
+}
+
+fn rotate_key(conn: &Connection, user: &User) {
+    // SECURITY: Key rotation needs the password again
+    if !session::reauthenticate(conn, user, "rotate the encryption key") {
+        return;
+    }
+
+    match key_rotation::rotate(conn, user) {
+        Ok(report) => println!(
+            "{}",
+            format!("✓ Key {} is now active: {} balances and {} secrets re-encrypted", report.key_id, report.balances, report.secrets).green().bold()
+        ),
+        Err(e) => println!("{}", format!("❌ Key rotation failed: {}", e).red().bold()),
+    }
+}
+
+/// Move the encryption keys out of .env into a key file encrypted under an operator passphrase
+fn move_keys_to_key_file(conn: &Connection, user: &User) {
+    println!("The keys will be encrypted with a passphrase that must be entered every time the casino starts,");
+    println!("or passed on the file descriptor in CASINO_KEY_PASSPHRASE_FD. Without it the data cannot be decrypted.");
+
+    // SECURITY: Moving the keys needs the password again
+    if !session::reauthenticate(conn, user, "move the encryption keys") {
+        return;
+    }
+
+    let passphrase = match dialoguer::Password::new()
+        .with_prompt(format!("New key file passphrase (at least {} characters)", keyfile::MIN_PASSPHRASE_LENGTH))
+        .with_confirmation("Confirm passphrase", "Passphrases do not match")
+        .interact()
+    {
+        Ok(passphrase) => passphrase,
+        Err(_) => return,
+    };
+
+    match crypto::move_keys_to_key_file(&passphrase) {
+        Ok(()) => {
+            if let Err(e) = user_admin::record_admin_action(conn, user, None, "move_keys_to_key_file", "encryption keys moved from .env to the key file") {
+                logger::error(&format!("Failed to record moving the encryption keys: {}", e));
+            }
+            println!("{}", "✓ The encryption keys are now in the passphrase protected key file and were removed from .env".green().bold());
+        }
+        Err(e) => println!("{}", format!("❌ The keys could not be moved: {}", e).red().bold()),
+    }
+}
@@ -3,18 +3,27 @@ use dialoguer::theme::ColorfulTheme;
 use clearscreen;
 use console::Style;
 use colored::*;
+use rusqlite::Connection;
 
-pub fn menu_generator<'a>(prompt: &str, options: &Vec<&'a str>) -> &'a str {
+use crate::authentication::session;
+use crate::interfaces::user::User;
 
-    let mut theme = ColorfulTheme::default();
+// Shared look for every menu in the casino
+fn casino_theme() -> ColorfulTheme {
+    ColorfulTheme {
+        active_item_style: Style::new().black().bold().on_white(),
+        inactive_item_style: Style::new().white(),
+        prompt_style: Style::new().white().bold(),
+        prompt_prefix: Style::new().apply_to("".to_string()),
+        prompt_suffix: Style::new().apply_to("".to_string()),
+        active_item_prefix: Style::new().white().bold().apply_to(">".to_string()),
+        inactive_item_prefix: Style::new().apply_to(" ".to_string()),
+        ..ColorfulTheme::default()
+    }
+}
 
-    theme.active_item_style = Style::new().black().bold().on_white();
-    theme.inactive_item_style = Style::new().white();
-    theme.prompt_style = Style::new().white().bold();
-    theme.prompt_prefix = Style::new().apply_to("".to_string());
-    theme.prompt_suffix = Style::new().apply_to("".to_string());
-    theme.active_item_prefix = Style::new().white().bold().apply_to(">".to_string());
-    theme.inactive_item_prefix = Style::new().apply_to(" ".to_string());
+pub fn menu_generator<'a>(prompt: &str, options: &Vec<&'a str>) -> &'a str {
+    let theme = casino_theme();
 
     let selection = Select::with_theme(&theme)
         .with_prompt(prompt)
@@ -27,16 +36,20 @@ pub fn menu_generator<'a>(prompt: &str, options: &Vec<&'a str>) -> &'a str {
     options[selection]
 }
 
-pub fn menu_generator_multi<'a>(prompt: &str, options: &Vec<&'a str>) -> Vec<usize> {
-    let mut theme = ColorfulTheme::default();
+// Menu shown to a signed in user. Returns None once their session has ended or was idle for too
+// long, the caller then leaves so every menu closes back to the login screen.
+pub fn session_menu<'a>(conn: &Connection, user: &User, prompt: &str, options: &Vec<&'a str>) -> Option<&'a str> {
+    // A submenu may have ended the session
+    if !session::is_active(conn, user) {
+        return None;
+    }
+    let choice = menu_generator(prompt, options);
+    // Time spent at the prompt counts as idle
+    session::touch(conn, user).then_some(choice)
+}
 
-    theme.active_item_style = Style::new().black().bold().on_white();
-    theme.inactive_item_style = Style::new().white();
-    theme.prompt_style = Style::new().white().bold();
-    theme.prompt_prefix = Style::new().apply_to("".to_string());
-    theme.prompt_suffix = Style::new().apply_to("".to_string());
-    theme.active_item_prefix = Style::new().white().bold().apply_to(">".to_string());
-    theme.inactive_item_prefix = Style::new().apply_to(" ".to_string());
+pub fn menu_generator_multi(prompt: &str, options: &Vec<&str>) -> Vec<usize> {
+    let theme = casino_theme();
     loop {
         
         let selections = MultiSelect::with_theme(&theme)
@@ -1,4 +1,8 @@
 pub mod user;
 pub mod technician;
 pub mod commisioner;
-pub mod menus;
\ No newline at end of file
+pub mod menus;
+pub mod replay;
+pub mod user_admin;
+pub mod two_factor;
+pub mod key_rotation;
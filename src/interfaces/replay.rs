@@ -0,0 +1,132 @@
+use rusqlite::Connection;
+use colored::*;
+use std::io::{self, Write};
+
+use crate::authentication::authorization;
+use crate::authentication::permissions::Permission;
+use crate::cryptography::provably_fair::{self, Verification};
+use crate::db::dbqueries;
+use crate::interfaces::menus::session_menu;
+use crate::interfaces::user::User;
+use crate::logger::logger;
+use crate::play::game::find_game;
+
+// Replay a disputed round: re-run the game logic with the recorded paytable, bet, seeds, nonce and
+// holds, and show the result next to what was recorded. Requires rounds.replay.
+pub fn replay_round_menu(conn: &Connection, user: &User) -> rusqlite::Result<()> {
+    if authorization::require_permission(conn, user, Permission::RoundsReplay).is_err() {
+        logger::security(&format!("User ID: {} attempted to access round replay without proper permissions", user.id));
+        return Ok(());
+    }
+
+    logger::security(&format!("User ID: {} accessed round replay", user.id));
+
+    loop {
+        let menu_options = vec!["Show Recent Rounds", "Replay Round by ID", "Back"];
+        let Some(user_input) = session_menu(conn, user, "═══ 🔁 Round Replay 🔁 ═══", &menu_options) else {
+            break;
+        };
+
+        match user_input.trim() {
+            "Show Recent Rounds" => show_recent_rounds(conn),
+            "Replay Round by ID" => {
+                print!("Round ID: ");
+                io::stdout().flush().ok();
+                let mut input = String::new();
+                io::stdin().read_line(&mut input).ok();
+                match input.trim().parse::<i64>() {
+                    Ok(round_id) => replay_round(conn, user, round_id),
+                    Err(_) => println!("{}", "Invalid round ID".red()),
+                }
+            }
+            "Back" => break,
+            _ => println!("Invalid choice"),
+        }
+    }
+
+    Ok(())
+}
+
+fn show_recent_rounds(conn: &Connection) {
+    match dbqueries::get_recent_fair_rounds(conn, 20) {
+        Ok(rounds) if rounds.is_empty() => println!("\nNo rounds recorded yet."),
+        Ok(rounds) => {
+            println!("\n{}", "═══ Recent Rounds ═══".cyan());
+            for (username, round) in rounds {
+                println!(
+                    "#{:<6} {:<16} {:<18} bet ${:<6.2} paid ${:<8.2} {}",
+                    round.id, username, round.game_name, round.bet, round.payout, round.result
+                );
+            }
+        }
+        Err(e) => println!("{}", format!("Error loading rounds: {}", e).red()),
+    }
+}
+
+fn replay_round(conn: &Connection, user: &User, round_id: i64) {
+    let (round, seed) = match dbqueries::get_fair_round(conn, round_id) {
+        Ok(found) => found,
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            println!("{}", format!("No round with ID {}", round_id).red());
+            return;
+        }
+        Err(e) => {
+            println!("{}", format!("Error loading round: {}", e).red());
+            return;
+        }
+    };
+    logger::security(&format!("User ID: {} replayed round {}", user.id, round_id));
+
+    let Some(mut game) = find_game(&round.game_name) else {
+        println!("{}", format!("Unknown game {}", round.game_name).red());
+        return;
+    };
+    // Only needed for rounds recorded before paytable snapshots were kept
+    if let Err(e) = game.load_config(conn) {
+        println!("{}", format!("Error loading {} configuration: {}", round.game_name, e).red());
+        return;
+    }
+
+    println!("\n{}", format!("═══ Replay of Round #{} ═══", round.id).bright_cyan().bold());
+    println!("{}: {}", "Game".yellow(), round.game_name);
+    println!("{}: ${:.2}", "Bet".yellow(), round.bet);
+    println!("{}: {} (nonce {})", "Seed pair".yellow(), seed.id, round.nonce);
+    println!("{}: {}", "Client seed".yellow(), seed.client_seed);
+    if !round.holds.is_empty() {
+        let holds: Vec<String> = round.holds.iter().map(|hold| (hold + 1).to_string()).collect();
+        println!("{}: reels {}", "Held".yellow(), holds.join(", "));
+    }
+    if round.paytable.is_empty() {
+        println!("{}", "No paytable snapshot for this round, replaying with the current paytable".yellow());
+    } else {
+        println!("{}", "Recorded paytable:".yellow());
+        for (symbol, weight, payout) in &round.paytable {
+            println!("  {} - weight {}, {}x", symbol, weight, payout);
+        }
+    }
+
+    let verification = provably_fair::verify_round(game.as_mut(), &seed, &round);
+    let replayed = provably_fair::replay(game.as_ref(), &seed.server_seed, &seed.client_seed, &round);
+
+    println!("\n{}: {} - paid ${:.2}", "Recorded".yellow(), round.result, round.payout);
+    println!("{}: {} - paid ${:.2}", "Replayed".yellow(), provably_fair::result_text(&replayed), replayed.payout);
+    for line in &replayed.lines {
+        println!("  {}", line);
+    }
+
+    match verification {
+        Verification::Verified => println!("{}", "✓ Replay is identical to the recorded round".green().bold()),
+        Verification::Mismatch(_) => {
+            logger::critical(&format!("Replay of round {} does not match the recorded result", round.id));
+            println!("{}", "✗ Replay differs from the recorded round!".red().bold());
+        }
+        Verification::PaytableChanged => println!("{}", "? The paytable has changed since this round, the replay is not comparable".yellow().bold()),
+        Verification::SnapshotTampered => {
+            logger::critical(&format!("Paytable snapshot of round {} does not match its fingerprint", round.id));
+            println!("{}", "✗ Recorded paytable does not match its fingerprint!".red().bold());
+        }
+    }
+
+    println!("\nPress Enter to continue...");
+    io::stdin().read_line(&mut String::new()).ok();
+}
@@ -1,25 +1,45 @@
 use rusqlite::{Connection};
 use colored::*;
+use std::io::{self, Write};
 
 use crate::{db::dbqueries, interfaces::user::User, logger};
 use crate::authentication::authorization;
+use crate::authentication::permissions::{self, Permission};
+use crate::authentication::session;
+use crate::db::ledger;
+use crate::db::transaction_limits::{self, TransactionCategory, TransactionPolicy};
 
-use crate::interfaces::menus::menu_generator;
+use crate::interfaces::menus::{menu_generator, session_menu};
+
+// Options of the technician menu and the permission each one needs
+pub const TECHNICIAN_OPTIONS: [(&str, Permission); 9] = [
+    ("Show Games", Permission::GamesToggle),
+    ("Show Statistics", Permission::GamesStats),
+    ("Security Logs", Permission::LogsView),
+    ("Replay Round", Permission::RoundsReplay),
+    ("Reconcile Balances", Permission::LedgerReconcile),
+    ("Transaction Limits", Permission::LimitsManage),
+    ("Lockouts", Permission::LimitsManage),
+    ("User Administration", Permission::UsersManage),
+    ("Encryption Keys", Permission::KeysRotate),
+];
 
 pub fn technician_menu(conn: &Connection, user: &User) -> rusqlite::Result<()> {
-    // SECURITY: Verify user has technician role
-    if let Err(e) = authorization::require_technician(conn, user) {
-        logger::logger::security(&format!("Blocked unauthorized access to technician menu by User ID: {}: {}", user.id, e));
+    // SECURITY: Only the options the user's role permits are offered
+    let mut menu_options = permissions::permitted_options(conn, user, &TECHNICIAN_OPTIONS);
+    if menu_options.is_empty() {
+        println!("{}", "Access denied: Insufficient privileges".red());
+        logger::logger::security(&format!("Blocked unauthorized access to technician menu by User ID: {}", user.id));
         return Ok(());
     }
+    menu_options.push("Two-Factor Authentication");
+    menu_options.push("Logout");
     
     // Log that technician has accessed the menu
     logger::logger::security(&format!("Technician (User ID: {}) accessed technician menu", user.id));
     
-    loop {
-        // Show options to user
-        let menu_options = vec!["Show Games", "Show Statistics", "Security Logs", "Logout"];
-        let user_input = menu_generator("═══ 🎰 Tech Menu 🎰 ═══", &menu_options);
+    // Show options to user until they log out or their session ends
+    while let Some(user_input) = session_menu(conn, user, "═══ 🎰 Tech Menu 🎰 ═══", &menu_options) {
 
         match user_input.trim() {
             "Show Games" => {
@@ -34,6 +54,33 @@ pub fn technician_menu(conn: &Connection, user: &User) -> rusqlite::Result<()> {
                 logger::logger::security(&format!("Technician (User ID: {}) accessed security logs", user.id));
                 logger::verification::log_verification_menu(conn, user)?;
             }
+            "Replay Round" => {
+                logger::logger::security(&format!("Technician (User ID: {}) accessed round replay", user.id));
+                crate::interfaces::replay::replay_round_menu(conn, user)?;
+            }
+            "Reconcile Balances" => {
+                logger::logger::security(&format!("Technician (User ID: {}) ran balance reconciliation", user.id));
+                reconcile_balances(conn, user);
+            }
+            "Transaction Limits" => {
+                logger::logger::security(&format!("Technician (User ID: {}) accessed transaction limits", user.id));
+                transaction_limits_menu(conn, user);
+            }
+            "Lockouts" => {
+                logger::logger::security(&format!("Technician (User ID: {}) accessed lockouts", user.id));
+                lockouts_menu(conn, user);
+            }
+            "User Administration" => {
+                logger::logger::security(&format!("Technician (User ID: {}) accessed user administration", user.id));
+                crate::interfaces::user_admin::user_admin_menu(conn, user);
+            }
+            "Encryption Keys" => {
+                logger::logger::security(&format!("Technician (User ID: {}) accessed encryption keys", user.id));
+                crate::interfaces::key_rotation::key_rotation_menu(conn, user);
+            }
+            "Two-Factor Authentication" => {
+                crate::interfaces::two_factor::two_factor_menu(conn, user);
+            }
             "Logout" => {
                 logger::logger::info(&format!("Technician (User ID: {}) logged out", user.id));
                 println!("Logging out...");
@@ -48,10 +95,10 @@ pub fn technician_menu(conn: &Connection, user: &User) -> rusqlite::Result<()> {
     Ok(())
 }
 
-/// Function to allow technician to change what games are available to the user - REQUIRES TECHNICIAN ROLE
+/// Function to allow technician to change what games are available to the user - REQUIRES games.toggle
 fn games_menu(conn: &Connection, user: &User) -> rusqlite::Result<()>{
     // SECURITY: Double-check authorization
-    if authorization::require_technician(conn, user).is_err() {
+    if authorization::require_permission(conn, user, Permission::GamesToggle).is_err() {
         return Ok(());
     }
     
@@ -77,7 +124,9 @@ fn games_menu(conn: &Connection, user: &User) -> rusqlite::Result<()>{
             .collect();
         // add exit
         all_games.push("exit");
-        let user_input = menu_generator("═══ 🎰 Technician Games Control 🎰 ═══", &all_games);
+        let Some(user_input) = session_menu(conn, user, "═══ 🎰 Technician Games Control 🎰 ═══", &all_games) else {
+            break;
+        };
 
         match user_input {
             "exit" => {
@@ -94,10 +143,10 @@ fn games_menu(conn: &Connection, user: &User) -> rusqlite::Result<()>{
     Ok(())
 }
 
-/// View game statistics - REQUIRES TECHNICIAN ROLE
+/// View game statistics - REQUIRES games.stats
 fn technician_statistics(conn: &Connection, user: &User) {
     // SECURITY: Double-check authorization
-    if authorization::require_technician(conn, user).is_err() {
+    if authorization::require_permission(conn, user, Permission::GamesStats).is_err() {
         return;
     }
     
@@ -106,48 +155,212 @@ fn technician_statistics(conn: &Connection, user: &User) {
     let _ = dbqueries::get_game_statistics(conn);
 }
 
-#[cfg(test)]
-mod integration_tests {
-    use super::*;
-    use rusqlite::Connection;
-    use crate::interfaces::user::User;
-
-    fn setup_test_db() -> Connection {
-        crate::cryptography::crypto::initialize_encryption_key();
-        let conn = Connection::open_in_memory().unwrap();
-        conn.execute("PRAGMA foreign_keys = ON", []).unwrap();
-        crate::db::dbinitialize::initialize_dbs(&conn).unwrap();
-        conn
+/// Compare every stored balance with the ledger - REQUIRES ledger.reconcile
+fn reconcile_balances(conn: &Connection, user: &User) {
+    // SECURITY: Double-check authorization
+    if authorization::require_permission(conn, user, Permission::LedgerReconcile).is_err() {
+        return;
     }
 
-    fn create_test_user(conn: &Connection, username: &str, role: &str) -> i32 {
-        conn.execute(
-            "INSERT INTO users (username, password, balance, role) VALUES (?1, ?2, '0.0', ?3)",
-            [username, "test_hash", role],
-        ).unwrap();
-        
-        conn.last_insert_rowid() as i32
+    let results = match ledger::reconcile(conn) {
+        Ok(results) => results,
+        Err(e) => {
+            println!("{}", format!("Error reconciling balances: {}", e).red());
+            return;
+        }
+    };
+
+    let mismatches: Vec<_> = results.iter().filter(|result| !result.matches()).collect();
+    println!("\n{}", "═══ Balance Reconciliation ═══".cyan());
+    println!("Accounts checked: {}", results.len());
+    if mismatches.is_empty() {
+        println!("{}", "✓ Every stored balance matches the ledger".green().bold());
+    } else {
+        println!("{}", format!("✗ {} balance(s) do not match the ledger!", mismatches.len()).red().bold());
+        for result in mismatches {
+            let stored = match &result.stored_balance {
+                Ok(balance) => format!("${}", balance),
+                Err(_) => "unreadable".to_string(),
+            };
+            println!(
+                "  User ID {:<6} stored {:<11} ledger ${:<10.2}",
+                result.user_id, stored, result.ledger_balance
+            );
+        }
     }
+}
+
+/// View and change the anti-fraud limits of each transaction category - REQUIRES limits.manage
+fn transaction_limits_menu(conn: &Connection, user: &User) {
+    // SECURITY: Double-check authorization
+    if authorization::require_permission(conn, user, Permission::LimitsManage).is_err() {
+        return;
+    }
+
+    loop {
+        let policies = match transaction_limits::get_policies(conn) {
+            Ok(policies) => policies,
+            Err(e) => {
+                println!("{}", format!("Error loading transaction limits: {}", e).red());
+                return;
+            }
+        };
+
+        println!("\n{}", "═══ Transaction Limits (0 = off) ═══".cyan());
+        println!("Win credits are never limited.");
+        for policy in &policies {
+            println!(
+                "  {:<26} {:>4} per minute, {:>4} identical amounts per minute",
+                policy.category.label(), policy.max_per_minute, policy.identical_per_minute
+            );
+        }
+
+        let mut menu_options: Vec<&str> = TransactionCategory::ALL.iter().map(|category| category.label()).collect();
+        menu_options.push("Back");
+        let Some(choice) = session_menu(conn, user, "Change the limits of", &menu_options) else {
+            return;
+        };
+        let Some(category) = TransactionCategory::ALL.into_iter().find(|category| category.label() == choice) else {
+            return;
+        };
+
+        let Some(max_per_minute) = read_limit("Transactions per minute") else {
+            continue;
+        };
+        let Some(identical_per_minute) = read_limit("Identical amounts per minute") else {
+            continue;
+        };
+
+        // The limit prompts may have been left idle
+        if !session::touch(conn, user) {
+            return;
+        }
+
+        let policy = TransactionPolicy { category, max_per_minute, identical_per_minute };
+        match transaction_limits::set_policy(conn, &policy) {
+            Ok(_) => {
+                logger::logger::security(&format!(
+                    "Technician (User ID: {}) set {} limits to {} per minute, {} identical per minute",
+                    user.id, category.as_str(), max_per_minute, identical_per_minute
+                ));
+                println!("{}", "✓ Transaction limits updated".green());
+            }
+            Err(e) => println!("{}", format!("Error updating transaction limits: {}", e).red()),
+        }
+    }
+}
+
+/// See login lockouts and users at their transaction limits, and lift them - REQUIRES limits.manage
+fn lockouts_menu(conn: &Connection, user: &User) {
+    // SECURITY: Double-check authorization
+    if authorization::require_permission(conn, user, Permission::LimitsManage).is_err() {
+        return;
+    }
+
+    loop {
+        let (attempts, activity) = match (authorization::get_all_login_attempts(conn), transaction_limits::get_recent_activity(conn)) {
+            (Ok(attempts), Ok(activity)) => (attempts, activity),
+            (Err(e), _) | (_, Err(e)) => {
+                println!("{}", format!("Error loading lockouts: {}", e).red());
+                return;
+            }
+        };
+
+        println!("\n{}", "═══ Failed Logins ═══".cyan());
+        if attempts.is_empty() {
+            println!("No failed logins on record.");
+        }
+        for attempt in &attempts {
+            match authorization::get_lockout_remaining(conn, &attempt.username) {
+                Some(remaining) => println!(
+                    "  {:<30} {} failed attempts - {}",
+                    attempt.username, attempt.failed_count, format!("locked for {}s", remaining).red()
+                ),
+                None => println!("  {:<30} {} failed attempts", attempt.username, attempt.failed_count),
+            }
+        }
+
+        println!("\n{}", "═══ Transactions In The Last Minute ═══".cyan());
+        if activity.is_empty() {
+            println!("No recent transactions.");
+        }
+        for entry in &activity {
+            println!("  {:<30} {:<26} {}", entry.username, entry.category.label(), entry.transactions);
+        }
+
+        let menu_options = vec!["Unlock Account", "Lift Transaction Limits", "Back"];
+        match session_menu(conn, user, "═══ Lockouts ═══", &menu_options) {
+            Some("Unlock Account") => {
+                let mut usernames: Vec<&str> = attempts.iter().map(|attempt| attempt.username.as_str()).collect();
+                usernames.push("Cancel");
+                let choice = menu_generator("Unlock which account?", &usernames);
+                if choice != "Cancel" && authorization::unlock_account(conn, user, choice) {
+                    println!("{}", format!("✓ {} unlocked", choice).green());
+                }
+            }
+            Some("Lift Transaction Limits") => {
+                let mut usernames: Vec<&str> = Vec::new();
+                for entry in &activity {
+                    if !usernames.contains(&entry.username.as_str()) {
+                        usernames.push(&entry.username);
+                    }
+                }
+                usernames.push("Cancel");
+                let choice = menu_generator("Lift the limits of which user?", &usernames);
+                let Some(entry) = activity.iter().find(|entry| entry.username == choice) else {
+                    continue;
+                };
+                match transaction_limits::clear_user(conn, entry.user_id) {
+                    Ok(_) => {
+                        logger::logger::security(&format!("Technician (User ID: {}) lifted the transaction limits of User ID: {}", user.id, entry.user_id));
+                        println!("{}", format!("✓ Transaction limits lifted for {}", choice).green());
+                    }
+                    Err(e) => println!("{}", format!("Error lifting transaction limits: {}", e).red()),
+                }
+            }
+            _ => return,
+        }
+    }
+}
+
+// Read a per-minute limit, 0 to 1000
+fn read_limit(prompt: &str) -> Option<u32> {
+    print!("{} (0-1000, 0 = off): ", prompt);
+    io::stdout().flush().ok();
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).ok();
+
+    match input.trim().parse() {
+        Ok(limit) if limit <= 1000 => Some(limit),
+        _ => {
+            println!("{}", "Invalid limit! Must be 0-1000".red());
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod integration_tests {
+    use super::*;
+    use crate::db::test_support::{create_account, setup_test_db};
 
     #[test]
     fn test_technician_authorization_succeeds() {
         let conn = setup_test_db();
-        let tech_id = create_test_user(&conn, "techuser", "technician");
-        let user = User { id: tech_id };
+        let user = create_account(&conn, "techuser", "technician");
         
         // Should succeed for technician
-        let result = crate::authentication::authorization::require_technician(&conn, &user);
+        let result = crate::authentication::authorization::require_permission(&conn, &user, Permission::GamesToggle);
         assert!(result.is_ok());
     }
 
     #[test]
     fn test_technician_authorization_fails_for_user() {
         let conn = setup_test_db();
-        let user_id = create_test_user(&conn, "normaluser", "user");
-        let user = User { id: user_id };
+        let user = create_account(&conn, "normaluser", "user");
         
         // Should fail for regular user
-        let result = crate::authentication::authorization::require_technician(&conn, &user);
+        let result = crate::authentication::authorization::require_permission(&conn, &user, Permission::GamesToggle);
         assert!(result.is_err());
     }
 
@@ -232,12 +445,11 @@ mod integration_tests {
     #[test]
     fn test_games_menu_requires_technician() {
         let conn = setup_test_db();
-        let user_id = create_test_user(&conn, "regularuser", "user");
-        let user = User { id: user_id };
+        let user = create_account(&conn, "regularuser", "user");
         
         // games_menu should return early for non-technician
         // Test is user is not technician
-        let auth_result = crate::authentication::authorization::require_technician(&conn, &user);
+        let auth_result = crate::authentication::authorization::require_permission(&conn, &user, Permission::GamesToggle);
         assert!(auth_result.is_err());
     }
 
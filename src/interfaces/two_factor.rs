@@ -0,0 +1,156 @@
+use rusqlite::Connection;
+use colored::*;
+use std::io::{self, Write};
+
+use crate::cryptography::totp;
+use crate::db::two_factor;
+use crate::interfaces::menus::session_menu;
+use crate::interfaces::user::User;
+use crate::logger::logger;
+
+// Attempts allowed at entering the first code of a new authenticator
+const ENROLLMENT_ATTEMPTS: u32 = 3;
+
+fn read_code(prompt: &str) -> Option<String> {
+    print!("{} ", prompt.bright_white().bold());
+    io::stdout().flush().ok();
+    let mut code = String::new();
+    if io::stdin().read_line(&mut code).is_err() {
+        logger::error("Failed to read two-factor code");
+        return None;
+    }
+    Some(code.trim().to_string())
+}
+
+/// Ask for the second factor during sign in. Returns whether it was given.
+pub fn challenge(conn: &Connection, user_id: i32) -> bool {
+    println!("\n{}", "═══ 🔑 Two-Factor Authentication 🔑 ═══".bright_cyan().bold());
+    let Some(code) = read_code("Code from your authenticator app, or a recovery code:") else {
+        return false;
+    };
+    match two_factor::verify(conn, user_id, &code) {
+        Ok(verified) => verified,
+        Err(e) => {
+            // Fail closed
+            logger::error(&format!("Failed to verify two-factor code for User ID: {}: {}", user_id, e));
+            false
+        }
+    }
+}
+
+/// Set up an authenticator app for the user. Returns whether two-factor authentication is now on.
+pub fn enroll(conn: &Connection, user_id: i32, username: &str) -> bool {
+    let secret = totp::generate_secret();
+
+    println!("\n{}", "═══ 🔑 Set Up Two-Factor Authentication 🔑 ═══".bright_cyan().bold());
+    println!("Add this account to your authenticator app with the key:");
+    println!("\n  {}\n", totp::base32_encode(&secret).bright_yellow().bold());
+    println!("or with this link (for QR code generators):");
+    println!("  {}\n", totp::provisioning_uri(&secret, username));
+
+    for _ in 0..ENROLLMENT_ATTEMPTS {
+        let Some(code) = read_code("Enter the code your app shows:") else {
+            return false;
+        };
+        let Some(step) = totp::verify(&secret, &code, chrono::Utc::now().timestamp() as u64) else {
+            println!("{}", "❌ That code does not match, check the key and your clock".red());
+            continue;
+        };
+        return match two_factor::enable(conn, user_id, &secret, step) {
+            Ok(recovery_codes) => {
+                println!("{}", "✓ Two-factor authentication enabled".green().bold());
+                show_recovery_codes(&recovery_codes);
+                true
+            }
+            Err(e) => {
+                logger::error(&format!("Failed to enable two-factor authentication for User ID: {}: {}", user_id, e));
+                println!("{}", "❌ Two-factor authentication could not be enabled".red().bold());
+                false
+            }
+        };
+    }
+    logger::security(&format!("Two-factor enrollment abandoned by User ID: {}", user_id));
+    false
+}
+
+fn show_recovery_codes(codes: &[String]) {
+    println!("\n{}", "Recovery codes - each signs you in once if you lose your authenticator.".yellow());
+    println!("{}", "Store them somewhere safe, they will not be shown again:".yellow());
+    for code in codes {
+        println!("  {}", code.bright_white().bold());
+    }
+    read_code("\nPress Enter once they are saved");
+}
+
+/// Two-factor settings of the signed in user
+pub fn two_factor_menu(conn: &Connection, user: &User) {
+    let username = match user.get_username(conn) {
+        Ok(username) => username,
+        Err(e) => {
+            println!("{}", format!("Error loading account: {}", e).red());
+            return;
+        }
+    };
+
+    loop {
+        let (enabled, required) = match (two_factor::is_enabled(conn, user.id), two_factor::is_required(conn, user.id)) {
+            (Ok(enabled), Ok(required)) => (enabled, required),
+            (Err(e), _) | (_, Err(e)) => {
+                println!("{}", format!("Error loading two-factor settings: {}", e).red());
+                return;
+            }
+        };
+
+        println!("\n{}", "═══ Two-Factor Authentication ═══".cyan());
+        if enabled {
+            let remaining = two_factor::remaining_recovery_codes(conn, user.id).unwrap_or(0);
+            println!("Status: {}, {} recovery codes left", "enabled".green(), remaining);
+        } else {
+            println!("Status: {}", "disabled".red());
+        }
+        if required {
+            println!("Required for your role by policy.");
+        }
+
+        let menu_options = if enabled {
+            vec!["Set Up New Authenticator", "Regenerate Recovery Codes", "Disable Two-Factor", "Back"]
+        } else {
+            vec!["Set Up Two-Factor", "Back"]
+        };
+        match session_menu(conn, user, "═══ 🔑 Two-Factor Authentication 🔑 ═══", &menu_options) {
+            Some("Set Up Two-Factor" | "Set Up New Authenticator") => {
+                // SECURITY: Replacing the authenticator needs a code from the current one
+                if enabled && !challenge(conn, user.id) {
+                    println!("{}", "❌ Invalid code".red());
+                    continue;
+                }
+                enroll(conn, user.id, &username);
+            }
+            Some("Regenerate Recovery Codes") => {
+                if !challenge(conn, user.id) {
+                    println!("{}", "❌ Invalid code".red());
+                    continue;
+                }
+                match two_factor::regenerate_recovery_codes(conn, user.id) {
+                    Ok(codes) => show_recovery_codes(&codes),
+                    Err(e) => println!("{}", format!("Error regenerating recovery codes: {}", e).red()),
+                }
+            }
+            Some("Disable Two-Factor") => {
+                if required {
+                    println!("{}", "❌ Two-factor authentication is required for your role".red());
+                    continue;
+                }
+                if !challenge(conn, user.id) {
+                    println!("{}", "❌ Invalid code".red());
+                    continue;
+                }
+                match two_factor::disable(conn, user.id) {
+                    Ok(_) => println!("{}", "✓ Two-factor authentication disabled".green()),
+                    Err(e) => println!("{}", format!("Error disabling two-factor authentication: {}", e).red()),
+                }
+            }
+            _ => return,
+        }
+    }
+}
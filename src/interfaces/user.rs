@@ -2,8 +2,11 @@ use rusqlite::{Connection};
 use colored::*;
 use std::io::{self, Write};
 use crate::play;
+use crate::authentication::session;
+use crate::logger::event::{LogEvent, TransactionKind};
 use crate::logger::logger;
-use crate::interfaces::menus::menu_generator;
+use crate::interfaces::menus::{menu_generator, session_menu};
+use crate::db::money::Money;
 // User struct to hold the id of the user
 pub struct User {
     pub id: i32,
@@ -14,7 +17,7 @@ impl User {
         dbqueries::user_get_username(conn, self.id)
     }
 
-    pub fn get_balance(&self, conn: &Connection) -> rusqlite::Result<f64> {
+    pub fn get_balance(&self, conn: &Connection) -> rusqlite::Result<Money> {
         dbqueries::user_get_balance(conn, self.id)
     }
 
@@ -35,8 +38,10 @@ pub fn user_menu(conn: &Connection, user: &User) -> rusqlite::Result<()> {
     
     loop {
         // print user menu
-        let menu_options = vec!["Play", "Account", "Logout"];
-        let user_input = menu_generator("═══ 🎰 777 🎰 ═══", &menu_options);
+        let menu_options = vec!["Play", "Account", "Provably Fair", "Logout"];
+        let Some(user_input) = session_menu(conn, user, "═══ 🎰 777 🎰 ═══", &menu_options) else {
+            break;
+        };
 
         match user_input.trim() {
             "Play" => {
@@ -47,6 +52,10 @@ pub fn user_menu(conn: &Connection, user: &User) -> rusqlite::Result<()> {
                 logger::info(&format!("User ID: {} selected Account option", user.id));
                 user_account(conn, user);
             }
+            "Provably Fair" => {
+                logger::info(&format!("User ID: {} selected Provably Fair option", user.id));
+                provably_fair_menu(conn, user);
+            }
             "Logout" => {
                 logger::info(&format!("User ID: {} logged out", user.id));
                 break;
@@ -91,122 +100,66 @@ fn play_menu(conn: &Connection, user: &User) -> rusqlite::Result<()>{
             .map(|s| s.as_str())
             .collect();
 
-        let user_input = menu_generator("Select a game", &menu_options);
+        let Some(user_input) = session_menu(conn, user, "Select a game", &menu_options) else {
+            break;
+        };
 
         match user_input.trim() {
-            "normal" => {
-                loop{ 
+            "Back" => {
+                logger::info(&format!("User ID: {} left game selection", user.id));
+                break;
+            }
+            name => {
+                // Games plug in through the registry, the engine runs the bet loop for all of them
+                let mut game = match play::game::find_game(name) {
+                    Some(game) => game,
+                    None => {
+                        logger::warning(&format!("User ID: {} selected unknown game type: {}", user.id, name));
+                        continue;
+                    }
+                };
+
+                loop {
                     // Get the bet amount
                     let bet = bet();
-                    if bet != 0.0 {
-                        logger::transaction(&format!("User ID: {} placed bet of ${:.2} on normal slots", user.id, bet));
-                        
-                        // Check if user has sufficient funds
-                        if !dbqueries::check_funds(conn, user, bet) {
-                            logger::warning(&format!("User ID: {} attempted to bet ${:.2} with insufficient funds", user.id, bet));
-                            println!("{}", "Insufficient funds for this bet".red());
-                            break;
-                        }
-                        
-                        if !play::slots::normal_slots(conn, bet, user) {
-                            break;
-                        }
-                    } else {
+                    if bet == Money::ZERO {
                         logger::info(&format!("User ID: {} cancelled betting", user.id));
                         break;
                     }
-                }
-            }
-            "multi" => {
-                loop{
-                    // Get the bet amount
-                    let bet = bet();
-                    if bet != 0.0 {
-                        logger::transaction(&format!("User ID: {} placed bet of ${:.2} on multiwin slots", user.id, bet));
-
-                        // Check if user has sufficient funds
-                        if !dbqueries::check_funds(conn, user, bet) {
-                            logger::warning(&format!("User ID: {} attempted to bet ${:.2} with insufficient funds", user.id, bet));
-                            println!("{}", "Insufficient funds for this bet".red());
-                            break;
-                        }
-                        if !play::multiwin::multi_win(conn, user, bet) {
-                            break;
-                        }
-                    } else {
-                        logger::info(&format!("User ID: {} cancelled betting", user.id));
+                    // The bet prompt may have been left idle
+                    if !session::touch(conn, user) {
                         break;
                     }
-                }
-            }
-            "holding" => {
-                loop{
-                    // Get the bet amount
-                    let bet = bet();
-                    if bet != 0.0 {
-                        logger::transaction(&format!("User ID: {} placed bet of ${:.2} on holding slots", user.id, bet));
-
-                        // Check if user has sufficient funds
-                        if !dbqueries::check_funds(conn, user, bet) {
-                            logger::warning(&format!("User ID: {} attempted to bet ${:.2} with insufficient funds", user.id, bet));
-                            println!("{}", "Insufficient funds for this bet".red());
-                            break;
-                        }
-                        if !play::holding::hold_game(conn, user, bet) {
-                            break;
-                        }
-                    } else {
-                        logger::info(&format!("User ID: {} cancelled betting", user.id));
+
+                    // Check if user has sufficient funds
+                    if !dbqueries::check_funds(conn, user, bet) {
+                        logger::warning(&format!("User ID: {} attempted to bet ${} with insufficient funds", user.id, bet));
+                        println!("{}", "Insufficient funds for this bet".red());
                         break;
                     }
-                }
-            }
-            "wheel of fortune" => {
-                loop{
-                    // Get the bet amount
-                    let bet = bet();
-                    if bet != 0.0 {
-                        logger::transaction(&format!("User ID: {} placed bet of ${:.2} on holding slots", user.id, bet));
-
-                        // Check if user has sufficient funds
-                        if !dbqueries::check_funds(conn, user, bet) {
-                            logger::warning(&format!("User ID: {} attempted to bet ${:.2} with insufficient funds", user.id, bet));
-                            println!("{}", "Insufficient funds for this bet".red());
-                            break;
-                        }
-                        if !play::wheelOfFortune::gameplay_wheel(conn, user, bet) {
-                            break;
-                        }
-                    } else {
-                        logger::info(&format!("User ID: {} cancelled betting", user.id));
+
+                    if !play::engine::play(conn, user, game.as_mut(), bet) {
                         break;
                     }
                 }
             }
-            "Back" => {
-                logger::info(&format!("User ID: {} selected holding game (not implemented)", user.id));
-                break;
-            }
-            _ => {
-                logger::warning(&format!("User ID: {} selected unknown game type: {}", user.id, user_input));
-            }
         }
     }
     Ok(())
 }
 
-fn bet()-> f64 {
+fn bet()-> Money {
     loop {
 
         let menu_options = vec!["$1", "$5", "$10", "$20", "Back"];
         let user_input = menu_generator("How much will you bet?", &menu_options);
         
         match user_input.trim() {
-            "$1" => return 1.0,
-            "$5" => return 5.0,
-            "$10" => return 10.0,
-            "$20" => return 20.0,
-            "Back" => return 0.0,
+            "$1" => return Money::from_dollars(1),
+            "$5" => return Money::from_dollars(5),
+            "$10" => return Money::from_dollars(10),
+            "$20" => return Money::from_dollars(20),
+            "Back" => return Money::ZERO,
             _ => println!("Invalid Input")
         }
     }
@@ -221,12 +174,14 @@ fn user_account(conn: &Connection, user: &User) {
                 println!("{}", "═══ 🎰 User Information 🎰 ═══".bright_cyan().bold());
                 println!("{}: {}", "Id".yellow(), user.id);
                 println!("{}: {}", "Username".yellow(), username);
-                println!("{}: {}", "Balance".yellow(), format!("${:.2}", balance).green());
+                println!("{}: {}", "Balance".yellow(), format!("${}", balance).green());
                 println!();
 
                 // Show options to user
-                let menu_options = vec!["Deposit", "Withdraw", "Statistics", "Change Password", "Exit"];
-                let user_input = menu_generator("═══ 🎰 User Options 🎰 ═══", &menu_options);
+                let menu_options = vec!["Deposit", "Withdraw", "Statistics", "My Play History", "Change Password", "Exit"];
+                let Some(user_input) = session_menu(conn, user, "═══ 🎰 User Options 🎰 ═══", &menu_options) else {
+                    return;
+                };
 
                 match user_input.trim() {
                     "Deposit" => {
@@ -255,6 +210,10 @@ fn user_account(conn: &Connection, user: &User) {
                         logger::info(&format!("User ID: {} accessed statistics", user.id));
                         user_statistics(conn, user);
                     }
+                    "My Play History" => {
+                        logger::info(&format!("User ID: {} viewed play history", user.id));
+                        play_history(conn, user);
+                    }
                     "Change Password" => {
                         logger::info(&format!("User ID: {} attempt to change password", user.id));
                         change_password(conn, user);
@@ -289,14 +248,14 @@ fn deposit(conn: &Connection, user: &User) -> rusqlite::Result<bool>{
     io::stdin().read_line(&mut choice).ok();
 
     // Parse the input
-    let deposit_amount: Result<f64, std::num::ParseFloatError> = choice.trim().parse();
+    let deposit_amount = Money::parse(&choice);
     
     match deposit_amount {
         Ok(amount) => {
             // Validate deposit amount
             if let Err(error) = validate_deposit(amount) {
                 display_validation_error(&error);
-                logger::warning(&format!("User ID: {} attempted invalid deposit: ${:.2}", user.id, amount));
+                logger::warning(&format!("User ID: {} attempted invalid deposit: ${}", user.id, amount));
                 return Ok(false);
             }
             
@@ -307,16 +266,12 @@ fn deposit(conn: &Connection, user: &User) -> rusqlite::Result<bool>{
 
             match user_input.trim() {
                 "Confirm" => {
-                    // Amount is valid, process deposit
-                    logger::transaction(&format!("User ID: {} depositing ${:.2}", user.id, amount));
-
+                    // Amount is valid, process deposit, which logs it once it is committed
                     match dbqueries::change_balance(conn, user, amount) {
-                        Ok(_) => {
+                        Ok(balance) => {
                             println!("\n{}", "✅ Deposit successful!".green().bold());
-                            println!("{} ${:.2}", "Deposited:".bright_white().bold(), amount);
-                            if let Ok(balance) = user.get_balance(conn) {
-                                println!("{} ${:.2}", "New Balance:".bright_white().bold(), balance);
-                            }
+                            println!("{} ${}", "Deposited:".bright_white().bold(), amount);
+                            println!("{} ${}", "New Balance:".bright_white().bold(), balance);
                             println!();
                             Ok(true)
                         }
@@ -328,17 +283,17 @@ fn deposit(conn: &Connection, user: &User) -> rusqlite::Result<bool>{
                     }
                 }
                 "Cancel" => {
-                    logger::transaction(&format!("User ID: {} Cancel deposit of ${:.2}", user.id, amount));
-                    return Ok(false);
+                    logger::event(LogEvent::requested(TransactionKind::DepositCancelled, user.id, Some(amount)));
+                    Ok(false)
                 }
                 _ => {
-                    logger::transaction(&format!("User ID: {} Deposit confirmation failed", user.id));
-                    return Ok(false);
+                    logger::event(LogEvent::requested(TransactionKind::DepositCancelled, user.id, None));
+                    Ok(false)
                 }
             }
         }
-        Err(_) => {
-            display_validation_error("❌ Invalid input! Please enter a valid number.");
+        Err(e) => {
+            display_validation_error(&format!("❌ Invalid input! {}", e));
             logger::warning(&format!("User ID: {} provided invalid deposit input: {}", user.id, choice.trim()));
             Ok(false)
         }
@@ -354,7 +309,7 @@ fn withdraw(conn: &Connection, user: &User) -> rusqlite::Result<bool>{
     // Show current balance first
     match user.get_balance(conn) {
         Ok(balance) => {
-            println!("{} ${:.2}", "Current Balance:".bright_white().bold(), balance);
+            println!("{} ${}", "Current Balance:".bright_white().bold(), balance);
         }
         Err(_) => {
             println!("{}", "❌ Cannot retrieve balance!".red().bold());
@@ -369,7 +324,7 @@ fn withdraw(conn: &Connection, user: &User) -> rusqlite::Result<bool>{
     let mut choice: String = String::new();
     io::stdin().read_line(&mut choice).ok();
     // Parse the input
-    let withdraw_amount: Result<f64, std::num::ParseFloatError> = choice.trim().parse();
+    let withdraw_amount = Money::parse(&choice);
     
     match withdraw_amount {
         Ok(amount) => {
@@ -386,7 +341,7 @@ fn withdraw(conn: &Connection, user: &User) -> rusqlite::Result<bool>{
             // Validate withdrawal amount
             if let Err(error) = validate_withdrawal(amount, current_balance) {
                 display_validation_error(&error);
-                logger::warning(&format!("User ID: {} attempted invalid withdrawal: ${:.2} (balance: ${:.2})", 
+                logger::warning(&format!("User ID: {} attempted invalid withdrawal: ${} (balance: ${})", 
                                         user.id, amount, current_balance));
                 return Ok(false);
             }
@@ -398,16 +353,19 @@ fn withdraw(conn: &Connection, user: &User) -> rusqlite::Result<bool>{
 
             match user_input.trim() {
                 "Confirm" => {
-                    // Amount is valid process withdrawal
-                    logger::transaction(&format!("User ID: {} withdrawing ${:.2}", user.id, amount));
-                    
+                    // SECURITY: Large withdrawals need the password again
+                    if session::withdrawal_needs_step_up(conn, amount)
+                        && !session::reauthenticate(conn, user, &format!("withdraw ${}", amount)) {
+                        logger::security(&format!("User ID: {} withdrawal of ${} refused without re-authentication", user.id, amount));
+                        return Ok(false);
+                    }
+
+                    // Amount is valid process withdrawal, which logs it once it is committed
                     match dbqueries::change_balance(conn, user, -amount) {
-                        Ok(_) => {
+                        Ok(balance) => {
                             println!("\n{}", "✅ Withdrawal successful!".green().bold());
-                            println!("{} ${:.2}", "Withdrawn:".bright_white().bold(), amount);
-                            if let Ok(balance) = user.get_balance(conn) {
-                                println!("{} ${:.2}", "New Balance:".bright_white().bold(), balance);
-                            }
+                            println!("{} ${}", "Withdrawn:".bright_white().bold(), amount);
+                            println!("{} ${}", "New Balance:".bright_white().bold(), balance);
                             println!();
                             Ok(true)
                         }
@@ -419,17 +377,17 @@ fn withdraw(conn: &Connection, user: &User) -> rusqlite::Result<bool>{
                     }
                 }
                 "Cancel" => {
-                    logger::transaction(&format!("User ID: {} Cancel withdrawal of ${:.2}", user.id, amount));
-                    return Ok(false);
+                    logger::event(LogEvent::requested(TransactionKind::WithdrawalCancelled, user.id, Some(amount)));
+                    Ok(false)
                 }
                 _ => {
-                    logger::transaction(&format!("User ID: {} Withdrawal confirmation failed", user.id));
-                    return Ok(false);
+                    logger::event(LogEvent::requested(TransactionKind::WithdrawalCancelled, user.id, None));
+                    Ok(false)
                 }
             }
         }
-        Err(_) => {
-            display_validation_error("❌ Invalid input! Please enter a valid number.");
+        Err(e) => {
+            display_validation_error(&format!("❌ Invalid input! {}", e));
             logger::warning(&format!("User ID: {} provided invalid withdraw input: {}", user.id, choice.trim()));
             Ok(false)
         }
@@ -441,7 +399,7 @@ fn user_statistics(conn: &Connection, user: &User) {
     let _ = dbqueries::query_user_statistics(conn, user);
 }
 
-fn change_password(conn: &Connection, user: &User) -> () {
+fn change_password(conn: &Connection, user: &User) {
     use crate::db::validator::{validate_password, display_validation_error};
     use dialoguer::Password;
 
@@ -470,7 +428,7 @@ fn change_password(conn: &Connection, user: &User) -> () {
     let result = dbqueries::check_users(conn, user.get_username(conn).unwrap().as_str(), &password);
 
     match result {
-        Ok(id) => {
+        Ok(_id) => {
             // Get password with secure input
             let password = match Password::new()
                 .with_prompt("New Password (min 12 chars)")
@@ -526,34 +484,225 @@ fn change_password(conn: &Connection, user: &User) -> () {
                 }
             }
         }
-        Err(e) => {            
+        Err(_e) => {            
             println!("Failed check");
-            return;
         },
     }
 }
 
+// Page through the player's rounds, newest first
+fn play_history(conn: &Connection, user: &User) {
+    use crate::play::engine::RoundStatus;
+
+    const PAGE_SIZE: usize = 10;
+    let mut page = 0;
+
+    loop {
+        let total = match dbqueries::count_user_rounds(conn, user) {
+            Ok(total) => total,
+            Err(e) => {
+                logger::error(&format!("Failed to count rounds for User ID: {}: {}", user.id, e));
+                println!("{}", "Error loading play history".red().bold());
+                return;
+            }
+        };
+        if total == 0 {
+            println!("{}", "You have not played any rounds yet.".yellow());
+            return;
+        }
+        let pages = total.div_ceil(PAGE_SIZE);
+        page = page.min(pages - 1);
+
+        let rounds = match dbqueries::get_user_rounds(conn, user, page, PAGE_SIZE) {
+            Ok(rounds) => rounds,
+            Err(e) => {
+                logger::error(&format!("Failed to load rounds for User ID: {}: {}", user.id, e));
+                println!("{}", "Error loading play history".red().bold());
+                return;
+            }
+        };
+
+        println!("{}", format!("═══ 🎰 My Play History (page {} of {}) 🎰 ═══", page + 1, pages).bright_cyan().bold());
+        for round in &rounds {
+            let net = round.payout - round.bet - round.hold_charges;
+            let net_text = if !net.is_negative() { format!("+${}", net).green() } else { format!("-${}", -net).red() };
+            println!(
+                "{} {} - bet ${}{} - paid ${} ({}) - balance ${}{}",
+                round.timestamp.bright_black(),
+                round.game_name.yellow(),
+                round.bet,
+                if round.hold_charges.is_positive() { format!(" + ${} holds", round.hold_charges) } else { String::new() },
+                round.payout,
+                net_text,
+                round.balance,
+                if round.status != RoundStatus::Settled { format!(" [{}]", round.status.as_str()).yellow() } else { "".normal() }
+            );
+            println!("    {}", round.grid);
+            for line in &round.lines {
+                println!("    {}", line.green());
+            }
+        }
+        println!();
+
+        let mut menu_options = Vec::new();
+        if page + 1 < pages {
+            menu_options.push("Older");
+        }
+        if page > 0 {
+            menu_options.push("Newer");
+        }
+        menu_options.push("Back");
+
+        match session_menu(conn, user, "═══ 🎰 History 🎰 ═══", &menu_options) {
+            Some("Older") => page += 1,
+            Some("Newer") => page -= 1,
+            _ => break,
+        }
+    }
+}
+
+// Let the player see their seed commitment, choose a client seed, rotate seeds and verify past rounds
+fn provably_fair_menu(conn: &Connection, user: &User) {
+    loop {
+        let seed = match dbqueries::get_active_fair_seed(conn, user) {
+            Ok(seed) => seed,
+            Err(e) => {
+                logger::error(&format!("Failed to load provably fair seed for User ID: {}: {}", user.id, e));
+                println!("{}", "Error loading provably fair seeds".red().bold());
+                return;
+            }
+        };
+
+        println!("{}", "═══ 🔐 Provably Fair 🔐 ═══".bright_cyan().bold());
+        println!("{}: {}", "Server seed commitment (SHA-256)".yellow(), seed.commitment);
+        println!("{}: {}", "Client seed".yellow(), seed.client_seed);
+        println!("{}: {}", "Next nonce".yellow(), seed.next_nonce);
+        println!("Every round uses HMAC-SHA256(server seed, \"client seed:nonce:block\") as its random stream.");
+        println!("Rotate your seeds to reveal the server seed and verify the rounds played with it.");
+        println!();
+
+        let menu_options = vec!["Change Client Seed", "Rotate Seeds", "Verify Past Rounds", "Exit"];
+        let Some(user_input) = session_menu(conn, user, "═══ 🔐 Provably Fair Options 🔐 ═══", &menu_options) else {
+            return;
+        };
+
+        match user_input.trim() {
+            "Change Client Seed" => {
+                print!("New client seed (1-64 characters): ");
+                io::stdout().flush().ok();
+                let mut client_seed = String::new();
+                io::stdin().read_line(&mut client_seed).ok();
+
+                // A new client seed starts a new seed pair so it cannot be matched to a known server seed
+                match dbqueries::rotate_fair_seed(conn, user, Some(client_seed.trim())) {
+                    Ok(revealed) => {
+                        logger::info(&format!("User ID: {} changed client seed", user.id));
+                        println!("{}", "Client seed changed, previous seed pair revealed:".green());
+                        println!("  Server seed: {}", revealed.server_seed);
+                    }
+                    Err(e) => println!("{}", format!("Could not change client seed: {}", e).red()),
+                }
+            }
+            "Rotate Seeds" => {
+                match dbqueries::rotate_fair_seed(conn, user, None) {
+                    Ok(revealed) => {
+                        logger::info(&format!("User ID: {} rotated provably fair seeds", user.id));
+                        println!("{}", "Seeds rotated, previous seed pair revealed:".green());
+                        println!("  Server seed: {}", revealed.server_seed);
+                        println!("  Commitment:  {}", revealed.commitment);
+                    }
+                    Err(e) => println!("{}", format!("Could not rotate seeds: {}", e).red()),
+                }
+            }
+            "Verify Past Rounds" => verify_fair_rounds(conn, user),
+            "Exit" => break,
+            _ => {
+                logger::warning(&format!("User ID: {} made invalid provably fair menu selection", user.id));
+            }
+        }
+    }
+}
+
+// Regenerate every round of a revealed seed pair and compare it with what was recorded
+fn verify_fair_rounds(conn: &Connection, user: &User) {
+    use crate::cryptography::provably_fair::{commitment, verify_round, Verification};
+    use crate::play::game::find_game;
+
+    let seeds = match dbqueries::get_revealed_fair_seeds(conn, user) {
+        Ok(seeds) if seeds.is_empty() => {
+            println!("{}", "No revealed seeds yet. Rotate your seeds to reveal the current server seed.".yellow());
+            return;
+        }
+        Ok(seeds) => seeds,
+        Err(e) => {
+            println!("{}", format!("Error loading revealed seeds: {}", e).red());
+            return;
+        }
+    };
+
+    let seed_options: Vec<String> = seeds.iter()
+        .map(|seed| format!("{} - {} rounds - commitment {}…", seed.created_at, seed.next_nonce, &seed.commitment[..16]))
+        .collect();
+    let mut menu_opts: Vec<&str> = seed_options.iter().map(|s| s.as_str()).collect();
+    menu_opts.push("Back");
+    let choice = menu_generator("Select a revealed seed pair", &menu_opts);
+    let Some(index) = seed_options.iter().position(|option| option == choice) else {
+        return;
+    };
+    let seed = &seeds[index];
+
+    println!("\n{}: {}", "Server seed".yellow(), seed.server_seed);
+    println!("{}: {}", "Client seed".yellow(), seed.client_seed);
+    if commitment(&seed.server_seed) == seed.commitment {
+        println!("{}", "✓ SHA-256 of the server seed matches the commitment shown before play".green());
+    } else {
+        println!("{}", "✗ SHA-256 of the server seed does NOT match the commitment".red().bold());
+        logger::critical(&format!("Revealed server seed {} for User ID: {} does not match its commitment", seed.id, user.id));
+    }
+
+    let rounds = match dbqueries::get_fair_rounds(conn, seed.id) {
+        Ok(rounds) => rounds,
+        Err(e) => {
+            println!("{}", format!("Error loading rounds: {}", e).red());
+            return;
+        }
+    };
+
+    for round in &rounds {
+        let Some(mut game) = find_game(&round.game_name) else {
+            println!("Nonce {}: unknown game {}", round.nonce, round.game_name);
+            continue;
+        };
+        if game.load_config(conn).is_err() {
+            println!("Nonce {}: could not load {} configuration", round.nonce, round.game_name);
+            continue;
+        }
+
+        let header = format!("Nonce {} - {} - bet ${} - {} - paid ${}", round.nonce, round.game_name, round.bet, round.result, round.payout);
+        match verify_round(game.as_mut(), seed, round) {
+            Verification::Verified => println!("{} {}", "✓".green(), header),
+            Verification::Mismatch(result) => {
+                logger::critical(&format!("Provably fair round {} of User ID: {} does not reproduce: recorded {}, regenerated {}", round.id, user.id, round.result, result));
+                println!("{} {} (regenerated: {})", "✗".red().bold(), header, result);
+            }
+            Verification::PaytableChanged => println!("{} {} (paytable changed since this round)", "?".yellow(), header),
+            Verification::SnapshotTampered => {
+                logger::critical(&format!("Paytable snapshot of provably fair round {} of User ID: {} does not match its fingerprint", round.id, user.id));
+                println!("{} {} (recorded paytable does not match its fingerprint)", "✗".red().bold(), header);
+            }
+        }
+    }
+
+    println!("\nPress Enter to continue...");
+    io::stdin().read_line(&mut String::new()).ok();
+}
+
 #[cfg(test)]
 mod integration_tests {
     use super::*;
     use rusqlite::Connection;
     use crate::db::dbqueries::{self, change_balance, insert_users};
-
-    // create a test database
-    fn setup_test_db() -> Connection {
-        // Initialize encryption BEFORE opening database
-        crate::cryptography::crypto::initialize_encryption_key();
-
-        let conn = Connection::open_in_memory().unwrap();
-        
-        // Enable foreign keys
-        conn.execute("PRAGMA foreign_keys = ON", []).unwrap();
-
-        // Use your actual initialization function
-        crate::db::dbinitialize::initialize_dbs(&conn).unwrap();
-        
-        conn
-    }
+    use crate::db::test_support::setup_test_db;
 
     // Create a test user in the database
     fn create_test_user(conn: &Connection, username: &str, password: &str) -> i32 {
@@ -566,7 +715,7 @@ mod integration_tests {
     }
 
     // Deposit balance for user
-    fn deposit_balance(conn: &Connection, user: &User, deposit: f64) {
+    fn deposit_balance(conn: &Connection, user: &User, deposit: Money) {
         let _ = change_balance(conn, user, deposit);
     }
 
@@ -587,10 +736,10 @@ mod integration_tests {
         let conn = setup_test_db();
         let user_id = create_test_user(&conn, "bob", "password1234");
         let user = User { id: user_id };
-        deposit_balance(&conn, &user, 250.50);
+        deposit_balance(&conn, &user, Money::from_cents(25050));
         
         let balance = user.get_balance(&conn).unwrap();
-        assert_eq!(balance, 250.50);
+        assert_eq!(balance, Money::from_cents(25050));
     }
 
     #[test]
@@ -626,8 +775,8 @@ mod integration_tests {
         assert_eq!(user1.get_username(&conn).unwrap(), "user1");
         assert_eq!(user2.get_username(&conn).unwrap(), "user2");
         
-        assert_eq!(user1.get_balance(&conn).unwrap(), 0.0);
-        assert_eq!(user2.get_balance(&conn).unwrap(), 0.0);
+        assert_eq!(user1.get_balance(&conn).unwrap(), Money::ZERO);
+        assert_eq!(user2.get_balance(&conn).unwrap(), Money::ZERO);
     }
 
     #[test]
@@ -637,14 +786,14 @@ mod integration_tests {
         let user = User { id: user_id };
         
         // Perform a transaction (deposit)
-        dbqueries::change_balance(&conn, &user, 50.0).unwrap();
+        dbqueries::change_balance(&conn, &user, Money::from_dollars(50)).unwrap();
         let original_balance = user.get_balance(&conn).unwrap();
         
         // Perform another transaction (deposit)
-        dbqueries::change_balance(&conn, &user, 50.0).unwrap();
+        dbqueries::change_balance(&conn, &user, Money::from_dollars(50)).unwrap();
         let new_balance = user.get_balance(&conn).unwrap();
-        assert_eq!(original_balance, 50.0);
-        assert_eq!(new_balance, 100.0);
+        assert_eq!(original_balance, Money::from_dollars(50));
+        assert_eq!(new_balance, Money::from_dollars(100));
     }
 
     #[test]
@@ -654,12 +803,12 @@ mod integration_tests {
         let user = User { id: user_id };
         
         // Perform a deposit
-        dbqueries::change_balance(&conn, &user, 50.0).unwrap();
+        dbqueries::change_balance(&conn, &user, Money::from_dollars(50)).unwrap();
 
         // Perform a withdrawal
-        dbqueries::change_balance(&conn, &user, -30.0).unwrap();
+        dbqueries::change_balance(&conn, &user, -Money::from_dollars(30)).unwrap();
         let new_balance = user.get_balance(&conn).unwrap();
-        assert_eq!(new_balance, 20.0);
+        assert_eq!(new_balance, Money::from_dollars(20));
     }
 
     #[test]
@@ -669,11 +818,11 @@ mod integration_tests {
         let user = User { id: user_id };
 
         // Perform a deposit
-        dbqueries::change_balance(&conn, &user, 200.0).unwrap();
+        dbqueries::change_balance(&conn, &user, Money::from_dollars(200)).unwrap();
 
         
-        assert!(dbqueries::check_funds(&conn, &user, 50.0));
-        assert!(dbqueries::check_funds(&conn, &user, 100.0));
+        assert!(dbqueries::check_funds(&conn, &user, Money::from_dollars(50)));
+        assert!(dbqueries::check_funds(&conn, &user, Money::from_dollars(100)));
     }
 
     #[test]
@@ -683,8 +832,8 @@ mod integration_tests {
         let user = User { id: user_id };
 
         // Perform a deposit
-        dbqueries::change_balance(&conn, &user, 100.0).unwrap();
-        assert!(!dbqueries::check_funds(&conn, &user, 150.0));
+        dbqueries::change_balance(&conn, &user, Money::from_dollars(100)).unwrap();
+        assert!(!dbqueries::check_funds(&conn, &user, Money::from_dollars(150)));
     }
 
     #[test]
@@ -740,4 +889,39 @@ mod integration_tests {
         
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_round_history_pages_newest_first() {
+        let conn = setup_test_db();
+        let user = User { id: create_test_user(&conn, "historyuser", "Password123!@#") };
+        let other = User { id: create_test_user(&conn, "otheruser", "Password123!@#") };
+
+        // Written straight to the table, betting for real would run into the rate limiter
+        let insert = |user: &User, game: &str, grid: String, lines: &str, hold_charges: Money| {
+            conn.execute(
+                "Insert Into rounds (user_id, game_name, bet_cents, hold_charges_cents, grid, lines, payout_cents, balance_cents, status)
+                Values (?1, ?2, 100, ?3, ?4, ?5, 250, 10000, 'settled')",
+                rusqlite::params![user.id, game, hold_charges, grid, lines],
+            ).unwrap();
+        };
+        for i in 0..25 {
+            insert(&user, "holding", format!("round {}", i), "Three of a kind: 🍒\nBonus", Money::from_cents(25));
+        }
+        insert(&other, "normal", "other".to_string(), "", Money::ZERO);
+
+        assert_eq!(dbqueries::count_user_rounds(&conn, &user).unwrap(), 25);
+        let first = dbqueries::get_user_rounds(&conn, &user, 0, 10).unwrap();
+        assert_eq!(first.len(), 10);
+        assert_eq!(first[0].grid, "round 24");
+        assert_eq!(first[0].lines, vec!["Three of a kind: 🍒".to_string(), "Bonus".to_string()]);
+        assert_eq!(first[0].hold_charges, Money::from_cents(25));
+        assert_eq!(first[0].payout, Money::from_cents(250));
+        assert_eq!(first[0].status, crate::play::engine::RoundStatus::Settled);
+        assert!(!first[0].timestamp.is_empty());
+
+        let last = dbqueries::get_user_rounds(&conn, &user, 2, 10).unwrap();
+        assert_eq!(last.len(), 5);
+        assert_eq!(last[4].grid, "round 0");
+        assert!(dbqueries::get_user_rounds(&conn, &user, 3, 10).unwrap().is_empty());
+    }
 }
@@ -0,0 +1,352 @@
+use rusqlite::Connection;
+use colored::*;
+use std::io::{self, Write};
+
+use crate::authentication::authorization;
+use crate::authentication::permissions::{self, Permission, Role};
+use crate::authentication::session::{self, SessionPolicy};
+use crate::db::two_factor;
+use crate::db::user_admin::{self, AccountStatus, AccountSummary};
+use crate::db::money::Money;
+use crate::interfaces::menus::{menu_generator, session_menu};
+use crate::interfaces::user::User;
+use crate::logger::logger;
+
+/// User Administration console - Requires users.manage.
+/// Staff accounts can only be administered, and roles only assigned, with roles.manage as well.
+pub fn user_admin_menu(conn: &Connection, user: &User) {
+    // SECURITY: Verify user may manage accounts
+    if authorization::require_permission(conn, user, Permission::UsersManage).is_err() {
+        return;
+    }
+
+    logger::security(&format!("User ID: {} accessed user administration", user.id));
+
+    loop {
+        let menu_options = vec!["Search Accounts", "Audit Trail", "Back"];
+        match session_menu(conn, user, "═══ 👥 User Administration 👥 ═══", &menu_options) {
+            Some("Search Accounts") => search_accounts(conn, user),
+            Some("Audit Trail") => show_audit_trail(conn),
+            _ => return,
+        }
+    }
+}
+
+fn search_accounts(conn: &Connection, user: &User) {
+    print!("Username contains (leave empty to list all): ");
+    io::stdout().flush().ok();
+    let mut query = String::new();
+    if io::stdin().read_line(&mut query).is_err() {
+        println!("{}", "Invalid input".red());
+        return;
+    }
+
+    let accounts = match user_admin::search_accounts(conn, query.trim()) {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            println!("{}", format!("Error searching accounts: {}", e).red());
+            return;
+        }
+    };
+    if accounts.is_empty() {
+        println!("No accounts found.");
+        return;
+    }
+
+    let labels: Vec<String> = accounts.iter()
+        .map(|account| format!("{:<30} {:<13} {}", account.username, account.role, account.status.as_str()))
+        .collect();
+    let mut menu_options: Vec<&str> = labels.iter().map(|label| label.as_str()).collect();
+    menu_options.push("Cancel");
+    let choice = menu_generator("Select an account", &menu_options);
+    if let Some(index) = labels.iter().position(|label| label == choice) {
+        account_menu(conn, user, accounts[index].id);
+    }
+}
+
+fn account_menu(conn: &Connection, user: &User, target_user_id: i32) {
+    loop {
+        let account = match user_admin::get_account(conn, target_user_id) {
+            Ok(account) => account,
+            Err(e) => {
+                println!("{}", format!("Error loading account: {}", e).red());
+                return;
+            }
+        };
+        show_account(conn, user, &account);
+
+        let toggle_status = match account.status {
+            AccountStatus::Active => "Suspend Account",
+            AccountStatus::Suspended => "Reactivate Account",
+        };
+        let menu_options = vec!["Change Role", toggle_status, "Force Password Reset", "Reset Two-Factor", "Back"];
+        let result = match session_menu(conn, user, &format!("═══ {} ═══", account.username), &menu_options) {
+            Some("Change Role") => change_role(conn, user, &account),
+            Some("Suspend Account") => user_admin::set_status(conn, user, account.id, AccountStatus::Suspended)
+                .map(|_| println!("{}", format!("✓ {} suspended", account.username).green())),
+            Some("Reactivate Account") => user_admin::set_status(conn, user, account.id, AccountStatus::Active)
+                .map(|_| println!("{}", format!("✓ {} reactivated", account.username).green())),
+            Some("Force Password Reset") => step_up(conn, user, "reset a password", || {
+                user_admin::force_password_reset(conn, user, account.id).map(|temporary_password| {
+                    println!("{}", format!("✓ Password of {} reset", account.username).green());
+                    println!("Temporary password: {}", temporary_password.bright_yellow().bold());
+                    println!("It must be changed at the next login.");
+                })
+            }),
+            Some("Reset Two-Factor") => step_up(conn, user, "reset two-factor authentication", || {
+                user_admin::reset_two_factor(conn, user, account.id)
+                    .map(|_| println!("{}", format!("✓ Two-factor authentication of {} reset", account.username).green()))
+            }),
+            _ => return,
+        };
+        if let Err(e) = result {
+            println!("{}", format!("❌ {}", e).red());
+        }
+    }
+}
+
+// Balances are confidential, so every view is recorded in the audit trail
+fn show_account(conn: &Connection, user: &User, account: &AccountSummary) {
+    let target = User { id: account.id };
+    let balance = match target.get_balance(conn) {
+        Ok(balance) => format!("${}", balance),
+        Err(e) => {
+            logger::error(&format!("Failed to load balance of User ID: {}: {}", account.id, e));
+            "unavailable".to_string()
+        }
+    };
+    if let Err(e) = user_admin::record_admin_action(conn, user, Some(account.id), "view_account", "") {
+        println!("{}", format!("Error recording audit trail: {}", e).red());
+        return;
+    }
+
+    println!("\n{}", "═══ Account ═══".cyan());
+    println!("  Username: {}", account.username);
+    println!("  Role:     {}", account.role);
+    println!("  Status:   {}", match account.status {
+        AccountStatus::Active => account.status.as_str().green(),
+        AccountStatus::Suspended => account.status.as_str().red(),
+    });
+    println!("  Balance:  {}", balance);
+    println!("  2FA:      {}", match two_factor::is_enabled(conn, account.id) {
+        Ok(true) => "enabled".green(),
+        Ok(false) => "disabled".normal(),
+        Err(_) => "unavailable".red(),
+    });
+    if account.must_reset_password {
+        println!("  {}", "Password reset pending".yellow());
+    }
+}
+
+fn change_role(conn: &Connection, user: &User, account: &AccountSummary) -> rusqlite::Result<()> {
+    // SECURITY: Verify user may assign roles
+    if authorization::require_permission(conn, user, Permission::RolesManage).is_err() {
+        return Ok(());
+    }
+
+    let roles = permissions::get_roles(conn)?;
+    let mut menu_options: Vec<&str> = roles.iter().map(|role| role.name.as_str()).collect();
+    menu_options.push("Cancel");
+    let role = menu_generator(&format!("New role for {}", account.username), &menu_options);
+    if role == "Cancel" || role == account.role {
+        return Ok(());
+    }
+    step_up(conn, user, "change a role", || {
+        user_admin::set_role(conn, user, account.id, role)?;
+        println!("{}", format!("✓ {} is now a {}", account.username, role).green());
+        Ok(())
+    })
+}
+
+// SECURITY: Make a change to accounts, roles or policies only once the user has entered their password again
+fn step_up(conn: &Connection, user: &User, action: &str, change: impl FnOnce() -> rusqlite::Result<()>) -> rusqlite::Result<()> {
+    if session::reauthenticate(conn, user, action) {
+        change()
+    } else {
+        Ok(())
+    }
+}
+
+fn show_audit_trail(conn: &Connection) {
+    match user_admin::get_audit_trail(conn, 50) {
+        Ok(actions) if actions.is_empty() => println!("\nNo administration actions recorded yet."),
+        Ok(actions) => {
+            println!("\n{}", "═══ Audit Trail ═══".cyan());
+            for action in actions {
+                println!(
+                    "#{:<5} {} {:<20} {:<22} {:<20} {}",
+                    action.id,
+                    action.timestamp,
+                    action.admin_username,
+                    action.action,
+                    action.target_username.unwrap_or_else(|| "-".to_string()),
+                    action.details
+                );
+            }
+        }
+        Err(e) => println!("{}", format!("Error loading audit trail: {}", e).red()),
+    }
+}
+
+/// Roles and permissions - Requires roles.manage.
+/// Built-in roles are shown but cannot be changed.
+pub fn roles_menu(conn: &Connection, user: &User) {
+    // SECURITY: Verify user may manage roles
+    if authorization::require_permission(conn, user, Permission::RolesManage).is_err() {
+        return;
+    }
+
+    loop {
+        let roles = match permissions::get_roles(conn) {
+            Ok(roles) => roles,
+            Err(e) => {
+                println!("{}", format!("Error loading roles: {}", e).red());
+                return;
+            }
+        };
+
+        println!("\n{}", "═══ Roles ═══".cyan());
+        for role in &roles {
+            let permissions: Vec<&str> = role.permissions.iter().map(|permission| permission.as_str()).collect();
+            let kind = if role.builtin { "built-in" } else { "custom" };
+            println!("  {:<16} {:<9} {}", role.name, kind, role.description);
+            println!("  {:<16} {}", "", permissions.join(", ").dimmed());
+        }
+
+        let two_factor_required = two_factor::is_required_for_staff(conn).unwrap_or(true);
+        println!(
+            "\nTwo-factor authentication for staff: {}",
+            if two_factor_required { "mandatory".green() } else { "optional".yellow() }
+        );
+        if let Ok(policy) = session::get_policy(conn) {
+            println!(
+                "Idle logout: staff after {}s, players after {}s. Withdrawals above ${} need the password again.",
+                policy.staff_idle_timeout, policy.player_idle_timeout, policy.step_up_withdrawal
+            );
+        }
+
+        let menu_options = vec!["Create Role", "Edit Role Permissions", "Delete Role", "Two-Factor Policy", "Session Policy", "Back"];
+        let result = match session_menu(conn, user, "═══ 🔑 Roles and Permissions 🔑 ═══", &menu_options) {
+            Some("Create Role") => create_role(conn, user),
+            Some("Edit Role Permissions") => match choose_custom_role(&roles, "Edit which role?") {
+                Some(role) => step_up(conn, user, "edit role permissions", || edit_role_permissions(conn, user, role)),
+                None => Ok(()),
+            },
+            Some("Delete Role") => match choose_custom_role(&roles, "Delete which role?") {
+                Some(role) => step_up(conn, user, "delete a role", || {
+                    permissions::delete_role(conn, user, &role.name)
+                        .map(|_| println!("{}", format!("✓ Role {} deleted", role.name).green()))
+                }),
+                None => Ok(()),
+            },
+            Some("Two-Factor Policy") => {
+                let menu_options = vec!["Mandatory for staff", "Optional", "Cancel"];
+                match menu_generator("Two-factor authentication for staff", &menu_options) {
+                    "Mandatory for staff" => step_up(conn, user, "change the two-factor policy", || two_factor::set_required_for_staff(conn, user, true)),
+                    "Optional" => step_up(conn, user, "change the two-factor policy", || two_factor::set_required_for_staff(conn, user, false)),
+                    _ => Ok(()),
+                }
+            }
+            Some("Session Policy") => change_session_policy(conn, user),
+            _ => return,
+        };
+        if let Err(e) = result {
+            println!("{}", format!("❌ {}", e).red());
+        }
+    }
+}
+
+fn choose_custom_role<'a>(roles: &'a [Role], prompt: &str) -> Option<&'a Role> {
+    let mut menu_options: Vec<&str> = roles.iter()
+        .filter(|role| !role.builtin)
+        .map(|role| role.name.as_str())
+        .collect();
+    if menu_options.is_empty() {
+        println!("There are no custom roles.");
+        return None;
+    }
+    menu_options.push("Cancel");
+    let choice = menu_generator(prompt, &menu_options);
+    roles.iter().find(|role| role.name == choice)
+}
+
+fn create_role(conn: &Connection, user: &User) -> rusqlite::Result<()> {
+    let Some(name) = read_line("Role name (lowercase letters, digits, _): ") else {
+        return Ok(());
+    };
+    let Some(description) = read_line("Description: ") else {
+        return Ok(());
+    };
+    permissions::create_role(conn, user, &name, &description)?;
+    println!("{}", format!("✓ Role {} created, give it permissions with Edit Role Permissions", name).green());
+    Ok(())
+}
+
+// Toggle the permissions of a custom role one at a time until the user is done
+fn edit_role_permissions(conn: &Connection, user: &User, role: &Role) -> rusqlite::Result<()> {
+    loop {
+        let role = permissions::get_role(conn, &role.name)?;
+        let labels: Vec<String> = Permission::ALL.iter()
+            .map(|permission| {
+                let mark = if role.permissions.contains(permission) { "[x]" } else { "[ ]" };
+                format!("{} {}", mark, permission.as_str())
+            })
+            .collect();
+        let mut menu_options: Vec<&str> = labels.iter().map(|label| label.as_str()).collect();
+        menu_options.push("Done");
+
+        let choice = menu_generator(&format!("Permissions of {}", role.name), &menu_options);
+        let Some(index) = labels.iter().position(|label| label == choice) else {
+            return Ok(());
+        };
+        let permission = Permission::ALL[index];
+        permissions::set_role_permission(conn, user, &role.name, permission, !role.permissions.contains(&permission))?;
+    }
+}
+
+fn change_session_policy(conn: &Connection, user: &User) -> rusqlite::Result<()> {
+    let read_seconds = |prompt: &str| -> Option<u32> {
+        let input = read_line(&format!("{} (at least {}s): ", prompt, session::MIN_IDLE_TIMEOUT_SECONDS))?;
+        match input.parse() {
+            Ok(seconds) if seconds >= session::MIN_IDLE_TIMEOUT_SECONDS => Some(seconds),
+            _ => {
+                println!("{}", "Invalid timeout".red());
+                None
+            }
+        }
+    };
+    let Some(staff_idle_timeout) = read_seconds("Staff idle timeout in seconds") else {
+        return Ok(());
+    };
+    let Some(player_idle_timeout) = read_seconds("Player idle timeout in seconds") else {
+        return Ok(());
+    };
+    let Some(threshold) = read_line("Ask for the password on withdrawals above $") else {
+        return Ok(());
+    };
+    let step_up_withdrawal = match Money::parse(&threshold) {
+        Ok(amount) => amount,
+        Err(e) => {
+            println!("{}", format!("Invalid amount: {}", e).red());
+            return Ok(());
+        }
+    };
+
+    let policy = SessionPolicy { staff_idle_timeout, player_idle_timeout, step_up_withdrawal };
+    step_up(conn, user, "change the session policy", || {
+        session::set_policy(conn, user, &policy)?;
+        println!("{}", "✓ Session policy updated".green());
+        Ok(())
+    })
+}
+
+fn read_line(prompt: &str) -> Option<String> {
+    print!("{}", prompt);
+    io::stdout().flush().ok();
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input).is_err() {
+        println!("{}", "Invalid input".red());
+        return None;
+    }
+    Some(input.trim().to_string())
+}
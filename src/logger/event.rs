@@ -0,0 +1,202 @@
+use chrono::{DateTime, FixedOffset};
+use serde_json::{Map, Value};
+
+use crate::db::money::Money;
+use crate::logger::logger::LogLevel;
+
+// Log entries are JSON Lines records: one JSON object per line with typed fields, so the log can be
+// queried by field instead of by matching message wording. Every record has "timestamp" (RFC 3339
+// with timezone), "level", "event" and "message"; user_id, username, game, amount_cents,
+// balance_cents and round_id are present when the event has them.
+
+// What happened to a balance. Deposit, Withdrawal and the round kinds are only logged once the
+// change is committed; the Requested and Cancelled kinds record what was asked for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransactionKind {
+    DepositRequested,
+    Deposit,
+    DepositCancelled,
+    WithdrawalRequested,
+    Withdrawal,
+    WithdrawalCancelled,
+    BetRequested,
+    BetPlaced,
+    Win,
+    Loss,
+    Refund,
+    OpeningBalance,
+}
+
+impl TransactionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionKind::DepositRequested => "deposit_requested",
+            TransactionKind::Deposit => "deposit",
+            TransactionKind::DepositCancelled => "deposit_cancelled",
+            TransactionKind::WithdrawalRequested => "withdrawal_requested",
+            TransactionKind::Withdrawal => "withdrawal",
+            TransactionKind::WithdrawalCancelled => "withdrawal_cancelled",
+            TransactionKind::BetRequested => "bet_requested",
+            TransactionKind::BetPlaced => "bet_placed",
+            TransactionKind::Win => "win",
+            TransactionKind::Loss => "loss",
+            TransactionKind::Refund => "refund",
+            TransactionKind::OpeningBalance => "opening_balance",
+        }
+    }
+}
+
+// Something worth logging, with the fields the log verification queries filter on
+pub enum LogEvent<'a> {
+    // Free text, for entries nothing queries by field
+    Message { level: LogLevel, text: &'a str },
+    LoginSucceeded { username: &'a str, user_id: i32 },
+    LoginFailed { username: &'a str, reason: &'a str },
+    // Built with the constructors below, which take every field the kind of transaction has
+    Transaction {
+        kind: TransactionKind,
+        user_id: i32,
+        amount: Option<Money>,
+        game: Option<&'a str>,
+        round_id: Option<i64>,
+        balance: Option<Money>,
+    },
+}
+
+impl<'a> LogEvent<'a> {
+    /// A balance change that was asked for or cancelled, with the amount if a valid one was given
+    pub fn requested(kind: TransactionKind, user_id: i32, amount: Option<Money>) -> Self {
+        LogEvent::Transaction { kind, user_id, amount, game: None, round_id: None, balance: None }
+    }
+
+    /// A bet asked for in `game`, before its round is opened
+    pub fn bet_requested(user_id: i32, game: &'a str, amount: Money) -> Self {
+        LogEvent::Transaction { kind: TransactionKind::BetRequested, user_id, amount: Some(amount), game: Some(game), round_id: None, balance: None }
+    }
+
+    /// A committed change outside any round and the balance it left
+    pub fn balance_changed(kind: TransactionKind, user_id: i32, amount: Money, balance: Money) -> Self {
+        LogEvent::Transaction { kind, user_id, amount: Some(amount), game: None, round_id: None, balance: Some(balance) }
+    }
+
+    /// A committed change to round `round_id` of `game` and the balance it left
+    pub fn round(kind: TransactionKind, user_id: i32, game: &'a str, round_id: i64, amount: Money, balance: Money) -> Self {
+        LogEvent::Transaction { kind, user_id, amount: Some(amount), game: Some(game), round_id: Some(round_id), balance: Some(balance) }
+    }
+
+    pub fn level(&self) -> LogLevel {
+        match self {
+            LogEvent::Message { level, .. } => *level,
+            LogEvent::LoginSucceeded { .. } | LogEvent::LoginFailed { .. } => LogLevel::Security,
+            LogEvent::Transaction { .. } => LogLevel::Transaction,
+        }
+    }
+
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            LogEvent::Message { .. } => "message",
+            LogEvent::LoginSucceeded { .. } => "login_succeeded",
+            LogEvent::LoginFailed { .. } => "login_failed",
+            LogEvent::Transaction { kind, .. } => kind.as_str(),
+        }
+    }
+
+    // Readable description, so the log still makes sense to a person reading it
+    pub fn message(&self) -> String {
+        match self {
+            LogEvent::Message { text, .. } => text.to_string(),
+            LogEvent::LoginSucceeded { username, user_id } => {
+                format!("Successful login for username: {} (User ID: {})", username, user_id)
+            }
+            LogEvent::LoginFailed { username, reason } => format!("Failed login for username: {}. {}", username, reason),
+            LogEvent::Transaction { kind, user_id, amount, game, round_id, balance } => {
+                let mut text = format!("User ID: {} {}", user_id, kind.as_str().replace('_', " "));
+                if let Some(amount) = amount {
+                    text.push_str(&format!(" {}", amount));
+                }
+                if let Some(game) = game {
+                    text.push_str(&format!(" in {}", game));
+                }
+                if let Some(round_id) = round_id {
+                    text.push_str(&format!(" on round {}", round_id));
+                }
+                if let Some(balance) = balance {
+                    text.push_str(&format!(". New balance: {}", balance));
+                }
+                text
+            }
+        }
+    }
+
+    /// The JSON record for this event at `timestamp`
+    pub fn to_record(&self, timestamp: &str) -> Map<String, Value> {
+        let mut record = Map::new();
+        record.insert("timestamp".to_string(), timestamp.into());
+        record.insert("level".to_string(), self.level().as_str().into());
+        record.insert("event".to_string(), self.event_type().into());
+        record.insert("message".to_string(), self.message().into());
+        match self {
+            LogEvent::Message { .. } => {}
+            LogEvent::LoginSucceeded { username, user_id } => {
+                record.insert("username".to_string(), (*username).into());
+                record.insert("user_id".to_string(), (*user_id).into());
+            }
+            LogEvent::LoginFailed { username, .. } => {
+                record.insert("username".to_string(), (*username).into());
+            }
+            LogEvent::Transaction { user_id, amount, game, round_id, balance, .. } => {
+                record.insert("user_id".to_string(), (*user_id).into());
+                if let Some(amount) = amount {
+                    record.insert("amount_cents".to_string(), amount.cents().into());
+                }
+                if let Some(game) = game {
+                    record.insert("game".to_string(), (*game).into());
+                }
+                if let Some(round_id) = round_id {
+                    record.insert("round_id".to_string(), (*round_id).into());
+                }
+                if let Some(balance) = balance {
+                    record.insert("balance_cents".to_string(), balance.cents().into());
+                }
+            }
+        }
+        record
+    }
+}
+
+// A record read back from the log
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRecord {
+    pub timestamp: DateTime<FixedOffset>,
+    pub level: String,
+    pub event: String,
+    pub message: String,
+    pub user_id: Option<i32>,
+    pub username: Option<String>,
+    pub game: Option<String>,
+    pub amount: Option<Money>,
+}
+
+impl LogRecord {
+    /// Parse one log line, None for lines that are not records (such as entries from before JSON Lines)
+    pub fn parse(line: &str) -> Option<LogRecord> {
+        let value: Value = serde_json::from_str(line).ok()?;
+        let text = |name: &str| value.get(name).and_then(Value::as_str).map(str::to_string);
+        Some(LogRecord {
+            timestamp: DateTime::parse_from_rfc3339(value.get("timestamp")?.as_str()?).ok()?,
+            level: text("level")?,
+            event: text("event")?,
+            message: text("message").unwrap_or_default(),
+            user_id: value.get("user_id").and_then(Value::as_i64).and_then(|id| i32::try_from(id).ok()),
+            username: text("username"),
+            game: text("game"),
+            amount: value.get("amount_cents").and_then(Value::as_i64).map(Money::from_cents),
+        })
+    }
+}
+
+impl std::fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "[{}] [{}] {}", self.timestamp.format("%Y-%m-%d %H:%M:%S %:z"), self.level, self.message)
+    }
+}
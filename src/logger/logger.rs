@@ -1,30 +1,111 @@
 use std::fs::{File, OpenOptions};
-use std::io::Write;
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::Path;
-use chrono::Local;
+use std::sync::OnceLock;
+use chrono::{Local, SecondsFormat};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::logger::event::{LogEvent, LogRecord};
+
+// Where every log entry is appended
+pub const LOG_FILE_PATH: &str = "casino_logs.log";
+
+// SECURITY: Hash chain over the log.
+// Once the encryption keys are loaded every record ends with a "chain" field "<seq>:<mac>", where mac
+// is an HMAC of the previous entry's mac, the sequence number and the record before the field, keyed from the
+// encryption key. Editing, inserting or deleting an entry breaks the chain at that entry, and
+// without the key a matching mac cannot be made. Entries written before the keys are loaded carry
+// no chain and are reported as unverifiable. Removing entries from the very end cannot be seen from
+// the file alone.
+static CHAIN_KEY: OnceLock<Vec<u8>> = OnceLock::new();
+const CHAIN_MARKER: &str = ",\"chain\":\"";
+
 // Define log levels
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LogLevel {
-    INFO,
-    WARNING,
-    ERROR,
-    SECURITY,
-    TRANSACTION,
-    CRITICAL,
+    Info,
+    Warning,
+    Error,
+    Security,
+    Transaction,
+    Critical,
 }
 
 impl LogLevel {
-    fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &'static str {
         match self {
-            LogLevel::INFO => "INFO",
-            LogLevel::WARNING => "WARNING",
-            LogLevel::ERROR => "ERROR",
-            LogLevel::SECURITY => "SECURITY",
-            LogLevel::TRANSACTION => "TRANSACTION",
-            LogLevel::CRITICAL => "CRITICAL",
+            LogLevel::Info => "INFO",
+            LogLevel::Warning => "WARNING",
+            LogLevel::Error => "ERROR",
+            LogLevel::Security => "SECURITY",
+            LogLevel::Transaction => "TRANSACTION",
+            LogLevel::Critical => "CRITICAL",
         }
     }
 }
 
+// Position of an entry in the hash chain
+#[derive(Debug, Clone, PartialEq)]
+struct Link {
+    seq: u64,
+    mac: String,
+}
+
+impl Link {
+    // What the first entry of a chain follows
+    fn genesis() -> Self {
+        Link { seq: 0, mac: String::new() }
+    }
+}
+
+fn chain_mac(key: &[u8], previous_mac: &str, seq: u64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}\n{}\n{}", previous_mac, seq, body).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+// Chain the JSON record `body` after `previous`, giving the line to write and its link
+fn chain_line(key: &[u8], previous: &Link, body: &str) -> (String, Link) {
+    let seq = previous.seq + 1;
+    let link = Link { seq, mac: chain_mac(key, &previous.mac, seq, body) };
+    let fields = body.strip_suffix('}').unwrap_or(body);
+    (format!("{}{}{}:{}\"}}", fields, CHAIN_MARKER, link.seq, link.mac), link)
+}
+
+// Split a chained line into the record without its chain field and its link, None for unchained lines.
+// Quotes inside JSON strings are escaped, so the chain field cannot be faked inside a value.
+fn parse_link(line: &str) -> Option<(String, Link)> {
+    let (fields, suffix) = line.strip_suffix("\"}")?.rsplit_once(CHAIN_MARKER)?;
+    let (seq, mac) = suffix.split_once(':')?;
+    Some((format!("{}}}", fields), Link { seq: seq.parse().ok()?, mac: mac.to_string() }))
+}
+
+// Link of the last chained entry in the file, read backwards from the end
+fn last_link(file: &mut File) -> Result<Link, std::io::Error> {
+    let length = file.seek(SeekFrom::End(0))?;
+    let mut window: u64 = 4096;
+    loop {
+        let start = length.saturating_sub(window);
+        file.seek(SeekFrom::Start(start))?;
+        let mut tail = Vec::new();
+        Read::by_ref(file).take(length - start).read_to_end(&mut tail)?;
+        // A line cut off at the window start still ends with its complete link
+        if let Some(link) = String::from_utf8_lossy(&tail).lines().rev().find_map(|line| parse_link(line).map(|(_, link)| link)) {
+            return Ok(link);
+        }
+        if start == 0 {
+            return Ok(Link::genesis());
+        }
+        window *= 4;
+    }
+}
+
+/// Chain every entry written from now on with `key`, called once the encryption keys are loaded
+pub fn start_chain(key: Vec<u8>) {
+    let _ = CHAIN_KEY.set(key);
+}
+
 pub struct Logger {
     file: File,
 }
@@ -33,164 +114,285 @@ impl Logger {
     // Create a new logger that writes to the specified file
     pub fn new(log_path: &str) -> Result<Self, std::io::Error> {
         let path = Path::new(log_path);
+        // Read access finds the last link of the chain
         let file = OpenOptions::new()
             .create(true)
-            .write(true)
+            .read(true)
             .append(true)
             .open(path)?;
         
         Ok(Logger { file })
     }
 
-    // Write a log entry with the given level and message
-    pub fn log(&mut self, level: LogLevel, message: &str) -> Result<(), std::io::Error> {
-        let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
-        let log_entry = format!("[{}] [{}] {}\n", timestamp, level.as_str(), message);
-        
-        self.file.write_all(log_entry.as_bytes())?;
-        self.file.flush()?;
-        
-        Ok(())
+    // Write an event as one JSON record
+    pub fn log(&mut self, event: &LogEvent) -> Result<(), std::io::Error> {
+        let timestamp = Local::now().to_rfc3339_opts(SecondsFormat::Secs, false);
+        // JSON escapes line breaks, so a message cannot fake a second entry
+        let body = serde_json::Value::Object(event.to_record(&timestamp)).to_string();
+
+        let Some(key) = CHAIN_KEY.get() else {
+            self.file.write_all(format!("{}\n", body).as_bytes())?;
+            return self.file.flush();
+        };
+
+        // Locked so entries from other threads and processes cannot fork the chain
+        self.file.lock()?;
+        let written = last_link(&mut self.file).and_then(|previous| {
+            let (line, _) = chain_line(key, &previous, &body);
+            self.file.write_all(format!("{}\n", line).as_bytes())?;
+            self.file.flush()
+        });
+        self.file.unlock()?;
+        written
     }
 }
 
 // Helper function to create a logger instance
 fn get_logger() -> Result<Logger, std::io::Error> {
-    Logger::new("casino_logs.log")
-    }
+    Logger::new(LOG_FILE_PATH)
+}
 
-// Public functions to log at different levels
-pub fn info(message: &str) {
+/// Log a typed event
+pub fn event(event: LogEvent) {
     if let Ok(mut logger) = get_logger() {
-        let _ = logger.log(LogLevel::INFO, message);
+        let _ = logger.log(&event);
     }
 }
 
-pub fn warning(message: &str) {
-    if let Ok(mut logger) = get_logger() {
-        let _ = logger.log(LogLevel::WARNING, message);
-    }
+fn message(level: LogLevel, text: &str) {
+    event(LogEvent::Message { level, text });
 }
 
-pub fn error(message: &str) {
-    if let Ok(mut logger) = get_logger() {
-        let _ = logger.log(LogLevel::ERROR, message);
-    }
+// Public functions to log at different levels
+pub fn info(text: &str) {
+    message(LogLevel::Info, text);
 }
 
-pub fn security(message: &str) {
-    if let Ok(mut logger) = get_logger() {
-        let _ = logger.log(LogLevel::SECURITY, message);
-    }
+pub fn warning(text: &str) {
+    message(LogLevel::Warning, text);
 }
 
-pub fn transaction(message: &str) {
-    if let Ok(mut logger) = get_logger() {
-        let _ = logger.log(LogLevel::TRANSACTION, message);
-    }
+pub fn error(text: &str) {
+    message(LogLevel::Error, text);
 }
 
-pub fn critical(message: &str) {
-    if let Ok(mut logger) = get_logger() {
-        let _ = logger.log(LogLevel::CRITICAL, message);
-    }
+pub fn security(text: &str) {
+    message(LogLevel::Security, text);
+}
+
+pub fn critical(text: &str) {
+    message(LogLevel::Critical, text);
 }
 
 // Log verification functions
-pub fn verify_login_attempts(username: &str, time_window_minutes: u32) -> Result<(u32, u32), std::io::Error> {
-    let log_content = std::fs::read_to_string("casino_logs.log")?;
-    let lines: Vec<&str> = log_content.lines().collect();
-    
-    let now = Local::now();
-    let window_start = now - chrono::Duration::minutes(time_window_minutes as i64);
-    
+
+// Records written in the last `time_window_minutes`. Lines that are not records, such as entries
+// from before JSON Lines, are skipped.
+fn records_since(log_content: &str, time_window_minutes: u32) -> Vec<LogRecord> {
+    let window_start = Local::now() - chrono::Duration::minutes(time_window_minutes as i64);
+    log_content.lines()
+        .filter_map(LogRecord::parse)
+        .filter(|record| record.timestamp >= window_start)
+        .collect()
+}
+
+fn count_login_attempts(records: &[LogRecord], username: &str) -> (u32, u32) {
     let mut successful_attempts = 0;
     let mut failed_attempts = 0;
-    
-    for line in lines {
-        if !line.contains(username) {
-            continue;
-        }
-        
-        // Parse the timestamp
-        if let Some(timestamp_str) = line.get(1..20) {
-            if let Ok(timestamp) = chrono::NaiveDateTime::parse_from_str(timestamp_str, "%Y-%m-%d %H:%M:%S") {
-                let log_time = chrono::DateTime::<chrono::Local>::from_naive_utc_and_offset(
-                    timestamp, *chrono::Local::now().offset()
-                );
-                
-                if log_time >= window_start {
-                    if line.contains("Successful login") {
-                        successful_attempts += 1;
-                    } else if line.contains("Failed login") {
-                        failed_attempts += 1;
-                    }
-                }
-            }
+    for record in records.iter().filter(|record| record.username.as_deref() == Some(username)) {
+        match record.event.as_str() {
+            "login_succeeded" => successful_attempts += 1,
+            "login_failed" => failed_attempts += 1,
+            _ => {}
         }
     }
-    
-    Ok((successful_attempts, failed_attempts))
-}
-
-pub fn verify_transactions(user_id: i32, time_window_minutes: u32) -> Result<Vec<String>, std::io::Error> {
-    let log_content = std::fs::read_to_string("casino_logs.log")?;
-    let lines: Vec<&str> = log_content.lines().collect();
-    
-    let now = Local::now();
-    let window_start = now - chrono::Duration::minutes(time_window_minutes as i64);
-    
-    let mut transactions = Vec::new();
-    
-    for line in lines {
-        if !line.contains(&format!("User ID: {}", user_id)) || !line.contains("[TRANSACTION]") {
-            continue;
-        }
-        
-        // Parse the timestamp
-        if let Some(timestamp_str) = line.get(1..20) {
-            if let Ok(timestamp) = chrono::NaiveDateTime::parse_from_str(timestamp_str, "%Y-%m-%d %H:%M:%S") {
-                let log_time = chrono::DateTime::<chrono::Local>::from_naive_utc_and_offset(
-                    timestamp, *chrono::Local::now().offset()
-                );
-                
-                if log_time >= window_start {
-                    transactions.push(line.to_string());
+    (successful_attempts, failed_attempts)
+}
+
+fn user_transactions(records: Vec<LogRecord>, user_id: i32) -> Vec<LogRecord> {
+    records.into_iter()
+        .filter(|record| record.level == LogLevel::Transaction.as_str() && record.user_id == Some(user_id))
+        .collect()
+}
+
+pub fn verify_login_attempts(username: &str, time_window_minutes: u32) -> Result<(u32, u32), std::io::Error> {
+    let log_content = std::fs::read_to_string(LOG_FILE_PATH)?;
+    Ok(count_login_attempts(&records_since(&log_content, time_window_minutes), username))
+}
+
+pub fn verify_transactions(user_id: i32, time_window_minutes: u32) -> Result<Vec<LogRecord>, std::io::Error> {
+    let log_content = std::fs::read_to_string(LOG_FILE_PATH)?;
+    Ok(user_transactions(records_since(&log_content, time_window_minutes), user_id))
+}
+
+pub fn verify_security_events(time_window_minutes: u32) -> Result<Vec<LogRecord>, std::io::Error> {
+    let log_content = std::fs::read_to_string(LOG_FILE_PATH)?;
+    Ok(records_since(&log_content, time_window_minutes).into_iter()
+        .filter(|record| record.level == LogLevel::Security.as_str())
+        .collect())
+}
+
+// First place where the hash chain does not hold
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChainBreak {
+    // The entry on this line was changed
+    Altered { line: usize },
+    // The entry on this line was not written by the casino
+    Inserted { line: usize },
+    // Entries are missing just before this line
+    Deleted { line: usize, missing: u64 },
+}
+
+// Result of walking the log's hash chain
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainReport {
+    pub chained: usize,
+    pub unchained: usize,
+    // First entry without a chain after chaining had started, written without the key or inserted
+    pub first_unchained_after_chain: Option<usize>,
+    pub first_break: Option<ChainBreak>,
+}
+
+fn verify_chain_of(content: &str, key: &[u8]) -> ChainReport {
+    let mut report = ChainReport { chained: 0, unchained: 0, first_unchained_after_chain: None, first_break: None };
+    let mut entries = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        match parse_link(line) {
+            Some((body, link)) => entries.push((index + 1, body, link)),
+            None => {
+                report.unchained += 1;
+                if !entries.is_empty() && report.first_unchained_after_chain.is_none() {
+                    report.first_unchained_after_chain = Some(index + 1);
                 }
             }
         }
     }
-    
-    Ok(transactions)
-}
-
-pub fn verify_security_events(time_window_minutes: u32) -> Result<Vec<String>, std::io::Error> {
-    let log_content = std::fs::read_to_string("casino_logs.log")?;
-    let lines: Vec<&str> = log_content.lines().collect();
-    
-    let now = Local::now();
-    let window_start = now - chrono::Duration::minutes(time_window_minutes as i64);
-    
-    let mut security_events = Vec::new();
-    
-    for line in lines {
-        if !line.contains("[SECURITY]") {
+    report.chained = entries.len();
+
+    let mut previous = Link::genesis();
+    for (position, (line, body, link)) in entries.iter().enumerate() {
+        let expected = previous.seq + 1;
+        if link.seq == expected && link.mac == chain_mac(key, &previous.mac, link.seq, body) {
+            previous = link.clone();
             continue;
         }
-        
-        // Parse the timestamp
-        if let Some(timestamp_str) = line.get(1..20) {
-            if let Ok(timestamp) = chrono::NaiveDateTime::parse_from_str(timestamp_str, "%Y-%m-%d %H:%M:%S") {
-                let log_time = chrono::DateTime::<chrono::Local>::from_naive_utc_and_offset(
-                    timestamp, *chrono::Local::now().offset()
-                );
-                
-                if log_time >= window_start {
-                    security_events.push(line.to_string());
-                }
-            }
+
+        // A genuine mac is confirmed by the entry after it, which was chained from it
+        let next = entries.get(position + 1);
+        let confirmed = next.is_none_or(|(_, next_body, next_link)| {
+            next_link.seq == link.seq + 1 && next_link.mac == chain_mac(key, &link.mac, next_link.seq, next_body)
+        });
+        report.first_break = Some(if !confirmed || link.seq < expected {
+            ChainBreak::Inserted { line: *line }
+        } else if link.seq > expected {
+            ChainBreak::Deleted { line: *line, missing: link.seq - expected }
+        } else {
+            ChainBreak::Altered { line: *line }
+        });
+        break;
+    }
+    report
+}
+
+/// Walk the hash chain of the log file and report the first altered, inserted or deleted entry
+pub fn verify_chain() -> Result<ChainReport, std::io::Error> {
+    verify_chain_file(LOG_FILE_PATH)
+}
+
+/// Walk the hash chain of the log at `log_path`
+pub fn verify_chain_file(log_path: &str) -> Result<ChainReport, std::io::Error> {
+    let key = CHAIN_KEY.get()
+        .ok_or_else(|| std::io::Error::other("The encryption keys are not loaded, the log cannot be verified"))?;
+    let log_content = std::fs::read_to_string(log_path)?;
+    Ok(verify_chain_of(&log_content, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::money::Money;
+    use crate::logger::event::TransactionKind;
+
+    const KEY: &[u8] = b"test log chain key";
+
+    fn record(event: LogEvent, second: usize) -> String {
+        serde_json::Value::Object(event.to_record(&format!("2025-01-01T00:00:{:02}+02:00", second))).to_string()
+    }
+
+    fn entry(text: &str, second: usize) -> String {
+        record(LogEvent::Message { level: LogLevel::Security, text }, second)
+    }
+
+    // A legacy entry followed by a chain of `count` entries
+    fn chained_log(count: usize) -> Vec<String> {
+        let mut lines = vec!["[2025-01-01 00:00:00] [INFO] Written before JSON Lines".to_string()];
+        let mut previous = Link::genesis();
+        for number in 0..count {
+            let (line, link) = chain_line(KEY, &previous, &entry(&format!("Entry {}", number), number));
+            lines.push(line);
+            previous = link;
         }
+        lines
     }
-    
-    Ok(security_events)
-}
\ No newline at end of file
+
+    fn verify(lines: &[String]) -> ChainReport {
+        verify_chain_of(&lines.join("\n"), KEY)
+    }
+
+    #[test]
+    fn test_intact_chain_verifies() {
+        let lines = chained_log(5);
+        let report = verify(&lines);
+        assert_eq!(report.chained, 5);
+        assert_eq!(report.unchained, 1);
+        assert_eq!(report.first_unchained_after_chain, None);
+        assert_eq!(report.first_break, None);
+        // Chained lines are still valid records
+        assert_eq!(LogRecord::parse(&lines[1]).unwrap().message, "Entry 0");
+    }
+
+    #[test]
+    fn test_altered_inserted_and_deleted_entries_are_found() {
+        let mut altered = chained_log(5);
+        altered[3] = altered[3].replace("Entry 2", "Entry 9");
+        assert_eq!(verify(&altered).first_break, Some(ChainBreak::Altered { line: 4 }));
+
+        let mut deleted = chained_log(5);
+        deleted.remove(2);
+        assert_eq!(verify(&deleted).first_break, Some(ChainBreak::Deleted { line: 3, missing: 1 }));
+
+        // A forged entry needs the key, a guessed mac does not fit the chain
+        let mut inserted = chained_log(5);
+        let (forged, _) = chain_line(b"wrong key", &Link::genesis(), &entry("Forged", 9));
+        let forged = forged.replace(",\"chain\":\"1:", ",\"chain\":\"3:");
+        inserted.insert(3, forged);
+        assert_eq!(verify(&inserted).first_break, Some(ChainBreak::Inserted { line: 4 }));
+
+        let mut unchained = chained_log(5);
+        unchained.insert(4, entry("Unchained", 9));
+        assert_eq!(verify(&unchained).first_unchained_after_chain, Some(5));
+    }
+
+    #[test]
+    fn test_queries_filter_on_fields() {
+        let transaction = |user_id: i32| {
+            LogEvent::round(TransactionKind::Win, user_id, "slots", 7, Money::from_dollars(5), Money::from_dollars(20))
+        };
+        let log = [
+            record(transaction(1), 1),
+            record(transaction(12), 2),
+            record(LogEvent::LoginSucceeded { username: "alice", user_id: 1 }, 3),
+            record(LogEvent::LoginFailed { username: "alice", reason: "Invalid credentials" }, 4),
+            // Wording no longer matters, only the fields
+            record(LogEvent::LoginFailed { username: "alice_2", reason: "Successful login for username: alice" }, 5),
+            entry("Failed login for username: alice", 6),
+        ].join("\n");
+        let records: Vec<LogRecord> = log.lines().filter_map(LogRecord::parse).collect();
+
+        let transactions = user_transactions(records.clone(), 1);
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].amount, Some(Money::from_dollars(5)));
+        assert_eq!(transactions[0].game.as_deref(), Some("slots"));
+        assert_eq!(count_login_attempts(&records, "alice"), (1, 1));
+    }
+}
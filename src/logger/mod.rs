@@ -1,2 +1,3 @@
+#[allow(clippy::module_inception)]
 pub mod logger;
-pub mod verification;
\ No newline at end of file
+pub mod verification;pub mod event;
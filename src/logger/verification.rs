@@ -1,16 +1,16 @@
 use rusqlite::Connection;
 use colored::*;
 use std::io::{self, Write};
-use crate::interfaces::menus::menu_generator;
+use crate::interfaces::menus::session_menu;
 
+use crate::authentication::authorization;
+use crate::authentication::permissions::Permission;
 use crate::interfaces::user::User;
 use crate::logger::logger;
 
 pub fn log_verification_menu(conn: &Connection, user: &User) -> rusqlite::Result<()> {
-    // First check if user has admin privileges
-    let role = user.get_role(conn)?;
-    if role != "technician" && role != "commissioner" {
-        println!("{}", "Access denied: Insufficient privileges".red());
+    // First check if user may view the logs
+    if authorization::require_permission(conn, user, Permission::LogsView).is_err() {
         logger::security(&format!("User ID: {} attempted to access log verification without proper permissions", user.id));
         return Ok(());
     }
@@ -19,13 +19,16 @@ pub fn log_verification_menu(conn: &Connection, user: &User) -> rusqlite::Result
 
     loop {
         // Show options to user
-        let menu_options = vec!["View Recent Security Events", "Check Login Attempts by Username", "View User Transactions", "Back"];
-        let user_input = menu_generator("═══ 🔒 Log Verification Menu 🔒 ═══", &menu_options);
+        let menu_options = vec!["View Recent Security Events", "Check Login Attempts by Username", "View User Transactions", "Verify Log Integrity", "Back"];
+        let Some(user_input) = session_menu(conn, user, "═══ 🔒 Log Verification Menu 🔒 ═══", &menu_options) else {
+            break;
+        };
         
         match user_input.trim() {
             "View Recent Security Events" => view_security_events(),
             "Check Login Attempts by Username" => check_login_attempts(),
             "View User Transactions" => view_user_transactions(),
+            "Verify Log Integrity" => verify_log_integrity(user),
             "Back" => break,
             _ => println!("Invalid choice"),
         }
@@ -119,4 +122,39 @@ fn view_user_transactions() {
             println!("{}", format!("Error retrieving transactions: {}", e).red());
         }
     }
-}
\ No newline at end of file
+}
+
+fn verify_log_integrity(user: &User) {
+    println!("\n{}", "═══ Verify Log Integrity ═══".cyan());
+
+    let report = match logger::verify_chain() {
+        Ok(report) => report,
+        Err(e) => {
+            println!("{}", format!("Error verifying the log: {}", e).red());
+            return;
+        }
+    };
+    println!("{} chained entries checked", report.chained);
+    if report.unchained > 0 {
+        println!("{} entries have no chain and cannot be verified (older entries, or written before the keys were loaded)", report.unchained);
+    }
+    if let Some(line) = report.first_unchained_after_chain {
+        println!("{}", format!("WARNING: Line {} has no chain although earlier entries do", line).yellow());
+    }
+
+    match report.first_break {
+        None => {
+            println!("{}", "✓ The hash chain is intact".green().bold());
+            logger::security(&format!("User ID: {} verified the log hash chain: intact", user.id));
+        }
+        Some(chain_break) => {
+            let description = match chain_break {
+                logger::ChainBreak::Altered { line } => format!("the entry on line {} was altered", line),
+                logger::ChainBreak::Inserted { line } => format!("the entry on line {} was inserted", line),
+                logger::ChainBreak::Deleted { line, missing } => format!("{} entries were deleted before line {}", missing, line),
+            };
+            println!("{}", format!("❌ The log has been tampered with: {}", description).red().bold());
+            logger::critical(&format!("SECURITY ALERT: Log hash chain broken, {} (found by User ID: {})", description, user.id));
+        }
+    }
+}
@@ -1,7 +1,5 @@
 use rusqlite::{Connection, Result};
-use clearscreen;
 use colored::*;
-use std::path::Path;
 
 mod interfaces;
 mod db;
@@ -9,55 +7,101 @@ mod authentication;
 mod play;
 mod logger;
 mod cryptography;
+mod statistics;
 
-// Check and initialize all required files at startup
-// If ANY file is missing, reinitialize ALL from scratch
+// Check the files a running casino needs and initialize the system
+// Only the log is recreated when missing, a missing key or database stops startup (see db::startup_check)
 fn initialize_system() -> bool {
     println!("{}", "═══════════════════════════════════════════════".bright_cyan().bold());
     println!("{}", "   🎰 Casino System Initialization 🎰".bright_yellow().bold());
     println!("{}", "═══════════════════════════════════════════════".bright_cyan().bold());
     println!();
-    
-    // Check if ALL critical files exist
-    let env_exists = Path::new(".env").exists();
-    let db_exists = Path::new("casino.db").exists();
-    let log_exists = Path::new("casino_logs.log").exists();
-    let all_files_exist = env_exists && db_exists && log_exists;
-    
-    // If ANY file is missing, reinitialize ALL from scratch
-    if !all_files_exist {
-        println!("{}", "⚠️  Incomplete system detected - reinitializing...".yellow());
-        
-        // Delete existing files silently to ensure clean state
-        if env_exists {
-            let _ = std::fs::remove_file(".env");
-        }
-        if db_exists {
-            let _ = std::fs::remove_file("casino.db");
-        }
-        if log_exists {
-            let _ = std::fs::remove_file("casino_logs.log");
-        }
+
+    // Starting over is only ever done on the operator's explicit command
+    if std::env::args().any(|arg| arg == db::startup_check::RESET_ARGUMENT) {
+        reset_system();
     }
-    
+
     // Load .env file (will be created if needed during admin account setup)
     dotenvy::dotenv().ok();
-    
+
+    let files = db::startup_check::find_files();
+    let plan = db::startup_check::diagnose(files);
+
+    if let db::startup_check::StartupPlan::Refuse(reason) = &plan {
+        println!("{}", "❌ The casino cannot start safely:".red().bold());
+        println!("{}", reason);
+        logger::logger::critical(&format!("Startup refused: {}", reason.replace('\n', " ")));
+        std::process::exit(1);
+    }
+
+    // A log left by an earlier install is chained with that install's key, the new one starts its own
+    let mut old_log = None;
+    if plan == db::startup_check::StartupPlan::FreshInstall {
+        match db::startup_check::set_aside_old_log(logger::logger::LOG_FILE_PATH) {
+            Ok(moved) => old_log = moved,
+            Err(e) => {
+                println!("{}", format!("❌ {}", e).red().bold());
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Initialize encryption system first, entries are only hash chained once the keys are loaded
+    // The keys come from the passphrase protected key file when there is one, otherwise from .env
+    if let Err(e) = cryptography::crypto::initialize_key_storage() {
+        println!("{}", format!("❌ Encryption keys could not be loaded: {}", e).red().bold());
+        logger::logger::critical(&format!("Encryption keys could not be loaded: {}", e));
+        std::process::exit(1);
+    }
+
     // Initialize logger (will create file if needed)
     logger::logger::info("═══════════════════════════════════════");
     logger::logger::info("Application is starting");
-    if !all_files_exist {
-        logger::logger::info("System reinitialized from scratch");
-    } else {
-        logger::logger::info("System continuing with existing files");
+    match &plan {
+        db::startup_check::StartupPlan::FreshInstall => {
+            logger::logger::info("No database or encryption key found, initializing a new system");
+            if let Some(old_log) = &old_log {
+                println!("{}", format!("The log of an earlier install was moved to {}", old_log).yellow());
+                logger::logger::security(&format!("The log of an earlier install was moved to {}", old_log));
+            }
+        }
+        db::startup_check::StartupPlan::Continue { recreate_log: true } => {
+            println!("{}", "⚠️  The log file was missing and has been recreated, earlier entries are lost".yellow());
+            logger::logger::security(&format!("{} was missing at startup and has been recreated", logger::logger::LOG_FILE_PATH));
+        }
+        _ => logger::logger::info("System continuing with existing files"),
     }
-    
-    // Initialize encryption system
-    cryptography::crypto::initialize_encryption_key();
     logger::logger::info("Database encryption initialized");
-    
+
     // Return whether this is a fresh initialization
-    !all_files_exist
+    plan == db::startup_check::StartupPlan::FreshInstall
+}
+
+// Move the current install into a backup directory after the operator confirms, then exit
+fn reset_system() {
+    println!("{}", "⚠️  RESET: the database, encryption keys, .env and log will be moved into a backup".red().bold());
+    println!("directory and the next start creates a new casino with no accounts.");
+    let confirmation: String = dialoguer::Input::new()
+        .with_prompt("Type RESET to continue")
+        .allow_empty(true)
+        .interact_text()
+        .unwrap_or_default();
+    if confirmation != "RESET" {
+        println!("Reset cancelled, nothing was changed.");
+        std::process::exit(0);
+    }
+
+    match db::startup_check::move_to_backup() {
+        Ok(directory) => {
+            println!("{}", format!("✓ The previous install was moved to {}. Start again to initialize.", directory).green().bold());
+            std::process::exit(0);
+        }
+        Err(e) => {
+            println!("{}", format!("❌ Reset failed, some files may already be in the backup directory: {}", e).red().bold());
+            std::process::exit(1);
+        }
+    }
 }
 
 // Main function, creates and connects to db, casino.db
@@ -69,7 +113,7 @@ fn main() -> Result<()> {
     let is_fresh_init = initialize_system();
     
     // Connect to database (creates if doesn't exist)
-    let conn = Connection::open("casino.db")?;
+    let conn = Connection::open(db::startup_check::DATABASE_PATH)?;
     logger::logger::info("Database connection established");
 
     // Allows casino.db to utilize foreign_keys
@@ -79,19 +123,30 @@ fn main() -> Result<()> {
     // Initializes db with all the tables (users, games, user_statistics) and adds records if needed
     db::dbinitialize::initialize_dbs(&conn)?;
     logger::logger::info("Database tables initialized");
+
+    // Rounds interrupted before settlement get their bets back
+    let recovered = db::dbqueries::recover_pending_rounds(&conn)?;
+    if recovered > 0 {
+        println!("{}", format!("⚠️  {} interrupted round(s) refunded", recovered).yellow());
+    }
+
+    // Stored balances must agree with the ledger, mismatches raise CRITICAL alerts
+    let mismatches = db::ledger::reconcile(&conn)?.iter().filter(|result| !result.matches()).count();
+    if mismatches > 0 {
+        println!("{}", format!("⚠️  {} balance(s) do not match the ledger, see the security log", mismatches).red().bold());
+    }
     
     println!("{}", "✓ System Ready!".bright_green().bold());
     println!();
     
     // If fresh initialization, inform user about credentials
-    if is_fresh_init {
-        if std::env::var("CASINO_TECH_PASSWORD").is_ok() || std::env::var("CASINO_COMM_PASSWORD").is_ok() {
+    if is_fresh_init
+        && (std::env::var("CASINO_TECH_PASSWORD").is_ok() || std::env::var("CASINO_COMM_PASSWORD").is_ok()) {
             println!("{}", "⚠️  NEW CREDENTIALS GENERATED".yellow().bold());
             println!("Check '.env' file for admin passwords");
             println!();
             std::thread::sleep(std::time::Duration::from_secs(3));
         }
-    }
     
     // Starts the application via the login function
     logger::logger::info("Starting application login flow");
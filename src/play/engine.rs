@@ -0,0 +1,348 @@
+use rusqlite::Connection;
+use colored::*;
+
+use crate::cryptography::provably_fair::{self, FairRound};
+use crate::cryptography::rng::CasinoRng;
+use crate::db::dbqueries;
+use crate::db::money::Money;
+use crate::interfaces::menus;
+use crate::interfaces::user::User;
+use crate::logger::event::{LogEvent, TransactionKind};
+use crate::logger::logger;
+use crate::play::game::Game;
+
+// Where a round is in its lifecycle. A round is pending from the moment its bet is taken until it
+// is settled, or refunded if it was interrupted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RoundStatus {
+    Pending,
+    Settled,
+    Refunded,
+}
+
+impl RoundStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RoundStatus::Pending => "pending",
+            RoundStatus::Settled => "settled",
+            RoundStatus::Refunded => "refunded",
+        }
+    }
+
+    pub fn parse(status: &str) -> Self {
+        match status {
+            "pending" => RoundStatus::Pending,
+            "refunded" => RoundStatus::Refunded,
+            _ => RoundStatus::Settled,
+        }
+    }
+}
+
+// One round of a player's history, as stored in the rounds table
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundRecord {
+    pub id: i64,
+    pub user_id: i32,
+    pub game_name: String,
+    // Set by the database when the round is stored
+    pub timestamp: String,
+    pub bet: Money,
+    // Charges for extras bought during the round, such as holds
+    pub hold_charges: Money,
+    // Symbols of the round, rows separated by " / "
+    pub grid: String,
+    pub lines: Vec<String>,
+    pub payout: Money,
+    // Player's balance once the round was settled
+    pub balance: Money,
+    // Provably fair record of the same round, if one was stored
+    pub fair_round_id: Option<i64>,
+    pub status: RoundStatus,
+}
+
+// Run a game session at a fixed bet: charge the bet, resolve the round through the game,
+// settle winnings and statistics, then ask the player what to do next.
+// Taking the bet and settling the round are each a single database transaction, so a round is
+// either pending with its bet taken or fully settled, never half applied.
+// Every round is provably fair: it draws from the player's committed seed pair at the next nonce
+// and is recorded so the player can verify it once the server seed is revealed.
+// Returns true to change the bet, false to leave the game.
+pub fn play(conn: &Connection, user: &User, game: &mut dyn Game, bet: Money) -> bool {
+    let name = game.name();
+
+    // Load the commissioner-configured paytable once per session
+    if let Err(e) = game.load_config(conn) {
+        logger::error(&format!("Failed to load configuration for {}: {}", name, e));
+        println!("{}", "Error loading game configuration".red());
+        return true;
+    }
+    game.show_welcome();
+
+    // Commit to the server seed before the first round
+    let seed = match dbqueries::get_active_fair_seed(conn, user) {
+        Ok(seed) => seed,
+        Err(e) => {
+            logger::error(&format!("Failed to load provably fair seed for User ID: {}: {}", user.id, e));
+            println!("{}", "Error preparing provably fair seeds".red());
+            return true;
+        }
+    };
+    println!("{}", format!("Provably fair - server seed commitment: {}", seed.commitment).bright_black());
+    println!("{}", format!("Provably fair - client seed: {}", seed.client_seed).bright_black());
+    let paytable_hash = provably_fair::paytable_hash(game.paytable());
+
+    loop {
+        // Check if player has the funds
+        if !dbqueries::check_funds(conn, user, bet) {
+            logger::warning(&format!("User ID: {} has insufficient funds for bet: ${}", user.id, bet));
+            println!("{}", "Insufficient funds!".red().bold());
+            return true;
+        }
+
+        let nonce = match dbqueries::take_fair_nonce(conn, seed.id) {
+            Ok(nonce) => nonce,
+            Err(e) => {
+                logger::error(&format!("Failed to reserve provably fair nonce for User ID: {}: {}", user.id, e));
+                println!("{}", "Error preparing provably fair round".red());
+                return true;
+            }
+        };
+        let mut rng = CasinoRng::provably_fair(&seed.server_seed, &seed.client_seed, nonce);
+
+        // Take the bet before playing
+        let (round_id, balance) = match dbqueries::place_bet(conn, user, name, bet) {
+            Ok(placed) => placed,
+            Err(e) => {
+                logger::error(&format!("Failed to place bet for User ID: {}: {}", user.id, e));
+                println!("{}", "Bet could not be placed".red());
+                return true;
+            }
+        };
+
+        println!("{}", format!("Bet placed: ${}", bet).yellow());
+        println!("{}", format!("Balance: ${}", balance).bright_white());
+
+        game.show_paytable(bet);
+        let outcome = game.play_round(&mut rng, bet, balance);
+        let result = provably_fair::result_text(&outcome);
+
+        let fair_round = FairRound {
+            id: 0,
+            seed_id: seed.id,
+            nonce,
+            game_name: name.to_string(),
+            bet,
+            holds: outcome.holds.clone(),
+            result: result.clone(),
+            payout: outcome.payout,
+            paytable: game.paytable().to_vec(),
+            paytable_hash: paytable_hash.clone(),
+        };
+        let mut record = RoundRecord {
+            id: round_id,
+            user_id: user.id,
+            game_name: name.to_string(),
+            timestamp: String::new(),
+            bet,
+            hold_charges: outcome.extra_wager,
+            grid: result,
+            lines: outcome.lines.clone(),
+            payout: outcome.payout,
+            balance,
+            fair_round_id: None,
+            status: RoundStatus::Pending,
+        };
+
+        // Charge anything bought during the round, pay any winnings and record the round, all at once
+        if let Err(e) = dbqueries::settle_round(conn, user, &fair_round, &mut record) {
+            logger::error(&format!("Failed to settle round {} for User ID: {}: {}", round_id, user.id, e));
+            println!("{}", "Error settling round - your bet is being refunded".red());
+            if let Err(e) = dbqueries::refund_round(conn, round_id) {
+                // Startup recovery refunds it if this fails too
+                logger::critical(&format!("Failed to refund round {} for User ID: {}: {}", round_id, user.id, e));
+            }
+            return true;
+        }
+        // Logged once the settlement is committed, so a rolled back round never shows up as won or lost
+        let balance = record.balance;
+        if outcome.payout.is_positive() {
+            logger::event(LogEvent::round(TransactionKind::Win, user.id, name, round_id, outcome.payout, balance));
+        } else {
+            logger::event(LogEvent::round(TransactionKind::Loss, user.id, name, round_id, outcome.total_wager(bet), balance));
+        }
+
+        game.show_result(&outcome, bet, balance);
+
+        // Show options to user
+        let menu_options = vec!["Spin Again", "Change Bet", "Exit"];
+        let Some(user_input) = menus::session_menu(conn, user, "═══ 🎰 Play Again? 🎰 ═══", &menu_options) else {
+            return false;
+        };
+
+        match user_input.trim() {
+            "Spin Again" => {
+                logger::info(&format!("User ID: {} continuing with same bet", user.id));
+                continue;
+            }
+            "Change Bet" => {
+                logger::info(&format!("User ID: {} changing bet", user.id));
+                return true;
+            }
+            "Exit" => {
+                logger::info(&format!("User ID: {} exiting {} game", user.id, name));
+                return false;
+            }
+            _ => {
+                logger::info(&format!("User ID: {} made invalid selection, continuing game", user.id));
+                println!("Playing again...");
+                continue;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::ledger;
+    use crate::db::test_support::{funded_user, setup_test_db};
+
+    fn round(conn: &Connection, user: &User, round_id: i64, bet: Money, hold_charges: Money, payout: Money) -> (FairRound, RoundRecord) {
+        let seed = dbqueries::get_active_fair_seed(conn, user).unwrap();
+        let fair_round = FairRound {
+            id: 0,
+            seed_id: seed.id,
+            nonce: dbqueries::take_fair_nonce(conn, seed.id).unwrap(),
+            game_name: "holding".to_string(),
+            bet,
+            holds: vec![0],
+            result: "🍒 🍒 🍒".to_string(),
+            payout,
+            paytable: Vec::new(),
+            paytable_hash: provably_fair::paytable_hash(&[]),
+        };
+        let record = RoundRecord {
+            id: round_id,
+            user_id: user.id,
+            game_name: "holding".to_string(),
+            timestamp: String::new(),
+            bet,
+            hold_charges,
+            grid: fair_round.result.clone(),
+            lines: vec!["Three of a kind: 🍒".to_string()],
+            payout,
+            balance: Money::ZERO,
+            fair_round_id: None,
+            status: RoundStatus::Pending,
+        };
+        (fair_round, record)
+    }
+
+    fn status(conn: &Connection, round_id: i64) -> String {
+        conn.query_row("Select status From rounds Where id = ?1", [round_id], |row| row.get(0)).unwrap()
+    }
+
+    fn games_won(conn: &Connection) -> i64 {
+        conn.query_row("Select win From games Where name = 'holding'", [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_settlement_commits_the_whole_round() {
+        let conn = setup_test_db();
+        let user = funded_user(&conn, "winner", Money::from_dollars(10));
+        let wins_before = games_won(&conn);
+
+        let (round_id, balance) = dbqueries::place_bet(&conn, &user, "holding", Money::from_dollars(1)).unwrap();
+        assert_eq!(balance, Money::from_dollars(9));
+        assert_eq!(status(&conn, round_id), "pending");
+
+        let (fair_round, mut record) = round(&conn, &user, round_id, Money::from_dollars(1), Money::from_cents(25), Money::from_cents(250));
+        dbqueries::settle_round(&conn, &user, &fair_round, &mut record).unwrap();
+        assert_eq!(record.balance, Money::from_cents(1125));
+        assert_eq!(user.get_balance(&conn).unwrap(), Money::from_cents(1125));
+        assert_eq!(ledger::ledger_balance(&conn, user.id).unwrap(), Money::from_cents(1125));
+        assert_eq!(status(&conn, round_id), "settled");
+        assert!(record.fair_round_id.is_some());
+        assert_eq!(games_won(&conn), wins_before + 1);
+
+        // A settled round cannot be paid twice
+        assert!(dbqueries::settle_round(&conn, &user, &fair_round, &mut record).is_err());
+        assert!(dbqueries::refund_round(&conn, round_id).is_err());
+        assert_eq!(user.get_balance(&conn).unwrap(), Money::from_cents(1125));
+    }
+
+    #[test]
+    fn test_failed_settlement_is_rolled_back_and_refunded_on_recovery() {
+        let conn = setup_test_db();
+        let user = funded_user(&conn, "interrupted", Money::from_dollars(10));
+        let wins_before = games_won(&conn);
+
+        let (round_id, _) = dbqueries::place_bet(&conn, &user, "holding", Money::from_dollars(1)).unwrap();
+        // Holds the player cannot afford fail the settlement after the win would have been credited
+        let (fair_round, mut record) = round(&conn, &user, round_id, Money::from_dollars(1), Money::from_dollars(50), Money::from_dollars(100));
+        assert!(dbqueries::settle_round(&conn, &user, &fair_round, &mut record).is_err());
+
+        assert_eq!(user.get_balance(&conn).unwrap(), Money::from_dollars(9));
+        assert_eq!(ledger::ledger_balance(&conn, user.id).unwrap(), Money::from_dollars(9));
+        assert_eq!(status(&conn, round_id), "pending");
+        assert_eq!(games_won(&conn), wins_before);
+        let fair_rounds: i64 = conn.query_row("Select Count(*) From fair_rounds", [], |row| row.get(0)).unwrap();
+        assert_eq!(fair_rounds, 0);
+
+        assert_eq!(dbqueries::recover_pending_rounds(&conn).unwrap(), 1);
+        assert_eq!(user.get_balance(&conn).unwrap(), Money::from_dollars(10));
+        assert_eq!(status(&conn, round_id), "refunded");
+        let refunded: Money = conn.query_row(
+            "Select Sum(amount_cents) From ledger_entries Where round_id = ?1 And account = 'player' And entry_type = 'refund'",
+            [round_id], |row| row.get(0)
+        ).unwrap();
+        assert_eq!(refunded, Money::from_dollars(1));
+        assert!(ledger::reconcile(&conn).unwrap().iter().all(|result| result.matches()));
+        assert_eq!(dbqueries::recover_pending_rounds(&conn).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_round_without_a_recorded_stake_is_not_refunded() {
+        let conn = setup_test_db();
+        let user = funded_user(&conn, "nostake", Money::from_dollars(10));
+
+        // A pending round with no bet entry in the ledger, so there is nothing to refund from
+        conn.execute(
+            "Insert Into rounds (user_id, game_name, bet_cents, grid, payout_cents, balance_cents, status)
+            Values (?1, 'holding', 100, '', 0, 0, 'pending')",
+            [user.id]
+        ).unwrap();
+        let round_id = conn.last_insert_rowid();
+
+        assert!(dbqueries::refund_round(&conn, round_id).is_err());
+        assert_eq!(dbqueries::recover_pending_rounds(&conn).unwrap(), 0);
+        assert_eq!(status(&conn, round_id), "pending");
+        assert_eq!(user.get_balance(&conn).unwrap(), Money::from_dollars(10));
+        assert_eq!(ledger::ledger_balance(&conn, user.id).unwrap(), Money::from_dollars(10));
+    }
+
+    #[test]
+    fn test_win_is_credited_after_the_wager_limit_is_reached() {
+        use crate::db::transaction_limits::{set_policy, TransactionCategory, TransactionPolicy};
+
+        let conn = setup_test_db();
+        let user = funded_user(&conn, "limited", Money::from_dollars(10));
+        set_policy(&conn, &TransactionPolicy { category: TransactionCategory::Wager, max_per_minute: 1, identical_per_minute: 0 }).unwrap();
+
+        let (round_id, _) = dbqueries::place_bet(&conn, &user, "holding", Money::from_dollars(1)).unwrap();
+        assert!(dbqueries::place_bet(&conn, &user, "holding", Money::from_dollars(1)).is_err());
+
+        // Holds and winnings still settle, and cash-outs have their own limit
+        let (fair_round, mut record) = round(&conn, &user, round_id, Money::from_dollars(1), Money::from_cents(25), Money::from_dollars(5));
+        dbqueries::settle_round(&conn, &user, &fair_round, &mut record).unwrap();
+        assert_eq!(user.get_balance(&conn).unwrap(), Money::from_cents(1375));
+        dbqueries::change_balance(&conn, &user, -Money::from_dollars(2)).unwrap();
+    }
+
+    #[test]
+    fn test_round_status_round_trips() {
+        for status in [RoundStatus::Pending, RoundStatus::Settled, RoundStatus::Refunded] {
+            assert_eq!(RoundStatus::parse(status.as_str()), status);
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use rusqlite::Connection;
+
+use crate::cryptography::rng::CasinoRng;
+use crate::db::money::Money;
+use crate::play::{holding, multiwin, slots, wheel_of_fortune};
+use crate::play::theory::OutcomeClass;
+
+// Result of a single round, before any money has moved.
+// Produced by each game's pure `resolve` function so it can be tested, simulated and replayed
+// without a terminal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundOutcome {
+    // Symbols showing when the round stopped, one inner vec per row
+    pub grid: Vec<Vec<String>>,
+    // Reels the player held before the second spin (holding only)
+    pub holds: Vec<usize>,
+    // Human readable description of every winning line or pattern
+    pub lines: Vec<String>,
+    // Multiplier applied to the stake (bet + extra wager), 0.0 on a loss
+    pub multiplier: f64,
+    // Extra amount wagered during the round on top of the bet (e.g. held reels)
+    pub extra_wager: Money,
+    // Amount credited to the player
+    pub payout: Money,
+}
+
+impl RoundOutcome {
+    // Outcome of a round that paid nothing
+    pub fn losing(grid: Vec<Vec<String>>) -> Self {
+        RoundOutcome { grid, holds: Vec::new(), lines: Vec::new(), multiplier: 0.0, extra_wager: Money::ZERO, payout: Money::ZERO }
+    }
+
+    // Everything the player put on the table this round
+    pub fn total_wager(&self, bet: Money) -> Money {
+        bet + self.extra_wager
+    }
+}
+
+// How fairness reports name a game's wins, partial wins and losses.
+// `partial` is None for games that never report partial wins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutcomeLabels {
+    pub win: &'static str,
+    pub partial: Option<&'static str>,
+    pub loss: &'static str,
+}
+
+// Label an outcome for theoretical reports: its first winning line, or "No win"
+pub fn outcome_label(outcome: &RoundOutcome) -> String {
+    outcome.lines.first().cloned().unwrap_or_else(|| String::from("No win"))
+}
+
+// Convert a commissioner paytable into the (symbol, weight) format CasinoRng expects
+pub fn weighted_symbols(symbol_probs: &[(String, usize, f64)]) -> Vec<(&str, usize)> {
+    symbol_probs.iter()
+        .map(|(sym, weight, _)| (sym.as_str(), *weight))
+        .collect()
+}
+
+// Common interface for every casino game.
+// The engine in play::engine owns the bet loop (debits, credits, statistics and logging),
+// a game only has to know its configuration, how to resolve a round and what it pays.
+pub trait Game {
+    // Name of the game as stored in the games table
+    fn name(&self) -> &'static str;
+
+    // Load the commissioner-configured paytable from the database
+    fn load_config(&mut self, conn: &Connection) -> rusqlite::Result<()>;
+
+    // Loaded paytable as (symbol or segment, weight, multiplier)
+    fn paytable(&self) -> &[(String, usize, f64)];
+
+    // Configure the game with a recorded paytable instead of the database one, to replay old rounds
+    fn set_paytable(&mut self, paytable: Vec<(String, usize, f64)>);
+
+    // Greet the player when a session starts
+    fn show_welcome(&self) {}
+
+    // Print the payout table for the given bet
+    fn show_paytable(&self, bet: Money);
+
+    // Play one round at the terminal (animation and any player decisions included).
+    // The outcome itself comes from the game's pure `resolve` function.
+    // `balance` is what the player has left after the bet, for games that sell extras mid-round.
+    fn play_round(&self, rng: &mut CasinoRng, bet: Money, balance: Money) -> RoundOutcome;
+
+    // Resolve one round with no terminal I/O, making any player decisions with the game's
+    // automatic strategy. Used by the commissioner fairness test so it certifies the real rules.
+    fn simulate_round(&self, rng: &mut CasinoRng, bet: Money) -> RoundOutcome;
+
+    // Resolve a round again from the same RNG stream with the player's recorded decisions
+    // (the reels held, for games with holds), reproducing the original outcome
+    fn replay_round(&self, rng: &mut CasinoRng, bet: Money, _holds: &[usize]) -> RoundOutcome {
+        self.simulate_round(rng, bet)
+    }
+
+    // Every distinct outcome of a round at a 1.0 base bet with its exact probability,
+    // computed from the loaded paytable (and the automatic strategy for player decisions)
+    fn exact_outcomes(&self) -> Vec<OutcomeClass>;
+
+    // Symbols of the round that were drawn straight from the paytable weights, for the
+    // chi-square check of the fairness test
+    fn observed_symbols(&self, outcome: &RoundOutcome) -> Vec<String> {
+        outcome.grid.iter().flatten().cloned().collect()
+    }
+
+    // Whether a paying round counts as a partial win in fairness reports (e.g. two of three reels)
+    fn is_partial_win(&self, _outcome: &RoundOutcome) -> bool {
+        false
+    }
+
+    // Names of the win categories counted by the fairness test
+    fn outcome_labels(&self) -> OutcomeLabels {
+        OutcomeLabels { win: "Wins", partial: None, loss: "Losses" }
+    }
+
+    // Print the result of a settled round
+    fn show_result(&self, outcome: &RoundOutcome, bet: Money, balance: Money);
+}
+
+// Every game the casino can run. New games plug in by adding them here.
+pub fn registered_games() -> Vec<Box<dyn Game>> {
+    vec![
+        Box::new(slots::NormalSlots::default()),
+        Box::new(multiwin::MultiWin::default()),
+        Box::new(holding::HoldSlots::default()),
+        Box::new(wheel_of_fortune::WheelOfFortune::default()),
+    ]
+}
+
+// Look up a registered game by its name in the games table
+pub fn find_game(name: &str) -> Option<Box<dyn Game>> {
+    registered_games().into_iter().find(|game| game.name() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_support;
+    use crate::play::theory;
+
+    // Every registered game with the paytable a fresh database gives it
+    fn configured_games() -> Vec<Box<dyn Game>> {
+        let conn = test_support::setup_test_db();
+        let mut games = registered_games();
+        for game in games.iter_mut() {
+            game.load_config(&conn).unwrap();
+        }
+        games
+    }
+
+    #[test]
+    fn test_every_default_game_is_registered() {
+        for name in ["normal", "multi", "holding", "wheel of fortune"] {
+            assert!(find_game(name).is_some(), "{} should be registered", name);
+        }
+    }
+
+    #[test]
+    fn test_unknown_game_is_not_found() {
+        assert!(find_game("roulette").is_none());
+    }
+
+    #[test]
+    fn test_losing_outcome_pays_nothing() {
+        let outcome = RoundOutcome::losing(vec![vec!["🍒".to_string()]]);
+        assert_eq!(outcome.payout, Money::ZERO);
+        assert_eq!(outcome.multiplier, 0.0);
+        assert!(outcome.lines.is_empty());
+        assert_eq!(outcome.total_wager(Money::from_dollars(10)), Money::from_dollars(10));
+    }
+
+    #[test]
+    fn test_simulate_round_is_reproducible_with_seed() {
+        for game in configured_games() {
+            let bet = Money::from_dollars(5);
+            let first = game.simulate_round(&mut CasinoRng::seeded(42), bet);
+            let second = game.simulate_round(&mut CasinoRng::seeded(42), bet);
+            assert_eq!(first, second, "{}", game.name());
+            assert_eq!(first.payout, first.total_wager(bet).times(first.multiplier), "{}", game.name());
+        }
+    }
+
+    #[test]
+    fn test_games_name_the_partial_wins_they_report() {
+        for game in configured_games() {
+            let mut rng = CasinoRng::seeded(9);
+            let reports_partials = (0..2_000).any(|_| game.is_partial_win(&game.simulate_round(&mut rng, Money::from_dollars(1))));
+            assert_eq!(game.outcome_labels().partial.is_some(), reports_partials, "{}", game.name());
+        }
+    }
+
+    #[test]
+    fn test_simulation_agrees_with_theory() {
+        for game in configured_games() {
+            let report = theory::TheoreticalReport::from_classes(game.exact_outcomes());
+
+            let mut rng = CasinoRng::seeded(2024);
+            let rounds = 20_000;
+            let (mut payout, mut wagered) = (Money::ZERO, Money::ZERO);
+            for _ in 0..rounds {
+                let outcome = game.simulate_round(&mut rng, Money::from_dollars(1));
+                payout += outcome.payout;
+                wagered += outcome.total_wager(Money::from_dollars(1));
+            }
+            assert!(!report.deviates(payout.to_f64() / wagered.to_f64() * 100.0, rounds), "{}", game.name());
+        }
+    }
+}
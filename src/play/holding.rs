@@ -2,15 +2,16 @@ use colored::*;
 use std::io::{self, Write};
 use rusqlite::Connection;
 
-use crate::logger::logger;
 use crate::cryptography::rng::CasinoRng;
 
-use crate::interfaces::user::User;
 use crate::db::dbqueries;
+use crate::db::money::Money;
 use crate::interfaces::menus;
+use crate::play::game::{outcome_label, weighted_symbols, Game, OutcomeLabels, RoundOutcome};
+use crate::play::theory::{self, OutcomeClass};
 
 // Display payout table to user before playing
-fn display_payout_table(symbol_probs: &[(String, usize, f64)], bet: f64) {
+fn display_payout_table(symbol_probs: &[(String, usize, f64)], bet: Money) {
     menus::print_box_top(50);
     menus::print_box_line("💰 PAYOUT TABLE 💰", 48);
     menus::print_box_separator(50);
@@ -23,239 +24,433 @@ fn display_payout_table(symbol_probs: &[(String, usize, f64)], bet: f64) {
     for (symbol, weight, payout) in symbol_probs {
         let probability = (*weight as f64 / total_weight as f64) * 100.0;
         menus::print_box_line(&format!("{} Symbol (base {:.1}x) [{:.1}% chance]:", symbol, payout, probability), 49);
-        menus::print_box_line(&format!("  • 5 of a kind: ${:<6.2} ({:.1}x)", payout * 5.0 * bet, payout * 5.0), 50);
-        menus::print_box_line(&format!("  • 4 of a kind: ${:<6.2} ({:.1}x)", payout * 2.5 * bet, payout * 2.5), 50);
-        menus::print_box_line(&format!("  • 3 of a kind: ${:<6.2} ({:.1}x)", payout * bet, payout), 50);
+        menus::print_box_line(&format!("  • 5 of a kind: ${:<6} ({:.1}x)", bet.times(payout * 5.0), payout * 5.0), 50);
+        menus::print_box_line(&format!("  • 4 of a kind: ${:<6} ({:.1}x)", bet.times(payout * 2.5), payout * 2.5), 50);
+        menus::print_box_line(&format!("  • 3 of a kind: ${:<6} ({:.1}x)", bet.times(*payout), payout), 50);
     }
     
     menus::print_box_separator(50);
     menus::print_box_line("💡 Hold up to 2 reels for second spin!", 49);
-    menus::print_box_line("⚠️  Each held reel costs 50% of base bet", 51);
+    menus::print_box_line("⚠️  Each held reel costs 25% of base bet", 51);
     menus::print_box_bottom(50);
     println!();
 }
 
 
 
-/// Hold 5x3 slot game - allows up to 2 reels to be held for next spin
-pub fn hold_game(conn: &Connection, user: &User, bet: f64) -> bool {
-    // Load symbol probabilities from database
-    let symbol_probs = match dbqueries::get_symbol_probabilities(conn, "holding") {
-        Ok(probs) => probs,
-        Err(e) => {
-            logger::error(&format!("Failed to load symbol probabilities: {}", e));
-            println!("{}", "Error loading game configuration".red());
-            return true;
-        }
-    };
-    
-    // Convert to weighted format for RNG
-    let weighted_symbols: Vec<(&str, usize)> = symbol_probs.iter()
-        .map(|(sym, weight, _)| (sym.as_str(), *weight))
-        .collect();
-    
-    let mut rng = CasinoRng::new();
-    
-    println!("\n{}", "═══ 🎰 Welcome to Hold Slots! 🎰 ═══".bright_yellow().bold());
-    println!("{}", "Hold up to 2 reels for a second spin!".bright_cyan());
-    println!("{} ${:.2}\n", "Your bet:".bright_white().bold(), bet);
-        
-    loop {
-        // Check if player has the funds for base bet
-        if !dbqueries::check_funds(conn, user, bet) {
-            logger::warning(&format!("User ID: {} has insufficient funds for bet: ${:.2}", user.id, bet));
-            println!("{}", "Insufficient funds!".red().bold());
-            return true;
-        }
-        
-        // CHARGE BASE BET FIRST
-        logger::transaction(&format!("User ID: {} placing bet of ${:.2} for holding slots", user.id, bet));
-        let mut current_balance = dbqueries::transaction(conn, user, -bet);
-        
-        if current_balance < 0.0 {
-            println!("{}", "Transaction failed!".red().bold());
-            return true;
-        }
-        
-        println!("{}", format!("Bet placed: ${:.2}", bet).yellow());
-        
-        // Use cryptographically secure weighted selection
-        let mut reels: [&str; 5] = [
-            rng.weighted_choice(&weighted_symbols).unwrap(),
-            rng.weighted_choice(&weighted_symbols).unwrap(),
-            rng.weighted_choice(&weighted_symbols).unwrap(),
-            rng.weighted_choice(&weighted_symbols).unwrap(),
-            rng.weighted_choice(&weighted_symbols).unwrap()
-        ];
-        let mut held = [false; 5]; 
-
-        // First result
-        println!("\n{}", "🎰 First Spin 🎰".bright_yellow().bold());
-
-        // Display payout table to user
-        display_payout_table(&symbol_probs, bet);
-
-        // Animate
-        for _ in 0..30 {
-            print!("\r{} | {} | {} | {} | {}", 
-                rng.weighted_choice(&weighted_symbols).unwrap(),
-                rng.weighted_choice(&weighted_symbols).unwrap(),
-                rng.weighted_choice(&weighted_symbols).unwrap(),
-                rng.weighted_choice(&weighted_symbols).unwrap(),
-                rng.weighted_choice(&weighted_symbols).unwrap()
-            );
+// Number of reels on the machine
+pub const REELS: usize = 5;
+// Most reels a player may hold for the second spin
+pub const MAX_HOLDS: usize = 2;
+// Price of each held reel as a fraction of the base bet
+pub const HOLD_COST: f64 = 0.25;
+
+// Hold 5-reel slots: after the first spin the player may hold up to 2 reels for a second spin
+#[derive(Default)]
+pub struct HoldSlots {
+    symbol_probs: Vec<(String, usize, f64)>,
+}
+
+impl Game for HoldSlots {
+    fn name(&self) -> &'static str {
+        "holding"
+    }
+
+    fn load_config(&mut self, conn: &Connection) -> rusqlite::Result<()> {
+        // Load symbol probabilities from database
+        self.symbol_probs = dbqueries::get_symbol_probabilities(conn, self.name())?;
+        Ok(())
+    }
+
+    fn paytable(&self) -> &[(String, usize, f64)] {
+        &self.symbol_probs
+    }
+
+    fn set_paytable(&mut self, paytable: Vec<(String, usize, f64)>) {
+        self.symbol_probs = paytable;
+    }
+
+    fn show_welcome(&self) {
+        println!("\n{}", "═══ 🎰 Welcome to Hold Slots! 🎰 ═══".bright_yellow().bold());
+        println!("{}", "Hold up to 2 reels for a second spin!".bright_cyan());
+    }
+
+    fn show_paytable(&self, bet: Money) {
+        display_payout_table(&self.symbol_probs, bet);
+    }
+
+    fn play_round(&self, rng: &mut CasinoRng, bet: Money, balance: Money) -> RoundOutcome {
+        let weighted = weighted_symbols(&self.symbol_probs);
+        // Animation frames are cosmetic and drawn separately from the round itself
+        let mut display_rng = CasinoRng::new();
+
+        let outcome = resolve(&self.symbol_probs, rng, bet, |reels| {
+            // First result
+            println!("\n{}", "🎰 First Spin 🎰".bright_yellow().bold());
+
+            // Animate
+            for _ in 0..30 {
+                let frame: Vec<&str> = (0..REELS).map(|_| *display_rng.weighted_choice(&weighted).unwrap()).collect();
+                print!("\r{}", frame.join(" | "));
+                io::stdout().flush().ok();
+                std::thread::sleep(std::time::Duration::from_millis(50));
+            }
+
+            print!("\r{}", reels.join(" | "));
             io::stdout().flush().ok();
-            std::thread::sleep(std::time::Duration::from_millis(50));
-        }
 
-        print!("\r{} | {} | {} | {} | {}", reels[0], reels[1], reels[2], reels[3], reels[4]);
-        io::stdout().flush().ok();
-        logger::info(&format!("User ID: {} slot result: {} | {} | {} | {} | {}", user.id, reels[0], reels[1], reels[2], reels[3], reels[4]));
-        
-        println!();
-        let menu_options = vec!["1", "2", "3", "4", "5"];
-        let user_input = menus::menu_generator_multi("Select up to 2 slots to hold (space to select)", &menu_options);
-        
-        for num in user_input {
-            // println!("selected index {}", num);
-            held[num] = true;
-        }
+            println!();
+            let menu_options = vec!["1", "2", "3", "4", "5"];
+            let holds = menus::menu_generator_multi("Select up to 2 slots to hold (space to select)", &menu_options);
+
+            // If user holds reels, charge additional bet (each held reel = +25% extra bet)
+            if !holds.is_empty() {
+                let hold_charge = hold_charge(bet, holds.len());
 
-        let held_count = held.iter().filter(|&&h| h).count();
-        
-        // If user holds reels, charge additional bet (each held reel = +25% extra bet)
-        if held_count > 0 {
-            let hold_charge = bet * 0.25 * held_count as f64;
-            
-            // Check if they can afford the hold charge
-            if current_balance < hold_charge {
-                println!("{}", format!("⚠️ Cannot afford to hold {} reels (costs ${:.2})", held_count, hold_charge).red());
-                println!("Continuing without holds...");
-                held = [false; 5]; // Reset holds
-            } else {
-                // Charge for holding reels
-                current_balance = dbqueries::transaction(conn, user, -hold_charge);
-                println!("{}", format!("Hold charge: ${:.2} for {} reel(s)", hold_charge, held_count).yellow());
-                println!("{}", format!("Balance: ${:.2}", current_balance).bright_white());
+                // Check if they can afford the hold charge
+                if balance < hold_charge {
+                    println!("{}", format!("⚠️ Cannot afford to hold {} reels (costs ${})", holds.len(), hold_charge).red());
+                    println!("Continuing without holds...");
+                    return Vec::new();
+                }
+                println!("{}", format!("Hold charge: ${} for {} reel(s)", hold_charge, holds.len()).yellow());
             }
-        }
-        
-        // Calculate total bet (for payout calculation)
-        let multiplier = 1.0 + 0.25 * held_count as f64;
-        let final_bet = bet * multiplier;
+            holds
+        });
 
         // Show result of second spin
         println!("\n{}", "🎰 Second Spin 🎰".bright_cyan().bold());
-        display_payout_table(&symbol_probs, bet);
 
         // Check if user holds then animate if so
-        if held_count > 0 {
-            
+        if !outcome.holds.is_empty() {
             for _ in 0..30 {
-                print!("\r{} | {} | {} | {} | {}", 
-                    if held[0] { reels[0] } else { rng.weighted_choice(&weighted_symbols).unwrap() },
-                    if held[1] { reels[1] } else { rng.weighted_choice(&weighted_symbols).unwrap() },
-                    if held[2] { reels[2] } else { rng.weighted_choice(&weighted_symbols).unwrap() },
-                    if held[3] { reels[3] } else { rng.weighted_choice(&weighted_symbols).unwrap() },
-                    if held[4] { reels[4] } else { rng.weighted_choice(&weighted_symbols).unwrap() }
-                );
+                let frame: Vec<&str> = outcome.grid[0].iter().enumerate()
+                    .map(|(i, reel)| if outcome.holds.contains(&i) { reel.as_str() } else { *display_rng.weighted_choice(&weighted).unwrap() })
+                    .collect();
+                print!("\r{}", frame.join(" | "));
                 io::stdout().flush().ok();
                 std::thread::sleep(std::time::Duration::from_millis(50));
             }
-
-            // Second spin - use cryptographic RNG for non-held reels
-            for i in 0..5 {
-                if !held[i] {
-                    reels[i] = rng.weighted_choice(&weighted_symbols).unwrap();
-                }
-            }
         }
         // Show final results
-        println!("\r{} | {} | {} | {} | {}", reels[0], reels[1], reels[2], reels[3], reels[4]);
+        println!("\r{}", outcome.grid[0].join(" | "));
+
+        outcome
+    }
+
+    fn simulate_round(&self, rng: &mut CasinoRng, bet: Money) -> RoundOutcome {
+        resolve(&self.symbol_probs, rng, bet, |reels| auto_holds(&self.symbol_probs, reels))
+    }
+
+    fn replay_round(&self, rng: &mut CasinoRng, bet: Money, holds: &[usize]) -> RoundOutcome {
+        resolve(&self.symbol_probs, rng, bet, |_| holds.to_vec())
+    }
+
+    fn exact_outcomes(&self) -> Vec<OutcomeClass> {
+        exact_outcomes(&self.symbol_probs)
+    }
 
-        // Simple win check (3+ of a kind)
-        let mut win_map = std::collections::HashMap::new();
-        for &symbol in &reels {
-            *win_map.entry(symbol).or_insert(0) += 1;
+    // The first spin is only kept when the strategy holds nothing, which depends on what it shows,
+    // so only reels respun after a hold are unbiased draws
+    fn observed_symbols(&self, outcome: &RoundOutcome) -> Vec<String> {
+        if outcome.holds.is_empty() {
+            return Vec::new();
         }
-        
-        // Find the winning symbol to get its multiplier
-        let (winning_symbol, max_count) = win_map.iter()
-            .max_by_key(|(_, &count)| count)
-            .map(|(sym, &count)| (*sym, count))
-            .unwrap_or((&"", 1));
-        
-        // Get multiplier from database for winning symbol
-        let base_multiplier = symbol_probs.iter()
-            .find(|(sym, _, _)| sym.as_str() == winning_symbol)
-            .map(|(_, _, mult)| *mult)
-            .unwrap_or(2.0); // Fallback to 2.0 if not found
-        
-        // Calculate payout based on match count and symbol multiplier
-        let payout = match max_count {
-            5 => base_multiplier * 5.0 * final_bet, // 5 of a kind: 5x multiplier
-            4 => base_multiplier * 2.5 * final_bet, // 4 of a kind: 2.5x multiplier
-            3 => base_multiplier * final_bet,       // 3 of a kind: 1x multiplier
-            _ => 0.0,
-        };
-
-        if payout > 0.0 {
-            // WIN - deposit winnings (bets already deducted)
-            let final_balance = dbqueries::transaction(conn, user, payout);
-            
+        outcome.grid[0].iter().enumerate()
+            .filter(|(i, _)| !outcome.holds.contains(i))
+            .map(|(_, symbol)| symbol.clone())
+            .collect()
+    }
+
+    fn outcome_labels(&self) -> OutcomeLabels {
+        OutcomeLabels { win: "Wins (3+ of a kind)", partial: None, loss: "Losses" }
+    }
+
+    fn show_result(&self, outcome: &RoundOutcome, bet: Money, balance: Money) {
+        let final_bet = outcome.total_wager(bet);
+
+        if outcome.payout.is_positive() {
+            let (winning_symbol, max_count) = best_match(&outcome.grid[0]);
+            let base_multiplier = base_multiplier(&self.symbol_probs, winning_symbol);
+
             let multiplier_text = match max_count {
                 5 => format!("{:.1}x (5 of a kind)", base_multiplier * 5.0),
                 4 => format!("{:.1}x (4 of a kind)", base_multiplier * 2.5),
                 3 => format!("{:.1}x (3 of a kind)", base_multiplier),
                 _ => String::from("0x"),
             };
-            
+
             println!("\n{}", "═══════════════════════════════════════".green().bold());
             println!("{}", "         🎉 YOU WIN! 🎉                ".green().bold());
             println!("{}", "═══════════════════════════════════════".green().bold());
             println!("\n{} {} {} symbols!", "Result:".bright_white().bold(), max_count, winning_symbol);
-            println!("{} ${:.2} × {} = ${:.2}", "Payout:".bright_white().bold(), final_bet, multiplier_text, payout);
-            println!("{} ${:.2}", "Balance:".bright_white().bold(), final_balance);
+            println!("{} ${} × {} = ${}", "Payout:".bright_white().bold(), final_bet, multiplier_text, outcome.payout);
+            println!("{} ${}", "Balance:".bright_white().bold(), balance);
             println!();
-            let _ = dbqueries::add_win(conn, "holding");
-            let _ = dbqueries::add_user_win(conn, user, "holding", payout);
-
         } else {
             // LOSS - bets already deducted, no winnings
             println!("\n{}", "═══════════════════════════════════════".red());
             println!("{}", "           ❌ NO WIN ❌                 ".red().bold());
             println!("{}", "═══════════════════════════════════════".red());
             println!("\n{}  No matching symbols", "Result:".bright_white().bold());
-            println!("{} ${:.2}", "Lost:".bright_white().bold(), final_bet);
-            println!("{} ${:.2}", "Balance:".bright_white().bold(), current_balance);
+            println!("{} ${}", "Lost:".bright_white().bold(), final_bet);
+            println!("{} ${}", "Balance:".bright_white().bold(), balance);
             println!();
-            let _ = dbqueries::add_loss(conn, "holding");
-            let _ = dbqueries::add_user_loss(conn, user, "holding");
-
         }
+    }
+}
 
-        // Show options to user
-        let menu_options = vec!["Spin Again", "Change Bet", "Exit"];
-        let user_input = menus::menu_generator("═══ 🎰 Play Again? 🎰 ═══", &menu_options);
+// Spin the five reels, ask `choose_holds` which reels to keep, respin the rest and score the result.
+// `choose_holds` sees the first spin and returns reel indexes (0-4); invalid and surplus holds are dropped.
+// The second spin is only bought by holding, so with no holds the first spin stands.
+// Pure apart from drawing from `rng` and whatever `choose_holds` does.
+pub fn resolve<F>(symbol_probs: &[(String, usize, f64)], rng: &mut CasinoRng, bet: Money, choose_holds: F) -> RoundOutcome
+where
+    F: FnOnce(&[String]) -> Vec<usize>,
+{
+    let weighted = weighted_symbols(symbol_probs);
+    let mut reels: Vec<String> = (0..REELS)
+        .map(|_| rng.weighted_choice(&weighted).unwrap().to_string())
+        .collect();
 
-        match user_input.trim() {
-            "Spin Again" => {
-                logger::info(&format!("User ID: {} continuing with same bet", user.id));
-                continue;
-            }
-            "Change Bet" => {
-                logger::info(&format!("User ID: {} changing bet", user.id));
-                return true;
-            }
-            "Exit" => {
-                logger::info(&format!("User ID: {} exiting slots game", user.id));
-                return false;
-            }
-            _ => {
-                logger::info(&format!("User ID: {} made invalid selection, continuing game", user.id));
-                println!("Playing again..."); 
-                continue;
+    let mut holds = choose_holds(&reels);
+    holds.sort_unstable();
+    holds.dedup();
+    holds.retain(|&i| i < REELS);
+    holds.truncate(MAX_HOLDS);
+
+    // Second spin - respin every reel that was not held
+    if !holds.is_empty() {
+        for (i, reel) in reels.iter_mut().enumerate() {
+            if !holds.contains(&i) {
+                *reel = rng.weighted_choice(&weighted).unwrap().to_string();
             }
         }
     }
+
+    evaluate(symbol_probs, reels, holds, bet)
+}
+
+// Enumerate every first spin, apply the automatic hold strategy and, when it holds,
+// every second spin of the free reels, scoring each with the game's own rules.
+pub fn exact_outcomes(symbol_probs: &[(String, usize, f64)]) -> Vec<OutcomeClass> {
+    let probs = theory::probabilities(symbol_probs);
+    let symbol = |i: usize| symbol_probs[i].0.clone();
+    let mut classes = Vec::new();
+    // Scoring only depends on symbol counts, so the second spin distribution is the same
+    // for every first spin that holds the same symbols
+    let mut second_spins: Vec<(Vec<String>, Vec<OutcomeClass>)> = Vec::new();
+
+    theory::for_each_combination(symbol_probs.len(), REELS, |first| {
+        let first_probability: f64 = first.iter().map(|&i| probs[i]).product();
+        let reels: Vec<String> = first.iter().map(|&i| symbol(i)).collect();
+        let holds = auto_holds(symbol_probs, &reels);
+
+        if holds.is_empty() {
+            let outcome = evaluate(symbol_probs, reels, Vec::new(), theory::THEORY_BET);
+            theory::add_outcome(&mut classes, outcome_label(&outcome), first_probability, theory::in_base_bets(outcome.payout), 1.0);
+            return;
+        }
+
+        let held: Vec<String> = holds.iter().map(|&i| reels[i].clone()).collect();
+        if !second_spins.iter().any(|(key, _)| *key == held) {
+            let mut results = Vec::new();
+            theory::for_each_combination(symbol_probs.len(), REELS - held.len(), |respin| {
+                let probability: f64 = respin.iter().map(|&i| probs[i]).product();
+                let final_reels = held.iter().cloned().chain(respin.iter().map(|&i| symbol(i))).collect();
+                let outcome = evaluate(symbol_probs, final_reels, (0..held.len()).collect(), theory::THEORY_BET);
+                let label = format!("{} ({} held)", outcome_label(&outcome), held.len());
+                theory::add_outcome(
+                    &mut results, label, probability,
+                    theory::in_base_bets(outcome.payout), theory::in_base_bets(outcome.total_wager(theory::THEORY_BET))
+                );
+            });
+            second_spins.push((held.clone(), results));
+        }
+
+        let (_, results) = second_spins.iter().find(|(key, _)| *key == held).unwrap();
+        for class in results {
+            theory::add_outcome(&mut classes, class.label.clone(), first_probability * class.probability, class.payout, class.wager);
+        }
+    });
+
+    classes
+}
+
+// Automatic hold strategy used by the fairness simulator:
+// keep a first spin that already wins, otherwise hold the best-paying pair, otherwise hold nothing.
+pub fn auto_holds(symbol_probs: &[(String, usize, f64)], reels: &[String]) -> Vec<usize> {
+    let (_, max_count) = best_match(reels);
+    if max_count != 2 {
+        return Vec::new();
+    }
+
+    let best_pair = reels.iter()
+        .filter(|symbol| reels.iter().filter(|other| other == symbol).count() == 2)
+        .max_by(|a, b| base_multiplier(symbol_probs, a).total_cmp(&base_multiplier(symbol_probs, b)));
+
+    match best_pair {
+        Some(symbol) => reels.iter().enumerate()
+            .filter(|(_, reel)| *reel == symbol)
+            .map(|(i, _)| i)
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+// Score the final reels. Hold charges are added to the stake the multiplier applies to.
+fn evaluate(symbol_probs: &[(String, usize, f64)], reels: Vec<String>, holds: Vec<usize>, bet: Money) -> RoundOutcome {
+    let extra_wager = hold_charge(bet, holds.len());
+    let (winning_symbol, max_count) = best_match(&reels);
+    let base_multiplier = base_multiplier(symbol_probs, winning_symbol);
+
+    // Calculate payout based on match count and symbol multiplier
+    let multiplier = match max_count {
+        5 => base_multiplier * 5.0, // 5 of a kind: 5x multiplier
+        4 => base_multiplier * 2.5, // 4 of a kind: 2.5x multiplier
+        3 => base_multiplier,       // 3 of a kind: 1x multiplier
+        _ => 0.0,
+    };
+    let lines = if multiplier > 0.0 {
+        vec![format!("{} of a kind: {}", max_count, winning_symbol)]
+    } else {
+        Vec::new()
+    };
+
+    RoundOutcome {
+        grid: vec![reels],
+        holds,
+        lines,
+        multiplier,
+        extra_wager,
+        payout: (bet + extra_wager).times(multiplier),
+    }
+}
+
+// Price of holding `holds` reels, rounded to the cent like a payout
+fn hold_charge(bet: Money, holds: usize) -> Money {
+    bet.times(HOLD_COST * holds as f64)
+}
+
+// Most frequent symbol on the reels and how many times it shows
+fn best_match(reels: &[String]) -> (&str, usize) {
+    let mut win_map = std::collections::HashMap::new();
+    for symbol in reels {
+        *win_map.entry(symbol.as_str()).or_insert(0) += 1;
+    }
+
+    win_map.into_iter()
+        .max_by_key(|&(_, count)| count)
+        .unwrap_or(("", 1))
+}
+
+// Get multiplier from database for winning symbol
+fn base_multiplier(symbol_probs: &[(String, usize, f64)], symbol: &str) -> f64 {
+    symbol_probs.iter()
+        .find(|(sym, _, _)| sym.as_str() == symbol)
+        .map(|(_, _, mult)| *mult)
+        .unwrap_or(2.0) // Fallback to 2.0 if not found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_paytable() -> Vec<(String, usize, f64)> {
+        vec![
+            ("🍒".to_string(), 10, 1.0),
+            ("🍋".to_string(), 10, 2.0),
+            ("💎".to_string(), 10, 4.0),
+        ]
+    }
+
+    fn reels(symbols: [&str; 5]) -> Vec<String> {
+        symbols.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_evaluate_five_of_a_kind() {
+        let outcome = evaluate(&test_paytable(), reels(["💎"; 5]), Vec::new(), Money::from_dollars(10));
+        assert_eq!(outcome.multiplier, 20.0);
+        assert_eq!(outcome.payout, Money::from_dollars(200));
+        assert_eq!(outcome.lines, vec!["5 of a kind: 💎".to_string()]);
+    }
+
+    #[test]
+    fn test_evaluate_hold_charge_is_part_of_stake() {
+        let outcome = evaluate(&test_paytable(), reels(["🍋", "🍋", "🍋", "🍒", "💎"]), vec![0, 1], Money::from_dollars(10));
+        assert_eq!(outcome.extra_wager, Money::from_dollars(5));
+        assert_eq!(outcome.multiplier, 2.0);
+        assert_eq!(outcome.payout, Money::from_dollars(30));
+        assert_eq!(outcome.total_wager(Money::from_dollars(10)), Money::from_dollars(15));
+    }
+
+    #[test]
+    fn test_evaluate_pair_loses() {
+        let outcome = evaluate(&test_paytable(), reels(["🍋", "🍋", "🍒", "🍒", "💎"]), vec![0], Money::from_dollars(10));
+        assert_eq!(outcome.payout, Money::ZERO);
+        assert!(outcome.lines.is_empty());
+        assert_eq!(outcome.extra_wager, Money::from_cents(250));
+    }
+
+    #[test]
+    fn test_resolve_keeps_held_reels() {
+        let paytable = test_paytable();
+        let mut first_spin = Vec::new();
+        let outcome = resolve(&paytable, &mut CasinoRng::seeded(3), Money::from_dollars(4), |reels| {
+            first_spin = reels.to_vec();
+            vec![1, 3]
+        });
+
+        assert_eq!(outcome.holds, vec![1, 3]);
+        assert_eq!(outcome.grid[0][1], first_spin[1]);
+        assert_eq!(outcome.grid[0][3], first_spin[3]);
+        assert_eq!(outcome.extra_wager, Money::from_dollars(2));
+    }
+
+    #[test]
+    fn test_resolve_without_holds_keeps_first_spin() {
+        let paytable = test_paytable();
+        let mut first_spin = Vec::new();
+        let outcome = resolve(&paytable, &mut CasinoRng::seeded(11), Money::from_dollars(4), |reels| {
+            first_spin = reels.to_vec();
+            Vec::new()
+        });
+
+        assert_eq!(outcome.grid[0], first_spin);
+        assert_eq!(outcome.extra_wager, Money::ZERO);
+    }
+
+    #[test]
+    fn test_auto_holds_keeps_a_winning_first_spin() {
+        let holds = auto_holds(&test_paytable(), &reels(["🍋", "🍋", "🍋", "🍒", "💎"]));
+        assert!(holds.is_empty());
+    }
+
+    #[test]
+    fn test_auto_holds_picks_best_paying_pair() {
+        let holds = auto_holds(&test_paytable(), &reels(["🍒", "💎", "🍒", "🍋", "💎"]));
+        assert_eq!(holds, vec![1, 4]);
+    }
+
+    #[test]
+    fn test_auto_holds_nothing_without_a_pair() {
+        let holds = auto_holds(&test_paytable(), &reels(["🍒", "💎", "🍋", "🔔", "⭐"]));
+        assert!(holds.is_empty());
+    }
+
+    #[test]
+    fn test_exact_outcomes_cover_every_round() {
+        let classes = exact_outcomes(&test_paytable());
+        let total: f64 = classes.iter().map(|c| c.probability).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        // Holding two reels costs half a bet on top of the base bet
+        assert!(classes.iter().filter(|c| c.label.contains("held")).all(|c| c.wager == 1.5));
+    }
+
+    #[test]
+    fn test_resolve_drops_invalid_and_extra_holds() {
+        let outcome = resolve(&test_paytable(), &mut CasinoRng::seeded(5), Money::from_dollars(4), |_| vec![4, 4, 9, 0, 2]);
+        assert_eq!(outcome.holds, vec![0, 2]);
+    }
 }
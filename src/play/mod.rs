@@ -1,4 +1,8 @@
+pub mod game;
+pub mod engine;
+pub mod simulator;
+pub mod theory;
 pub mod slots;
 pub mod multiwin;
 pub mod holding;
-pub mod wheelOfFortune;
\ No newline at end of file
+pub mod wheel_of_fortune;
@@ -1,32 +1,32 @@
 use std::thread;
 use std::time::Duration;
-use clearscreen;
-use rusqlite::{Connection};
-use crate::interfaces::user::User;
+use rusqlite::Connection;
 use crate::db::dbqueries;
-use crate::logger::logger;
+use crate::db::money::Money;
 use crate::cryptography::rng::CasinoRng;
+use crate::play::game::{Game, OutcomeLabels, RoundOutcome};
+use crate::play::theory::{self, OutcomeClass};
 use colored::*;
 
 use crate::interfaces::menus;
 
 // Display payout table to user before playing
-fn display_payout_table(symbol_probs: &[(String, usize, f64)], bet: f64) {
+fn display_payout_table(symbol_probs: &[(String, usize, f64)], bet: Money) {
     // Calculate average multiplier
     let base_multiplier: f64 = symbol_probs.iter()
         .map(|(_, _, mult)| mult)
         .sum::<f64>() / symbol_probs.len() as f64;
     
-    let single_win = bet * base_multiplier;
-    let double_jackpot = bet * base_multiplier * 2.0;
+    let single_win = bet.times(base_multiplier);
+    let double_jackpot = bet.times(base_multiplier * 2.0);
     
     menus::print_box_top(50);
     menus::print_box_line("💰 PAYOUT TABLE 💰", 48);
     menus::print_box_line("Match any ROW, COLUMN, or DIAGONAL:", 50);
-    menus::print_box_line(&format!("  Regular Win:     ${:<6.2} ({:.1}x)", single_win, base_multiplier), 50);
+    menus::print_box_line(&format!("  Regular Win:     ${:<6} ({:.1}x)", single_win, base_multiplier), 50);
     menus::print_box_separator(50);
     menus::print_box_line("Match ANY ROW + FOUR CORNERS:", 50);
-    menus::print_box_line(&format!("  Double Jackpot:  ${:<6.2} ({:.1}x)", double_jackpot, base_multiplier * 2.0), 50);
+    menus::print_box_line(&format!("  Double Jackpot:  ${:<6} ({:.1}x)", double_jackpot, base_multiplier * 2.0), 50);
     menus::print_box_separator(50);
     menus::print_box_line("Symbols in play:", 50);
     
@@ -44,8 +44,6 @@ fn display_payout_table(symbol_probs: &[(String, usize, f64)], bet: f64) {
     println!();
 }
 
-// CRITICAL: check grid size is used or not and adjust and fix it
-// CRITICAL: implement rng here
 const GRID_SIZE: usize = 5;
 
 type Grid = [[char; GRID_SIZE]; GRID_SIZE];
@@ -56,158 +54,274 @@ struct WinCheckResults {
     has_four_corner_win: bool,
 }
 
-pub fn multi_win(conn: &Connection, user: &User, bet: f64) -> bool{
-    // Load symbol probabilities from database
-    let symbol_probs = match dbqueries::get_symbol_probabilities(conn, "multi") {
-        Ok(probs) => probs,
-        Err(e) => {
-            logger::error(&format!("Failed to load symbol probabilities: {}", e));
-            println!("{}", "Error loading game configuration".red());
-            return true;
-        }
-    };
-    
-    // Extract symbols for grid
-    let symbols: Vec<char> = symbol_probs.iter()
-        .map(|(sym, _, _)| sym.chars().next().unwrap())
-        .collect();
-    
-    let mut rng = CasinoRng::new();
-    
-    println!("\n{}", "═══ 🎰 Welcome to 5x5 Multi-Win Slots! 🎰 ═══".bright_yellow().bold());
-    println!("{}", "Win by matching any row, column, or diagonal!".bright_cyan());
-    println!("{} ${:.2}\n", "Your bet:".bright_white().bold(), bet);
-    
+// 5x5 multi-win slots: any row, column or diagonal of matching symbols pays
+#[derive(Default)]
+pub struct MultiWin {
+    symbol_probs: Vec<(String, usize, f64)>,
+}
 
-    loop {
-        // Check if player has the funds
-        if !dbqueries::check_funds(conn, user, bet as f64) {
-            logger::warning(&format!("User ID: {} has insufficient funds for bet: ${:.2}", user.id, bet));
-            println!("{}", "Insufficient funds!".red().bold());
-            return true;
-        }
+// Extract weighted symbols for grid
+fn symbols(symbol_probs: &[(String, usize, f64)]) -> Vec<(char, usize)> {
+    symbol_probs.iter()
+        .map(|(sym, weight, _)| (sym.chars().next().unwrap(), *weight))
+        .collect()
+}
+
+// Calculate base multiplier from database (average of all symbols)
+fn base_multiplier(symbol_probs: &[(String, usize, f64)]) -> f64 {
+    symbol_probs.iter()
+        .map(|(_, _, mult)| mult)
+        .sum::<f64>() / symbol_probs.len() as f64
+}
 
-        // CHARGE BET FIRST before playing
-        logger::transaction(&format!("User ID: {} placing bet of ${:.2} for multi-win slots", user.id, bet));
-        let balance_after_bet = dbqueries::transaction(conn, user, -bet);
-        
-        if balance_after_bet < 0.0 {
-            println!("{}", "Transaction failed!".red().bold());
-            return true;
+// Rebuild the character grid from a round outcome
+fn grid_from_outcome(outcome: &RoundOutcome) -> Grid {
+    let mut grid = [[' '; GRID_SIZE]; GRID_SIZE];
+    for (r, row) in outcome.grid.iter().enumerate().take(GRID_SIZE) {
+        for (c, symbol) in row.iter().enumerate().take(GRID_SIZE) {
+            grid[r][c] = symbol.chars().next().unwrap_or(' ');
         }
-        
-        println!("{}", format!("Bet placed: ${:.2}", bet).yellow());
-        println!("{}", format!("Balance: ${:.2}", balance_after_bet).bright_white());
+    }
+    grid
+}
+
+impl Game for MultiWin {
+    fn name(&self) -> &'static str {
+        "multi"
+    }
+
+    fn load_config(&mut self, conn: &Connection) -> rusqlite::Result<()> {
+        // Load symbol probabilities from database
+        self.symbol_probs = dbqueries::get_symbol_probabilities(conn, self.name())?;
+        Ok(())
+    }
+
+    fn paytable(&self) -> &[(String, usize, f64)] {
+        &self.symbol_probs
+    }
+
+    fn set_paytable(&mut self, paytable: Vec<(String, usize, f64)>) {
+        self.symbol_probs = paytable;
+    }
+
+    fn show_welcome(&self) {
+        println!("\n{}", "═══ 🎰 Welcome to 5x5 Multi-Win Slots! 🎰 ═══".bright_yellow().bold());
+        println!("{}", "Win by matching any row, column, or diagonal!".bright_cyan());
+    }
+
+    fn show_paytable(&self, bet: Money) {
+        display_payout_table(&self.symbol_probs, bet);
+    }
+
+    fn play_round(&self, rng: &mut CasinoRng, bet: Money, _balance: Money) -> RoundOutcome {
+        let outcome = resolve(&self.symbol_probs, rng, bet);
 
-        //spinning animation
-        run_spin_animation(&mut rng, &symbols);
-        // CRITICAL: double check here  
-        //grid after the animation
-        let grid = spin(&mut rng, &symbols);
+        //spinning animation, drawn separately from the round itself
+        run_spin_animation(&mut CasinoRng::new(), &symbols(&self.symbol_probs));
 
         //final result
         clearscreen::clear().expect("Failed to clear screen");
         println!("...And the result!\n");
-        print_grid(&grid);
+        print_grid(&grid_from_outcome(&outcome));
 
-        // Display payout table to user
-        display_payout_table(&symbol_probs, bet);
+        outcome
+    }
+
+    fn simulate_round(&self, rng: &mut CasinoRng, bet: Money) -> RoundOutcome {
+        resolve(&self.symbol_probs, rng, bet)
+    }
+
+    fn exact_outcomes(&self) -> Vec<OutcomeClass> {
+        exact_outcomes(&self.symbol_probs)
+    }
+
+    // The grid only keeps the first character of each symbol, map it back to the paytable
+    fn observed_symbols(&self, outcome: &RoundOutcome) -> Vec<String> {
+        outcome.grid.iter().flatten()
+            .map(|cell| {
+                self.symbol_probs.iter()
+                    .find(|(sym, _, _)| sym.starts_with(cell.as_str()))
+                    .map(|(sym, _, _)| sym.clone())
+                    .unwrap_or_else(|| cell.clone())
+            })
+            .collect()
+    }
+
+    fn outcome_labels(&self) -> OutcomeLabels {
+        OutcomeLabels { win: "Wins (any line match)", partial: None, loss: "Losses" }
+    }
 
-        //check dor wins
-        let win_results = check_wins(&grid);
+    fn show_result(&self, outcome: &RoundOutcome, bet: Money, balance: Money) {
+        let win_results = check_wins(&grid_from_outcome(outcome));
 
         //show to user for win or lose
-        if win_results.win_descriptions.is_empty() {
+        if outcome.lines.is_empty() {
             // Loss - bet already deducted, no winnings
             println!("\n{}", "═══════════════════════════════════════".red());
             println!("{}", "           ❌ NO WIN ❌                 ".red().bold());
             println!("{}", "═══════════════════════════════════════".red());
             println!("\n{}  No matching lines found", "Result:".bright_white().bold());
-            println!("{} ${:.2}", "Lost:".bright_white().bold(), bet);
-            println!("{} ${:.2}", "Balance:".bright_white().bold(), balance_after_bet);
+            println!("{} ${}", "Lost:".bright_white().bold(), bet);
+            println!("{} ${}", "Balance:".bright_white().bold(), balance);
+            println!();
+        } else if win_results.has_horizontal_win && win_results.has_four_corner_win {
+            println!("\n{}", "═══════════════════════════════════════".green().bold());
+            println!("{}", "      💥 DOUBLE JACKPOT! 💥            ".green().bold());
+            println!("{}", "═══════════════════════════════════════".green().bold());
+            println!("\n{}  Horizontal + Four Corners!", "Result:".bright_white().bold());
+            println!("{} ${} × {:.1}x = ${}", "Payout:".bright_white().bold(), bet, outcome.multiplier, outcome.payout);
+            println!("{} ${}", "Balance:".bright_white().bold(), balance);
             println!();
-            let _ = dbqueries::add_loss(conn, "holding");
-            let _ = dbqueries::add_user_loss(conn, user, "multi");
         } else {
-            // ADDED BY SUCA
-            // Calculate base multiplier from database (average of all symbols)
-            let base_multiplier: f64 = symbol_probs.iter()
-                .map(|(_, _, mult)| mult)
-                .sum::<f64>() / symbol_probs.len() as f64;
-            //////////////////////////////////////////////////////////////
-            
-            // check for Double Jackpot condition first
-            if win_results.has_horizontal_win && win_results.has_four_corner_win {
-                // REMOVED BY SUCA
-                //let winnings = bet * 4.0;
-                //////////////////////////////////////////////////////////////
-                // ADDED BY SUCA INSTEAD
-                let payout_multiplier = base_multiplier * 2.0; // Double jackpot
-                let winnings = bet * payout_multiplier;
-                //////////////////////////////////////////////////////////////
-                
-                // DEPOSIT WINNINGS
-                let final_balance = dbqueries::transaction(conn, user, winnings);
-                
-                println!("\n{}", "═══════════════════════════════════════".green().bold());
-                println!("{}", "      💥 DOUBLE JACKPOT! 💥            ".green().bold());
-                println!("{}", "═══════════════════════════════════════".green().bold());
-                println!("\n{}  Horizontal + Four Corners!", "Result:".bright_white().bold());
-                println!("{} ${:.2} × {:.1}x = ${:.2}", "Payout:".bright_white().bold(), bet, payout_multiplier, winnings);
-                println!("{} ${:.2}", "Balance:".bright_white().bold(), final_balance);
-                println!();
-                let _ = dbqueries::add_win(conn, "multi");
-                let _ = dbqueries::add_user_win(conn, user, "multi", winnings);
-            } else {
-                let payout_multiplier = base_multiplier;
-                let winnings = bet * payout_multiplier;
-                
-                // DEPOSIT WINNINGS
-                let final_balance = dbqueries::transaction(conn, user, winnings);
-                
-                println!("\n{}", "═══════════════════════════════════════".green().bold());
-                println!("{}", "         🎉 JACKPOT! 🎉                ".green().bold());
-                println!("{}", "═══════════════════════════════════════".green().bold());
-                println!();
-                for win_line in &win_results.win_descriptions {
-                    println!("  ✓ {}", win_line.bright_cyan());
-                }
-                println!("\n{} ${:.2} × {:.1}x = ${:.2}", "Payout:".bright_white().bold(), bet, payout_multiplier, winnings);
-                println!("{} ${:.2}", "Balance:".bright_white().bold(), final_balance);
-                println!();
-                let _ = dbqueries::add_win(conn, "holding");
-                let _ = dbqueries::add_user_win(conn, user, "holding", winnings);
+            println!("\n{}", "═══════════════════════════════════════".green().bold());
+            println!("{}", "         🎉 JACKPOT! 🎉                ".green().bold());
+            println!("{}", "═══════════════════════════════════════".green().bold());
+            println!();
+            for win_line in &outcome.lines {
+                println!("  ✓ {}", win_line.bright_cyan());
             }
+            println!("\n{} ${} × {:.1}x = ${}", "Payout:".bright_white().bold(), bet, outcome.multiplier, outcome.payout);
+            println!("{} ${}", "Balance:".bright_white().bold(), balance);
+            println!();
         }
+    }
+}
 
-        // Show options to user
-        let menu_options = vec!["Spin Again", "Change Bet", "Exit"];
-        let user_input = menus::menu_generator("═══ 🎰 Play Again? 🎰 ═══", &menu_options);
+// Spin the 5x5 grid and score every row, column, diagonal and the four corners.
+// Pure: no printing or sleeping, the only side effect is drawing from `rng`.
+pub fn resolve(symbol_probs: &[(String, usize, f64)], rng: &mut CasinoRng, bet: Money) -> RoundOutcome {
+    let grid = spin(rng, &symbols(symbol_probs));
+    evaluate(symbol_probs, &grid, bet)
+}
 
-        match user_input.trim() {
-            "Spin Again" => {
-                logger::info(&format!("User ID: {} continuing with same bet", user.id));
-                continue;
-            }
-            "Change Bet" => {
-                logger::info(&format!("User ID: {} changing bet", user.id));
-                return true;
-            }
-            "Exit" => {
-                logger::info(&format!("User ID: {} exiting slots game", user.id));
-                return false;
-            }
-            _ => {
-                logger::info(&format!("User ID: {} made invalid selection, continuing game", user.id));
-                println!("Playing again..."); 
-                continue;
-            }
+// Cells (row * GRID_SIZE + column) of every pattern check_wins pays for:
+// rows first, then columns, both diagonals and finally the four corners
+fn win_patterns() -> Vec<Vec<usize>> {
+    let last_idx = GRID_SIZE - 1;
+    let mut patterns = Vec::new();
+    for r in 0..GRID_SIZE {
+        patterns.push((0..GRID_SIZE).map(|c| r * GRID_SIZE + c).collect());
+    }
+    for c in 0..GRID_SIZE {
+        patterns.push((0..GRID_SIZE).map(|r| r * GRID_SIZE + c).collect());
+    }
+    patterns.push((0..GRID_SIZE).map(|i| i * GRID_SIZE + i).collect());
+    patterns.push((0..GRID_SIZE).map(|i| i * GRID_SIZE + last_idx - i).collect());
+    patterns.push(vec![0, last_idx, last_idx * GRID_SIZE, last_idx * GRID_SIZE + last_idx]);
+    patterns
+}
+
+// Probability that every pattern picked by `mask` is a single symbol.
+// Overlapping patterns merge into groups of cells that must all match; separate groups are independent.
+fn all_match_probability(patterns: &[Vec<usize>], mask: u32, probs: &[f64]) -> f64 {
+    let mut group: Vec<usize> = (0..GRID_SIZE * GRID_SIZE).collect();
+    fn root(group: &mut [usize], mut cell: usize) -> usize {
+        while group[cell] != cell {
+            group[cell] = group[group[cell]];
+            cell = group[cell];
         }
+        cell
+    }
+
+    let mut covered = [false; GRID_SIZE * GRID_SIZE];
+    for (p, pattern) in patterns.iter().enumerate() {
+        if mask & (1 << p) == 0 {
+            continue;
+        }
+        for &cell in pattern {
+            covered[cell] = true;
+            let (a, b) = (root(&mut group, pattern[0]), root(&mut group, cell));
+            group[b] = a;
+        }
+    }
+
+    let mut sizes = [0; GRID_SIZE * GRID_SIZE];
+    for cell in 0..GRID_SIZE * GRID_SIZE {
+        if covered[cell] {
+            sizes[root(&mut group, cell)] += 1;
+        }
+    }
+
+    sizes.iter()
+        .filter(|&&size| size > 0)
+        .map(|&size| probs.iter().map(|p| p.powi(size)).sum::<f64>())
+        .product()
+}
+
+// Probability that at least one of the patterns in `candidates` matches, restricted to rounds
+// where every pattern in `required` also matches (inclusion-exclusion over the candidates)
+fn any_match_probability(patterns: &[Vec<usize>], candidates: &[usize], required: u32, probs: &[f64]) -> f64 {
+    (1..1u32 << candidates.len())
+        .map(|subset| {
+            let mask = candidates.iter().enumerate()
+                .filter(|(bit, _)| subset & (1 << bit) != 0)
+                .fold(required, |mask, (_, &p)| mask | (1 << p));
+            let sign = if subset.count_ones() % 2 == 1 { 1.0 } else { -1.0 };
+            sign * all_match_probability(patterns, mask, probs)
+        })
+        .sum()
+}
+
+// Exact outcome classes for the 5x5 grid. The grid has too many combinations to enumerate,
+// so the win probabilities come from inclusion-exclusion over the winning patterns.
+pub fn exact_outcomes(symbol_probs: &[(String, usize, f64)]) -> Vec<OutcomeClass> {
+    if symbol_probs.is_empty() {
+        return Vec::new();
+    }
+    // Patterns are told apart by their first character, like the grid itself
+    let mut probs: Vec<(char, f64)> = Vec::new();
+    for ((symbol, _), probability) in symbols(symbol_probs).into_iter().zip(theory::probabilities(symbol_probs)) {
+        match probs.iter_mut().find(|(s, _)| *s == symbol) {
+            Some((_, p)) => *p += probability,
+            None => probs.push((symbol, probability)),
+        }
+    }
+    let probs: Vec<f64> = probs.into_iter().map(|(_, p)| p).collect();
+
+    let patterns = win_patterns();
+    let corners = patterns.len() - 1;
+    let rows: Vec<usize> = (0..GRID_SIZE).collect();
+    let all: Vec<usize> = (0..patterns.len()).collect();
+
+    let any_win = any_match_probability(&patterns, &all, 0, &probs);
+    let double_jackpot = any_match_probability(&patterns, &rows, 1 << corners, &probs);
+    let base = base_multiplier(symbol_probs);
+    let pays = |multiplier: f64| theory::in_base_bets(theory::THEORY_BET.times(multiplier));
+
+    vec![
+        OutcomeClass { label: String::from("Double jackpot"), probability: double_jackpot, payout: pays(base * 2.0), wager: 1.0 },
+        OutcomeClass { label: String::from("Line win"), probability: any_win - double_jackpot, payout: pays(base), wager: 1.0 },
+        OutcomeClass { label: String::from("No win"), probability: 1.0 - any_win, payout: 0.0, wager: 1.0 },
+    ]
+}
+
+// Score a finished grid against the paytable
+fn evaluate(symbol_probs: &[(String, usize, f64)], grid: &Grid, bet: Money) -> RoundOutcome {
+    let cells = grid.iter().map(|row| row.iter().map(|c| c.to_string()).collect()).collect();
+    let win_results = check_wins(grid);
+
+    if win_results.win_descriptions.is_empty() {
+        return RoundOutcome::losing(cells);
+    }
+
+    let multiplier = if win_results.has_horizontal_win && win_results.has_four_corner_win {
+        base_multiplier(symbol_probs) * 2.0 // Double jackpot
+    } else {
+        base_multiplier(symbol_probs)
+    };
+
+    RoundOutcome {
+        grid: cells,
+        holds: Vec::new(),
+        lines: win_results.win_descriptions,
+        multiplier,
+        extra_wager: Money::ZERO,
+        payout: bet.times(multiplier),
     }
 }
 
  //spining animation
-fn run_spin_animation(rng: &mut CasinoRng, symbols: &[char]) {
+fn run_spin_animation(rng: &mut CasinoRng, symbols: &[(char, usize)]) {
     let animation_frames = 12; 
     let spin_delay_ms = 70; 
 
@@ -219,14 +333,12 @@ fn run_spin_animation(rng: &mut CasinoRng, symbols: &[char]) {
         thread::sleep(Duration::from_millis(spin_delay_ms));
     }
 }
-// CRITICAL:  using cryptographically secure RNG
-//creates 5 by 5 grid
-fn spin(rng: &mut CasinoRng, symbols: &[char]) -> Grid {
+//creates 5 by 5 grid using the commissioner-configured symbol weights
+fn spin(rng: &mut CasinoRng, symbols: &[(char, usize)]) -> Grid {
     let mut grid = [[' '; GRID_SIZE]; GRID_SIZE];
-    for r in 0..GRID_SIZE {
-        for c in 0..GRID_SIZE {
-            let symbol_index = rng.gen_range(0, symbols.len());
-            grid[r][c] = symbols[symbol_index];
+    for row in grid.iter_mut() {
+        for cell in row.iter_mut() {
+            *cell = *rng.weighted_choice(symbols).unwrap();
         }
     }
     grid
@@ -254,9 +366,9 @@ fn check_wins(grid: &Grid) -> WinCheckResults {
     let last_idx = GRID_SIZE - 1;
 
     //check if row is a win
-    for r in 0..GRID_SIZE {
-        let first = grid[r][0];
-        if (1..GRID_SIZE).all(|c| grid[r][c] == first) {
+    for (r, row) in grid.iter().enumerate() {
+        let first = row[0];
+        if row.iter().all(|&symbol| symbol == first) {
             wins.push(format!(
                 "Row {} win: {}",
                 r + 1,
@@ -269,7 +381,7 @@ fn check_wins(grid: &Grid) -> WinCheckResults {
     //check if column is a wil
     for c in 0..GRID_SIZE {
         let first = grid[0][c];
-        if (1..GRID_SIZE).all(|r| grid[r][c] == first) {
+        if grid.iter().all(|row| row[c] == first) {
             wins.push(format!(
                 "Column {} win: {}",
                 c + 1,
@@ -318,7 +430,6 @@ fn check_wins(grid: &Grid) -> WinCheckResults {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rand; // to use the rand crate 
 
     // Helper function to create a grid from a 2D vector of chars.
     fn grid_from_vec(vec: Vec<Vec<char>>) -> Grid {
@@ -340,7 +451,7 @@ mod tests {
             vec!['🍊', '🍋', '🔔', '⭐', '💎'],
             vec!['🍋', '🔔', '⭐', '💎', '🍒'],
             vec!['🔔', '⭐', '💎', '🍒', '🍊'],
-            vec!['⭐', '💎', '🍒', '🍊', '🍋'],
+            vec!['💎', '🍒', '🍊', '🍋', '🔔'],
         ]);
         let results = check_wins(&grid);
         assert!(results.win_descriptions.is_empty(), "Should be no wins");
@@ -444,4 +555,58 @@ mod tests {
         assert!(results.win_descriptions.iter().any(|s| s.contains("Row 2 win")));
         assert!(results.win_descriptions.iter().any(|s| s.contains("Four Corners win")));
     }
+
+    // Test pure round resolution
+    fn test_paytable() -> Vec<(String, usize, f64)> {
+        vec![
+            ("🍒".to_string(), 10, 2.0),
+            ("💎".to_string(), 10, 4.0),
+        ]
+    }
+
+    #[test]
+    fn test_evaluate_double_jackpot_pays_double() {
+        let grid = grid_from_vec(vec![
+            vec!['💎', '🍒', '🍒', '💎', '💎'],
+            vec!['🍒', '🍒', '🍒', '🍒', '🍒'],
+            vec!['🍒', '💎', '🍒', '💎', '💎'],
+            vec!['💎', '🍒', '💎', '🍒', '🍒'],
+            vec!['💎', '💎', '🍒', '🍒', '💎'],
+        ]);
+        let outcome = evaluate(&test_paytable(), &grid, Money::from_dollars(10));
+        assert_eq!(outcome.multiplier, 6.0);
+        assert_eq!(outcome.payout, Money::from_dollars(60));
+        assert_eq!(outcome.lines.len(), 2);
+    }
+
+    #[test]
+    fn test_evaluate_no_win_pays_nothing() {
+        let grid = grid_from_vec(vec![
+            vec!['🍒', '💎', '🍒', '💎', '💎'],
+            vec!['💎', '🍒', '💎', '🍒', '🍒'],
+            vec!['🍒', '🍒', '💎', '💎', '🍒'],
+            vec!['💎', '💎', '🍒', '🍒', '🍒'],
+            vec!['💎', '🍒', '💎', '🍒', '💎'],
+        ]);
+        let outcome = evaluate(&test_paytable(), &grid, Money::from_dollars(10));
+        assert!(outcome.lines.is_empty());
+        assert_eq!(outcome.payout, Money::ZERO);
+    }
+
+    #[test]
+    fn test_exact_outcomes_single_symbol_always_double() {
+        let paytable = vec![("🍒".to_string(), 10, 3.0)];
+        let classes = exact_outcomes(&paytable);
+        assert!((classes[0].probability - 1.0).abs() < 1e-12);
+        assert!(classes[1].probability.abs() < 1e-12);
+        assert!(classes[2].probability.abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_exact_row_probability_by_hand() {
+        // With two equal symbols a given row matches with probability 2 * (1/2)^5
+        let probs = [0.5, 0.5];
+        let p = all_match_probability(&win_patterns(), 1, &probs);
+        assert!((p - 1.0 / 16.0).abs() < 1e-12);
+    }
 }
@@ -0,0 +1,363 @@
+use crate::cryptography::rng::CasinoRng;
+use crate::db::money::Money;
+use crate::play::game::Game;
+use crate::statistics::distributions;
+
+// z-score for the 95% confidence intervals in fairness reports
+pub const CONFIDENCE_Z: f64 = 1.96;
+// Symbol frequencies with a chi-square p-value below this fail the fairness test
+pub const CHI_SQUARE_ALPHA: f64 = 0.01;
+
+// Totals from an automated run of a game
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SimulationStats {
+    pub rounds: u32,
+    // Paying rounds that are not partial wins
+    pub wins: u32,
+    // Paying rounds the game reports as partial (e.g. two of three reels)
+    pub partials: u32,
+    pub losses: u32,
+    // Everything wagered, hold charges included
+    pub total_bet: f64,
+    pub total_payout: f64,
+    // Per-round sums of squares and products, for the RTP confidence interval
+    pub payout_squares: f64,
+    pub bet_squares: f64,
+    pub payout_bet_products: f64,
+    // How often each symbol was drawn, in order of first appearance
+    pub symbol_counts: Vec<(String, u64)>,
+}
+
+impl SimulationStats {
+    // Return To Player as a percentage of everything wagered
+    pub fn rtp(&self) -> f64 {
+        if self.total_bet == 0.0 {
+            return 0.0;
+        }
+        (self.total_payout / self.total_bet) * 100.0
+    }
+
+    // Confidence interval for the RTP in percent. RTP is a ratio of two sums, so the spread
+    // comes from the per-round residuals payout - RTP * bet.
+    pub fn rtp_interval(&self, z: f64) -> (f64, f64) {
+        if self.rounds < 2 || self.total_bet == 0.0 {
+            return (0.0, f64::INFINITY);
+        }
+        let n = self.rounds as f64;
+        let ratio = self.total_payout / self.total_bet;
+        let residual_squares = self.payout_squares - 2.0 * ratio * self.payout_bet_products + ratio * ratio * self.bet_squares;
+        let variance = (residual_squares / (n - 1.0)).max(0.0);
+        let std_error = (variance / n).sqrt() / (self.total_bet / n);
+
+        (((ratio - z * std_error) * 100.0).max(0.0), (ratio + z * std_error) * 100.0)
+    }
+
+    // Percent of rounds that paid anything
+    pub fn hit_rate(&self) -> f64 {
+        if self.rounds == 0 {
+            return 0.0;
+        }
+        (self.wins + self.partials) as f64 / self.rounds as f64 * 100.0
+    }
+
+    // Wilson confidence interval for the hit frequency in percent
+    pub fn hit_interval(&self, z: f64) -> (f64, f64) {
+        let (low, high) = distributions::wilson_interval((self.wins + self.partials) as u64, self.rounds as u64, z);
+        (low * 100.0, high * 100.0)
+    }
+
+    // Number of draws of a symbol
+    pub fn symbol_count(&self, symbol: &str) -> u64 {
+        self.symbol_counts.iter()
+            .find(|(s, _)| s == symbol)
+            .map(|(_, count)| *count)
+            .unwrap_or(0)
+    }
+
+    // Goodness of fit of the drawn symbols against the configured weights
+    pub fn chi_square(&self, paytable: &[(String, usize, f64)]) -> ChiSquare {
+        // Entries sharing a symbol (e.g. two BANKRUPT segments) form one category
+        let mut expected: Vec<(&str, usize)> = Vec::new();
+        for (symbol, weight, _) in paytable {
+            match expected.iter_mut().find(|(s, _)| s == symbol) {
+                Some((_, total)) => *total += weight,
+                None => expected.push((symbol.as_str(), *weight)),
+            }
+        }
+        let total_weight: usize = expected.iter().map(|(_, w)| w).sum();
+        let draws: u64 = self.symbol_counts.iter().map(|(_, count)| count).sum();
+        if total_weight == 0 || draws == 0 {
+            return ChiSquare { statistic: 0.0, df: 0, p_value: 1.0 };
+        }
+
+        let mut statistic = 0.0;
+        let mut categories = 0;
+        for (symbol, weight) in &expected {
+            if *weight == 0 {
+                continue;
+            }
+            let expected_count = draws as f64 * *weight as f64 / total_weight as f64;
+            let observed = self.symbol_count(symbol) as f64;
+            statistic += (observed - expected_count).powi(2) / expected_count;
+            categories += 1;
+        }
+        // Anything drawn that the paytable does not allow is as unfair as it gets
+        let unexpected: u64 = self.symbol_counts.iter()
+            .filter(|(symbol, _)| !expected.iter().any(|(s, w)| s == symbol && *w > 0))
+            .map(|(_, count)| count)
+            .sum();
+        if unexpected > 0 {
+            return ChiSquare { statistic: f64::INFINITY, df: categories.max(1) - 1, p_value: 0.0 };
+        }
+
+        let df = categories.max(1) - 1;
+        ChiSquare { statistic, df, p_value: distributions::chi_square_p_value(statistic, df) }
+    }
+
+    fn count_symbol(&mut self, symbol: String) {
+        match self.symbol_counts.iter_mut().find(|(s, _)| *s == symbol) {
+            Some((_, count)) => *count += 1,
+            None => self.symbol_counts.push((symbol, 1)),
+        }
+    }
+}
+
+// Chi-square goodness-of-fit result
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChiSquare {
+    pub statistic: f64,
+    pub df: usize,
+    pub p_value: f64,
+}
+
+// Outcome of a fairness test
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    Pass,
+    Fail,
+    // The sample is too small to tell, run more rounds
+    Inconclusive,
+}
+
+impl Verdict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Verdict::Pass => "PASS",
+            Verdict::Fail => "FAIL",
+            Verdict::Inconclusive => "INCONCLUSIVE",
+        }
+    }
+}
+
+// Statistical summary of a simulation judged against a target RTP band (in percent)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FairnessReport {
+    pub rtp_interval: (f64, f64),
+    pub hit_rate: f64,
+    pub hit_interval: (f64, f64),
+    pub chi_square: ChiSquare,
+    pub target: (f64, f64),
+    pub verdict: Verdict,
+}
+
+impl FairnessReport {
+    // Symbol frequencies must fit the weights, and the whole RTP interval must sit inside the band
+    // to pass. An interval that lies entirely outside the band fails; one straddling an edge is inconclusive.
+    pub fn new(stats: &SimulationStats, paytable: &[(String, usize, f64)], target: (f64, f64)) -> Self {
+        let rtp_interval = stats.rtp_interval(CONFIDENCE_Z);
+        let chi_square = stats.chi_square(paytable);
+        let (min_rtp, max_rtp) = target;
+
+        let verdict = if chi_square.p_value < CHI_SQUARE_ALPHA || rtp_interval.1 < min_rtp || rtp_interval.0 > max_rtp {
+            Verdict::Fail
+        } else if rtp_interval.0 >= min_rtp && rtp_interval.1 <= max_rtp {
+            Verdict::Pass
+        } else {
+            Verdict::Inconclusive
+        };
+
+        FairnessReport {
+            rtp_interval,
+            hit_rate: stats.hit_rate(),
+            hit_interval: stats.hit_interval(CONFIDENCE_Z),
+            chi_square,
+            target,
+            verdict,
+        }
+    }
+}
+
+// Play `rounds` rounds of a configured game through its real resolution code, without any terminal I/O
+pub fn simulate(game: &dyn Game, rng: &mut CasinoRng, rounds: u32, bet: Money) -> SimulationStats {
+    let mut stats = SimulationStats { rounds, ..Default::default() };
+
+    for _ in 0..rounds {
+        let outcome = game.simulate_round(rng, bet);
+        let wager = outcome.total_wager(bet).to_f64();
+        let payout = outcome.payout.to_f64();
+        stats.total_bet += wager;
+        stats.total_payout += payout;
+        stats.payout_squares += payout * payout;
+        stats.bet_squares += wager * wager;
+        stats.payout_bet_products += payout * wager;
+        for symbol in game.observed_symbols(&outcome) {
+            stats.count_symbol(symbol);
+        }
+
+        if !outcome.payout.is_positive() {
+            stats.losses += 1;
+        } else if game.is_partial_win(&outcome) {
+            stats.partials += 1;
+        } else {
+            stats.wins += 1;
+        }
+    }
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::dbqueries;
+    use crate::db::test_support::{configured_game, setup_test_db};
+    use crate::play::game::registered_games;
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_rtp_with_no_bets_is_zero() {
+        assert_eq!(SimulationStats::default().rtp(), 0.0);
+    }
+
+    #[test]
+    fn test_chi_square_flags_skewed_symbols() {
+        let paytable = vec![("🍒".to_string(), 1, 2.0), ("💎".to_string(), 1, 2.0)];
+        let fair = SimulationStats { symbol_counts: vec![("🍒".to_string(), 505), ("💎".to_string(), 495)], ..Default::default() };
+        let skewed = SimulationStats { symbol_counts: vec![("🍒".to_string(), 600), ("💎".to_string(), 400)], ..Default::default() };
+        let impossible = SimulationStats { symbol_counts: vec![("🍒".to_string(), 500), ("7️⃣".to_string(), 1)], ..Default::default() };
+
+        assert!(fair.chi_square(&paytable).p_value > 0.5);
+        assert!(skewed.chi_square(&paytable).p_value < CHI_SQUARE_ALPHA);
+        assert_eq!(impossible.chi_square(&paytable).p_value, 0.0);
+        assert_eq!(fair.chi_square(&paytable).df, 1);
+    }
+
+    #[test]
+    fn test_verdicts_against_target_band() {
+        let conn = setup_test_db();
+        let game = configured_game(&conn, "normal");
+        let stats = simulate(game.as_ref(), &mut CasinoRng::seeded(8), 20_000, Money::from_dollars(1));
+        let (low, high) = stats.rtp_interval(CONFIDENCE_Z);
+        assert!(low < stats.rtp() && stats.rtp() < high);
+
+        let wide = FairnessReport::new(&stats, game.paytable(), (low - 5.0, high + 5.0));
+        let above = FairnessReport::new(&stats, game.paytable(), (high + 1.0, high + 10.0));
+        let straddling = FairnessReport::new(&stats, game.paytable(), (stats.rtp(), high + 10.0));
+        assert_eq!(wide.verdict, Verdict::Pass);
+        assert_eq!(above.verdict, Verdict::Fail);
+        assert_eq!(straddling.verdict, Verdict::Inconclusive);
+    }
+
+    #[test]
+    fn test_rtp_target_defaults_and_updates() {
+        let conn = setup_test_db();
+        assert_eq!(dbqueries::get_rtp_target(&conn, "normal").unwrap(), (85.0, 98.0));
+
+        dbqueries::set_rtp_target(&conn, "normal", 90.0, 96.0).unwrap();
+        assert_eq!(dbqueries::get_rtp_target(&conn, "normal").unwrap(), (90.0, 96.0));
+        assert!(dbqueries::set_rtp_target(&conn, "normal", 97.0, 96.0).is_err());
+        assert_eq!(dbqueries::get_rtp_target(&conn, "multi").unwrap(), (85.0, 98.0));
+    }
+
+    #[test]
+    fn test_commissioner_log_stores_verdict() {
+        let conn = setup_test_db();
+        let game = configured_game(&conn, "normal");
+        let stats = simulate(game.as_ref(), &mut CasinoRng::seeded(3), 500, Money::from_dollars(1));
+        let report = FairnessReport::new(&stats, game.paytable(), (85.0, 98.0));
+        dbqueries::insert_commissioner_log(&conn, "normal", "3", &stats, &report, 84.2).unwrap();
+
+        let (verdict, chi_square_df, ci_low): (String, i32, f64) = conn.query_row(
+            "SELECT verdict, chi_square_df, rtp_ci_low FROM commissioner_log WHERE game_name = 'normal'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        ).unwrap();
+        assert_eq!(verdict, report.verdict.as_str());
+        assert_eq!(chi_square_df as usize, report.chi_square.df);
+        assert_eq!(ci_low, report.rtp_interval.0);
+    }
+
+    #[test]
+    fn test_old_commissioner_log_gains_verdict_columns() {
+        // The table as it looked before fairness verdicts
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE commissioner_log (id INTEGER PRIMARY KEY, game_name TEXT, seed TEXT, rounds INTEGER,
+                wins INTEGER, partials INTEGER, losses INTEGER, rtp REAL, timestamp DATETIME DEFAULT CURRENT_TIMESTAMP)",
+            [],
+        ).unwrap();
+        crate::cryptography::crypto::initialize_encryption_key();
+        crate::db::dbinitialize::initialize_dbs(&conn).unwrap();
+        crate::db::dbinitialize::initialize_dbs(&conn).unwrap();
+
+        let columns: Vec<String> = conn.prepare("PRAGMA table_info(commissioner_log)").unwrap()
+            .query_map([], |row| row.get(1)).unwrap()
+            .collect::<rusqlite::Result<_>>().unwrap();
+        for column in ["rtp_ci_low", "chi_square_p", "target_rtp_max", "verdict"] {
+            assert!(columns.iter().any(|c| c == column), "commissioner_log is missing {}", column);
+        }
+    }
+
+    #[test]
+    fn test_real_games_fit_their_weights() {
+        let conn = setup_test_db();
+        for mut game in registered_games() {
+            game.load_config(&conn).unwrap();
+            let stats = simulate(game.as_ref(), &mut CasinoRng::seeded(12), 2_000, Money::from_dollars(1));
+            let chi_square = stats.chi_square(game.paytable());
+            assert!(chi_square.p_value > CHI_SQUARE_ALPHA, "{} symbols do not fit their weights: {:?}", game.name(), chi_square);
+        }
+    }
+
+    #[test]
+    fn test_simulation_counts_every_round() {
+        let conn = setup_test_db();
+        for mut game in registered_games() {
+            game.load_config(&conn).unwrap();
+            let stats = simulate(game.as_ref(), &mut CasinoRng::seeded(1), 500, Money::from_dollars(1));
+
+            assert_eq!(stats.wins + stats.partials + stats.losses, 500, "{} lost rounds", game.name());
+            assert!(stats.total_bet >= 500.0, "{} under-counted the wagers", game.name());
+        }
+    }
+
+    #[test]
+    fn test_simulation_is_reproducible_with_seed() {
+        let conn = setup_test_db();
+        let game = configured_game(&conn, "holding");
+
+        let first = simulate(game.as_ref(), &mut CasinoRng::seeded(99), 1000, Money::from_dollars(2));
+        let second = simulate(game.as_ref(), &mut CasinoRng::seeded(99), 1000, Money::from_dollars(2));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_holding_simulation_pays_for_holds() {
+        let conn = setup_test_db();
+        let game = configured_game(&conn, "holding");
+
+        // The automatic strategy holds every first-spin pair, which is common over 1000 rounds
+        let stats = simulate(game.as_ref(), &mut CasinoRng::seeded(5), 1000, Money::from_dollars(1));
+        assert!(stats.total_bet > 1000.0);
+    }
+
+    #[test]
+    fn test_only_normal_reports_partials() {
+        let conn = setup_test_db();
+        let normal = simulate(configured_game(&conn, "normal").as_ref(), &mut CasinoRng::seeded(3), 1000, Money::from_dollars(1));
+        let multi = simulate(configured_game(&conn, "multi").as_ref(), &mut CasinoRng::seeded(3), 1000, Money::from_dollars(1));
+
+        assert!(normal.partials > 0);
+        assert_eq!(multi.partials, 0);
+    }
+}
@@ -1,14 +1,15 @@
-use rusqlite::{Connection};
+use rusqlite::Connection;
 use crate::db::dbqueries;
-use crate::interfaces::user::User;
-use crate::logger::logger;
+use crate::db::money::Money;
 use crate::cryptography::rng::CasinoRng;
+use crate::play::game::{outcome_label, weighted_symbols, Game, OutcomeLabels, RoundOutcome};
+use crate::play::theory::{self, OutcomeClass};
 use colored::*;
 use std::io::{self, Write};
 
 use crate::interfaces::menus;
 // Display payout table to user before playing
-fn display_payout_table(symbol_probs: &[(String, usize, f64)], bet: f64) {
+fn display_payout_table(symbol_probs: &[(String, usize, f64)], bet: Money) {
     menus::print_box_top(50);
     menus::print_box_line("💰 PAYOUT TABLE 💰", 48);
     menus::print_box_separator(50);
@@ -20,8 +21,8 @@ fn display_payout_table(symbol_probs: &[(String, usize, f64)], bet: f64) {
     
     for (symbol, weight, payout) in symbol_probs {
         let probability = (*weight as f64 / total_weight as f64) * 100.0;
-        let winnings = payout * bet;
-        menus::print_box_line(&format!("{} {} {} = ${:<6.2} ({}x) [{:.1}% chance]", 
+        let winnings = bet.times(*payout);
+        menus::print_box_line(&format!("{} {} {} = ${:<6} ({}x) [{:.1}% chance]", 
             symbol, symbol, symbol, 
             winnings, 
             payout,
@@ -35,145 +36,147 @@ fn display_payout_table(symbol_probs: &[(String, usize, f64)], bet: f64) {
     println!();
 }
 
-// function to run the normal slots game, returns a bool to indiciate whether to change bet (true) or to exit the game (false)
-pub fn normal_slots(conn: &Connection, bet: f64, user: &User) -> bool {
-
-    // Logging player attempt
-    logger::info(&format!("User ID: {} started normal slots game with bet: ${:.2}", user.id, bet));
-    
-    // Load symbol probabilities from database once (commissioner-configured)
-    let symbol_probs = match dbqueries::get_symbol_probabilities(conn, "normal") {
-        Ok(probs) => probs,
-        Err(e) => {
-            logger::error(&format!("Failed to load symbol probabilities: {}", e));
-            println!("Error loading game configuration");
-            return true;
-        }
-    };
+// Normal 3-reel slots: three of a kind pays the symbol multiplier, two matching pay half
+#[derive(Default)]
+pub struct NormalSlots {
+    symbol_probs: Vec<(String, usize, f64)>,
+}
 
-    loop {
-        // Check if player has the funds
-        if !dbqueries::check_funds(conn, user, bet as f64) {
-            logger::warning(&format!("User ID: {} has insufficient funds for bet: ${:.2}", user.id, bet));
-            println!("{}", "Insufficient funds!".red().bold());
-            return true;
-        }
+impl Game for NormalSlots {
+    fn name(&self) -> &'static str {
+        "normal"
+    }
 
-        // CHARGE BET FIRST before playing
-        logger::transaction(&format!("User ID: {} placing bet of ${:.2} for normal slots", user.id, bet));
-        let balance_after_bet = dbqueries::transaction(conn, user, -bet);
-        
-        if balance_after_bet < 0.0 {
-            // This shouldn't happen due to check_funds, but safety check
-            println!("{}", "Transaction failed!".red().bold());
-            return true;
-        }
-        
-        println!("{}", format!("Bet placed: ${:.2}", bet).yellow());
-        println!("{}", format!("Balance: ${:.2}", balance_after_bet).bright_white());
+    fn load_config(&mut self, conn: &Connection) -> rusqlite::Result<()> {
+        // Load symbol probabilities from database (commissioner-configured)
+        self.symbol_probs = dbqueries::get_symbol_probabilities(conn, self.name())?;
+        Ok(())
+    }
 
-        // Convert to weighted format for RNG
-        let weighted_symbols: Vec<(&str, usize)> = symbol_probs.iter()
-            .map(|(sym, weight, _)| (sym.as_str(), *weight))
-            .collect();
+    fn paytable(&self) -> &[(String, usize, f64)] {
+        &self.symbol_probs
+    }
 
-        // Create cryptographically secure RNG
-        let mut rng = CasinoRng::new();
+    fn set_paytable(&mut self, paytable: Vec<(String, usize, f64)>) {
+        self.symbol_probs = paytable;
+    }
 
-        // Log game activity
-        logger::info(&format!("User ID: {} spinning slots with bet: ${:.2}", user.id, bet));
+    fn show_paytable(&self, bet: Money) {
         println!("\n{}", "🎰 SLOT MACHINE 🎰".bright_yellow().bold());
-        
-        // Display payout table to user
-        display_payout_table(&symbol_probs, bet);
+        display_payout_table(&self.symbol_probs, bet);
+    }
 
-        // Spin the slots using cryptographically secure weighted random selection
-        let slot1 = rng.weighted_choice(&weighted_symbols).unwrap();
-        let slot2 = rng.weighted_choice(&weighted_symbols).unwrap();
-        let slot3 = rng.weighted_choice(&weighted_symbols).unwrap();
+    fn play_round(&self, rng: &mut CasinoRng, bet: Money, _balance: Money) -> RoundOutcome {
+        let outcome = resolve(&self.symbol_probs, rng, bet);
+        let weighted = weighted_symbols(&self.symbol_probs);
+        // Animation frames are cosmetic and drawn separately from the round itself
+        let mut display_rng = CasinoRng::new();
 
         // Animate
         for _ in 0..30 {
-            let anim1 = rng.weighted_choice(&weighted_symbols).unwrap();
-            let anim2 = rng.weighted_choice(&weighted_symbols).unwrap();
-            let anim3 = rng.weighted_choice(&weighted_symbols).unwrap();
+            let anim1 = display_rng.weighted_choice(&weighted).unwrap();
+            let anim2 = display_rng.weighted_choice(&weighted).unwrap();
+            let anim3 = display_rng.weighted_choice(&weighted).unwrap();
             print!("\r{} | {} | {}", anim1, anim2, anim3);
             io::stdout().flush().ok();
             std::thread::sleep(std::time::Duration::from_millis(50));
         }
 
         // Final result
-        println!("\r{} | {} | {}", slot1, slot2, slot3);
-        logger::info(&format!("User ID: {} slot result: {} | {} | {}", user.id, slot1, slot2, slot3));
-
+        println!("\r{}", outcome.grid[0].join(" | "));
         std::thread::sleep(std::time::Duration::from_millis(500));
 
+        outcome
+    }
+
+    fn simulate_round(&self, rng: &mut CasinoRng, bet: Money) -> RoundOutcome {
+        resolve(&self.symbol_probs, rng, bet)
+    }
+
+    fn exact_outcomes(&self) -> Vec<OutcomeClass> {
+        exact_outcomes(&self.symbol_probs)
+    }
+
+    // Two matching reels pay half the three-match multiplier
+    fn is_partial_win(&self, outcome: &RoundOutcome) -> bool {
+        let reels = &outcome.grid[0];
+        outcome.payout.is_positive() && !check_three_of_kind(&reels[0], &reels[1], &reels[2])
+    }
+
+    fn outcome_labels(&self) -> OutcomeLabels {
+        OutcomeLabels { win: "Wins (3 match)", partial: Some("Two-symbol matches"), loss: "Losses" }
+    }
+
+    fn show_result(&self, outcome: &RoundOutcome, bet: Money, balance: Money) {
+        let reels = &outcome.grid[0];
+        let (slot1, slot2, slot3) = (reels[0].as_str(), reels[1].as_str(), reels[2].as_str());
+
         if check_three_of_kind(slot1, slot2, slot3) { // jackpot(match three)
-            let base_multiplier = *get_base_multiplier(&symbol_probs, &slot1);
-            // Calculate the winnings
-            let winnings = calculate_three_match_payout(bet, base_multiplier);
-            // Log the results
-            logger::transaction(&format!("User ID: {} won ${:.2} with three {}s in normal slots", user.id, winnings, slot1));
-            // Deposit winnings
-            let final_balance = dbqueries::transaction(conn, user, winnings);
-            print_jackpot_message(slot1, bet, base_multiplier, winnings, final_balance);
-            // Collect statistics
-            let _ = dbqueries::add_win(conn, "normal");
-            let _ = dbqueries::add_user_win(conn, user, "normal", winnings);
+            print_jackpot_message(slot1, bet, outcome.multiplier, outcome.payout, balance);
         } else if let Some(symbol) = check_two_match(slot1, slot2, slot3) { // match two
-            let base_multiplier = *get_base_multiplier(&symbol_probs, symbol);
-            // Calculate the winnings
-            let winnings = calculate_two_match_payout(bet, base_multiplier);
-            // Log the results
-            logger::transaction(&format!("User ID: {} won ${:.2} with three {}s in normal slots", user.id, winnings, slot1));
-            // Deposit winnings
-            let final_balance = dbqueries::transaction(conn, user, winnings);
-            print_semi_jackpot_message(symbol, bet, base_multiplier, winnings, final_balance);
-            // Collect statistics
-            let _ = dbqueries::add_win(conn, "normal");
-            let _ = dbqueries::add_user_win(conn, user, "normal", winnings);
+            print_semi_jackpot_message(symbol, bet, outcome.multiplier, outcome.payout, balance);
         } else { // Lose
-            // Log the loss
-            logger::transaction(&format!("User ID: {} lost ${:.2} in normal slots", user.id, bet));
-            print_losing_message(bet, balance_after_bet);
-            // Collect statistics
-            let _ = dbqueries::add_loss(conn, "normal");
-            let _ = dbqueries::add_user_loss(conn, user, "normal");
+            print_losing_message(bet, balance);
         }
+    }
+}
 
-        // Show options to user
-        let menu_options = vec!["Spin Again", "Change Bet", "Exit"];
-        let user_input = menus::menu_generator("═══ 🎰 Play Again? 🎰 ═══", &menu_options);
-
-        match user_input.trim() {
-            "Spin Again" => {
-                logger::info(&format!("User ID: {} continuing with same bet", user.id));
-                continue;
-            }
-            "Change Bet" => {
-                logger::info(&format!("User ID: {} changing bet", user.id));
-                return true;
-            }
-            "Exit" => {
-                logger::info(&format!("User ID: {} exiting slots game", user.id));
-                return false;
-            }
-            _ => {
-                logger::info(&format!("User ID: {} made invalid selection, continuing game", user.id));
-                println!("Playing again..."); 
-                continue;
-            }
-        }
+// Spin the three reels with the commissioner-configured weights and score them.
+// Pure: no printing or sleeping, the only side effect is drawing from `rng`.
+pub fn resolve(symbol_probs: &[(String, usize, f64)], rng: &mut CasinoRng, bet: Money) -> RoundOutcome {
+    let weighted = weighted_symbols(symbol_probs);
+    let reels = (0..3)
+        .map(|_| rng.weighted_choice(&weighted).unwrap().to_string())
+        .collect();
+    evaluate(symbol_probs, reels, bet)
+}
+
+// Enumerate every combination of the three reels and score it with the game's own rules
+pub fn exact_outcomes(symbol_probs: &[(String, usize, f64)]) -> Vec<OutcomeClass> {
+    let probs = theory::probabilities(symbol_probs);
+    let mut classes = Vec::new();
+
+    theory::for_each_combination(symbol_probs.len(), 3, |indexes| {
+        let probability: f64 = indexes.iter().map(|&i| probs[i]).product();
+        let reels = indexes.iter().map(|&i| symbol_probs[i].0.clone()).collect();
+        let outcome = evaluate(symbol_probs, reels, theory::THEORY_BET);
+        theory::add_outcome(&mut classes, outcome_label(&outcome), probability, theory::in_base_bets(outcome.payout), 1.0);
+    });
+
+    classes
+}
+
+// Score a finished set of three reels against the paytable
+pub fn evaluate(symbol_probs: &[(String, usize, f64)], reels: Vec<String>, bet: Money) -> RoundOutcome {
+    let (slot1, slot2, slot3) = (reels[0].as_str(), reels[1].as_str(), reels[2].as_str());
+
+    let (line, multiplier, payout) = if check_three_of_kind(slot1, slot2, slot3) {
+        let base_multiplier = *get_base_multiplier(symbol_probs, slot1);
+        (format!("Three of a kind: {}", slot1), base_multiplier, calculate_three_match_payout(bet, base_multiplier))
+    } else if let Some(symbol) = check_two_match(slot1, slot2, slot3) {
+        let base_multiplier = *get_base_multiplier(symbol_probs, symbol);
+        (format!("Two matching: {}", symbol), base_multiplier * 0.5, calculate_two_match_payout(bet, base_multiplier))
+    } else {
+        return RoundOutcome::losing(vec![reels]);
+    };
+
+    RoundOutcome {
+        grid: vec![reels],
+        holds: Vec::new(),
+        lines: vec![line],
+        multiplier,
+        extra_wager: Money::ZERO,
+        payout,
     }
 }
 
 // Normal Slots helper functions
-fn calculate_three_match_payout(bet: f64, multiplier: f64) -> f64 {
-    bet * multiplier
+fn calculate_three_match_payout(bet: Money, multiplier: f64) -> Money {
+    bet.times(multiplier)
 }
 
-fn calculate_two_match_payout(bet: f64, base_multiplier: f64) -> f64 {
-    bet * base_multiplier * 0.5
+fn calculate_two_match_payout(bet: Money, base_multiplier: f64) -> Money {
+    bet.times(base_multiplier * 0.5)
 }
 
 fn check_three_of_kind(slot1: &str, slot2: &str, slot3: &str) -> bool {
@@ -192,40 +195,40 @@ fn check_two_match<'a>(slot1: &'a str, slot2: &'a str, slot3: &'a str) -> Option
     }
 }
 
-fn get_base_multiplier<'a>(symbol_probs: &'a[(String, usize, f64)], symbol: &str) -> &'a f64 {
+fn get_base_multiplier<'a>(symbol_probs: &'a [(String, usize, f64)], symbol: &str) -> &'a f64 {
     symbol_probs.iter()
         .find(|(sym, _, _)| sym == symbol)
         .map(|(_, _, mult)| mult)
         .unwrap_or(&3.0)
 }
 
-fn print_jackpot_message(symbol: &str, bet: f64, base_multiplier: f64, winnings: f64, final_balance: f64) {
+fn print_jackpot_message(symbol: &str, bet: Money, base_multiplier: f64, winnings: Money, final_balance: Money) {
     println!("\n{}", "═══════════════════════════════════════".green().bold());
     println!("{}", "    🎉 JACKPOT! THREE OF A KIND! 🎉    ".green().bold());
     println!("{}", "═══════════════════════════════════════".green().bold());
     println!("\n{}  {} {} {}", "Result:".bright_white().bold(), symbol, symbol, symbol);
-    println!("{} ${:.2} × {:.1}x = ${:.2}", "Payout:".bright_white().bold(), bet, base_multiplier, winnings);
-    println!("{} ${:.2}", "Balance:".bright_white().bold(), final_balance);
+    println!("{} ${} × {:.1}x = ${}", "Payout:".bright_white().bold(), bet, base_multiplier, winnings);
+    println!("{} ${}", "Balance:".bright_white().bold(), final_balance);
     println!();
 }
 
-fn print_semi_jackpot_message(matching_symbol:&str, bet: f64, base_multiplier: f64, winnings: f64, final_balance: f64) {
-            println!("\n{}", "═══════════════════════════════════════".yellow().bold());
-            println!("{}", "      ✨ TWO MATCHING SYMBOLS! ✨       ".yellow().bold());
-            println!("{}", "═══════════════════════════════════════".yellow().bold());
-            println!("\n{}  Two {}s matched!", "Result:".bright_white().bold(), matching_symbol);
-            println!("{} ${:.2} × {:.1}x = ${:.2}", "Payout:".bright_white().bold(), bet, base_multiplier, winnings);
-            println!("{} ${:.2}", "Balance:".bright_white().bold(), final_balance);
-            println!();
+fn print_semi_jackpot_message(matching_symbol: &str, bet: Money, base_multiplier: f64, winnings: Money, final_balance: Money) {
+    println!("\n{}", "═══════════════════════════════════════".yellow().bold());
+    println!("{}", "      ✨ TWO MATCHING SYMBOLS! ✨       ".yellow().bold());
+    println!("{}", "═══════════════════════════════════════".yellow().bold());
+    println!("\n{}  Two {}s matched!", "Result:".bright_white().bold(), matching_symbol);
+    println!("{} ${} × {:.1}x = ${}", "Payout:".bright_white().bold(), bet, base_multiplier * 0.5, winnings);
+    println!("{} ${}", "Balance:".bright_white().bold(), final_balance);
+    println!();
 }
 
-fn print_losing_message(bet: f64, final_balance: f64) {
+fn print_losing_message(bet: Money, final_balance: Money) {
     println!("\n{}", "═══════════════════════════════════════".red());
     println!("{}", "           ❌ NO MATCH ❌               ".red().bold());
     println!("{}", "═══════════════════════════════════════".red());
     println!("\n{}  No matching symbols", "Result:".bright_white().bold());
-    println!("{} ${:.2}", "Lost:".bright_white().bold(), bet);
-    println!("{} ${:.2}", "Balance:".bright_white().bold(), final_balance);
+    println!("{} ${}", "Lost:".bright_white().bold(), bet);
+    println!("{} ${}", "Balance:".bright_white().bold(), final_balance);
     println!();
 
 }
@@ -237,26 +240,33 @@ mod tests {
     // Test payout helper functions
     #[test]
     fn test_three_match_payout_basic() {
-        let bet = 10.0;
+        let bet = Money::from_dollars(10);
         let multiplier = 2.5;
         let result = calculate_three_match_payout(bet, multiplier);
-        assert_eq!(result, 25.0);
+        assert_eq!(result, Money::from_dollars(25));
     }
 
     #[test]
     fn test_two_match_payout_is_half() {
-        let bet = 10.0;
+        let bet = Money::from_dollars(10);
         let base_multiplier = 4.0;
         let result = calculate_two_match_payout(bet, base_multiplier);
-        assert_eq!(result, 20.0); // 10 * 4.0 * 0.5 = 20
+        assert_eq!(result, Money::from_dollars(20)); // 10 * 4.0 * 0.5 = 20
     }
 
     #[test]
     fn test_payout_float() {
-        let bet = 2.50;
+        let bet = Money::from_cents(250);
         let multiplier = 1.5;
         let result = calculate_three_match_payout(bet, multiplier);
-        assert!((result - 3.75).abs() < 0.01);
+        assert_eq!(result, Money::from_cents(375));
+    }
+
+    #[test]
+    fn test_fractional_payout_rounds_to_cent() {
+        // 0.25 * 2.5 * 0.5 = 0.3125, paid as 0.31
+        let result = calculate_two_match_payout(Money::from_cents(25), 2.5);
+        assert_eq!(result, Money::from_cents(31));
     }
     
     // Test win detection
@@ -305,4 +315,53 @@ mod tests {
         let result = check_two_match("🍒", "🍒", "🍒");
         assert_eq!(result, Some("🍒"));
     }
+
+    // Test pure round resolution
+    fn test_paytable() -> Vec<(String, usize, f64)> {
+        vec![
+            ("🍒".to_string(), 25, 2.0),
+            ("🍋".to_string(), 20, 2.5),
+            ("💎".to_string(), 8, 8.0),
+        ]
+    }
+
+    fn reels(symbols: [&str; 3]) -> Vec<String> {
+        symbols.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_evaluate_three_of_kind() {
+        let outcome = evaluate(&test_paytable(), reels(["💎", "💎", "💎"]), Money::from_dollars(10));
+        assert_eq!(outcome.multiplier, 8.0);
+        assert_eq!(outcome.payout, Money::from_dollars(80));
+        assert_eq!(outcome.lines, vec!["Three of a kind: 💎".to_string()]);
+    }
+
+    #[test]
+    fn test_evaluate_two_match() {
+        let outcome = evaluate(&test_paytable(), reels(["🍋", "🍒", "🍋"]), Money::from_dollars(10));
+        assert_eq!(outcome.multiplier, 1.25);
+        assert_eq!(outcome.payout, Money::from_cents(1250));
+        assert_eq!(outcome.lines.len(), 1);
+    }
+
+    #[test]
+    fn test_evaluate_no_match() {
+        let outcome = evaluate(&test_paytable(), reels(["🍒", "🍋", "💎"]), Money::from_dollars(10));
+        assert_eq!(outcome.payout, Money::ZERO);
+        assert!(outcome.lines.is_empty());
+    }
+
+    #[test]
+    fn test_exact_outcomes_by_hand() {
+        // Two equally likely symbols: each three of a kind 1/8, each pair 3/8
+        let paytable = vec![("🍒".to_string(), 1, 2.0), ("💎".to_string(), 1, 4.0)];
+        let report = theory::TheoreticalReport::from_classes(exact_outcomes(&paytable));
+
+        // 1/8 * 2 + 1/8 * 4 + 3/8 * 1 + 3/8 * 2 = 1.875
+        assert!((report.rtp - 187.5).abs() < 1e-9);
+        assert!((report.hit_rate - 100.0).abs() < 1e-9);
+        let total: f64 = report.classes.iter().map(|c| c.probability).sum();
+        assert!((total - 1.0).abs() < 1e-12);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,175 @@
+// Exact (non-simulated) return figures for a game's paytable.
+// Each game enumerates its outcome classes, this module turns them into RTP, hit rate and volatility.
+
+use crate::db::money::Money;
+
+// z-score for the 99% two-sided bound used to flag simulations that disagree with theory
+pub const DEVIATION_Z: f64 = 2.576;
+
+// Stake the games are evaluated at for exact outcomes. Payouts are rounded to the cent, at this
+// stake the rounding is far below anything the reports show.
+pub const THEORY_BET: Money = Money::from_dollars(10_000);
+
+// An amount paid or wagered at THEORY_BET, in base bets of 1.0
+pub fn in_base_bets(amount: Money) -> f64 {
+    amount.to_f64() / THEORY_BET.to_f64()
+}
+
+// One distinct kind of round result with its exact probability, for a base bet of 1.0
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutcomeClass {
+    pub label: String,
+    pub probability: f64,
+    // Amount paid back for this result
+    pub payout: f64,
+    // Everything wagered for this result, hold charges included
+    pub wager: f64,
+}
+
+// Add `probability` to the class with this label, creating the class on first use
+pub fn add_outcome(classes: &mut Vec<OutcomeClass>, label: String, probability: f64, payout: f64, wager: f64) {
+    match classes.iter_mut().find(|class| class.label == label) {
+        Some(class) => class.probability += probability,
+        None => classes.push(OutcomeClass { label, probability, payout, wager }),
+    }
+}
+
+// Call `f` with every sequence of `len` indexes into a paytable of `n` entries (n^len sequences)
+pub fn for_each_combination<F: FnMut(&[usize])>(n: usize, len: usize, mut f: F) {
+    if n == 0 {
+        return;
+    }
+    let mut indexes = vec![0; len];
+    loop {
+        f(&indexes);
+
+        // Advance like an odometer, rightmost reel first
+        let mut reel = len;
+        loop {
+            if reel == 0 {
+                return;
+            }
+            reel -= 1;
+            indexes[reel] += 1;
+            if indexes[reel] < n {
+                break;
+            }
+            indexes[reel] = 0;
+        }
+    }
+}
+
+// Probability of each paytable entry from its weight
+pub fn probabilities(paytable: &[(String, usize, f64)]) -> Vec<f64> {
+    let total_weight: usize = paytable.iter().map(|(_, w, _)| w).sum();
+    if total_weight == 0 {
+        return vec![0.0; paytable.len()];
+    }
+    paytable.iter().map(|(_, w, _)| *w as f64 / total_weight as f64).collect()
+}
+
+// Theoretical figures for a paytable
+#[derive(Debug, Clone)]
+pub struct TheoreticalReport {
+    pub classes: Vec<OutcomeClass>,
+    // Return To Player in percent of everything wagered
+    pub rtp: f64,
+    // Percent of rounds that pay anything
+    pub hit_rate: f64,
+    // Standard deviation of the payout of one round, in base bets
+    pub std_dev: f64,
+    expected_wager: f64,
+    // Variance of (payout - RTP * wager) for one round, which drives the spread of a simulated RTP
+    ratio_variance: f64,
+}
+
+impl TheoreticalReport {
+    pub fn from_classes(classes: Vec<OutcomeClass>) -> Self {
+        let expected_payout: f64 = classes.iter().map(|c| c.probability * c.payout).sum();
+        let expected_wager: f64 = classes.iter().map(|c| c.probability * c.wager).sum();
+        let payout_square: f64 = classes.iter().map(|c| c.probability * c.payout * c.payout).sum();
+        let hit_rate: f64 = classes.iter().filter(|c| c.payout > 0.0).map(|c| c.probability).sum();
+
+        let ratio = if expected_wager > 0.0 { expected_payout / expected_wager } else { 0.0 };
+        let ratio_variance = classes.iter()
+            .map(|c| c.probability * (c.payout - ratio * c.wager).powi(2))
+            .sum();
+
+        TheoreticalReport {
+            classes,
+            rtp: ratio * 100.0,
+            hit_rate: hit_rate * 100.0,
+            std_dev: (payout_square - expected_payout * expected_payout).max(0.0).sqrt(),
+            expected_wager,
+            ratio_variance,
+        }
+    }
+
+    // Share of the RTP (in percentage points) that comes from one outcome class
+    pub fn contribution(&self, class: &OutcomeClass) -> f64 {
+        if self.expected_wager == 0.0 {
+            return 0.0;
+        }
+        class.probability * class.payout / self.expected_wager * 100.0
+    }
+
+    // Largest gap (in percentage points) between theory and an RTP simulated over `rounds` rounds
+    // that chance alone explains at the given z-score
+    pub fn rtp_tolerance(&self, rounds: u32, z: f64) -> f64 {
+        if rounds == 0 || self.expected_wager == 0.0 {
+            return f64::INFINITY;
+        }
+        z * (self.ratio_variance / rounds as f64).sqrt() / self.expected_wager * 100.0
+    }
+
+    // Whether a simulated RTP is further from theory than chance explains at the 99% level
+    pub fn deviates(&self, simulated_rtp: f64, rounds: u32) -> bool {
+        (simulated_rtp - self.rtp).abs() > self.rtp_tolerance(rounds, DEVIATION_Z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn class(label: &str, probability: f64, payout: f64) -> OutcomeClass {
+        OutcomeClass { label: label.to_string(), probability, payout, wager: 1.0 }
+    }
+
+    #[test]
+    fn test_for_each_combination_visits_every_sequence_once() {
+        let mut seen = Vec::new();
+        for_each_combination(3, 2, |indexes| seen.push(indexes.to_vec()));
+        assert_eq!(seen.len(), 9);
+        assert_eq!(seen[0], vec![0, 0]);
+        assert_eq!(seen[8], vec![2, 2]);
+    }
+
+    #[test]
+    fn test_add_outcome_merges_labels() {
+        let mut classes = Vec::new();
+        add_outcome(&mut classes, "Win".to_string(), 0.25, 2.0, 1.0);
+        add_outcome(&mut classes, "Win".to_string(), 0.25, 2.0, 1.0);
+        assert_eq!(classes.len(), 1);
+        assert_eq!(classes[0].probability, 0.5);
+    }
+
+    #[test]
+    fn test_report_coin_flip() {
+        // Pays 2x half the time: 100% RTP, 50% hit rate, standard deviation of 1 bet
+        let report = TheoreticalReport::from_classes(vec![class("Win", 0.5, 2.0), class("No win", 0.5, 0.0)]);
+        assert!((report.rtp - 100.0).abs() < 1e-9);
+        assert!((report.hit_rate - 50.0).abs() < 1e-9);
+        assert!((report.std_dev - 1.0).abs() < 1e-9);
+        assert!((report.contribution(&report.classes[0]) - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_tolerance_shrinks_with_more_rounds() {
+        let report = TheoreticalReport::from_classes(vec![class("Win", 0.5, 2.0), class("No win", 0.5, 0.0)]);
+        assert!(report.rtp_tolerance(100, DEVIATION_Z) > report.rtp_tolerance(10_000, DEVIATION_Z));
+        assert!(report.deviates(80.0, 10_000));
+        assert!(!report.deviates(99.0, 10_000));
+        assert!(report.rtp_tolerance(0, DEVIATION_Z).is_infinite());
+    }
+}
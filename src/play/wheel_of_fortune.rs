@@ -0,0 +1,329 @@
+use rusqlite::Connection;
+use std::thread;
+use std::time::Duration;
+use crate::cryptography::rng::CasinoRng;
+use crate::db::dbqueries;
+use crate::db::money::Money;
+use crate::interfaces::menus;
+use crate::play::game::{Game, OutcomeLabels, RoundOutcome};
+use crate::play::theory::{self, OutcomeClass};
+
+//Shows state of wheel and uses as a multiplier 
+pub struct Segment {
+    pub display: &'static str,
+    pub weight: usize,
+    pub multiplier: f32,
+}
+
+//each value of a segment on the wheel
+//default layout, seeded into the wheel_segments table where the commissioner can change it
+pub const WHEEL: [Segment; 8] = [
+    Segment { display: "2x", weight: 1, multiplier: 2.0 },
+    Segment { display: "BANKRUPT", weight: 1, multiplier: 0.0 },
+    Segment { display: "1.5x", weight: 1, multiplier: 1.5 },
+    Segment { display: "0.5x (Lose Half)", weight: 1, multiplier: 0.5 },
+    Segment { display: "3x", weight: 1, multiplier: 3.0 },
+    Segment { display: "BANKRUPT", weight: 1, multiplier: 0.0 },
+    Segment { display: "1x (Bet Back)", weight: 1, multiplier: 1.0 },
+    Segment { display: "JACKPOT 10x", weight: 1, multiplier: 10.0 },
+];
+
+//used some asii art to create wheel
+const ANIMATION_FRAMES: [&str; 4] = [
+    r"
+          . - ~ ~ - .
+      /               \
+     |                 |
+    <         |         >
+     |                 |
+      \               /
+          ' - ~ ~ - '
+    ",
+    r"
+          . - ~ ~ - .
+      /               \
+     |        /        |
+    <                 >
+     |        \        |
+      \               /
+          ' - ~ ~ - '
+    ",
+    r"
+          . - ~ ~ - .
+      /               \
+     |                 |
+    <        - -        >
+     |                 |
+      \               /
+          ' - ~ ~ - '
+    ",
+    r"
+          . - ~ ~ - .
+      /               \
+     |        \        |
+    <                 >
+     |        /        |
+      \               /
+          ' - ~ ~ - '
+    ",
+];
+
+// Display payout table to user before playing
+fn display_payout_table(segments: &[(String, usize, f64)], bet: Money) {
+    menus::print_box_top(50);
+    menus::print_box_line("💰 WHEEL SEGMENTS 💰", 48);
+    menus::print_box_separator(50);
+
+    // Calculate total weight for probability display
+    let total_weight: usize = segments.iter().map(|(_, w, _)| w).sum();
+
+    for (label, weight, multiplier) in segments {
+        let probability = (*weight as f64 / total_weight as f64) * 100.0;
+        menus::print_box_line(&format!("{:<18} ${:<7} [{:.1}% chance]", label, bet.times(*multiplier), probability), 50);
+    }
+
+    menus::print_box_bottom(50);
+    println!();
+}
+
+// Wheel of fortune: one spin, the segment's multiplier is applied to the bet
+#[derive(Default)]
+pub struct WheelOfFortune {
+    // Commissioner-configured segments in wheel order: (label, weight, multiplier)
+    segments: Vec<(String, usize, f64)>,
+}
+
+impl Game for WheelOfFortune {
+    fn name(&self) -> &'static str {
+        "wheel of fortune"
+    }
+
+    fn load_config(&mut self, conn: &Connection) -> rusqlite::Result<()> {
+        // Load wheel segments from database (commissioner-configured)
+        self.segments = dbqueries::get_wheel_segments(conn, self.name())?;
+        Ok(())
+    }
+
+    fn paytable(&self) -> &[(String, usize, f64)] {
+        &self.segments
+    }
+
+    fn set_paytable(&mut self, paytable: Vec<(String, usize, f64)>) {
+        self.segments = paytable;
+    }
+
+    fn show_welcome(&self) {
+        println!("--- ♛ Welcome to the Wheel of Fortune! ♛ ---");
+    }
+
+    fn show_paytable(&self, bet: Money) {
+        display_payout_table(&self.segments, bet);
+        println!("You bet ${}. Spinning the wheel...", bet);
+    }
+
+    fn play_round(&self, rng: &mut CasinoRng, bet: Money, _balance: Money) -> RoundOutcome {
+        let outcome = resolve(&self.segments, rng, bet);
+
+        //animation once bet is entered, drawn separately from the round itself
+        run_spin_animation(&mut CasinoRng::new(), &self.segments);
+
+        //let user know where the wheel stopped
+        clearscreen::clear().expect("Failed to clear screen");
+        println!("The wheel slows down... and lands on:");
+        println!("\n      *** {} ***", outcome.grid[0][0]);
+
+        outcome
+    }
+
+    fn simulate_round(&self, rng: &mut CasinoRng, bet: Money) -> RoundOutcome {
+        resolve(&self.segments, rng, bet)
+    }
+
+    fn exact_outcomes(&self) -> Vec<OutcomeClass> {
+        exact_outcomes(&self.segments)
+    }
+
+    // Segments below 1x hand back part of the bet
+    fn is_partial_win(&self, outcome: &RoundOutcome) -> bool {
+        outcome.payout.is_positive() && outcome.multiplier < 1.0
+    }
+
+    fn outcome_labels(&self) -> OutcomeLabels {
+        OutcomeLabels { win: "Wins (1x or more)", partial: Some("Partial returns (under 1x)"), loss: "Losses (bankrupt)" }
+    }
+
+    fn show_result(&self, outcome: &RoundOutcome, _bet: Money, balance: Money) {
+        if outcome.payout == Money::ZERO {
+            println!("\nOh no! You lost your bet.");
+        } else {
+            println!("\nCongratulations! You won ${}", outcome.payout);
+        }
+        println!("Current balance is {}", balance);
+    }
+}
+
+// Spin the wheel once, landing on each segment in proportion to its weight,
+// and apply the segment's multiplier to the bet.
+// Pure: no printing or sleeping, the only side effect is drawing from `rng`.
+pub fn resolve(segments: &[(String, usize, f64)], rng: &mut CasinoRng, bet: Money) -> RoundOutcome {
+    let weighted: Vec<(usize, usize)> = segments.iter()
+        .enumerate()
+        .map(|(index, (_, weight, _))| (index, *weight))
+        .collect();
+    let index = *rng.weighted_choice(&weighted).unwrap();
+    evaluate(segments, index, bet)
+}
+
+// One outcome class per segment, in wheel order
+pub fn exact_outcomes(segments: &[(String, usize, f64)]) -> Vec<OutcomeClass> {
+    theory::probabilities(segments).into_iter()
+        .zip(segments)
+        .enumerate()
+        .map(|(index, (probability, (label, _, multiplier)))| OutcomeClass {
+            label: format!("#{} {}", index + 1, label),
+            probability,
+            payout: theory::in_base_bets(theory::THEORY_BET.times(*multiplier)),
+            wager: 1.0,
+        })
+        .collect()
+}
+
+// Score the segment the wheel stopped on
+fn evaluate(segments: &[(String, usize, f64)], index: usize, bet: Money) -> RoundOutcome {
+    let (label, _, multiplier) = &segments[index];
+
+    RoundOutcome {
+        grid: vec![vec![label.clone()]],
+        holds: Vec::new(),
+        lines: if *multiplier > 0.0 { vec![label.clone()] } else { Vec::new() },
+        multiplier: *multiplier,
+        extra_wager: Money::ZERO,
+        payout: bet.times(*multiplier),
+    }
+}
+
+// Runs a spinning animation
+fn run_spin_animation(rng: &mut CasinoRng, segments: &[(String, usize, f64)]) {
+    let total_frames = 25; // Total number of "ticks"
+    let mut delay = Duration::from_millis(50); // Starting delay
+
+    for i in 0..total_frames {
+        clearscreen::clear().expect("Failed to clear screen");
+
+        // ASCII art frame
+        let frame_art = ANIMATION_FRAMES[i % ANIMATION_FRAMES.len()];
+
+        //the wheel segments flying past
+
+        let (random_segment, _, _) = &segments[rng.gen_range(0, segments.len())];
+        println!("Spinning the Wheel!");
+        println!("{}", frame_art);
+        println!("\n  >> {} <<", random_segment);
+
+        thread::sleep(delay);
+
+        // Slow down the animation towards the end may adjust 
+        if i > total_frames - 7 {
+            delay += Duration::from_millis(30);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    
+    #[test]
+    fn test_wheel_constants() {
+        // Test that the wheel has segments
+        assert_eq!(WHEEL.len(), 8);
+
+        // Test that there are two "BANKRUPT" segments
+        let bankrupt_count = WHEEL.iter().filter(|s| s.display == "BANKRUPT").count();
+        assert_eq!(bankrupt_count, 2);
+        
+        // Test that both BANKRUPT segments have a 0.0 multiplier
+        assert!(WHEEL.iter()
+                     .filter(|s| s.display == "BANKRUPT")
+                     .all(|s| s.multiplier == 0.0));
+
+        // Test that all multipliers are non-negative
+        assert!(WHEEL.iter().all(|s| s.multiplier >= 0.0));
+
+        // Test that the JACKPOT exists and is correct
+        let jackpot_exists = WHEEL.iter().any(|s| s.display == "JACKPOT 10x" && s.multiplier == 10.0);
+        assert!(jackpot_exists, "The 10x JACKPOT segment is missing or incorrect");
+    }
+
+    #[test]
+    fn test_animation_frames_constant() {
+        // Ensure there are 4 animation frames
+        assert_eq!(ANIMATION_FRAMES.len(), 4);
+        // Ensure no frame is an empty string
+        assert!(ANIMATION_FRAMES.iter().all(|&frame| !frame.is_empty()));
+    }
+
+    //test pure round resolution
+    fn default_segments() -> Vec<(String, usize, f64)> {
+        WHEEL.iter()
+            .map(|s| (s.display.to_string(), s.weight, s.multiplier as f64))
+            .collect()
+    }
+
+    #[test]
+    fn test_evaluate_matches_segment() {
+        let jackpot_index = WHEEL.iter().position(|s| s.multiplier == 10.0).unwrap();
+        let outcome = evaluate(&default_segments(), jackpot_index, Money::from_dollars(10));
+
+        assert_eq!(outcome.grid, vec![vec!["JACKPOT 10x".to_string()]]);
+        assert_eq!(outcome.multiplier, 10.0);
+        assert_eq!(outcome.payout, Money::from_dollars(100));
+    }
+
+    #[test]
+    fn test_resolve_lands_on_a_segment() {
+        let segments = default_segments();
+        let outcome = resolve(&segments, &mut CasinoRng::seeded(9), Money::from_dollars(20));
+        let (_, _, multiplier) = segments.iter().find(|(label, _, _)| *label == outcome.grid[0][0]).expect("unknown segment");
+
+        assert_eq!(outcome.payout, Money::from_dollars(20).times(*multiplier));
+        assert_eq!(outcome, resolve(&segments, &mut CasinoRng::seeded(9), Money::from_dollars(20)));
+    }
+
+    #[test]
+    fn test_resolve_never_lands_on_zero_weight() {
+        let mut segments = default_segments();
+        for segment in segments.iter_mut().filter(|(label, _, _)| label != "3x") {
+            segment.1 = 0;
+        }
+
+        let mut rng = CasinoRng::seeded(4);
+        for _ in 0..50 {
+            assert_eq!(resolve(&segments, &mut rng, Money::from_dollars(1)).grid[0][0], "3x");
+        }
+    }
+
+    #[test]
+    fn test_default_wheel_theory() {
+        // Eight equal segments paying 2 + 1.5 + 0.5 + 3 + 1 + 10 = 18 bets in total
+        let report = theory::TheoreticalReport::from_classes(exact_outcomes(&default_segments()));
+        assert!((report.rtp - 225.0).abs() < 1e-9);
+        assert!((report.hit_rate - 75.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_segments_load_from_database() {
+        let conn = crate::db::test_support::setup_test_db();
+
+        let mut game = WheelOfFortune::default();
+        game.load_config(&conn).unwrap();
+        assert_eq!(game.segments, default_segments());
+
+        dbqueries::update_wheel_segment(&conn, game.name(), 2, 5, 0.25).unwrap();
+        game.load_config(&conn).unwrap();
+        assert_eq!(game.segments[1], ("BANKRUPT".to_string(), 5, 0.25));
+
+        assert!(dbqueries::update_wheel_segment(&conn, game.name(), 99, 1, 1.0).is_err());
+    }
+}
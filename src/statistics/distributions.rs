@@ -0,0 +1,152 @@
+// Distribution functions for the fairness and RNG test reports.
+// Accurate to well beyond the precision the reports print, without pulling in a stats crate.
+
+// Natural log of the gamma function (Lanczos approximation, g = 7)
+pub fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        // Reflection formula
+        return (std::f64::consts::PI / (std::f64::consts::PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFICIENTS.iter()
+        .enumerate()
+        .skip(1)
+        .fold(COEFFICIENTS[0], |sum, (i, c)| sum + c / (x + i as f64));
+
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+// Upper regularized incomplete gamma function Q(a, x)
+pub fn gamma_q(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let log_prefix = a * x.ln() - x - ln_gamma(a);
+
+    if x < a + 1.0 {
+        // Series for P(a, x), then Q = 1 - P
+        let (mut term, mut sum, mut n) = (1.0 / a, 1.0 / a, a);
+        for _ in 0..1000 {
+            n += 1.0;
+            term *= x / n;
+            sum += term;
+            if term.abs() < sum.abs() * 1e-15 {
+                break;
+            }
+        }
+        (1.0 - sum * log_prefix.exp()).clamp(0.0, 1.0)
+    } else {
+        // Continued fraction for Q(a, x) (modified Lentz)
+        let tiny = 1e-300;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..1000 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < tiny {
+                d = tiny;
+            }
+            c = b + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < 1e-15 {
+                break;
+            }
+        }
+        (log_prefix.exp() * h).clamp(0.0, 1.0)
+    }
+}
+
+// Probability of a chi-square statistic at least this large with `df` degrees of freedom
+pub fn chi_square_p_value(statistic: f64, df: usize) -> f64 {
+    if df == 0 {
+        return 1.0;
+    }
+    gamma_q(df as f64 / 2.0, statistic / 2.0)
+}
+
+// Complementary error function
+pub fn erfc(x: f64) -> f64 {
+    if x < 0.0 {
+        return 2.0 - erfc(-x);
+    }
+    // erfc(x) = Q(1/2, x^2)
+    gamma_q(0.5, x * x)
+}
+
+// Two-sided p-value of a standard normal z-score
+pub fn normal_two_sided_p_value(z: f64) -> f64 {
+    erfc(z.abs() / std::f64::consts::SQRT_2)
+}
+
+// Wilson score interval for a proportion, as fractions between 0 and 1
+pub fn wilson_interval(successes: u64, trials: u64, z: f64) -> (f64, f64) {
+    if trials == 0 {
+        return (0.0, 1.0);
+    }
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let z2 = z * z;
+
+    let centre = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let half_width = z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / (1.0 + z2 / n);
+    ((centre - half_width).max(0.0), (centre + half_width).min(1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ln_gamma_factorials() {
+        // Gamma(n) = (n - 1)!
+        assert!((ln_gamma(1.0)).abs() < 1e-12);
+        assert!((ln_gamma(5.0) - 24f64.ln()).abs() < 1e-12);
+        assert!((ln_gamma(0.5) - std::f64::consts::PI.sqrt().ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_chi_square_critical_values() {
+        // Textbook 5% critical values
+        assert!((chi_square_p_value(3.841, 1) - 0.05).abs() < 1e-3);
+        assert!((chi_square_p_value(11.070, 5) - 0.05).abs() < 1e-3);
+        assert!((chi_square_p_value(124.342, 100) - 0.05).abs() < 1e-3);
+        assert_eq!(chi_square_p_value(0.0, 3), 1.0);
+    }
+
+    #[test]
+    fn test_normal_p_values() {
+        assert!((normal_two_sided_p_value(1.96) - 0.05).abs() < 1e-4);
+        assert!((normal_two_sided_p_value(-2.576) - 0.01).abs() < 1e-4);
+        assert!((normal_two_sided_p_value(0.0) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_wilson_interval_contains_estimate() {
+        let (low, high) = wilson_interval(50, 100, 1.96);
+        assert!(low < 0.5 && 0.5 < high);
+        assert!((low - 0.4038).abs() < 1e-3);
+        assert_eq!(wilson_interval(0, 0, 1.96), (0.0, 1.0));
+    }
+}
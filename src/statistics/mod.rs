@@ -0,0 +1,2 @@
+pub mod distributions;
+pub mod rng_battery;
@@ -0,0 +1,263 @@
+// Statistical test battery for CasinoRng output.
+// Each test turns a sample of generator output into a p-value. A fair generator still fails any
+// single test ALPHA of the time by chance, so a failure calls for a rerun rather than a verdict.
+
+use crate::cryptography::rng::CasinoRng;
+use crate::statistics::distributions::{chi_square_p_value, normal_two_sided_p_value};
+
+// A test fails when its p-value falls below this
+pub const ALPHA: f64 = 0.01;
+// Number of gen_range buckets in the uniformity test
+pub const BUCKETS: usize = 10;
+// Digits per hand in the poker test
+const HAND_SIZE: usize = 5;
+// Values below GAP_LIMIT are hits in the gap test; gaps of GAP_CATEGORIES - 1 or more share one category
+const GAP_LIMIT: f64 = 0.5;
+const GAP_CATEGORIES: usize = 10;
+
+// Result of one test in the battery
+#[derive(Debug, Clone, PartialEq)]
+pub struct RngTestResult {
+    pub name: String,
+    pub statistic: f64,
+    pub p_value: f64,
+    pub passed: bool,
+}
+
+impl RngTestResult {
+    fn new(name: &str, statistic: f64, p_value: f64) -> Self {
+        RngTestResult { name: name.to_string(), statistic, p_value, passed: p_value >= ALPHA }
+    }
+}
+
+// A full run of the battery against one generator
+#[derive(Debug, Clone, PartialEq)]
+pub struct RngReport {
+    // Which generator was tested, e.g. "secure" or "seeded:42"
+    pub source: String,
+    // Values drawn for each test
+    pub samples: usize,
+    pub timestamp: String,
+    pub results: Vec<RngTestResult>,
+}
+
+impl RngReport {
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|result| result.passed)
+    }
+}
+
+// Run every test on fresh output of `rng`, `samples` values per test
+pub fn run_battery(rng: &mut CasinoRng, source: &str, samples: usize) -> RngReport {
+    let words: Vec<u64> = (0..samples).map(|_| rng.next_u64()).collect();
+    let uniforms: Vec<f64> = (0..samples).map(|_| to_unit(rng.next_u64())).collect();
+    let buckets: Vec<usize> = (0..samples).map(|_| rng.gen_range(0, BUCKETS)).collect();
+    let digits: Vec<usize> = (0..samples * HAND_SIZE).map(|_| rng.gen_range(0, 10)).collect();
+    let gap_uniforms: Vec<f64> = (0..samples).map(|_| to_unit(rng.next_u64())).collect();
+
+    RngReport {
+        source: source.to_string(),
+        samples,
+        timestamp: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        results: vec![
+            frequency_test(&words),
+            runs_test(&words),
+            serial_correlation_test(&uniforms),
+            bucket_test(&buckets, BUCKETS),
+            poker_test(&digits),
+            gap_test(&gap_uniforms),
+        ],
+    }
+}
+
+// Top 53 bits as a float in [0, 1)
+fn to_unit(word: u64) -> f64 {
+    (word >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// Chi-square statistic and p-value of observed counts against category probabilities
+fn chi_square(observed: &[u64], probabilities: &[f64]) -> (f64, f64) {
+    let total: u64 = observed.iter().sum();
+    if total == 0 {
+        return (0.0, 1.0);
+    }
+    let statistic: f64 = observed.iter()
+        .zip(probabilities)
+        .map(|(count, p)| {
+            let expected = total as f64 * p;
+            (*count as f64 - expected).powi(2) / expected
+        })
+        .sum();
+    (statistic, chi_square_p_value(statistic, observed.len() - 1))
+}
+
+// Monobit test: ones and zeros should be equally common
+pub fn frequency_test(words: &[u64]) -> RngTestResult {
+    let bits = words.len() as f64 * 64.0;
+    if bits == 0.0 {
+        return RngTestResult::new("Frequency (monobit)", 0.0, 1.0);
+    }
+    let ones: u64 = words.iter().map(|w| w.count_ones() as u64).sum();
+    let z = (2.0 * ones as f64 - bits) / bits.sqrt();
+    RngTestResult::new("Frequency (monobit)", z, normal_two_sided_p_value(z))
+}
+
+// Runs test: the number of uninterrupted runs of equal bits should match a fair coin
+pub fn runs_test(words: &[u64]) -> RngTestResult {
+    let n = words.len() as f64 * 64.0;
+    if n == 0.0 {
+        return RngTestResult::new("Runs", 0.0, 1.0);
+    }
+    let ones: u64 = words.iter().map(|w| w.count_ones() as u64).sum();
+    let pi = ones as f64 / n;
+    // The run count is only meaningful when the bits are roughly balanced
+    if (pi - 0.5).abs() >= 2.0 / n.sqrt() {
+        return RngTestResult::new("Runs", f64::INFINITY, 0.0);
+    }
+
+    let mut runs = 1u64;
+    let mut previous = words[0] & 1;
+    for word in words {
+        for bit in 0..64 {
+            let current = (word >> bit) & 1;
+            if current != previous {
+                runs += 1;
+            }
+            previous = current;
+        }
+    }
+
+    let spread = pi * (1.0 - pi);
+    let z = (runs as f64 - 2.0 * n * spread) / (2.0 * n.sqrt() * spread);
+    RngTestResult::new("Runs", z, normal_two_sided_p_value(z))
+}
+
+// Serial correlation test: each value should say nothing about the next
+pub fn serial_correlation_test(values: &[f64]) -> RngTestResult {
+    let n = values.len();
+    if n < 3 {
+        return RngTestResult::new("Serial correlation", 0.0, 1.0);
+    }
+    let mean = values.iter().sum::<f64>() / n as f64;
+    let variance: f64 = values.iter().map(|v| (v - mean).powi(2)).sum();
+    if variance == 0.0 {
+        return RngTestResult::new("Serial correlation", f64::INFINITY, 0.0);
+    }
+    let covariance: f64 = values.windows(2).map(|pair| (pair[0] - mean) * (pair[1] - mean)).sum();
+
+    // Lag-1 autocorrelation is approximately normal with mean -1/n and variance 1/n
+    let r = covariance / variance;
+    let z = (r + 1.0 / n as f64) * (n as f64).sqrt();
+    RngTestResult::new("Serial correlation", r, normal_two_sided_p_value(z))
+}
+
+// Chi-square test of gen_range results in [0, buckets): every bucket should be equally likely
+pub fn bucket_test(values: &[usize], buckets: usize) -> RngTestResult {
+    let mut observed = vec![0u64; buckets];
+    for value in values {
+        observed[*value] += 1;
+    }
+    let (statistic, p_value) = chi_square(&observed, &vec![1.0 / buckets as f64; buckets]);
+    RngTestResult::new("Chi-square (gen_range buckets)", statistic, p_value)
+}
+
+// Poker test: hands of five decimal digits, classified by how many different digits they hold
+pub fn poker_test(digits: &[usize]) -> RngTestResult {
+    // P(r different digits) = 10!/(10-r)! * S(5, r) / 10^5, with one and two different merged
+    // because both are rare
+    let probabilities = [0.0136, 0.18, 0.504, 0.3024];
+    let mut observed = [0u64; 4];
+    for hand in digits.chunks_exact(HAND_SIZE) {
+        let mut seen = [false; 10];
+        for digit in hand {
+            seen[*digit] = true;
+        }
+        let distinct = seen.iter().filter(|s| **s).count();
+        observed[distinct.max(2) - 2] += 1;
+    }
+    let (statistic, p_value) = chi_square(&observed, &probabilities);
+    RngTestResult::new("Poker", statistic, p_value)
+}
+
+// Gap test: the number of misses between two values below GAP_LIMIT should be geometric
+pub fn gap_test(values: &[f64]) -> RngTestResult {
+    let mut observed = [0u64; GAP_CATEGORIES];
+    let mut gap = 0;
+    for value in values {
+        if *value < GAP_LIMIT {
+            observed[gap.min(GAP_CATEGORIES - 1)] += 1;
+            gap = 0;
+        } else {
+            gap += 1;
+        }
+    }
+
+    let mut probabilities: Vec<f64> = (0..GAP_CATEGORIES - 1)
+        .map(|j| GAP_LIMIT * (1.0 - GAP_LIMIT).powi(j as i32))
+        .collect();
+    probabilities.push((1.0 - GAP_LIMIT).powi(GAP_CATEGORIES as i32 - 1));
+
+    let (statistic, p_value) = chi_square(&observed, &probabilities);
+    RngTestResult::new("Gap", statistic, p_value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_rng_passes_battery() {
+        let report = run_battery(&mut CasinoRng::seeded(42), "seeded:42", 20_000);
+        assert_eq!(report.results.len(), 6);
+        for result in &report.results {
+            assert!(result.passed, "{} failed: {:?}", result.name, result);
+        }
+        assert!(report.passed());
+    }
+
+    #[test]
+    fn test_secure_rng_runs_battery() {
+        // Random output cannot be asserted to pass, only to produce valid p-values
+        let report = run_battery(&mut CasinoRng::new(), "secure", 5_000);
+        assert!(report.results.iter().all(|r| (0.0..=1.0).contains(&r.p_value)));
+    }
+
+    #[test]
+    fn test_reports_round_trip_through_db() {
+        use crate::db::dbqueries;
+
+        let conn = crate::db::test_support::setup_test_db();
+
+        let first = run_battery(&mut CasinoRng::seeded(1), "seeded:1", 1_000);
+        let second = run_battery(&mut CasinoRng::seeded(2), "seeded:2", 1_000);
+        dbqueries::insert_rng_report(&conn, &first).unwrap();
+        dbqueries::insert_rng_report(&conn, &second).unwrap();
+
+        assert_eq!(dbqueries::get_rng_reports(&conn, 10).unwrap(), vec![second, first]);
+        assert_eq!(dbqueries::get_rng_reports(&conn, 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_biased_bits_fail() {
+        assert!(!frequency_test(&[u64::MAX; 100]).passed);
+        // Balanced, but alternates every bit
+        let alternating = [0xAAAA_AAAA_AAAA_AAAAu64; 100];
+        assert!(frequency_test(&alternating).passed);
+        assert!(!runs_test(&alternating).passed);
+    }
+
+    #[test]
+    fn test_patterned_values_fail() {
+        let rising: Vec<f64> = (0..1000).map(|i| i as f64 / 1000.0).collect();
+        assert!(!serial_correlation_test(&rising).passed);
+
+        let stuck = vec![3usize; 1000];
+        assert!(!bucket_test(&stuck, BUCKETS).passed);
+
+        let straights: Vec<usize> = (0..5000).map(|i| i % 5).collect();
+        assert!(!poker_test(&straights).passed);
+
+        let alternating: Vec<f64> = (0..1000).map(|i| if i % 2 == 0 { 0.25 } else { 0.75 }).collect();
+        assert!(!gap_test(&alternating).passed);
+    }
+}